@@ -0,0 +1,116 @@
+//! Cache of opened files
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::{self, File, Metadata};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::SystemTime;
+
+/// Default number of cached files.
+const DEFAULT_CAPACITY: usize = 256;
+
+/// Opened file, valid while modification time and size match the file at path.
+struct Entry {
+    file: File,
+    md: Metadata,
+    modified: Option<SystemTime>,
+}
+
+/// Cache of opened files, used by `NamedFile::open_cached()`.
+///
+/// Keeps `File` handle and metadata of served files keyed by path, so hot
+/// files are not opened again on every request. Before cached handle is
+/// reused, file at the path is checked with a single `stat` call and entry
+/// is replaced if file is missing or its modification time or size has
+/// changed. Paths are used as given, they are not canonicalized.
+///
+/// Cache is not thread safe and is meant to be shared by handlers of a
+/// single worker, i.e. created in the application factory closure. Clones
+/// refer to the same cache.
+///
+/// Files returned by `open_cached()` share cursor with the cached handle.
+/// Response body is read by offset, so concurrent responses of the same file
+/// do not interfere.
+///
+/// # Examples
+///
+/// ```rust
+/// use actix_files::{NamedFile, OpenCache};
+/// use actix_web::{web, App};
+///
+/// async fn index(cache: web::Data<OpenCache>) -> std::io::Result<NamedFile> {
+///     NamedFile::open_cached("index.html", &cache)
+/// }
+///
+/// let app = App::new()
+///     .data(OpenCache::new(64))
+///     .route("/", web::get().to(index));
+/// ```
+#[derive(Clone)]
+pub struct OpenCache(Rc<Inner>);
+
+struct Inner {
+    capacity: usize,
+    entries: RefCell<HashMap<PathBuf, Entry>>,
+}
+
+impl OpenCache {
+    /// Creates cache which keeps up to `capacity` opened files.
+    ///
+    /// Once cache is full, other files are opened without caching.
+    pub fn new(capacity: usize) -> Self {
+        OpenCache(Rc::new(Inner {
+            capacity,
+            entries: RefCell::new(HashMap::new()),
+        }))
+    }
+
+    /// Closes all cached files.
+    pub fn clear(&self) {
+        self.0.entries.borrow_mut().clear();
+    }
+
+    /// Returns opened file at `path` along with its metadata.
+    pub(crate) fn open(&self, path: &Path) -> io::Result<(File, Metadata)> {
+        let current = match fs::metadata(path) {
+            Ok(md) => md,
+            Err(e) => {
+                self.0.entries.borrow_mut().remove(path);
+                return Err(e);
+            }
+        };
+
+        if let Some(entry) = self.0.entries.borrow().get(path) {
+            if entry.modified == current.modified().ok()
+                && entry.md.len() == current.len()
+            {
+                return Ok((entry.file.try_clone()?, entry.md.clone()));
+            }
+        }
+
+        let file = File::open(path)?;
+        let md = file.metadata()?;
+
+        let mut entries = self.0.entries.borrow_mut();
+        entries.remove(path);
+        if entries.len() < self.0.capacity {
+            entries.insert(
+                path.to_owned(),
+                Entry {
+                    file: file.try_clone()?,
+                    md: md.clone(),
+                    modified: md.modified().ok(),
+                },
+            );
+        }
+        Ok((file, md))
+    }
+}
+
+impl Default for OpenCache {
+    fn default() -> Self {
+        OpenCache::new(DEFAULT_CAPACITY)
+    }
+}
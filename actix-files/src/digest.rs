@@ -0,0 +1,181 @@
+//! `Digest` header values of served files
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use actix_web::web;
+use ring::digest::{Context, SHA256};
+
+/// Files up to this size are hashed inline, larger ones on thread pool.
+const INLINE_LIMIT: u64 = 262_144;
+
+/// Max number of cached digests per worker.
+const CAPACITY: usize = 1024;
+
+/// Digest of a file, valid while modification time and size match.
+struct Entry {
+    modified: SystemTime,
+    size: u64,
+    /// `None` while digest is being computed
+    value: Option<String>,
+}
+
+thread_local! {
+    static CACHE: RefCell<HashMap<PathBuf, Entry>> = RefCell::new(HashMap::new());
+}
+
+/// Returns `Digest` header value of the opened file, `sha-256=<base64>`.
+///
+/// Digest is cached per worker by `path`. Small files are hashed on first
+/// request, large files are hashed on thread pool and `None` is returned
+/// until digest is ready. Once cache is full, small files are hashed on
+/// every request and large files are not hashed.
+pub(crate) fn file_digest(
+    file: &File,
+    path: &Path,
+    modified: SystemTime,
+    size: u64,
+) -> Option<String> {
+    let cached = CACHE.with(|cache| match cache.borrow().get(path) {
+        Some(entry) if entry.modified == modified && entry.size == size => {
+            Some(entry.value.clone())
+        }
+        _ => None,
+    });
+    if let Some(value) = cached {
+        return value;
+    }
+
+    if size <= INLINE_LIMIT {
+        return match compute(file, path, modified, size) {
+            Ok(value) => {
+                insert(path, modified, size, Some(value.clone()));
+                Some(value)
+            }
+            Err(e) => {
+                log::debug!("Can not compute digest of {:?}: {}", path, e);
+                None
+            }
+        };
+    }
+
+    let file = match file.try_clone() {
+        Ok(file) => file,
+        Err(e) => {
+            log::debug!("Can not compute digest of {:?}: {}", path, e);
+            return None;
+        }
+    };
+    if !insert(path, modified, size, None) {
+        return None;
+    }
+    let path = path.to_owned();
+    actix_rt::spawn(async move {
+        let p = path.clone();
+        match web::block(move || compute(&file, &p, modified, size)).await {
+            Ok(value) => {
+                insert(&path, modified, size, Some(value));
+            }
+            Err(e) => {
+                log::debug!("Can not compute digest of {:?}: {}", path, e);
+                // let next request retry
+                CACHE.with(|cache| cache.borrow_mut().remove(&path));
+            }
+        }
+    });
+    None
+}
+
+/// Returns false if cache is full and `path` is not cached.
+fn insert(path: &Path, modified: SystemTime, size: u64, value: Option<String>) -> bool {
+    CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if cache.len() >= CAPACITY && !cache.contains_key(path) {
+            return false;
+        }
+        cache.insert(
+            path.to_owned(),
+            Entry {
+                modified,
+                size,
+                value,
+            },
+        );
+        true
+    })
+}
+
+/// Hash content of the opened file, fails if file is changed in the meantime.
+///
+/// Content is read by offset, so cursor shared with the response body stream
+/// is not moved and file replaced at `path` is not hashed.
+#[cfg(unix)]
+fn compute(
+    file: &File,
+    _: &Path,
+    modified: SystemTime,
+    size: u64,
+) -> io::Result<String> {
+    use std::os::unix::fs::FileExt;
+
+    let mut ctx = Context::new(&SHA256);
+    let mut buf = vec![0; 65_536];
+    let mut offset = 0;
+    loop {
+        let n = file.read_at(&mut buf, offset)?;
+        if n == 0 {
+            break;
+        }
+        ctx.update(&buf[..n]);
+        offset += n as u64;
+    }
+
+    verify(file, modified, size)?;
+    Ok(format!("sha-256={}", base64::encode(ctx.finish().as_ref())))
+}
+
+/// Hash content of the file at `path`, fails if it is not the opened file.
+///
+/// Platforms without positional reads would move cursor shared with the
+/// response body stream, so file is opened again and compared with the
+/// opened one by size and modification time.
+#[cfg(not(unix))]
+fn compute(
+    file: &File,
+    path: &Path,
+    modified: SystemTime,
+    size: u64,
+) -> io::Result<String> {
+    use std::io::Read;
+
+    let mut reopened = File::open(path)?;
+    let mut ctx = Context::new(&SHA256);
+    let mut buf = vec![0; 65_536];
+    loop {
+        let n = reopened.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        ctx.update(&buf[..n]);
+    }
+
+    verify(&reopened, modified, size)?;
+    verify(file, modified, size)?;
+    Ok(format!("sha-256={}", base64::encode(ctx.finish().as_ref())))
+}
+
+/// Check that file is not changed since it was opened.
+fn verify(file: &File, modified: SystemTime, size: u64) -> io::Result<()> {
+    let md = file.metadata()?;
+    if md.len() != size || md.modified().ok() != Some(modified) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "file is changed",
+        ));
+    }
+    Ok(())
+}
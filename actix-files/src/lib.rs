@@ -2,10 +2,12 @@
 
 //! Static files support
 use std::cell::RefCell;
+use std::collections::hash_map::RandomState;
+use std::collections::VecDeque;
 use std::fmt::Write;
 use std::fs::{DirEntry, File};
 use std::future::Future;
-use std::io::{Read, Seek};
+use std::hash::{BuildHasher, Hasher};
 use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use std::rc::Rc;
@@ -20,10 +22,14 @@ use actix_web::dev::{
 };
 use actix_web::error::{BlockingError, Error, ErrorInternalServerError};
 use actix_web::guard::Guard;
-use actix_web::http::header::{self, DispositionType};
-use actix_web::http::Method;
+use actix_web::http::header::{self, Charset, DispositionType};
+use actix_web::http::{ContentEncoding, Method};
 use actix_web::{web, FromRequest, HttpRequest, HttpResponse};
 use bytes::Bytes;
+#[cfg(feature = "compress")]
+use flate2::write::{GzEncoder, ZlibEncoder};
+#[cfg(feature = "compress")]
+use flate2::Compression;
 use futures::future::{ok, ready, Either, FutureExt, LocalBoxFuture, Ready};
 use futures::Stream;
 use mime;
@@ -31,12 +37,20 @@ use mime_guess::from_ext;
 use percent_encoding::{utf8_percent_encode, CONTROLS};
 use v_htmlescape::escape as escape_html_entity;
 
+mod cache;
+#[cfg(feature = "digest")]
+mod digest;
 mod error;
+mod listing;
 mod named;
 mod range;
 
 use self::error::{FilesError, UriSegmentError};
-pub use crate::named::NamedFile;
+pub use crate::cache::OpenCache;
+pub use crate::listing::DirectoryListing;
+pub use crate::named::{
+    NamedFile, NamedFileOffload, NamedFileOr404, OffloadHeader, PrecompressedFile,
+};
 pub use crate::range::HttpRange;
 
 type HttpService = BoxService<ServiceRequest, ServiceResponse, Error>;
@@ -66,6 +80,8 @@ pub struct ChunkedReadFile {
     fut:
         Option<LocalBoxFuture<'static, Result<(File, Bytes), BlockingError<io::Error>>>>,
     counter: u64,
+    chunk_size: usize,
+    map_body: Option<fn(Bytes) -> Bytes>,
 }
 
 impl Stream for ChunkedReadFile {
@@ -82,7 +98,10 @@ impl Stream for ChunkedReadFile {
                     self.file = Some(file);
                     self.offset += bytes.len() as u64;
                     self.counter += bytes.len() as u64;
-                    Poll::Ready(Some(Ok(bytes)))
+                    match self.map_body {
+                        Some(f) => Poll::Ready(Some(Ok(f(bytes)))),
+                        None => Poll::Ready(Some(Ok(bytes))),
+                    }
                 }
                 Poll::Ready(Err(e)) => Poll::Ready(Some(Err(handle_error(e)))),
                 Poll::Pending => Poll::Pending,
@@ -92,6 +111,7 @@ impl Stream for ChunkedReadFile {
         let size = self.size;
         let offset = self.offset;
         let counter = self.counter;
+        let chunk_size = self.chunk_size as u64;
 
         if size == counter {
             Poll::Ready(None)
@@ -100,12 +120,10 @@ impl Stream for ChunkedReadFile {
             self.fut = Some(
                 web::block(move || {
                     let max_bytes: usize;
-                    max_bytes = cmp::min(size.saturating_sub(counter), 65_536) as usize;
-                    let mut buf = Vec::with_capacity(max_bytes);
-                    file.seek(io::SeekFrom::Start(offset))?;
-                    let nbytes =
-                        file.by_ref().take(max_bytes as u64).read_to_end(&mut buf)?;
-                    if nbytes == 0 {
+                    max_bytes =
+                        cmp::min(size.saturating_sub(counter), chunk_size) as usize;
+                    let buf = read_chunk(&mut file, offset, max_bytes)?;
+                    if buf.is_empty() {
                         return Err(io::ErrorKind::UnexpectedEof.into());
                     }
                     Ok((file, Bytes::from(buf)))
@@ -117,6 +135,230 @@ impl Stream for ChunkedReadFile {
     }
 }
 
+/// Reads up to `max_bytes` of the file starting at `offset`.
+///
+/// Content is read by offset where platform supports it, so file handles
+/// sharing cursor, i.e. ones reused by `OpenCache`, can be read concurrently.
+#[cfg(any(unix, windows))]
+fn read_chunk(file: &mut File, offset: u64, max_bytes: usize) -> io::Result<Vec<u8>> {
+    #[cfg(unix)]
+    use std::os::unix::fs::FileExt;
+    #[cfg(windows)]
+    use std::os::windows::fs::FileExt;
+
+    let mut buf = vec![0; max_bytes];
+    let mut filled = 0;
+    while filled < max_bytes {
+        let pos = offset + filled as u64;
+        #[cfg(unix)]
+        let res = file.read_at(&mut buf[filled..], pos);
+        #[cfg(windows)]
+        let res = file.seek_read(&mut buf[filled..], pos);
+        match res {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    buf.truncate(filled);
+    Ok(buf)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn read_chunk(file: &mut File, offset: u64, max_bytes: usize) -> io::Result<Vec<u8>> {
+    use std::io::{Read, Seek};
+
+    let mut buf = Vec::with_capacity(max_bytes);
+    file.seek(io::SeekFrom::Start(offset))?;
+    file.by_ref().take(max_bytes as u64).read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+/// Stream which compresses chunks of the file on the fly.
+#[cfg(feature = "compress")]
+struct EncodedReadFile {
+    reader: ChunkedReadFile,
+    encoder: Option<FileEncoder>,
+}
+
+#[cfg(feature = "compress")]
+enum FileEncoder {
+    Deflate(ZlibEncoder<Vec<u8>>),
+    Gzip(GzEncoder<Vec<u8>>),
+}
+
+#[cfg(feature = "compress")]
+impl EncodedReadFile {
+    fn new(reader: ChunkedReadFile, enc: ContentEncoding) -> Self {
+        let encoder = match enc {
+            ContentEncoding::Deflate => {
+                FileEncoder::Deflate(ZlibEncoder::new(Vec::new(), Compression::fast()))
+            }
+            _ => FileEncoder::Gzip(GzEncoder::new(Vec::new(), Compression::fast())),
+        };
+        EncodedReadFile {
+            reader,
+            encoder: Some(encoder),
+        }
+    }
+}
+
+#[cfg(feature = "compress")]
+impl FileEncoder {
+    fn write(&mut self, data: &[u8]) -> io::Result<Bytes> {
+        let buf = match self {
+            FileEncoder::Deflate(ref mut enc) => {
+                io::Write::write_all(enc, data)?;
+                enc.get_mut()
+            }
+            FileEncoder::Gzip(ref mut enc) => {
+                io::Write::write_all(enc, data)?;
+                enc.get_mut()
+            }
+        };
+        Ok(Bytes::from(std::mem::take(buf)))
+    }
+
+    fn finish(self) -> io::Result<Bytes> {
+        let buf = match self {
+            FileEncoder::Deflate(enc) => enc.finish()?,
+            FileEncoder::Gzip(enc) => enc.finish()?,
+        };
+        Ok(Bytes::from(buf))
+    }
+}
+
+#[cfg(feature = "compress")]
+impl Stream for EncodedReadFile {
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            let encoder = match this.encoder {
+                Some(ref mut encoder) => encoder,
+                None => return Poll::Ready(None),
+            };
+            let res = match Pin::new(&mut this.reader).poll_next(cx) {
+                Poll::Ready(Some(Ok(chunk))) => encoder.write(&chunk),
+                Poll::Ready(Some(Err(e))) => {
+                    this.encoder.take();
+                    return Poll::Ready(Some(Err(e)));
+                }
+                Poll::Ready(None) => this.encoder.take().unwrap().finish(),
+                Poll::Pending => return Poll::Pending,
+            };
+            match res {
+                Ok(ref bytes) if bytes.is_empty() => continue,
+                Ok(bytes) => return Poll::Ready(Some(Ok(bytes))),
+                Err(e) => {
+                    this.encoder.take();
+                    return Poll::Ready(Some(Err(e.into())));
+                }
+            }
+        }
+    }
+}
+
+/// Stream of `multipart/byteranges` body with selected ranges of the file.
+struct ChunkedReadRanges {
+    boundary: String,
+    parts: VecDeque<(Bytes, HttpRange)>,
+    size: u64,
+    file: Option<File>,
+    reader: Option<ChunkedReadFile>,
+    chunk_size: usize,
+}
+
+impl ChunkedReadRanges {
+    fn new(
+        ranges: Vec<HttpRange>,
+        content_type: &str,
+        file_size: u64,
+        chunk_size: usize,
+    ) -> Self {
+        let boundary = format!("{:016x}", RandomState::new().build_hasher().finish());
+
+        let mut size = 0;
+        let parts = ranges
+            .into_iter()
+            .enumerate()
+            .map(|(idx, range)| {
+                let head = format!(
+                    "{}--{}\r\nContent-Type: {}\r\nContent-Range: bytes {}-{}/{}\r\n\r\n",
+                    if idx == 0 { "" } else { "\r\n" },
+                    boundary,
+                    content_type,
+                    range.start,
+                    range.start + range.length - 1,
+                    file_size
+                );
+                size += head.len() as u64 + range.length;
+                (Bytes::from(head), range)
+            })
+            .collect();
+        size += boundary.len() as u64 + 8;
+
+        ChunkedReadRanges {
+            boundary,
+            parts,
+            size,
+            file: None,
+            reader: None,
+            chunk_size,
+        }
+    }
+
+    /// Length of complete body
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn boundary(&self) -> &str {
+        &self.boundary
+    }
+
+    fn set_file(&mut self, file: File) {
+        self.file = Some(file);
+    }
+}
+
+impl Stream for ChunkedReadRanges {
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if let Some(ref mut reader) = this.reader {
+            match Pin::new(reader).poll_next(cx) {
+                Poll::Ready(Some(item)) => return Poll::Ready(Some(item)),
+                Poll::Ready(None) => this.file = this.reader.take().and_then(|r| r.file),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        if let Some((head, range)) = this.parts.pop_front() {
+            this.reader = Some(ChunkedReadFile {
+                offset: range.start,
+                size: range.length,
+                file: Some(this.file.take().expect("Use after completion")),
+                fut: None,
+                counter: 0,
+                chunk_size: this.chunk_size,
+                map_body: None,
+            });
+            Poll::Ready(Some(Ok(head)))
+        } else if this.file.take().is_some() {
+            let trailer = format!("\r\n--{}--\r\n", this.boundary);
+            Poll::Ready(Some(Ok(Bytes::from(trailer))))
+        } else {
+            Poll::Ready(None)
+        }
+    }
+}
+
 type DirectoryRenderer =
     dyn Fn(&Directory, &HttpRequest) -> Result<ServiceResponse, io::Error>;
 
@@ -227,6 +469,8 @@ fn directory_listing(
 
 type MimeOverride = dyn Fn(&mime::Name) -> DispositionType;
 
+type DispositionFilename = dyn Fn(&Path) -> Option<String>;
+
 /// Static files handling
 ///
 /// `Files` service must be registered with `App::service()` method.
@@ -249,7 +493,9 @@ pub struct Files {
     default: Rc<RefCell<Option<Rc<HttpNewService>>>>,
     renderer: Rc<DirectoryRenderer>,
     mime_override: Option<Rc<MimeOverride>>,
+    disposition_filename: Option<Rc<DispositionFilename>>,
     file_flags: named::Flags,
+    charset: Charset,
     guards: Option<Rc<Box<dyn Guard>>>,
 }
 
@@ -263,8 +509,10 @@ impl Clone for Files {
             default: self.default.clone(),
             renderer: self.renderer.clone(),
             file_flags: self.file_flags,
+            charset: self.charset.clone(),
             path: self.path.clone(),
             mime_override: self.mime_override.clone(),
+            disposition_filename: self.disposition_filename.clone(),
             guards: self.guards.clone(),
         }
     }
@@ -295,7 +543,9 @@ impl Files {
             default: Rc::new(RefCell::new(None)),
             renderer: Rc::new(directory_listing),
             mime_override: None,
+            disposition_filename: None,
             file_flags: named::Flags::default(),
+            charset: Charset::Ext(String::from("utf-8")),
             guards: None,
         }
     }
@@ -335,6 +585,20 @@ impl Files {
         self
     }
 
+    /// Specifies callback to rewrite file name of `Content-Disposition`
+    /// header, i.e. to present original upload name instead of internal one.
+    ///
+    /// Callback receives full path of the served file, if it returns `None`
+    /// file name is used. Non-ASCII names are encoded as `filename*`
+    /// parameter.
+    pub fn disposition_filename<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Path) -> Option<String> + 'static,
+    {
+        self.disposition_filename = Some(Rc::new(f));
+        self
+    }
+
     /// Set index file
     ///
     /// Shows specific index file for directory "/" instead of
@@ -353,6 +617,17 @@ impl Files {
         self
     }
 
+    #[inline]
+    /// Specifies whether generated ETag is weak.
+    ///
+    /// See [`NamedFile::use_weak_etag()`](struct.NamedFile.html#method.use_weak_etag).
+    ///
+    /// Default is false.
+    pub fn use_weak_etag(mut self, value: bool) -> Self {
+        self.file_flags.set(named::Flags::WEAK_ETAG, value);
+        self
+    }
+
     #[inline]
     /// Specifies whether to use Last-Modified or not.
     ///
@@ -362,6 +637,18 @@ impl Files {
         self
     }
 
+    #[inline]
+    /// Specifies whether to send `Digest` header with sha-256 hash of files.
+    ///
+    /// See [`NamedFile::use_digest()`](struct.NamedFile.html#method.use_digest).
+    ///
+    /// Default is false.
+    #[cfg(feature = "digest")]
+    pub fn use_digest(mut self, value: bool) -> Self {
+        self.file_flags.set(named::Flags::DIGEST, value);
+        self
+    }
+
     /// Specifies custom guards to use for directory listings and files.
     ///
     /// Default behaviour allows GET and HEAD.
@@ -380,6 +667,27 @@ impl Files {
         self
     }
 
+    /// Set charset appended to text content types without charset.
+    ///
+    /// By default `utf-8` charset is used.
+    #[inline]
+    pub fn default_charset(mut self, charset: Charset) -> Self {
+        self.charset = charset;
+        self
+    }
+
+    #[inline]
+    /// Specifies whether clients must revalidate cached files on every use.
+    ///
+    /// Responses get `Cache-Control: no-cache` directive, and `ETag` and
+    /// `Last-Modified` validators are sent regardless of other settings.
+    ///
+    /// Default is false.
+    pub fn force_revalidation(mut self, value: bool) -> Self {
+        self.file_flags.set(named::Flags::REVALIDATE, value);
+        self
+    }
+
     /// Sets default handler which is used when no matched file could be found.
     pub fn default_handler<F, U>(mut self, f: F) -> Self
     where
@@ -432,7 +740,9 @@ impl ServiceFactory for Files {
             default: None,
             renderer: self.renderer.clone(),
             mime_override: self.mime_override.clone(),
+            disposition_filename: self.disposition_filename.clone(),
             file_flags: self.file_flags,
+            charset: self.charset.clone(),
             guards: self.guards.clone(),
         };
 
@@ -461,7 +771,9 @@ pub struct FilesService {
     default: Option<HttpService>,
     renderer: Rc<DirectoryRenderer>,
     mime_override: Option<Rc<MimeOverride>>,
+    disposition_filename: Option<Rc<DispositionFilename>>,
     file_flags: named::Flags,
+    charset: Charset,
     guards: Option<Rc<Box<dyn Guard>>>,
 }
 
@@ -548,8 +860,15 @@ impl Service for FilesService {
                                 mime_override(&named_file.content_type.type_());
                             named_file.content_disposition.disposition = new_disposition;
                         }
+                        if let Some(ref disposition_filename) = self.disposition_filename
+                        {
+                            if let Some(name) = disposition_filename(named_file.path()) {
+                                named_file = named_file.set_disposition_filename(name);
+                            }
+                        }
 
                         named_file.flags = self.file_flags;
+                        named_file.charset = self.charset.clone();
                         let (req, _) = req.into_parts();
                         Either::Left(ok(match named_file.into_response(&req) {
                             Ok(item) => ServiceResponse::new(req, item),
@@ -580,8 +899,14 @@ impl Service for FilesService {
                             mime_override(&named_file.content_type.type_());
                         named_file.content_disposition.disposition = new_disposition;
                     }
+                    if let Some(ref disposition_filename) = self.disposition_filename {
+                        if let Some(name) = disposition_filename(named_file.path()) {
+                            named_file = named_file.set_disposition_filename(name);
+                        }
+                    }
 
                     named_file.flags = self.file_flags;
+                    named_file.charset = self.charset.clone();
                     let (req, _) = req.into_parts();
                     match named_file.into_response(&req) {
                         Ok(item) => {
@@ -643,7 +968,7 @@ mod tests {
     use std::fs;
     use std::iter::FromIterator;
     use std::ops::Add;
-    use std::time::{Duration, SystemTime};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
     use super::*;
     use actix_web::guard;
@@ -654,6 +979,7 @@ mod tests {
     use actix_web::middleware::Compress;
     use actix_web::test::{self, TestRequest};
     use actix_web::{App, Responder};
+    use futures::StreamExt;
 
     #[actix_rt::test]
     async fn test_file_extension_to_mime() {
@@ -667,6 +993,98 @@ mod tests {
         assert_eq!(m, mime::APPLICATION_OCTET_STREAM);
     }
 
+    #[actix_rt::test]
+    async fn test_if_modified_since_subsec_mtime() {
+        let open = || {
+            let mut file = NamedFile::open("Cargo.toml").unwrap();
+            file.modified = Some(UNIX_EPOCH + Duration::new(1_500_000_000, 500_000_000));
+            file
+        };
+
+        let req = TestRequest::default().to_http_request();
+        let resp = open().respond_to(&req).await.unwrap();
+        let last_modified = resp.headers().get(header::LAST_MODIFIED).unwrap().clone();
+        assert_eq!(last_modified, "Fri, 14 Jul 2017 02:40:00 GMT");
+
+        // client echoes exact header value back
+        let req = TestRequest::default()
+            .header(header::IF_MODIFIED_SINCE, last_modified.clone())
+            .to_http_request();
+        let resp = open().respond_to(&req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+
+        let req = TestRequest::default()
+            .header(header::IF_UNMODIFIED_SINCE, last_modified)
+            .to_http_request();
+        let resp = open().respond_to(&req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[cfg(feature = "digest")]
+    #[actix_rt::test]
+    async fn test_named_file_digest() {
+        fn expected(path: &Path) -> String {
+            let data = fs::read(path).unwrap();
+            let hash = ring::digest::digest(&ring::digest::SHA256, &data);
+            format!("sha-256={}", base64::encode(hash.as_ref()))
+        }
+
+        let req = TestRequest::default().to_http_request();
+        let resp = NamedFile::open("Cargo.toml")
+            .unwrap()
+            .respond_to(&req)
+            .await
+            .unwrap();
+        assert!(resp.headers().get("digest").is_none());
+
+        let resp = NamedFile::open("Cargo.toml")
+            .unwrap()
+            .use_digest(true)
+            .respond_to(&req)
+            .await
+            .unwrap();
+        let digest = resp.headers().get("digest").unwrap();
+        assert_eq!(digest.to_str().unwrap(), expected(Path::new("Cargo.toml")));
+
+        let mut srv = test::init_service(
+            App::new().service(Files::new("/", ".").use_digest(true)),
+        )
+        .await;
+        let req = TestRequest::with_uri("/Cargo.toml").to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        let digest = resp.headers().get("digest").unwrap();
+        assert_eq!(digest.to_str().unwrap(), expected(Path::new("Cargo.toml")));
+
+        // large file is hashed in background
+        let path = std::env::temp_dir().join("actix-files-digest.bin");
+        fs::write(&path, vec![b'x'; 1_000_000]).unwrap();
+        let resp = NamedFile::open(&path)
+            .unwrap()
+            .use_digest(true)
+            .respond_to(&TestRequest::default().to_http_request())
+            .await
+            .unwrap();
+        assert!(resp.headers().get("digest").is_none());
+
+        let mut digest = None;
+        for _ in 0..500 {
+            actix_rt::time::delay_for(Duration::from_millis(10)).await;
+            let resp = NamedFile::open(&path)
+                .unwrap()
+                .use_digest(true)
+                .respond_to(&TestRequest::default().to_http_request())
+                .await
+                .unwrap();
+            digest = resp.headers().get("digest").cloned();
+            if digest.is_some() {
+                break;
+            }
+        }
+        assert_eq!(digest.unwrap().to_str().unwrap(), expected(&path));
+
+        let _ = fs::remove_file(&path);
+    }
+
     #[actix_rt::test]
     async fn test_if_modified_since_without_if_none_match() {
         let file = NamedFile::open("Cargo.toml").unwrap();
@@ -694,6 +1112,147 @@ mod tests {
         assert_ne!(resp.status(), StatusCode::NOT_MODIFIED);
     }
 
+    #[actix_rt::test]
+    async fn test_if_none_match_large_list() {
+        let file = NamedFile::open("Cargo.toml").unwrap();
+        let etag = file.etag().unwrap();
+
+        let mut tags: Vec<_> = (0..500).map(|i| format!("W/\"miss{}\"", i)).collect();
+        let req = TestRequest::default()
+            .header(header::IF_NONE_MATCH, tags.join(", "))
+            .to_http_request();
+        let resp = file.respond_to(&req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let file = NamedFile::open("Cargo.toml").unwrap();
+        tags.push(format!("W/\"{}\"", etag.tag()));
+        let req = TestRequest::default()
+            .header(header::IF_NONE_MATCH, tags.join(","))
+            .to_http_request();
+        let resp = file.respond_to(&req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+    }
+
+    #[actix_rt::test]
+    async fn test_if_none_match_any_mixed_with_items() {
+        let file = NamedFile::open("Cargo.toml").unwrap();
+        let req = TestRequest::default()
+            .header(header::IF_NONE_MATCH, "\"miss\", *, \"other\"")
+            .to_http_request();
+        let resp = file.respond_to(&req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+    }
+
+    #[actix_rt::test]
+    async fn test_if_match() {
+        let file = NamedFile::open("Cargo.toml").unwrap();
+        let etag = file.etag().unwrap();
+
+        let mut tags: Vec<_> = (0..500).map(|i| format!("\"miss{}\"", i)).collect();
+        let req = TestRequest::default()
+            .header(header::IF_MATCH, tags.join(", "))
+            .to_http_request();
+        let resp = file.respond_to(&req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::PRECONDITION_FAILED);
+
+        // weak tags never match strongly
+        let file = NamedFile::open("Cargo.toml").unwrap();
+        tags.push(format!("W/\"{}\"", etag.tag()));
+        let req = TestRequest::default()
+            .header(header::IF_MATCH, tags.join(", "))
+            .to_http_request();
+        let resp = file.respond_to(&req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::PRECONDITION_FAILED);
+
+        let file = NamedFile::open("Cargo.toml").unwrap();
+        tags.push(format!("\"{}\"", etag.tag()));
+        let req = TestRequest::default()
+            .header(header::IF_MATCH, tags.join(", "))
+            .to_http_request();
+        let resp = file.respond_to(&req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let file = NamedFile::open("Cargo.toml").unwrap();
+        let req = TestRequest::default()
+            .header(header::IF_MATCH, "\"miss\", *")
+            .to_http_request();
+        let resp = file.respond_to(&req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[actix_rt::test]
+    async fn test_weak_etag() {
+        let file = NamedFile::open("Cargo.toml").unwrap().use_weak_etag(true);
+        let etag = file.etag().unwrap();
+        assert!(etag.weak);
+
+        let req = TestRequest::default().to_http_request();
+        let resp = file.respond_to(&req).await.unwrap();
+        assert_eq!(
+            resp.headers().get(header::ETAG).unwrap().to_str().unwrap(),
+            format!("W/\"{}\"", etag.tag())
+        );
+
+        // weak comparison for `If-None-Match`
+        let tags = [
+            format!("W/\"{}\"", etag.tag()),
+            format!("\"{}\"", etag.tag()),
+        ];
+        for tag in &tags {
+            let file = NamedFile::open("Cargo.toml").unwrap().use_weak_etag(true);
+            let req = TestRequest::default()
+                .header(header::IF_NONE_MATCH, tag.as_str())
+                .to_http_request();
+            let resp = file.respond_to(&req).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+        }
+        let file = NamedFile::open("Cargo.toml").unwrap().use_weak_etag(true);
+        let req = TestRequest::default()
+            .header(header::IF_NONE_MATCH, "W/\"miss\"")
+            .to_http_request();
+        let resp = file.respond_to(&req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // strong comparison for `If-Match`, weak tag never matches
+        for tag in &tags {
+            let file = NamedFile::open("Cargo.toml").unwrap().use_weak_etag(true);
+            let req = TestRequest::default()
+                .header(header::IF_MATCH, tag.as_str())
+                .to_http_request();
+            let resp = file.respond_to(&req).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::PRECONDITION_FAILED);
+        }
+        let file = NamedFile::open("Cargo.toml").unwrap().use_weak_etag(true);
+        let req = TestRequest::default()
+            .header(header::IF_MATCH, "*")
+            .to_http_request();
+        let resp = file.respond_to(&req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // weak tag does not validate range request
+        let file = NamedFile::open("Cargo.toml").unwrap().use_weak_etag(true);
+        let req = TestRequest::default()
+            .header(header::RANGE, "bytes=0-9")
+            .header(header::IF_RANGE, format!("W/\"{}\"", etag.tag()))
+            .to_http_request();
+        let resp = file.respond_to(&req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let mut srv = test::init_service(
+            App::new().service(Files::new("/", ".").use_weak_etag(true)),
+        )
+        .await;
+        let req = TestRequest::with_uri("/Cargo.toml").to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        let etag = resp.headers().get(header::ETAG).unwrap().clone();
+        assert!(etag.to_str().unwrap().starts_with("W/\""));
+        let req = TestRequest::with_uri("/Cargo.toml")
+            .header(header::IF_NONE_MATCH, etag)
+            .to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+    }
+
     #[actix_rt::test]
     async fn test_named_file_text() {
         assert!(NamedFile::open("test--").is_err());
@@ -710,7 +1269,7 @@ mod tests {
         let resp = file.respond_to(&req).await.unwrap();
         assert_eq!(
             resp.headers().get(header::CONTENT_TYPE).unwrap(),
-            "text/x-toml"
+            "text/x-toml; charset=utf-8"
         );
         assert_eq!(
             resp.headers().get(header::CONTENT_DISPOSITION).unwrap(),
@@ -762,7 +1321,7 @@ mod tests {
         let resp = file.respond_to(&req).await.unwrap();
         assert_eq!(
             resp.headers().get(header::CONTENT_TYPE).unwrap(),
-            "text/x-toml"
+            "text/x-toml; charset=utf-8"
         );
         assert_eq!(
             resp.headers().get(header::CONTENT_DISPOSITION).unwrap(),
@@ -771,46 +1330,515 @@ mod tests {
     }
 
     #[actix_rt::test]
-    async fn test_named_file_set_content_type() {
-        let mut file = NamedFile::open("Cargo.toml")
-            .unwrap()
-            .set_content_type(mime::TEXT_XML);
-        {
-            file.file();
-            let _f: &File = &file;
-        }
-        {
-            let _f: &mut File = &mut file;
-        }
-
+    async fn test_named_file_default_charset() {
+        let file = fs::File::open("Cargo.toml").unwrap();
+        let file = NamedFile::from_file(file, "index.html").unwrap();
         let req = TestRequest::default().to_http_request();
         let resp = file.respond_to(&req).await.unwrap();
         assert_eq!(
             resp.headers().get(header::CONTENT_TYPE).unwrap(),
-            "text/xml"
-        );
-        assert_eq!(
-            resp.headers().get(header::CONTENT_DISPOSITION).unwrap(),
-            "inline; filename=\"Cargo.toml\""
+            "text/html; charset=utf-8"
         );
-    }
-
-    #[actix_rt::test]
-    async fn test_named_file_image() {
-        let mut file = NamedFile::open("tests/test.png").unwrap();
-        {
-            file.file();
-            let _f: &File = &file;
-        }
-        {
-            let _f: &mut File = &mut file;
-        }
 
-        let req = TestRequest::default().to_http_request();
+        let file = fs::File::open("Cargo.toml").unwrap();
+        let file = NamedFile::from_file(file, "index.html")
+            .unwrap()
+            .default_charset(header::Charset::Iso_8859_1);
         let resp = file.respond_to(&req).await.unwrap();
         assert_eq!(
             resp.headers().get(header::CONTENT_TYPE).unwrap(),
-            "image/png"
+            "text/html; charset=iso-8859-1"
+        );
+
+        // explicit charset is kept, non-text types get no charset
+        let file = NamedFile::open("Cargo.toml")
+            .unwrap()
+            .set_content_type("text/plain; charset=us-ascii".parse().unwrap());
+        let resp = file.respond_to(&req).await.unwrap();
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/plain; charset=us-ascii"
+        );
+
+        let file = NamedFile::open("tests/test.png").unwrap();
+        let resp = file.respond_to(&req).await.unwrap();
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "image/png"
+        );
+
+        let mut srv =
+            test::init_service(App::new().service(
+                Files::new("/", ".").default_charset(header::Charset::Us_Ascii),
+            ))
+            .await;
+        let req = TestRequest::with_uri("/Cargo.toml").to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/x-toml; charset=us-ascii"
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_force_revalidation() {
+        let mut srv = test::init_service(
+            App::new().service(
+                Files::new("/", ".")
+                    .use_etag(false)
+                    .use_last_modified(false)
+                    .force_revalidation(true),
+            ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/Cargo.toml").to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CACHE_CONTROL).unwrap(),
+            "no-cache"
+        );
+        assert!(resp.headers().contains_key(header::LAST_MODIFIED));
+        let etag = resp.headers().get(header::ETAG).unwrap().clone();
+
+        let req = TestRequest::with_uri("/Cargo.toml")
+            .header(header::IF_NONE_MATCH, etag)
+            .to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(
+            resp.headers().get(header::CACHE_CONTROL).unwrap(),
+            "no-cache"
+        );
+
+        let file = NamedFile::open("Cargo.toml").unwrap();
+        let req = TestRequest::default().to_http_request();
+        let resp = file.respond_to(&req).await.unwrap();
+        assert!(!resp.headers().contains_key(header::CACHE_CONTROL));
+    }
+
+    #[actix_rt::test]
+    async fn test_named_file_open_async() {
+        let file = NamedFile::open_async("Cargo.toml").await.unwrap();
+        assert_eq!(file.path(), Path::new("Cargo.toml"));
+        assert_eq!(
+            file.content_type(),
+            NamedFile::open("Cargo.toml").unwrap().content_type()
+        );
+
+        let req = TestRequest::default().to_http_request();
+        let resp = file.respond_to(&req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let bytes = test::read_body(ServiceResponse::new(req, resp)).await;
+        assert_eq!(&bytes[..], &fs::read("Cargo.toml").unwrap()[..]);
+
+        let err = NamedFile::open_async("missing.txt").await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[actix_rt::test]
+    async fn test_named_file_open_cached() {
+        use std::io::{Seek, SeekFrom};
+
+        let path = std::env::temp_dir().join("actix-files-open-cached.txt");
+        fs::write(&path, b"cached content").unwrap();
+        let cache = OpenCache::new(8);
+
+        let mut first = NamedFile::open_cached(&path, &cache).unwrap();
+        first.seek(SeekFrom::Start(7)).unwrap();
+
+        // cached handle is reused, cursor is shared with the first file
+        let mut second = NamedFile::open_cached(&path, &cache).unwrap();
+        assert_eq!(second.seek(SeekFrom::Current(0)).unwrap(), 7);
+
+        // body is read by offset regardless of cursor
+        let req = TestRequest::default().to_http_request();
+        let resp = second.respond_to(&req).await.unwrap();
+        let bytes = test::read_body(ServiceResponse::new(req, resp)).await;
+        assert_eq!(&bytes[..], b"cached content");
+
+        // modified file is opened again
+        fs::write(&path, b"modified cached content").unwrap();
+        let mut third = NamedFile::open_cached(&path, &cache).unwrap();
+        assert_eq!(third.seek(SeekFrom::Current(0)).unwrap(), 0);
+
+        let req = TestRequest::default().to_http_request();
+        let resp = third.respond_to(&req).await.unwrap();
+        let bytes = test::read_body(ServiceResponse::new(req, resp)).await;
+        assert_eq!(&bytes[..], b"modified cached content");
+
+        let _ = fs::remove_file(&path);
+        let err = NamedFile::open_cached(&path, &cache).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[actix_rt::test]
+    async fn test_named_file_map_body() {
+        let mut srv =
+            test::init_service(App::new().service(web::resource("/").to(|| async {
+                NamedFile::open("Cargo.toml")
+                    .unwrap()
+                    .map_body(|chunk| Bytes::from(chunk.to_ascii_uppercase()))
+            })))
+            .await;
+
+        let req = TestRequest::get()
+            .uri("/")
+            .header(header::RANGE, "bytes=0-4")
+            .to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(!resp.headers().contains_key(header::CONTENT_LENGTH));
+        assert!(!resp.headers().contains_key(header::ACCEPT_RANGES));
+        assert!(!resp.headers().contains_key(header::CONTENT_RANGE));
+        assert!(resp.headers().contains_key(header::ETAG));
+
+        let bytes = test::read_body(resp).await;
+        let data = fs::read("Cargo.toml").unwrap().to_ascii_uppercase();
+        assert_eq!(&bytes[..], &data[..]);
+    }
+
+    #[actix_rt::test]
+    async fn test_named_file_map_body_content_length() {
+        use actix_web::dev::{BodySize, MessageBody};
+
+        let data = fs::read("Cargo.toml").unwrap();
+        let req = TestRequest::default().to_http_request();
+
+        // unknown length of transformed body
+        let resp = NamedFile::open("Cargo.toml")
+            .unwrap()
+            .map_body(|chunk| chunk.slice(..chunk.len() / 2))
+            .respond_to(&req)
+            .await
+            .unwrap();
+        assert_eq!(resp.body().size(), BodySize::Stream);
+        assert!(!resp.headers().contains_key(header::CONTENT_LENGTH));
+
+        // explicit length is ignored without transform
+        let resp = NamedFile::open("Cargo.toml")
+            .unwrap()
+            .set_content_length(1)
+            .respond_to(&req)
+            .await
+            .unwrap();
+        assert_eq!(resp.body().size(), BodySize::Sized64(data.len() as u64));
+
+        let named = NamedFile::open("Cargo.toml")
+            .unwrap()
+            .map_body(|chunk| Bytes::from(chunk.to_ascii_uppercase()))
+            .set_content_length(data.len() as u64);
+        let meta = named.metadata_response(&req);
+        assert_eq!(
+            meta.headers().get(header::CONTENT_LENGTH).unwrap(),
+            &data.len().to_string()
+        );
+        let mut resp = named.respond_to(&req).await.unwrap();
+        assert_eq!(resp.body().size(), BodySize::Sized64(data.len() as u64));
+
+        let mut body = resp.take_body();
+        let mut received = Vec::new();
+        while let Some(chunk) = body.next().await {
+            received.extend_from_slice(&chunk.unwrap());
+        }
+        assert_eq!(received, data.to_ascii_uppercase());
+    }
+
+    #[actix_rt::test]
+    async fn test_named_file_chunk_size() {
+        let err = NamedFile::open("Cargo.toml")
+            .unwrap()
+            .set_chunk_size(0)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        let data = fs::read("tests/test.binary").unwrap();
+        let req = TestRequest::default().to_http_request();
+        let mut resp = NamedFile::open("tests/test.binary")
+            .unwrap()
+            .set_chunk_size(16)
+            .unwrap()
+            .respond_to(&req)
+            .await
+            .unwrap();
+        let mut body = resp.take_body();
+        let mut received = Vec::new();
+        while let Some(chunk) = body.next().await {
+            let chunk = chunk.unwrap();
+            assert!(chunk.len() <= 16);
+            received.extend_from_slice(&chunk);
+        }
+        assert_eq!(received, data);
+
+        let req = TestRequest::default()
+            .header(header::RANGE, "bytes=10-40")
+            .to_http_request();
+        let mut resp = NamedFile::open("tests/test.binary")
+            .unwrap()
+            .set_chunk_size(16)
+            .unwrap()
+            .respond_to(&req)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+        let mut body = resp.take_body();
+        let mut chunks = 0;
+        while let Some(chunk) = body.next().await {
+            assert!(chunk.unwrap().len() <= 16);
+            chunks += 1;
+        }
+        assert_eq!(chunks, 2);
+    }
+
+    #[actix_rt::test]
+    async fn test_precompressed_file() {
+        let plain = fs::read("tests/precompressed/app.js").unwrap();
+        let gzipped = fs::read("tests/precompressed/app.js.gz").unwrap();
+
+        let req = TestRequest::default()
+            .header(header::ACCEPT_ENCODING, "br, gzip;q=0.5")
+            .to_http_request();
+        let resp = PrecompressedFile::new("tests/precompressed/app.js")
+            .respond_to(&req)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_ENCODING).unwrap(),
+            "gzip"
+        );
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/javascript; charset=utf-8"
+        );
+        assert_eq!(
+            resp.headers().get(header::CONTENT_DISPOSITION).unwrap(),
+            "inline; filename=\"app.js\""
+        );
+        assert_eq!(resp.headers().get(header::VARY).unwrap(), "accept-encoding");
+        let gz_etag = resp.headers().get(header::ETAG).unwrap().clone();
+        let body = test::read_body(ServiceResponse::new(req.clone(), resp)).await;
+        assert_eq!(&body[..], &gzipped[..]);
+
+        // no acceptable variant, plain file is served
+        let req = TestRequest::default()
+            .header(header::ACCEPT_ENCODING, "br")
+            .to_http_request();
+        let resp = NamedFile::open_precompressed("tests/precompressed/app.js", &req)
+            .unwrap()
+            .respond_to(&req)
+            .await
+            .unwrap();
+        assert!(!resp.headers().contains_key(header::CONTENT_ENCODING));
+        assert_eq!(resp.headers().get(header::VARY).unwrap(), "accept-encoding");
+        assert_ne!(resp.headers().get(header::ETAG).unwrap(), &gz_etag);
+        let body = test::read_body(ServiceResponse::new(req.clone(), resp)).await;
+        assert_eq!(&body[..], &plain[..]);
+
+        let req = TestRequest::default()
+            .header(header::ACCEPT_ENCODING, "gzip")
+            .to_http_request();
+        let resp = PrecompressedFile::new("tests/precompressed/app.js")
+            .encodings(&[ContentEncoding::Identity])
+            .respond_to(&req)
+            .await
+            .unwrap();
+        assert!(!resp.headers().contains_key(header::CONTENT_ENCODING));
+
+        let req = TestRequest::default().to_http_request();
+        let resp = PrecompressedFile::new("tests/precompressed/app.js")
+            .respond_to(&req)
+            .await
+            .unwrap();
+        assert!(!resp.headers().contains_key(header::CONTENT_ENCODING));
+
+        let resp = PrecompressedFile::new("tests/precompressed/missing.js")
+            .respond_to(&req)
+            .await;
+        assert_eq!(
+            resp.err().unwrap().as_response_error().status_code(),
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_named_file_set_etag() {
+        let etag = header::EntityTag::strong("v1-abc".to_owned());
+
+        let file = NamedFile::open("Cargo.toml")
+            .unwrap()
+            .set_etag(etag.clone());
+        let req = TestRequest::default().to_http_request();
+        let resp = file.respond_to(&req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get(header::ETAG).unwrap(), "\"v1-abc\"");
+
+        let file = NamedFile::open("Cargo.toml")
+            .unwrap()
+            .set_etag(etag.clone());
+        let req = TestRequest::default()
+            .header(header::IF_NONE_MATCH, "\"v1-abc\"")
+            .to_http_request();
+        let resp = file.respond_to(&req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+
+        let file = NamedFile::open("Cargo.toml").unwrap().set_etag(etag);
+        let req = TestRequest::default()
+            .header(header::IF_MATCH, "\"v2-def\"")
+            .to_http_request();
+        let resp = file.respond_to(&req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::PRECONDITION_FAILED);
+
+        let file = NamedFile::open("Cargo.toml").unwrap().disable_etag();
+        let req = TestRequest::default().to_http_request();
+        let resp = file.respond_to(&req).await.unwrap();
+        assert!(!resp.headers().contains_key(header::ETAG));
+        assert!(resp.headers().contains_key(header::LAST_MODIFIED));
+    }
+
+    #[actix_rt::test]
+    async fn test_named_file_replaced_after_open() {
+        use actix_web::dev::{BodySize, MessageBody};
+
+        let path = std::env::temp_dir().join("actix-files-replaced.txt");
+        let tmp = std::env::temp_dir().join("actix-files-replaced.tmp");
+        fs::write(&path, b"original content").unwrap();
+        let file = NamedFile::open(&path).unwrap();
+        #[cfg(feature = "digest")]
+        let file = file.use_digest(true);
+
+        // atomically replace file at path with longer one
+        fs::write(&tmp, b"replacement content, longer").unwrap();
+        fs::rename(&tmp, &path).unwrap();
+
+        let req = TestRequest::default().to_http_request();
+        let resp = file.respond_to(&req).await.unwrap();
+        assert_eq!(resp.body().size(), BodySize::Sized64(16));
+        #[cfg(all(unix, feature = "digest"))]
+        {
+            let hash = ring::digest::digest(&ring::digest::SHA256, b"original content");
+            let expected = format!("sha-256={}", base64::encode(hash.as_ref()));
+            assert_eq!(resp.headers().get("digest").unwrap(), expected.as_str());
+        }
+        let body = test::read_body(ServiceResponse::new(req, resp)).await;
+        assert_eq!(body, Bytes::from_static(b"original content"));
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[actix_rt::test]
+    async fn test_named_file_etag_with() {
+        let open = |tenant: &'static str| {
+            NamedFile::open("Cargo.toml").unwrap().etag_with(|md| {
+                header::EntityTag::strong(format!("{}-{:x}", tenant, md.len()))
+            })
+        };
+
+        let req = TestRequest::default().to_http_request();
+        let resp = open("a").respond_to(&req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let etag = resp.headers().get(header::ETAG).unwrap().clone();
+        assert!(etag.to_str().unwrap().starts_with("\"a-"));
+
+        let req = TestRequest::default()
+            .header(header::IF_NONE_MATCH, etag.clone())
+            .to_http_request();
+        let resp = open("a").respond_to(&req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+
+        // same file, other tenant
+        let req = TestRequest::default()
+            .header(header::IF_NONE_MATCH, etag)
+            .to_http_request();
+        let resp = open("b").respond_to(&req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let etag = resp.headers().get(header::ETAG).unwrap().clone();
+        assert!(etag.to_str().unwrap().starts_with("\"b-"));
+
+        let req = TestRequest::default()
+            .header(header::IF_NONE_MATCH, etag)
+            .to_http_request();
+        let resp = open("b").respond_to(&req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+    }
+
+    #[actix_rt::test]
+    async fn test_named_file_open_or_404() {
+        let req = TestRequest::default().to_http_request();
+
+        let resp = NamedFile::open_or_404("Cargo.toml")
+            .respond_to(&req)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/x-toml; charset=utf-8"
+        );
+
+        let resp = NamedFile::open_or_404("missing.toml")
+            .respond_to(&req)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let resp = NamedFile::open_or_404("missing.toml")
+            .not_found(
+                HttpResponse::NotFound()
+                    .header("x-missing", "1")
+                    .body("Page is not found"),
+            )
+            .respond_to(&req)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        assert_eq!(resp.headers().get("x-missing").unwrap(), "1");
+        let bytes = test::read_body(ServiceResponse::new(req, resp)).await;
+        assert_eq!(bytes, Bytes::from_static(b"Page is not found"));
+    }
+
+    #[actix_rt::test]
+    async fn test_named_file_set_content_type() {
+        let mut file = NamedFile::open("Cargo.toml")
+            .unwrap()
+            .set_content_type(mime::TEXT_XML);
+        {
+            file.file();
+            let _f: &File = &file;
+        }
+        {
+            let _f: &mut File = &mut file;
+        }
+
+        let req = TestRequest::default().to_http_request();
+        let resp = file.respond_to(&req).await.unwrap();
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/xml; charset=utf-8"
+        );
+        assert_eq!(
+            resp.headers().get(header::CONTENT_DISPOSITION).unwrap(),
+            "inline; filename=\"Cargo.toml\""
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_named_file_image() {
+        let mut file = NamedFile::open("tests/test.png").unwrap();
+        {
+            file.file();
+            let _f: &File = &file;
+        }
+        {
+            let _f: &mut File = &mut file;
+        }
+
+        let req = TestRequest::default().to_http_request();
+        let resp = file.respond_to(&req).await.unwrap();
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "image/png"
         );
         assert_eq!(
             resp.headers().get(header::CONTENT_DISPOSITION).unwrap(),
@@ -887,7 +1915,7 @@ mod tests {
         let resp = file.respond_to(&req).await.unwrap();
         assert_eq!(
             resp.headers().get(header::CONTENT_TYPE).unwrap(),
-            "text/x-toml"
+            "text/x-toml; charset=utf-8"
         );
         assert_eq!(
             resp.headers().get(header::CONTENT_DISPOSITION).unwrap(),
@@ -925,6 +1953,46 @@ mod tests {
         assert_eq!(content_disposition, "attachment; filename=\"Cargo.toml\"");
     }
 
+    #[actix_rt::test]
+    async fn test_disposition_filename() {
+        let mut srv = test::init_service(App::new().service(
+            Files::new("/", ".").disposition_filename(|path| {
+                if path.ends_with("tests/test.png") {
+                    Some("фото.png".to_owned())
+                } else {
+                    None
+                }
+            }),
+        ))
+        .await;
+
+        let request = TestRequest::get().uri("/tests/test.png").to_request();
+        let response = test::call_service(&mut srv, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_DISPOSITION).unwrap(),
+            "inline; filename=\"фото.png\"; filename*=UTF-8''%D1%84%D0%BE%D1%82%D0%BE.png"
+        );
+
+        // falls back to file name
+        let request = TestRequest::get().uri("/Cargo.toml").to_request();
+        let response = test::call_service(&mut srv, request).await;
+        assert_eq!(
+            response.headers().get(header::CONTENT_DISPOSITION).unwrap(),
+            "inline; filename=\"Cargo.toml\""
+        );
+
+        let file = NamedFile::open("Cargo.toml")
+            .unwrap()
+            .set_disposition_filename("manifest.toml");
+        let req = TestRequest::default().to_http_request();
+        let resp = file.respond_to(&req).await.unwrap();
+        assert_eq!(
+            resp.headers().get(header::CONTENT_DISPOSITION).unwrap(),
+            "inline; filename=\"manifest.toml\""
+        );
+    }
+
     #[actix_rt::test]
     async fn test_named_file_ranges_status_code() {
         let mut srv = test::init_service(
@@ -990,6 +2058,142 @@ mod tests {
         assert_eq!(contentrange, "bytes */100");
     }
 
+    #[actix_rt::test]
+    async fn test_named_file_multiple_ranges() {
+        let data = fs::read("tests/test.binary").unwrap();
+        let size = data.len();
+
+        let req = TestRequest::default()
+            .header(header::RANGE, "bytes=10-14,0-4,3-6")
+            .to_http_request();
+        let meta = NamedFile::open("tests/test.binary")
+            .unwrap()
+            .metadata_response(&req);
+        let resp = NamedFile::open("tests/test.binary")
+            .unwrap()
+            .respond_to(&req)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+        assert!(!resp.headers().contains_key(header::CONTENT_RANGE));
+        let content_type = resp.headers().get(header::CONTENT_TYPE).unwrap();
+        let content_type = content_type.to_str().unwrap();
+        let prefix = "multipart/byteranges; boundary=";
+        assert!(content_type.starts_with(prefix));
+        let boundary = content_type[prefix.len()..].to_owned();
+
+        let mut expected = Vec::new();
+        expected.extend_from_slice(
+            format!(
+                "--{}\r\nContent-Type: application/octet-stream\r\n\
+                 Content-Range: bytes 0-6/{}\r\n\r\n",
+                boundary, size
+            )
+            .as_bytes(),
+        );
+        expected.extend_from_slice(&data[0..7]);
+        expected.extend_from_slice(
+            format!(
+                "\r\n--{}\r\nContent-Type: application/octet-stream\r\n\
+                 Content-Range: bytes 10-14/{}\r\n\r\n",
+                boundary, size
+            )
+            .as_bytes(),
+        );
+        expected.extend_from_slice(&data[10..15]);
+        expected.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+
+        let body = test::read_body(ServiceResponse::new(req.clone(), resp)).await;
+        assert_eq!(&body[..], &expected[..]);
+        assert_eq!(
+            meta.headers().get(header::CONTENT_LENGTH).unwrap(),
+            &expected.len().to_string()
+        );
+
+        // ranges coalesced into a single one
+        let req = TestRequest::default()
+            .header(header::RANGE, "bytes=5-9,0-4")
+            .to_http_request();
+        let resp = NamedFile::open("tests/test.binary")
+            .unwrap()
+            .respond_to(&req)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_RANGE).unwrap(),
+            &format!("bytes 0-9/{}", size)
+        );
+        let body = test::read_body(ServiceResponse::new(req.clone(), resp)).await;
+        assert_eq!(&body[..], &data[0..10]);
+
+        // malformed range
+        let req = TestRequest::default()
+            .header(header::RANGE, "bytes=0-4,x-y")
+            .to_http_request();
+        let resp = NamedFile::open("tests/test.binary")
+            .unwrap()
+            .respond_to(&req)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+    }
+
+    #[actix_rt::test]
+    async fn test_named_file_if_range() {
+        let req = TestRequest::default().to_http_request();
+        let resp = NamedFile::open("tests/test.binary")
+            .unwrap()
+            .respond_to(&req)
+            .await
+            .unwrap();
+        let etag = resp.headers().get(header::ETAG).unwrap().clone();
+        let last_modified = resp.headers().get(header::LAST_MODIFIED).unwrap().clone();
+
+        let status = |if_range: header::HeaderValue| {
+            let req = TestRequest::default()
+                .header(header::RANGE, "bytes=0-4")
+                .header(header::IF_RANGE, if_range)
+                .to_http_request();
+            let resp = NamedFile::open("tests/test.binary")
+                .unwrap()
+                .respond_to(&req);
+            async { resp.await.unwrap().status() }
+        };
+
+        assert_eq!(status(etag.clone()).await, StatusCode::PARTIAL_CONTENT);
+        assert_eq!(status(last_modified).await, StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            status(header::HeaderValue::from_static("\"other\"")).await,
+            StatusCode::OK
+        );
+        let weak = format!("W/{}", etag.to_str().unwrap());
+        assert_eq!(
+            status(header::HeaderValue::from_str(&weak).unwrap()).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(header::HeaderValue::from_static(
+                "Sun, 06 Nov 1994 08:49:37 GMT"
+            ))
+            .await,
+            StatusCode::OK
+        );
+
+        let req = TestRequest::default()
+            .header(header::RANGE, "bytes=0-4")
+            .header(header::IF_RANGE, "\"other\"")
+            .to_http_request();
+        let resp = NamedFile::open("tests/test.binary")
+            .unwrap()
+            .respond_to(&req)
+            .await
+            .unwrap();
+        assert!(!resp.headers().contains_key(header::CONTENT_RANGE));
+        let body = test::read_body(ServiceResponse::new(req.clone(), resp)).await;
+        assert_eq!(&body[..], &fs::read("tests/test.binary").unwrap()[..]);
+    }
+
     #[actix_rt::test]
     async fn test_named_file_content_length_headers() {
         // use actix_web::body::{MessageBody, ResponseBody};
@@ -1083,6 +2287,126 @@ mod tests {
         // assert_eq!(contentlength, "100");
     }
 
+    #[actix_rt::test]
+    async fn test_named_file_metadata_response() {
+        let srv = test::start(|| App::new().service(Files::new("test", ".")));
+
+        let response = srv.get("/test/Cargo.toml").send().await.unwrap();
+        let file = NamedFile::open("Cargo.toml").unwrap();
+        let req = TestRequest::default().to_http_request();
+        let meta = file.metadata_response(&req);
+
+        assert_eq!(meta.status(), response.status());
+        for name in &[
+            header::ETAG,
+            header::LAST_MODIFIED,
+            header::CONTENT_LENGTH,
+            header::CONTENT_TYPE,
+            header::CONTENT_DISPOSITION,
+            header::ACCEPT_RANGES,
+        ] {
+            assert_eq!(meta.headers().get(name), response.headers().get(name));
+        }
+
+        // conditional request
+        let etag = meta.headers().get(header::ETAG).unwrap().clone();
+        let req = TestRequest::default()
+            .header(header::IF_NONE_MATCH, etag)
+            .to_http_request();
+        let meta = file.metadata_response(&req);
+        assert_eq!(meta.status(), StatusCode::NOT_MODIFIED);
+
+        // range request
+        let req = TestRequest::default()
+            .header(header::RANGE, "bytes=10-20")
+            .to_http_request();
+        let meta = file.metadata_response(&req);
+        assert_eq!(meta.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(meta.headers().get(header::CONTENT_LENGTH).unwrap(), "11");
+    }
+
+    #[actix_rt::test]
+    async fn test_head_range_content_length() {
+        let srv = test::start(|| App::new().service(Files::new("test", ".")));
+
+        let response = srv
+            .head("/test/tests/test.binary")
+            .header(header::RANGE, "bytes=10-20")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            response.headers().get(header::CONTENT_LENGTH).unwrap(),
+            "11"
+        );
+        assert_eq!(
+            response.headers().get(header::CONTENT_RANGE).unwrap(),
+            "bytes 10-20/100"
+        );
+
+        let response = srv
+            .get("/test/tests/test.binary")
+            .header(header::RANGE, "bytes=10-20")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            response.headers().get(header::CONTENT_LENGTH).unwrap(),
+            "11"
+        );
+
+        let response = srv.head("/test/tests/test.binary").send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_LENGTH).unwrap(),
+            "100"
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_head_range_matches_get() {
+        let srv = test::start(|| App::new().service(Files::new("test", ".")));
+
+        for range in &["bytes=10-20", "bytes=-5", "bytes=90-", "bytes=200-300"] {
+            let mut head = srv
+                .head("/test/tests/test.binary")
+                .header(header::RANGE, *range)
+                .send()
+                .await
+                .unwrap();
+            let mut get = srv
+                .get("/test/tests/test.binary")
+                .header(header::RANGE, *range)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(head.status(), get.status(), "range {}", range);
+            for name in &[
+                header::CONTENT_LENGTH,
+                header::CONTENT_RANGE,
+                header::CONTENT_TYPE,
+                header::ACCEPT_RANGES,
+                header::ETAG,
+                header::LAST_MODIFIED,
+            ] {
+                assert_eq!(
+                    head.headers().get(name),
+                    get.headers().get(name),
+                    "range {}, header {}",
+                    range,
+                    name
+                );
+            }
+            assert!(head.body().await.unwrap().is_empty());
+            let body = get.body().await.unwrap();
+            if get.status() == StatusCode::PARTIAL_CONTENT {
+                let length = head.headers().get(header::CONTENT_LENGTH).unwrap();
+                assert_eq!(length.to_str().unwrap(), body.len().to_string());
+            }
+        }
+    }
+
     #[actix_rt::test]
     async fn test_static_files_with_spaces() {
         let mut srv = test::init_service(
@@ -1188,6 +2512,123 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "compress")]
+    #[actix_rt::test]
+    async fn test_named_file_auto_encode() {
+        use std::io::Read;
+
+        let mut srv = test::init_service(App::new().wrap(Compress::default()).service(
+            web::resource("/").to(|| async {
+                NamedFile::open("Cargo.toml")
+                    .unwrap()
+                    .auto_encode(header::ContentEncoding::Gzip)
+            }),
+        ))
+        .await;
+
+        let request = TestRequest::get()
+            .uri("/")
+            .header(header::ACCEPT_ENCODING, "deflate, gzip;q=0.5")
+            .header(header::RANGE, "bytes=10-20")
+            .to_request();
+        let res = test::call_service(&mut srv, request).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get(header::CONTENT_ENCODING).unwrap(), "gzip");
+        assert_eq!(res.headers().get(header::VARY).unwrap(), "accept-encoding");
+        assert!(!res.headers().contains_key(header::CONTENT_LENGTH));
+        assert!(!res.headers().contains_key(header::ACCEPT_RANGES));
+        assert!(!res.headers().contains_key(header::CONTENT_RANGE));
+        let etag = res.headers().get(header::ETAG).unwrap().to_str().unwrap();
+        assert!(etag.ends_with("-gzip\""));
+
+        let body = test::read_body(res).await;
+        let mut data = Vec::new();
+        flate2::read::GzDecoder::new(&body[..])
+            .read_to_end(&mut data)
+            .unwrap();
+        assert_eq!(data, fs::read("Cargo.toml").unwrap());
+
+        let request = TestRequest::get()
+            .uri("/")
+            .header(header::ACCEPT_ENCODING, "gzip;q=0, br")
+            .to_request();
+        let res = test::call_service(&mut srv, request).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get(header::CONTENT_ENCODING).unwrap(), "br");
+        assert!(!res
+            .headers()
+            .get(header::ETAG)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .ends_with("-gzip\""));
+
+        let file = NamedFile::open("Cargo.toml")
+            .unwrap()
+            .auto_encode(header::ContentEncoding::Deflate);
+        let req = TestRequest::default()
+            .header(header::ACCEPT_ENCODING, "*")
+            .header(header::RANGE, "bytes=10-20")
+            .to_http_request();
+        let resp = file.respond_to(&req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_ENCODING).unwrap(),
+            "deflate"
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_named_file_offload() {
+        let req = TestRequest::default().to_http_request();
+
+        let resp = NamedFile::open("Cargo.toml")
+            .unwrap()
+            .as_offload("/internal/my file.toml", OffloadHeader::XAccelRedirect)
+            .respond_to(&req)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get("x-accel-redirect").unwrap(),
+            "/internal/my file.toml"
+        );
+        assert!(!resp.headers().contains_key("x-sendfile"));
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/x-toml; charset=utf-8"
+        );
+        assert_eq!(
+            resp.headers().get(header::CONTENT_DISPOSITION).unwrap(),
+            "inline; filename=\"Cargo.toml\""
+        );
+        let bytes = test::read_body(ServiceResponse::new(req.clone(), resp)).await;
+        assert!(bytes.is_empty());
+
+        let resp = NamedFile::open("Cargo.toml")
+            .unwrap()
+            .as_offload("/srv/files/Cargo.toml", OffloadHeader::XSendfile)
+            .respond_to(&req)
+            .await
+            .unwrap();
+        assert_eq!(
+            resp.headers().get("x-sendfile").unwrap(),
+            "/srv/files/Cargo.toml"
+        );
+        assert!(!resp.headers().contains_key("x-accel-redirect"));
+
+        let resp = NamedFile::open("Cargo.toml")
+            .unwrap()
+            .as_offload("/internal/\n", OffloadHeader::XAccelRedirect)
+            .respond_to(&req)
+            .await
+            .unwrap();
+        assert_eq!(
+            resp.headers().get("x-accel-redirect").unwrap(),
+            "/internal/%0A"
+        );
+    }
+
     #[actix_rt::test]
     async fn test_named_file_allowed_method() {
         let req = TestRequest::default().method(Method::GET).to_http_request();
@@ -1426,4 +2867,71 @@ mod tests {
             PathBuf::from_iter(vec!["seg2"])
         );
     }
+
+    #[actix_rt::test]
+    async fn test_directory_listing_responder() {
+        let base = std::env::temp_dir().join("actix-files-listing");
+        let _ = fs::remove_dir_all(&base);
+        let root = base.join("root");
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("a.txt"), "hello").unwrap();
+        fs::write(root.join("<b>&x.txt"), "").unwrap();
+        fs::write(root.join(".hidden"), "").unwrap();
+        fs::write(base.join("secret.txt"), "secret").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(base.join("secret.txt"), root.join("link.txt"))
+            .unwrap();
+
+        async fn listing(path: web::Path<PathBuf>) -> DirectoryListing {
+            let root = std::env::temp_dir().join("actix-files-listing/root");
+            DirectoryListing::new(root, path.into_inner())
+        }
+        let mut srv = test::init_service(
+            App::new().route("/files/{path:.*}", web::get().to(listing)),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/files/").to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/html; charset=utf-8"
+        );
+        let body = test::read_body(resp).await;
+        let body = std::str::from_utf8(&body).unwrap();
+        assert!(body.contains("<title>Index of &#x2f;files&#x2f;</title>"));
+        assert!(body.contains(
+            "<a href=\"&#x2f;files&#x2f;%3Cb%3E%26x.txt\">&lt;b&gt;&amp;x.txt</a>"
+        ));
+        assert!(!body.contains("<b>"));
+        assert!(body.contains("a.txt</a></td><td>5</td>"));
+        assert!(body.contains("sub/</a></td><td>-</td>"));
+        assert!(body.find("sub/").unwrap() < body.find("a.txt").unwrap());
+        assert!(!body.contains(".hidden"));
+        assert!(!body.contains("link.txt"));
+
+        let req = TestRequest::with_uri("/files/a.txt").to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(resp.headers().contains_key(header::ETAG));
+        let body = test::read_body(resp).await;
+        assert_eq!(&body[..], b"hello");
+
+        let req = TestRequest::with_uri("/files/link.txt").to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let req = TestRequest::default().to_http_request();
+        let resp = DirectoryListing::new(&root, "sub/../../secret.txt")
+            .respond_to(&req)
+            .await;
+        assert_eq!(resp.unwrap_err().as_response_error().status_code(), 404);
+
+        let req = TestRequest::with_uri("/files/missing").to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let _ = fs::remove_dir_all(&base);
+    }
 }
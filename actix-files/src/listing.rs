@@ -0,0 +1,206 @@
+use std::fmt::Write;
+use std::fs::Metadata;
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+use std::{fs, io};
+
+use actix_web::error::{BlockingError, Error};
+use actix_web::http::header::{self, HttpDate};
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use futures::future::{FutureExt, LocalBoxFuture};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use v_htmlescape::escape as escape_html_entity;
+
+use crate::named::NamedFile;
+
+/// Characters percent-encoded in entry links
+const ENTRY_URL: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// Responder serving a file or an HTML index of a directory.
+///
+/// `path` is resolved relative to `root`. Files, and directories containing
+/// `index.html`, are served with `NamedFile`. For other directories a listing
+/// of entries with their sizes and modification times is generated.
+/// Hidden entries (names starting with `.`) are not listed.
+///
+/// Filesystem is accessed on the blocking thread pool. Paths which resolve
+/// outside of `root`, either with `..` segments or through symlinks, are
+/// refused with `404 Not Found`.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::path::PathBuf;
+/// use actix_files::DirectoryListing;
+/// use actix_web::{web, App};
+///
+/// async fn index(path: web::Path<PathBuf>) -> DirectoryListing {
+///     DirectoryListing::new("./static", path.into_inner())
+/// }
+///
+/// fn main() {
+///     let app = App::new().route("/static/{path:.*}", web::get().to(index));
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DirectoryListing {
+    root: PathBuf,
+    path: PathBuf,
+}
+
+/// Entry of generated listing
+struct ListingEntry {
+    name: String,
+    is_dir: bool,
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+enum Resolved {
+    File(NamedFile),
+    Listing(Vec<ListingEntry>),
+}
+
+impl DirectoryListing {
+    /// Create responder for `path` under `root` directory
+    pub fn new<R: Into<PathBuf>, P: Into<PathBuf>>(root: R, path: P) -> Self {
+        DirectoryListing {
+            root: root.into(),
+            path: path.into(),
+        }
+    }
+
+    fn resolve(self) -> io::Result<Resolved> {
+        let root = self.root.canonicalize()?;
+        let mut target = root.clone();
+        for component in self.path.components() {
+            match component {
+                Component::Normal(segment) => target.push(segment),
+                Component::CurDir => (),
+                _ => return Err(not_found()),
+            }
+        }
+        let target = target.canonicalize()?;
+        if !target.starts_with(&root) {
+            return Err(not_found());
+        }
+
+        if target.is_file() {
+            return NamedFile::open(target).map(Resolved::File);
+        }
+        let index = target.join("index.html");
+        if index.is_file() {
+            return NamedFile::open(index).map(Resolved::File);
+        }
+
+        let mut entries = Vec::new();
+        for entry in target.read_dir()? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.starts_with('.') {
+                continue;
+            }
+            let meta = match entry_metadata(&entry.path(), &root) {
+                Some(meta) => meta,
+                None => continue,
+            };
+            entries.push(ListingEntry {
+                name,
+                is_dir: meta.is_dir(),
+                size: meta.len(),
+                modified: meta.modified().ok(),
+            });
+        }
+        entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then(a.name.cmp(&b.name)));
+        Ok(Resolved::Listing(entries))
+    }
+}
+
+/// Metadata of directory entry, `None` for symlinks pointing outside of `root`
+fn entry_metadata(path: &Path, root: &Path) -> Option<Metadata> {
+    let meta = fs::symlink_metadata(path).ok()?;
+    if !meta.file_type().is_symlink() {
+        return Some(meta);
+    }
+    match path.canonicalize() {
+        Ok(ref target) if target.starts_with(root) => fs::metadata(target).ok(),
+        _ => None,
+    }
+}
+
+fn not_found() -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, "Path is outside of root")
+}
+
+fn render(req: &HttpRequest, entries: &[ListingEntry]) -> HttpResponse {
+    let index_of = format!("Index of {}", escape_html_entity(req.path()));
+    let base = req.path().trim_end_matches('/');
+    let mut body = String::new();
+
+    for entry in entries {
+        let slash = if entry.is_dir { "/" } else { "" };
+        let url = format!(
+            "{}/{}{}",
+            base,
+            utf8_percent_encode(&entry.name, ENTRY_URL),
+            slash
+        );
+        let size = if entry.is_dir {
+            "-".to_owned()
+        } else {
+            entry.size.to_string()
+        };
+        let modified = entry
+            .modified
+            .map(|time| HttpDate::from(time).to_string())
+            .unwrap_or_default();
+        let _ = write!(
+            body,
+            "<tr><td><a href=\"{}\">{}{}</a></td><td>{}</td><td>{}</td></tr>",
+            escape_html_entity(&url),
+            escape_html_entity(&entry.name),
+            slash,
+            size,
+            modified,
+        );
+    }
+
+    let html = format!(
+        "<html>\
+         <head><title>{}</title></head>\
+         <body><h1>{}</h1>\
+         <table>\
+         <tr><th>Name</th><th>Size</th><th>Modified</th></tr>\
+         {}\
+         </table></body>\n</html>",
+        index_of, index_of, body
+    );
+    HttpResponse::Ok()
+        .set_header(header::CONTENT_TYPE, "text/html; charset=utf-8")
+        .body(html)
+}
+
+impl Responder for DirectoryListing {
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<HttpResponse, Error>>;
+
+    fn respond_to(self, req: &HttpRequest) -> Self::Future {
+        let req = req.clone();
+        async move {
+            match web::block(move || self.resolve()).await {
+                Ok(Resolved::File(file)) => file.into_response(&req),
+                Ok(Resolved::Listing(entries)) => Ok(render(&req, &entries)),
+                Err(BlockingError::Error(err)) => Err(err.into()),
+                Err(BlockingError::Canceled) => {
+                    Err(io::Error::new(io::ErrorKind::Other, "Thread pool is gone")
+                        .into())
+                }
+            }
+        }
+        .boxed_local()
+    }
+}
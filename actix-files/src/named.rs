@@ -1,54 +1,77 @@
+use std::cmp;
 use std::fs::{File, Metadata};
 use std::io;
 use std::ops::{Deref, DerefMut};
 use std::path::{Path, PathBuf};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 #[cfg(unix)]
 use std::os::unix::fs::MetadataExt;
 
 use bitflags::bitflags;
+use bytes::Bytes;
 use mime;
 use mime_guess::from_path;
 
 use actix_http::body::SizedStream;
-use actix_web::dev::BodyEncoding;
+use actix_web::dev::{BodyEncoding, HttpResponseBuilder};
+use actix_web::error::{BlockingError, ErrorInternalServerError};
 use actix_web::http::header::{
     self, Charset, ContentDisposition, DispositionParam, DispositionType, ExtendedValue,
 };
 use actix_web::http::{ContentEncoding, StatusCode};
-use actix_web::{Error, HttpMessage, HttpRequest, HttpResponse, Responder};
+use actix_web::{web, Error, HttpMessage, HttpRequest, HttpResponse, Responder};
 use futures::future::{ready, Ready};
+use percent_encoding::{utf8_percent_encode, CONTROLS};
 
+use crate::cache::OpenCache;
+#[cfg(feature = "digest")]
+use crate::digest::file_digest;
 use crate::range::HttpRange;
-use crate::ChunkedReadFile;
+#[cfg(feature = "compress")]
+use crate::EncodedReadFile;
+use crate::{ChunkedReadFile, ChunkedReadRanges};
 
 bitflags! {
     pub(crate) struct Flags: u8 {
         const ETAG = 0b0000_0001;
         const LAST_MD = 0b0000_0010;
         const CONTENT_DISPOSITION = 0b0000_0100;
+        const REVALIDATE = 0b0000_1000;
+        const VARY_ENCODING = 0b0001_0000;
+        const DIGEST = 0b0010_0000;
+        const WEAK_ETAG = 0b0100_0000;
     }
 }
 
 impl Default for Flags {
     fn default() -> Self {
-        Flags::all()
+        Flags::ETAG | Flags::LAST_MD | Flags::CONTENT_DISPOSITION
     }
 }
 
+/// Default size of chunks the file is read by, 64 KiB.
+const DEFAULT_CHUNK_SIZE: usize = 65_536;
+
 /// A file with an associated name.
 #[derive(Debug)]
 pub struct NamedFile {
     path: PathBuf,
     file: File,
-    modified: Option<SystemTime>,
+    pub(crate) modified: Option<SystemTime>,
     pub(crate) md: Metadata,
     pub(crate) flags: Flags,
     pub(crate) status_code: StatusCode,
     pub(crate) content_type: mime::Mime,
     pub(crate) content_disposition: header::ContentDisposition,
     pub(crate) encoding: Option<ContentEncoding>,
+    pub(crate) auto_encoding: Option<ContentEncoding>,
+    precompressed: Option<ContentEncoding>,
+    pub(crate) charset: Charset,
+    custom_etag: Option<header::EntityTag>,
+    map_body: Option<fn(Bytes) -> Bytes>,
+    content_length: Option<u64>,
+    chunk_size: usize,
 }
 
 impl NamedFile {
@@ -57,6 +80,10 @@ impl NamedFile {
     /// The given `path` need not exist and is only used to determine the `ContentType` and
     /// `ContentDisposition` headers.
     ///
+    /// Metadata is read from the opened `file`, never by `path`, so length,
+    /// `Last-Modified`, `ETag` and content of the response all describe the
+    /// same file, even if file at `path` is replaced or renamed afterwards.
+    ///
     /// # Examples
     ///
     /// ```rust
@@ -74,6 +101,15 @@ impl NamedFile {
     /// }
     /// ```
     pub fn from_file<P: AsRef<Path>>(file: File, path: P) -> io::Result<NamedFile> {
+        let md = file.metadata()?;
+        Self::from_file_metadata(file, md, path)
+    }
+
+    fn from_file_metadata<P: AsRef<Path>>(
+        file: File,
+        md: Metadata,
+        path: P,
+    ) -> io::Result<NamedFile> {
         let path = path.as_ref().to_path_buf();
 
         // Get the name of the file and use it to construct default Content-Type
@@ -94,23 +130,13 @@ impl NamedFile {
                 mime::IMAGE | mime::TEXT | mime::VIDEO => DispositionType::Inline,
                 _ => DispositionType::Attachment,
             };
-            let mut parameters =
-                vec![DispositionParam::Filename(String::from(filename.as_ref()))];
-            if !filename.is_ascii() {
-                parameters.push(DispositionParam::FilenameExt(ExtendedValue {
-                    charset: Charset::Ext(String::from("UTF-8")),
-                    language_tag: None,
-                    value: filename.into_owned().into_bytes(),
-                }))
-            }
             let cd = ContentDisposition {
                 disposition: disposition_type,
-                parameters: parameters,
+                parameters: filename_params(filename.into_owned()),
             };
             (ct, cd)
         };
 
-        let md = file.metadata()?;
         let modified = md.modified().ok();
         let encoding = None;
         Ok(NamedFile {
@@ -121,8 +147,15 @@ impl NamedFile {
             md,
             modified,
             encoding,
+            auto_encoding: None,
+            precompressed: None,
             status_code: StatusCode::OK,
             flags: Flags::default(),
+            charset: Charset::Ext(String::from("utf-8")),
+            custom_etag: None,
+            map_body: None,
+            content_length: None,
+            chunk_size: DEFAULT_CHUNK_SIZE,
         })
     }
 
@@ -135,10 +168,95 @@ impl NamedFile {
     ///
     /// let file = NamedFile::open("foo.txt");
     /// ```
+    ///
+    /// Metadata is read from the opened file, see `from_file()`.
     pub fn open<P: AsRef<Path>>(path: P) -> io::Result<NamedFile> {
         Self::from_file(File::open(&path)?, path)
     }
 
+    /// Attempts to open a file in read-only mode on a thread pool.
+    ///
+    /// Opening file and reading its metadata is performed on a thread pool,
+    /// so slow storage does not block the event loop. Resulting `NamedFile`
+    /// is the same as one returned by `open()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use actix_files::NamedFile;
+    ///
+    /// async fn index() -> std::io::Result<NamedFile> {
+    ///     Ok(NamedFile::open_async("foo.txt").await?)
+    /// }
+    /// ```
+    pub async fn open_async<P: AsRef<Path>>(path: P) -> io::Result<NamedFile> {
+        let path = path.as_ref().to_path_buf();
+        match web::block(move || Self::open(path)).await {
+            Ok(file) => Ok(file),
+            Err(BlockingError::Error(err)) => Err(err),
+            Err(BlockingError::Canceled) => {
+                Err(io::Error::new(io::ErrorKind::Other, "Thread pool is gone"))
+            }
+        }
+    }
+
+    /// Attempts to open a file in read-only mode, reusing file handle kept
+    /// by `cache`.
+    ///
+    /// File is opened only if it is not cached yet or file at `path` has
+    /// been modified since it was cached, see `OpenCache` for details.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use actix_files::{NamedFile, OpenCache};
+    ///
+    /// let cache = OpenCache::new(16);
+    /// let file = NamedFile::open_cached("foo.txt", &cache);
+    /// ```
+    pub fn open_cached<P: AsRef<Path>>(
+        path: P,
+        cache: &OpenCache,
+    ) -> io::Result<NamedFile> {
+        let (file, md) = cache.open(path.as_ref())?;
+        Self::from_file_metadata(file, md, path)
+    }
+
+    /// Attempts to open a file, responds with `404 Not Found` if it does
+    /// not exist.
+    ///
+    /// Response for missing file can be customized with
+    /// `NamedFileOr404::not_found()`. Other io errors are returned as is.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use actix_files::{NamedFile, NamedFileOr404};
+    /// use actix_web::HttpResponse;
+    ///
+    /// async fn index() -> NamedFileOr404 {
+    ///     NamedFile::open_or_404("index.html")
+    ///         .not_found(HttpResponse::NotFound().body("Page is not found"))
+    /// }
+    /// ```
+    pub fn open_or_404<P: AsRef<Path>>(path: P) -> NamedFileOr404 {
+        NamedFileOr404 {
+            file: Self::open(path),
+            not_found: None,
+        }
+    }
+
+    /// Attempts to open pre-compressed variant of the file accepted by the
+    /// client, i.e. `app.js.br` or `app.js.gz` for `app.js`.
+    ///
+    /// Shortcut for `PrecompressedFile::new(path).open(req)`.
+    pub fn open_precompressed<P: AsRef<Path>>(
+        path: P,
+        req: &HttpRequest,
+    ) -> io::Result<NamedFile> {
+        PrecompressedFile::new(path).open(req)
+    }
+
     /// Returns reference to the underlying `File` object.
     #[inline]
     pub fn file(&self) -> &File {
@@ -196,6 +314,17 @@ impl NamedFile {
         self
     }
 
+    /// Set file name of `Content-Disposition` header.
+    ///
+    /// By default name of the file is used. Non-ASCII names are encoded
+    /// as `filename*` parameter in addition to plain `filename`.
+    #[inline]
+    pub fn set_disposition_filename<S: Into<String>>(mut self, name: S) -> Self {
+        self.content_disposition.parameters = filename_params(name.into());
+        self.flags.insert(Flags::CONTENT_DISPOSITION);
+        self
+    }
+
     /// Disable `Content-Disposition` header.
     ///
     /// By default Content-Disposition` header is enabled.
@@ -205,6 +334,15 @@ impl NamedFile {
         self
     }
 
+    /// Set charset appended to text content types without charset.
+    ///
+    /// By default `utf-8` charset is used.
+    #[inline]
+    pub fn default_charset(mut self, charset: Charset) -> Self {
+        self.charset = charset;
+        self
+    }
+
     /// Set content encoding for serving this file
     #[inline]
     pub fn set_content_encoding(mut self, enc: ContentEncoding) -> Self {
@@ -212,6 +350,25 @@ impl NamedFile {
         self
     }
 
+    /// Compress file on the fly while serving it.
+    ///
+    /// Only `gzip` and `deflate` encodings are supported. File is compressed
+    /// if client accepts the encoding via `Accept-Encoding` header, and no
+    /// pre-compressed encoding is set with `set_content_encoding()`.
+    ///
+    /// Compressed response has no `Content-Length` header and its `ETag` is
+    /// suffixed with the encoding name. Range requests are not supported
+    /// for compressed responses, complete file is sent instead.
+    #[cfg(feature = "compress")]
+    #[inline]
+    pub fn auto_encode(mut self, enc: ContentEncoding) -> Self {
+        self.auto_encoding = match enc {
+            ContentEncoding::Gzip | ContentEncoding::Deflate => Some(enc),
+            _ => None,
+        };
+        self
+    }
+
     #[inline]
     ///Specifies whether to use ETag or not.
     ///
@@ -221,6 +378,152 @@ impl NamedFile {
         self
     }
 
+    #[inline]
+    /// Specifies whether generated ETag is weak, i.e. `W/"..."`.
+    ///
+    /// Weak tags are appropriate when content is transformed downstream,
+    /// e.g. compressed by a proxy. `If-None-Match` uses weak comparison, so
+    /// `304 Not Modified` responses are not affected. `If-Match` and `If-Range`
+    /// require strong comparison, which a weak tag never satisfies: `If-Match`
+    /// requests fail with `412 Precondition Failed` unless `*` is listed, and
+    /// full content is sent instead of requested ranges. Tags set with
+    /// `set_etag()` or `etag_with()` are used as is.
+    ///
+    /// Default is false.
+    pub fn use_weak_etag(mut self, value: bool) -> Self {
+        self.flags.set(Flags::WEAK_ETAG, value);
+        self
+    }
+
+    /// Transform file content before sending it.
+    ///
+    /// Function is applied to each chunk of the file separately, so the
+    /// transformation must not depend on chunk boundaries, i.e. replacing
+    /// bytes one by one. Since length of the body may change, response has
+    /// no `Content-Length` header and range requests are not supported,
+    /// complete file is sent instead. Conditional requests are still
+    /// evaluated with validators of the original file, use `set_etag()`
+    /// if transformed content changes independently of the file.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use actix_files::NamedFile;
+    /// use bytes::Bytes;
+    ///
+    /// async fn index() -> std::io::Result<NamedFile> {
+    ///     Ok(NamedFile::open("foo.txt")?
+    ///         .map_body(|chunk| Bytes::from(chunk.to_ascii_uppercase())))
+    /// }
+    /// ```
+    #[inline]
+    pub fn map_body(mut self, f: fn(Bytes) -> Bytes) -> Self {
+        self.map_body = Some(f);
+        self
+    }
+
+    /// Set length of the body transformed with `map_body()`.
+    ///
+    /// By default transformed body is sent using chunked transfer encoding.
+    /// If length of transformed content is known ahead of time, it is sent in
+    /// `Content-Length` header instead. Length must match the transformed
+    /// content exactly. Has no effect if body is not transformed or is
+    /// compressed on the fly.
+    #[inline]
+    pub fn set_content_length(mut self, length: u64) -> Self {
+        self.content_length = Some(length);
+        self
+    }
+
+    /// Set size of chunks the file is read and sent by.
+    ///
+    /// Larger chunks reduce number of reads for big files, smaller ones
+    /// reduce memory usage per response. Default is 64 KiB.
+    ///
+    /// Returns `InvalidInput` error if `size` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use actix_files::NamedFile;
+    ///
+    /// async fn index() -> std::io::Result<NamedFile> {
+    ///     NamedFile::open("video.mp4")?.set_chunk_size(1024 * 1024)
+    /// }
+    /// ```
+    pub fn set_chunk_size(mut self, size: usize) -> io::Result<Self> {
+        if size == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Chunk size must be greater than zero",
+            ));
+        }
+        self.chunk_size = size;
+        Ok(self)
+    }
+
+    /// Set `ETag` of the file, instead of one computed from file metadata.
+    ///
+    /// Useful if inode and modification time of the file differ between
+    /// replicas, i.e. on network mounts. Explicit etag is used in
+    /// `If-Match` and `If-None-Match` checks as well.
+    #[inline]
+    pub fn set_etag(mut self, etag: header::EntityTag) -> Self {
+        self.custom_etag = Some(etag);
+        self.flags.insert(Flags::ETAG);
+        self
+    }
+
+    /// Set `ETag` of the file computed by `f` from file metadata.
+    ///
+    /// Allows to build etag per request, i.e. to scope validators to a
+    /// tenant. Etag is used in `If-Match` and `If-None-Match` checks and
+    /// overrides the default one, same as `set_etag()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use actix_files::NamedFile;
+    /// use actix_web::http::header::EntityTag;
+    ///
+    /// fn tenant_file(tenant: &str) -> std::io::Result<NamedFile> {
+    ///     Ok(NamedFile::open("Cargo.toml")?.etag_with(|md| {
+    ///         EntityTag::strong(format!("{}-{:x}", tenant, md.len()))
+    ///     }))
+    /// }
+    /// ```
+    pub fn etag_with<F>(self, f: F) -> Self
+    where
+        F: FnOnce(&Metadata) -> header::EntityTag,
+    {
+        let etag = f(&self.md);
+        self.set_etag(etag)
+    }
+
+    /// Disable `ETag` header.
+    ///
+    /// Shortcut for `use_etag(false)`.
+    #[inline]
+    pub fn disable_etag(self) -> Self {
+        self.use_etag(false)
+    }
+
+    #[inline]
+    /// Specifies whether to send `Digest` header with sha-256 hash of file.
+    ///
+    /// Digest is computed once and cached per worker until file
+    /// modification time or size changes. Small files are hashed on first
+    /// request, large files are hashed in background and header is sent
+    /// once digest is ready. Header is omitted if body is compressed or
+    /// transformed.
+    ///
+    /// Default is false.
+    #[cfg(feature = "digest")]
+    pub fn use_digest(mut self, value: bool) -> Self {
+        self.flags.set(Flags::DIGEST, value);
+        self
+    }
+
     #[inline]
     ///Specifies whether to use Last-Modified or not.
     ///
@@ -230,7 +533,52 @@ impl NamedFile {
         self
     }
 
+    #[inline]
+    ///Specifies whether clients must revalidate cached file on every use.
+    ///
+    ///Response gets `Cache-Control: no-cache` directive, and `ETag` and
+    ///`Last-Modified` validators are sent regardless of other settings.
+    ///
+    ///Default is false.
+    pub fn force_revalidation(mut self, value: bool) -> Self {
+        self.flags.set(Flags::REVALIDATE, value);
+        self
+    }
+
+    /// Delegate sending of the file to the front-end server.
+    ///
+    /// Response has an empty body and a header with `internal_path`, which
+    /// instructs the server (nginx, Apache, lighttpd) to send the file
+    /// itself. `Content-Type` and `Content-Disposition` headers are sent
+    /// as usual, the file is not read.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use actix_files::{NamedFile, NamedFileOffload, OffloadHeader};
+    ///
+    /// async fn index() -> std::io::Result<NamedFileOffload> {
+    ///     let file = NamedFile::open("/srv/files/report.pdf")?;
+    ///     Ok(file.as_offload("/protected/report.pdf", OffloadHeader::XAccelRedirect))
+    /// }
+    /// ```
+    pub fn as_offload<S: Into<String>>(
+        self,
+        internal_path: S,
+        header: OffloadHeader,
+    ) -> NamedFileOffload {
+        NamedFileOffload {
+            file: self,
+            path: internal_path.into(),
+            header,
+        }
+    }
+
     pub(crate) fn etag(&self) -> Option<header::EntityTag> {
+        if let Some(ref etag) = self.custom_etag {
+            return Some(etag.clone());
+        }
+
         // This etag format is similar to Apache's.
         self.modified.as_ref().map(|mtime| {
             let ino = {
@@ -247,49 +595,182 @@ impl NamedFile {
             let dur = mtime
                 .duration_since(UNIX_EPOCH)
                 .expect("modification time must be after epoch");
-            header::EntityTag::strong(format!(
-                "{:x}:{:x}:{:x}:{:x}",
-                ino,
-                self.md.len(),
-                dur.as_secs(),
-                dur.subsec_nanos()
-            ))
+            header::EntityTag::new(
+                self.flags.contains(Flags::WEAK_ETAG),
+                format!(
+                    "{:x}:{:x}:{:x}:{:x}",
+                    ino,
+                    self.md.len(),
+                    dur.as_secs(),
+                    dur.subsec_nanos()
+                ),
+            )
         })
     }
 
+    /// `Digest` header value if it is computed already.
+    #[cfg(feature = "digest")]
+    fn digest(&self) -> Option<String> {
+        // served file is not the one at `path`
+        if self.precompressed.is_some() {
+            return None;
+        }
+        file_digest(&self.file, &self.path, self.modified?, self.md.len())
+    }
+
+    /// Modification time truncated to whole seconds, as it is sent in
+    /// `Last-Modified` header, so that dates echoed by clients compare equal.
     pub(crate) fn last_modified(&self) -> Option<header::HttpDate> {
-        self.modified.map(|mtime| mtime.into())
+        self.modified
+            .map(|mtime| match mtime.duration_since(UNIX_EPOCH) {
+                Ok(dur) => (UNIX_EPOCH + Duration::from_secs(dur.as_secs())).into(),
+                Err(_) => mtime.into(),
+            })
+    }
+
+    /// Content type with default charset appended for text types.
+    fn response_content_type(&self) -> mime::Mime {
+        if self.content_type.type_() == mime::TEXT
+            && self.content_type.get_param(mime::CHARSET).is_none()
+        {
+            format!("{}; charset={}", self.content_type, self.charset)
+                .parse()
+                .unwrap_or_else(|_| self.content_type.clone())
+        } else {
+            self.content_type.clone()
+        }
+    }
+
+    /// Returns response metadata that would be sent for the given request,
+    /// without reading the file.
+    ///
+    /// Conditional and range headers of the request are evaluated the same way
+    /// as by `into_response()`, so resulting status may be `304`, `206`, etc.
+    /// `Content-Length` header is set to the length of the would-be body.
+    pub fn metadata_response(&self, req: &HttpRequest) -> HttpResponse {
+        let encode = self.negotiate_encoding(req);
+        match self.prepare_response(req, encode) {
+            Ok((mut resp, _, _, _)) if encode.is_some() => resp.finish(),
+            Ok((mut resp, _, _, _)) if self.map_body.is_some() => {
+                if let Some(length) = self.content_length {
+                    resp.header(header::CONTENT_LENGTH, length);
+                }
+                resp.finish()
+            }
+            Ok((mut resp, _, length, _)) => {
+                resp.header(header::CONTENT_LENGTH, length).finish()
+            }
+            Err(resp) => resp,
+        }
     }
 
     pub fn into_response(self, req: &HttpRequest) -> Result<HttpResponse, Error> {
+        let encode = self.negotiate_encoding(req);
+        let (mut resp, offset, length, ranges) = match self.prepare_response(req, encode)
+        {
+            Ok(prepared) => prepared,
+            Err(resp) => return Ok(resp),
+        };
+
+        if let Some(mut ranges) = ranges {
+            ranges.set_file(self.file);
+            return Ok(resp.body(SizedStream::new(length, ranges)));
+        }
+
+        let reader = ChunkedReadFile {
+            offset,
+            size: length,
+            file: Some(self.file),
+            fut: None,
+            counter: 0,
+            chunk_size: self.chunk_size,
+            map_body: self.map_body,
+        };
+        #[cfg(feature = "compress")]
+        if let Some(enc) = encode {
+            return Ok(resp.streaming(EncodedReadFile::new(reader, enc)));
+        }
+        if self.map_body.is_some() {
+            return match self.content_length {
+                Some(length) => Ok(resp.body(SizedStream::new(length, reader))),
+                None => Ok(resp.streaming(reader)),
+            };
+        }
+        if self.status_code != StatusCode::OK {
+            return Ok(resp.streaming(reader));
+        }
+        Ok(resp.body(SizedStream::new(length, reader)))
+    }
+
+    /// Selects encoding for on the fly compression accepted by the client.
+    fn negotiate_encoding(&self, req: &HttpRequest) -> Option<ContentEncoding> {
+        let enc = self.auto_encoding?;
+        if self.precompressed.is_some()
+            || self.encoding.filter(|enc| enc.is_compression()).is_some()
+        {
+            return None;
+        }
+        if accepts_encoding(req, enc) {
+            Some(enc)
+        } else {
+            None
+        }
+    }
+
+    /// Sets `Content-Encoding` and `Vary` headers of the response.
+    fn set_encoding(
+        &self,
+        resp: &mut HttpResponseBuilder,
+        encode: Option<ContentEncoding>,
+    ) {
+        if let Some(enc) = encode.or(self.precompressed) {
+            resp.header(header::CONTENT_ENCODING, enc.as_str());
+        } else if let Some(current_encoding) = self.encoding {
+            // default compressing
+            resp.encoding(current_encoding);
+        }
+        if self.auto_encoding.is_some() || self.flags.contains(Flags::VARY_ENCODING) {
+            resp.header(header::VARY, "accept-encoding");
+        }
+    }
+
+    /// Builds response head and selects file range to send.
+    ///
+    /// Multiple ranges are returned as `multipart/byteranges` body, its
+    /// length is returned instead of range length. Returns complete
+    /// response in case body must not be sent.
+    fn prepare_response(
+        &self,
+        req: &HttpRequest,
+        encode: Option<ContentEncoding>,
+    ) -> Result<(HttpResponseBuilder, u64, u64, Option<ChunkedReadRanges>), HttpResponse>
+    {
         if self.status_code != StatusCode::OK {
             let mut resp = HttpResponse::build(self.status_code);
-            resp.set(header::ContentType(self.content_type.clone()))
+            resp.set(header::ContentType(self.response_content_type()))
                 .if_true(self.flags.contains(Flags::CONTENT_DISPOSITION), |res| {
                     res.header(
                         header::CONTENT_DISPOSITION,
                         self.content_disposition.to_string(),
                     );
                 });
-            if let Some(current_encoding) = self.encoding {
-                resp.encoding(current_encoding);
-            }
-            let reader = ChunkedReadFile {
-                size: self.md.len(),
-                offset: 0,
-                file: Some(self.file),
-                fut: None,
-                counter: 0,
-            };
-            return Ok(resp.streaming(reader));
+            self.set_encoding(&mut resp, encode);
+            return Ok((resp, 0, self.md.len(), None));
         }
 
-        let etag = if self.flags.contains(Flags::ETAG) {
-            self.etag()
+        let revalidate = self.flags.contains(Flags::REVALIDATE);
+        let etag = if revalidate || self.flags.contains(Flags::ETAG) {
+            self.etag().map(|etag| match encode {
+                Some(enc) => header::EntityTag::new(
+                    etag.weak,
+                    format!("{}-{}", etag.tag(), enc.as_str()),
+                ),
+                None => etag,
+            })
         } else {
             None
         };
-        let last_modified = if self.flags.contains(Flags::LAST_MD) {
+        let last_modified = if revalidate || self.flags.contains(Flags::LAST_MD) {
             self.last_modified()
         } else {
             None
@@ -329,18 +810,18 @@ impl NamedFile {
             false
         };
 
+        // range is served only if `If-Range` validator matches current file
+        let range_matches = if_range_matches(etag.as_ref(), last_modified.as_ref(), req);
+
         let mut resp = HttpResponse::build(self.status_code);
-        resp.set(header::ContentType(self.content_type.clone()))
+        resp.set(header::ContentType(self.response_content_type()))
             .if_true(self.flags.contains(Flags::CONTENT_DISPOSITION), |res| {
                 res.header(
                     header::CONTENT_DISPOSITION,
                     self.content_disposition.to_string(),
                 );
             });
-        // default compressing
-        if let Some(current_encoding) = self.encoding {
-            resp.encoding(current_encoding);
-        }
+        self.set_encoding(&mut resp, encode);
 
         resp.if_some(last_modified, |lm, resp| {
             resp.set(header::LastModified(lm));
@@ -348,55 +829,95 @@ impl NamedFile {
         .if_some(etag, |etag, resp| {
             resp.set(header::ETag(etag));
         });
+        #[cfg(feature = "digest")]
+        if self.flags.contains(Flags::DIGEST)
+            && encode.is_none()
+            && self.map_body.is_none()
+        {
+            if let Some(digest) = self.digest() {
+                resp.header("digest", digest);
+            }
+        }
 
-        resp.header(header::ACCEPT_RANGES, "bytes");
+        // byte ranges of transformed body are not supported
+        let ranges_allowed = encode.is_none() && self.map_body.is_none();
+        if ranges_allowed {
+            resp.header(header::ACCEPT_RANGES, "bytes");
+        }
+        if revalidate {
+            resp.header(header::CACHE_CONTROL, "no-cache");
+        }
 
         let mut length = self.md.len();
         let mut offset = 0;
+        let mut multipart = None;
 
         // check for range header
-        if let Some(ranges) = req.headers().get(&header::RANGE) {
+        if let Some(ranges) = req
+            .headers()
+            .get(&header::RANGE)
+            .filter(|_| ranges_allowed && range_matches)
+        {
             if let Ok(rangesheader) = ranges.to_str() {
-                if let Ok(rangesvec) = HttpRange::parse(rangesheader, length) {
-                    length = rangesvec[0].length;
-                    offset = rangesvec[0].start;
-                    resp.encoding(ContentEncoding::Identity);
-                    resp.header(
-                        header::CONTENT_RANGE,
-                        format!(
-                            "bytes {}-{}/{}",
-                            offset,
-                            offset + length - 1,
-                            self.md.len()
-                        ),
-                    );
-                } else {
-                    resp.header(header::CONTENT_RANGE, format!("bytes */{}", length));
-                    return Ok(resp.status(StatusCode::RANGE_NOT_SATISFIABLE).finish());
-                };
+                match HttpRange::parse(rangesheader, length).map(coalesce_ranges) {
+                    Ok(ref rangesvec) if rangesvec.len() == 1 => {
+                        length = rangesvec[0].length;
+                        offset = rangesvec[0].start;
+                        resp.encoding(ContentEncoding::Identity);
+                        resp.header(
+                            header::CONTENT_RANGE,
+                            format!(
+                                "bytes {}-{}/{}",
+                                offset,
+                                offset + length - 1,
+                                self.md.len()
+                            ),
+                        );
+                    }
+                    Ok(rangesvec) if rangesvec.len() > 1 => {
+                        let content_type = self.response_content_type().to_string();
+                        let ranges = ChunkedReadRanges::new(
+                            rangesvec,
+                            &content_type,
+                            length,
+                            self.chunk_size,
+                        );
+                        length = ranges.size();
+                        resp.encoding(ContentEncoding::Identity);
+                        resp.set_header(
+                            header::CONTENT_TYPE,
+                            format!(
+                                "multipart/byteranges; boundary={}",
+                                ranges.boundary()
+                            ),
+                        );
+                        multipart = Some(ranges);
+                    }
+                    _ => {
+                        resp.header(
+                            header::CONTENT_RANGE,
+                            format!("bytes */{}", length),
+                        );
+                        return Err(resp
+                            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                            .finish());
+                    }
+                }
             } else {
-                return Ok(resp.status(StatusCode::BAD_REQUEST).finish());
+                return Err(resp.status(StatusCode::BAD_REQUEST).finish());
             };
         };
 
         if precondition_failed {
-            return Ok(resp.status(StatusCode::PRECONDITION_FAILED).finish());
+            return Err(resp.status(StatusCode::PRECONDITION_FAILED).finish());
         } else if not_modified {
-            return Ok(resp.status(StatusCode::NOT_MODIFIED).finish());
+            return Err(resp.status(StatusCode::NOT_MODIFIED).finish());
         }
 
-        let reader = ChunkedReadFile {
-            offset,
-            size: length,
-            file: Some(self.file),
-            fut: None,
-            counter: 0,
-        };
-        if offset != 0 || length != self.md.len() {
-            Ok(resp.status(StatusCode::PARTIAL_CONTENT).streaming(reader))
-        } else {
-            Ok(resp.body(SizedStream::new(length, reader)))
+        if offset != 0 || length != self.md.len() || multipart.is_some() {
+            resp.status(StatusCode::PARTIAL_CONTENT);
         }
+        Ok((resp, offset, length, multipart))
     }
 }
 
@@ -414,38 +935,196 @@ impl DerefMut for NamedFile {
     }
 }
 
-/// Returns true if `req` has no `If-Match` header or one which matches `etag`.
-fn any_match(etag: Option<&header::EntityTag>, req: &HttpRequest) -> bool {
-    match req.get_header::<header::IfMatch>() {
-        None | Some(header::IfMatch::Any) => true,
-        Some(header::IfMatch::Items(ref items)) => {
-            if let Some(some_etag) = etag {
-                for item in items {
-                    if item.strong_eq(some_etag) {
-                        return true;
-                    }
+/// Returns true if `req` has no `If-Range` header or one which matches
+/// `etag` or `last_modified` validator.
+///
+/// Entity tags are compared with strong comparison, dates must match exactly.
+fn if_range_matches(
+    etag: Option<&header::EntityTag>,
+    last_modified: Option<&header::HttpDate>,
+    req: &HttpRequest,
+) -> bool {
+    if !req.headers().contains_key(&header::IF_RANGE) {
+        return true;
+    }
+
+    match (req.get_header(), etag, last_modified) {
+        (Some(header::IfRange::EntityTag(ref tag)), Some(etag), _) => {
+            etag.strong_eq(tag)
+        }
+        (Some(header::IfRange::Date(since)), _, Some(m)) => {
+            let t1: SystemTime = (*m).into();
+            let t2: SystemTime = since.into();
+            match (t1.duration_since(UNIX_EPOCH), t2.duration_since(UNIX_EPOCH)) {
+                (Ok(t1), Ok(t2)) => t1.as_secs() == t2.as_secs(),
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Sorts ranges and merges overlapping or adjacent ones, as permitted by
+/// RFC 7233. Empty ranges are dropped.
+fn coalesce_ranges(mut ranges: Vec<HttpRange>) -> Vec<HttpRange> {
+    ranges.retain(|range| range.length > 0);
+    ranges.sort_by_key(|range| range.start);
+
+    let mut merged: Vec<HttpRange> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.start + last.length => {
+                let end = cmp::max(last.start + last.length, range.start + range.length);
+                last.length = end - last.start;
+            }
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// `Content-Disposition` parameters for the file name.
+fn filename_params(filename: String) -> Vec<DispositionParam> {
+    let mut parameters = vec![DispositionParam::Filename(filename.clone())];
+    if !filename.is_ascii() {
+        parameters.push(DispositionParam::FilenameExt(ExtendedValue {
+            charset: Charset::Ext(String::from("UTF-8")),
+            language_tag: None,
+            value: filename.into_bytes(),
+        }))
+    }
+    parameters
+}
+
+/// Returns true if `Accept-Encoding` header of `req` allows `enc` encoding.
+fn accepts_encoding(req: &HttpRequest, enc: ContentEncoding) -> bool {
+    let mut any = false;
+
+    for value in req.headers().get_all(&header::ACCEPT_ENCODING) {
+        let value = match value.to_str() {
+            Ok(value) => value,
+            Err(_) => continue,
+        };
+        for item in value.split(',') {
+            let mut parts = item.split(';');
+            let coding = parts.next().unwrap_or("").trim();
+            let quality = parts.find_map(|param| {
+                let param = param.trim();
+                if param.len() > 2 && param[..2].eq_ignore_ascii_case("q=") {
+                    param[2..].trim().parse::<f32>().ok()
+                } else {
+                    None
                 }
+            });
+            let allowed = quality.map(|q| q > 0.0).unwrap_or(true);
+
+            if coding.eq_ignore_ascii_case(enc.as_str()) {
+                return allowed;
+            } else if coding == "*" {
+                any = allowed;
             }
-            false
         }
     }
+    any
+}
+
+/// Returns true if `req` has no `If-Match` header or one which matches `etag`.
+fn any_match(etag: Option<&header::EntityTag>, req: &HttpRequest) -> bool {
+    etag_matches(req, &header::IF_MATCH, etag, false).unwrap_or(true)
 }
 
 /// Returns true if `req` doesn't have an `If-None-Match` header matching `req`.
 fn none_match(etag: Option<&header::EntityTag>, req: &HttpRequest) -> bool {
-    match req.get_header::<header::IfNoneMatch>() {
-        Some(header::IfNoneMatch::Any) => false,
-        Some(header::IfNoneMatch::Items(ref items)) => {
-            if let Some(some_etag) = etag {
-                for item in items {
-                    if item.weak_eq(some_etag) {
-                        return false;
+    !etag_matches(req, &header::IF_NONE_MATCH, etag, true).unwrap_or(false)
+}
+
+/// Checks entity tags of `If-Match` or `If-None-Match` header against `etag`.
+///
+/// Header values are scanned in place, without allocating every listed tag,
+/// and scanning stops at the first match. A `*` mixed with other items is
+/// treated as a plain `*`. Returns `None` if header is missing or contains
+/// no valid items.
+fn etag_matches(
+    req: &HttpRequest,
+    name: &header::HeaderName,
+    etag: Option<&header::EntityTag>,
+    weak: bool,
+) -> Option<bool> {
+    let mut valid = false;
+
+    for value in req.headers().get_all(name) {
+        let value = match value.to_str() {
+            Ok(value) => value,
+            Err(_) => continue,
+        };
+        for item in EntityTagItems(value) {
+            match item {
+                EntityTagItem::Any => return Some(true),
+                EntityTagItem::Tag(item_weak, tag) => {
+                    valid = true;
+                    if let Some(etag) = etag {
+                        if etag.tag() == tag && (weak || !(item_weak || etag.weak)) {
+                            return Some(true);
+                        }
                     }
                 }
+                EntityTagItem::Invalid => (),
             }
-            true
         }
-        None => true,
+    }
+
+    if valid {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+enum EntityTagItem<'a> {
+    Any,
+    Tag(bool, &'a str),
+    Invalid,
+}
+
+/// Iterator over comma separated entity tags of a header value.
+struct EntityTagItems<'a>(&'a str);
+
+impl<'a> Iterator for EntityTagItems<'a> {
+    type Item = EntityTagItem<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let s = self
+            .0
+            .trim_start_matches(|c: char| c == ',' || c.is_ascii_whitespace());
+        if s.is_empty() {
+            self.0 = s;
+            return None;
+        }
+
+        if s.starts_with('*') {
+            self.0 = &s[1..];
+            return Some(EntityTagItem::Any);
+        }
+
+        let (weak, rest) = if s.starts_with("W/") {
+            (true, &s[2..])
+        } else {
+            (false, s)
+        };
+        if rest.starts_with('"') {
+            let rest = &rest[1..];
+            if let Some(end) = rest.find('"') {
+                self.0 = &rest[end + 1..];
+                return Some(EntityTagItem::Tag(weak, &rest[..end]));
+            }
+        }
+
+        // skip malformed item
+        self.0 = match s.find(',') {
+            Some(idx) => &s[idx..],
+            None => "",
+        };
+        Some(EntityTagItem::Invalid)
     }
 }
 
@@ -457,3 +1136,210 @@ impl Responder for NamedFile {
         ready(self.into_response(req))
     }
 }
+
+/// Header used to delegate sending of a file to the front-end server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OffloadHeader {
+    /// `X-Accel-Redirect` header, internal path is an uri (nginx)
+    XAccelRedirect,
+    /// `X-Sendfile` header, internal path is a file path (Apache, lighttpd)
+    XSendfile,
+}
+
+impl OffloadHeader {
+    /// Name of the header
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OffloadHeader::XAccelRedirect => "x-accel-redirect",
+            OffloadHeader::XSendfile => "x-sendfile",
+        }
+    }
+}
+
+/// Named file responder which delegates sending of the file to the
+/// front-end server.
+///
+/// Created by [`NamedFile::as_offload()`](struct.NamedFile.html#method.as_offload).
+#[derive(Debug)]
+pub struct NamedFileOffload {
+    file: NamedFile,
+    path: String,
+    header: OffloadHeader,
+}
+
+impl NamedFileOffload {
+    /// Returns internal path passed to the front-end server.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn into_response(self, _: &HttpRequest) -> Result<HttpResponse, Error> {
+        let value = match self.header {
+            OffloadHeader::XAccelRedirect => {
+                utf8_percent_encode(&self.path, CONTROLS).to_string()
+            }
+            OffloadHeader::XSendfile => self.path,
+        };
+        let value =
+            header::HeaderValue::from_str(&value).map_err(ErrorInternalServerError)?;
+
+        let file = self.file;
+        let mut resp = HttpResponse::build(file.status_code);
+        resp.set(header::ContentType(file.response_content_type()))
+            .if_true(file.flags.contains(Flags::CONTENT_DISPOSITION), |res| {
+                res.header(
+                    header::CONTENT_DISPOSITION,
+                    file.content_disposition.to_string(),
+                );
+            })
+            .header(self.header.as_str(), value);
+        Ok(resp.finish())
+    }
+}
+
+impl Responder for NamedFileOffload {
+    type Error = Error;
+    type Future = Ready<Result<HttpResponse, Error>>;
+
+    fn respond_to(self, req: &HttpRequest) -> Self::Future {
+        ready(self.into_response(req))
+    }
+}
+
+/// Named file responder which falls back to `404 Not Found` response
+/// if file does not exist.
+///
+/// Created by [`NamedFile::open_or_404()`](struct.NamedFile.html#method.open_or_404).
+pub struct NamedFileOr404 {
+    file: io::Result<NamedFile>,
+    not_found: Option<HttpResponse>,
+}
+
+impl NamedFileOr404 {
+    /// Set response which is sent if file does not exist.
+    ///
+    /// By default empty `404 Not Found` response is sent.
+    pub fn not_found(mut self, res: HttpResponse) -> Self {
+        self.not_found = Some(res);
+        self
+    }
+
+    /// Deconstruct to the result of opening the file
+    pub fn into_inner(self) -> io::Result<NamedFile> {
+        self.file
+    }
+}
+
+impl Responder for NamedFileOr404 {
+    type Error = Error;
+    type Future = Ready<Result<HttpResponse, Error>>;
+
+    fn respond_to(self, req: &HttpRequest) -> Self::Future {
+        match self.file {
+            Ok(file) => file.respond_to(req),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => ready(Ok(self
+                .not_found
+                .unwrap_or_else(|| HttpResponse::NotFound().finish()))),
+            Err(e) => ready(Err(e.into())),
+        }
+    }
+}
+
+/// Lookup of pre-compressed variants of a file.
+///
+/// Looks for sibling files with `.br` and `.gz` extensions appended,
+/// i.e. `app.js.br` and `app.js.gz` for `app.js`, and serves the first one
+/// accepted by client's `Accept-Encoding` header with proper
+/// `Content-Encoding`. Plain file is served if no variant is acceptable.
+///
+/// `Content-Type` and `Content-Disposition` are derived from the original
+/// file name, `ETag` and `Last-Modified` from the served file. Response
+/// always has `Vary: Accept-Encoding` header.
+///
+/// # Examples
+///
+/// ```rust
+/// use actix_files::PrecompressedFile;
+///
+/// async fn index() -> PrecompressedFile {
+///     PrecompressedFile::new("static/app.js")
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct PrecompressedFile {
+    path: PathBuf,
+    encodings: Vec<ContentEncoding>,
+}
+
+impl PrecompressedFile {
+    /// Create lookup for the file, `br` variant is preferred over `gzip` one.
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        PrecompressedFile {
+            path: path.as_ref().to_path_buf(),
+            encodings: vec![ContentEncoding::Br, ContentEncoding::Gzip],
+        }
+    }
+
+    /// Set encodings of variants to look for, in order of preference.
+    ///
+    /// Only `br`, `gzip` and `deflate` (`.zz` extension) are supported,
+    /// other encodings are ignored.
+    pub fn encodings(mut self, encodings: &[ContentEncoding]) -> Self {
+        self.encodings = encodings
+            .iter()
+            .copied()
+            .filter(|enc| extension(*enc).is_some())
+            .collect();
+        self
+    }
+
+    /// Open variant of the file accepted by the client.
+    pub fn open(&self, req: &HttpRequest) -> io::Result<NamedFile> {
+        for enc in &self.encodings {
+            if !accepts_encoding(req, *enc) {
+                continue;
+            }
+            let ext = extension(*enc).expect("Unsupported encoding");
+            let mut path = self.path.clone().into_os_string();
+            path.push(ext);
+
+            match File::open(&path) {
+                Ok(file) if file.metadata()?.is_file() => {
+                    let mut named = NamedFile::from_file(file, &self.path)?;
+                    named.precompressed = Some(*enc);
+                    named.flags.insert(Flags::VARY_ENCODING);
+                    return Ok(named);
+                }
+                Ok(_) => continue,
+                Err(ref e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        let mut named = NamedFile::open(&self.path)?;
+        named.flags.insert(Flags::VARY_ENCODING);
+        Ok(named)
+    }
+}
+
+impl Responder for PrecompressedFile {
+    type Error = Error;
+    type Future = Ready<Result<HttpResponse, Error>>;
+
+    fn respond_to(self, req: &HttpRequest) -> Self::Future {
+        match self.open(req) {
+            Ok(file) => file.respond_to(req),
+            Err(e) => ready(Err(e.into())),
+        }
+    }
+}
+
+/// Extension of pre-compressed file variant
+fn extension(enc: ContentEncoding) -> Option<&'static str> {
+    match enc {
+        ContentEncoding::Br => Some(".br"),
+        ContentEncoding::Gzip => Some(".gz"),
+        ContentEncoding::Deflate => Some(".zz"),
+        _ => None,
+    }
+}
@@ -1,19 +1,26 @@
 use std::marker::PhantomData;
 use std::rc::Rc;
-use std::{fmt, net};
+use std::time::Duration;
+use std::{fmt, io, net};
 
 use actix_codec::Framed;
 use actix_service::{IntoServiceFactory, Service, ServiceFactory};
 
 use crate::body::MessageBody;
-use crate::config::{KeepAlive, ServiceConfig};
+use crate::config::{
+    EarlyDataPolicy, KeepAlive, NormalizePath, ServiceConfig, SlowRequest,
+    WriteErrorContext,
+};
 use crate::error::Error;
 use crate::h1::{Codec, ExpectHandler, H1Service, UpgradeHandler};
-use crate::h2::H2Service;
+use crate::h2::{GracefulShutdown, H2Service};
+use crate::header::HeaderValue;
 use crate::helpers::{Data, DataFactory};
+use crate::http::StatusCode;
 use crate::request::Request;
 use crate::response::Response;
 use crate::service::HttpService;
+use crate::Protocol;
 
 /// A http service builder
 ///
@@ -23,8 +30,27 @@ pub struct HttpServiceBuilder<T, S, X = ExpectHandler, U = UpgradeHandler<T>> {
     keep_alive: KeepAlive,
     client_timeout: u64,
     client_disconnect: u64,
+    client_shutdown: u64,
     secure: bool,
     local_addr: Option<net::SocketAddr>,
+    normalize_path: Option<NormalizePath>,
+    max_requests: usize,
+    bad_request: Option<Rc<dyn Fn() -> Response>>,
+    alt_svc: Option<HeaderValue>,
+    load_shed: Option<u64>,
+    max_concurrent_requests: usize,
+    alpn_fallback: Protocol,
+    early_data: EarlyDataPolicy,
+    h2_idle_timeout: u64,
+    graceful_shutdown: Option<GracefulShutdown>,
+    send_buffer: Option<usize>,
+    recv_buffer: Option<usize>,
+    request_deadline: Option<Duration>,
+    request_deadline_status: StatusCode,
+    slow_request_threshold: Option<Duration>,
+    slow_request_logger: Option<Rc<dyn Fn(&SlowRequest)>>,
+    server_timing: bool,
+    write_error_hook: Option<Rc<dyn Fn(&WriteErrorContext, &io::Error)>>,
     expect: X,
     upgrade: Option<U>,
     on_connect: Option<Rc<dyn Fn(&T) -> Box<dyn DataFactory>>>,
@@ -44,8 +70,27 @@ where
             keep_alive: KeepAlive::Timeout(5),
             client_timeout: 5000,
             client_disconnect: 0,
+            client_shutdown: 0,
             secure: false,
             local_addr: None,
+            normalize_path: None,
+            max_requests: 0,
+            bad_request: None,
+            alt_svc: None,
+            load_shed: None,
+            max_concurrent_requests: 0,
+            alpn_fallback: Protocol::Http1,
+            early_data: EarlyDataPolicy::SafeMethods,
+            h2_idle_timeout: 0,
+            graceful_shutdown: None,
+            send_buffer: None,
+            recv_buffer: None,
+            request_deadline: None,
+            request_deadline_status: StatusCode::SERVICE_UNAVAILABLE,
+            slow_request_threshold: None,
+            slow_request_logger: None,
+            server_timing: false,
+            write_error_hook: None,
             expect: ExpectHandler,
             upgrade: None,
             on_connect: None,
@@ -93,7 +138,9 @@ where
     ///
     /// Defines a timeout for reading client request header. If a client does not transmit
     /// the entire set headers within this time, the request is terminated with
-    /// the 408 (Request Time-out) error.
+    /// the 408 (Request Time-out) error. For http/2 connections the same timeout
+    /// applies to connection handshake, connection is closed if client does not
+    /// complete handshake in time.
     ///
     /// To disable timeout set value to 0.
     ///
@@ -116,6 +163,270 @@ where
         self
     }
 
+    /// Set server connection shutdown timeout in milliseconds.
+    ///
+    /// Defines how long a connection may stay open once graceful shutdown
+    /// is initiated via `graceful_shutdown()` handle. Connection which is
+    /// still open when the timeout expires, i.e. client keeps idle keep-alive
+    /// connection open or in-flight requests do not complete, is dropped.
+    /// Unlike keep-alive, it applies to shutdown only. Unlike
+    /// `client_disconnect()`, it does not apply to tls shutdown.
+    ///
+    /// To disable timeout set value to 0. By default shutdown timeout is disabled.
+    pub fn client_shutdown(mut self, val: u64) -> Self {
+        self.client_shutdown = val;
+        self
+    }
+
+    /// Set request path normalization policy.
+    ///
+    /// Request path is normalized before request enters the service,
+    /// so routing sees normalized path.
+    ///
+    /// By default path is not normalized.
+    pub fn normalize_path(mut self, policy: NormalizePath) -> Self {
+        self.normalize_path = Some(policy);
+        self
+    }
+
+    /// Set maximum number of requests served by a keep-alive connection.
+    ///
+    /// Response to the last allowed request is sent with `Connection: close`
+    /// and the connection is closed afterwards. Applies to HTTP/1 only.
+    ///
+    /// To disable limit set value to 0.
+    ///
+    /// By default number of requests is not limited.
+    pub fn max_requests_per_connection(mut self, max: usize) -> Self {
+        self.max_requests = max;
+        self
+    }
+
+    /// Set factory of response for requests which can not be parsed.
+    ///
+    /// Response is sent by HTTP/1 dispatcher when request line or headers are
+    /// malformed, connection is closed afterwards.
+    ///
+    /// By default empty `400 Bad Request` response is sent.
+    pub fn bad_request_response<F>(mut self, f: F) -> Self
+    where
+        F: Fn() -> Response + 'static,
+    {
+        self.bad_request = Some(Rc::new(f));
+        self
+    }
+
+    /// Set `Alt-Svc` header value advertised on all responses.
+    ///
+    /// Allows to point clients to alternative services, i.e. HTTP/3
+    /// endpoint. Header is not overridden if response already has one.
+    ///
+    /// By default header is not sent.
+    pub fn alt_svc(mut self, value: HeaderValue) -> Self {
+        self.alt_svc = Some(value);
+        self
+    }
+
+    /// Shed requests while service is not ready.
+    ///
+    /// If service's `poll_ready` returns not-ready, request is not passed
+    /// to the service and `503 Service Unavailable` response is sent with
+    /// `Retry-After` header set to `retry_after` seconds.
+    ///
+    /// By default requests are not shed.
+    pub fn load_shed(mut self, retry_after: u64) -> Self {
+        self.load_shed = Some(retry_after);
+        self
+    }
+
+    /// Set protocol of connections without negotiated ALPN protocol.
+    ///
+    /// Service created with `finish()` selects http/1 or http/2 dispatcher
+    /// by ALPN protocol negotiated by `openssl()` and `rustls()` transports.
+    /// Fallback protocol is used if client did not negotiate known protocol,
+    /// and for every connection of `tcp()` transport, e.g. set it to
+    /// `Protocol::Http2` to serve http/2 with prior knowledge.
+    ///
+    /// By default `Protocol::Http1` is used.
+    pub fn alpn_fallback(mut self, proto: Protocol) -> Self {
+        self.alpn_fallback = proto;
+        self
+    }
+
+    /// Set handling of requests received in TLS 1.3 early data (0-RTT).
+    ///
+    /// Early data can be replayed, so requests marked with `EarlyData` in
+    /// request extensions are rejected with `425 Too Early` or passed to the
+    /// service according to `policy`.
+    ///
+    /// By default only `GET` and `HEAD` requests are accepted in early data.
+    pub fn early_data(mut self, policy: EarlyDataPolicy) -> Self {
+        self.early_data = policy;
+        self
+    }
+
+    /// Set maximum number of concurrent requests per worker.
+    ///
+    /// Request occupies a slot from the moment it is passed to the service
+    /// until response head is ready. Once the limit is reached service
+    /// readiness reports not-ready, so no new connections are accepted by
+    /// the worker, and new requests of open connections are queued until a
+    /// slot is released. Http/2 connections keep accepting streams while
+    /// their requests are queued.
+    ///
+    /// To disable limit set value to 0. By default limit is disabled.
+    pub fn max_concurrent_requests(mut self, max: usize) -> Self {
+        self.max_concurrent_requests = max;
+        self
+    }
+
+    /// Set idle timeout for http/2 connections in milliseconds.
+    ///
+    /// Connection without open streams is gracefully closed with `GOAWAY`
+    /// frame once the timeout expires. Timer starts over whenever the last
+    /// open stream completes. Unlike keep-alive, it applies to http/2 only.
+    ///
+    /// To disable timeout set value to 0. By default timeout is disabled.
+    pub fn h2_idle_timeout(mut self, val: u64) -> Self {
+        self.h2_idle_timeout = val;
+        self
+    }
+
+    /// Set handle to gracefully shut down connections.
+    ///
+    /// Once `GracefulShutdown::shutdown()` is called, connections send
+    /// `GOAWAY` frame, refuse new streams and close after in-flight streams
+    /// complete. Http/1 connections stop keep-alive, response to in-flight
+    /// request closes the connection. Handle is usually cloned into service
+    /// factory of every worker and triggered before server is stopped.
+    ///
+    /// By default connections are not shut down gracefully.
+    pub fn graceful_shutdown(mut self, handle: GracefulShutdown) -> Self {
+        self.graceful_shutdown = Some(handle);
+        self
+    }
+
+    /// Set send buffer size (`SO_SNDBUF`) of accepted sockets in bytes.
+    ///
+    /// Requested size is a hint, operating system clamps it to its own
+    /// limits. I.e. on linux the value is doubled to account for
+    /// bookkeeping overhead and capped by `net.core.wmem_max`, so
+    /// the effective size could differ from requested one. If option
+    /// can not be set, error is logged and connection is served with
+    /// the default size.
+    ///
+    /// Applies to tcp based services only (`tcp()`, `openssl()`,
+    /// `rustls()`), option is set on accepted socket before tls handshake.
+    /// By default os default size is used.
+    pub fn socket_send_buffer(mut self, size: usize) -> Self {
+        self.send_buffer = Some(size);
+        self
+    }
+
+    /// Set receive buffer size (`SO_RCVBUF`) of accepted sockets in bytes.
+    ///
+    /// Requested size is a hint, operating system clamps it to its own
+    /// limits. I.e. on linux the value is doubled to account for
+    /// bookkeeping overhead and capped by `net.core.rmem_max`.
+    /// Setting the size disables receive buffer auto-tuning on linux.
+    ///
+    /// Applies to tcp based services only (`tcp()`, `openssl()`,
+    /// `rustls()`), option is set on accepted socket before tls handshake.
+    /// By default os default size is used.
+    ///
+    /// Tcp window scale is negotiated during connection handshake, before
+    /// socket is accepted, so this option can not raise it. To get receive
+    /// window larger than 64KiB, set `SO_RCVBUF` on listening socket instead,
+    /// accepted sockets inherit it.
+    pub fn socket_recv_buffer(mut self, size: usize) -> Self {
+        self.recv_buffer = Some(size);
+        self
+    }
+
+    /// Set overall deadline of request processing.
+    ///
+    /// Deadline starts once request head is received and covers the whole
+    /// service call, including `EXPECT: 100-Continue` handling, payload
+    /// extraction and the handler itself. If response head is not ready
+    /// once the deadline is reached, service future is dropped and response
+    /// with status configured via `request_deadline_status()` is sent
+    /// instead. Http/1 connection is closed after such response.
+    ///
+    /// Unlike `client_timeout()` and `client_disconnect()`, which limit
+    /// single phases of client i/o, the deadline bounds time to first
+    /// response byte regardless of where the time is spent. Streaming of
+    /// response body is not limited.
+    ///
+    /// By default deadline is not set.
+    pub fn request_deadline(mut self, deadline: Duration) -> Self {
+        self.request_deadline = Some(deadline);
+        self
+    }
+
+    /// Set status of response sent once request deadline is reached.
+    ///
+    /// By default `503 Service Unavailable` is used.
+    pub fn request_deadline_status(mut self, status: StatusCode) -> Self {
+        self.request_deadline_status = status;
+        self
+    }
+
+    /// Set latency threshold of slow request logging.
+    ///
+    /// Latency is measured by dispatcher from receiving request head until
+    /// response head is sent, so it covers every request served by the
+    /// service. Requests with latency at or above the threshold are passed
+    /// to logger configured via `slow_request_logger()`, by default method,
+    /// path, status and latency are logged at `warn` level.
+    ///
+    /// By default slow requests are not logged.
+    pub fn slow_request_threshold(mut self, threshold: Duration) -> Self {
+        self.slow_request_threshold = Some(threshold);
+        self
+    }
+
+    /// Set logger of requests which exceed slow request threshold.
+    ///
+    /// Logger has no effect unless `slow_request_threshold()` is set.
+    pub fn slow_request_logger<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&SlowRequest) + 'static,
+    {
+        self.slow_request_logger = Some(Rc::new(f));
+        self
+    }
+
+    /// Enable `Server-Timing` response header.
+    ///
+    /// Dispatcher measures connection, http/2 handshake and total request
+    /// processing time and stores `ServerTiming` in request extensions, so
+    /// services can record additional metrics. Recorded metrics are appended
+    /// to response as `Server-Timing` header.
+    ///
+    /// Measuring has a per-request cost, so it is disabled by default.
+    pub fn server_timing(mut self, enabled: bool) -> Self {
+        self.server_timing = enabled;
+        self
+    }
+
+    /// Set hook called when writing response to the client fails.
+    ///
+    /// Write fails if client disconnects while response is sent, i.e. in the
+    /// middle of a streamed body. Connection error detected while reading is
+    /// treated the same way if response is not written completely, status is
+    /// `None` if response head was not sent yet. Connection is dropped along
+    /// with response body either way, hook is called before that with
+    /// context of the request and io error, so expensive work started for
+    /// the request can be canceled. Hook is called once per failed response
+    /// and only for http/1 connections.
+    pub fn on_write_error<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&WriteErrorContext, &io::Error) + 'static,
+    {
+        self.write_error_hook = Some(Rc::new(f));
+        self
+    }
+
     /// Provide service for `EXPECT: 100-Continue` support.
     ///
     /// Service get called with request that contains `EXPECT` header.
@@ -133,8 +444,27 @@ where
             keep_alive: self.keep_alive,
             client_timeout: self.client_timeout,
             client_disconnect: self.client_disconnect,
+            client_shutdown: self.client_shutdown,
             secure: self.secure,
             local_addr: self.local_addr,
+            normalize_path: self.normalize_path,
+            max_requests: self.max_requests,
+            bad_request: self.bad_request,
+            alt_svc: self.alt_svc,
+            load_shed: self.load_shed,
+            max_concurrent_requests: self.max_concurrent_requests,
+            alpn_fallback: self.alpn_fallback,
+            early_data: self.early_data,
+            h2_idle_timeout: self.h2_idle_timeout,
+            graceful_shutdown: self.graceful_shutdown,
+            send_buffer: self.send_buffer,
+            recv_buffer: self.recv_buffer,
+            request_deadline: self.request_deadline,
+            request_deadline_status: self.request_deadline_status,
+            slow_request_threshold: self.slow_request_threshold,
+            slow_request_logger: self.slow_request_logger,
+            server_timing: self.server_timing,
+            write_error_hook: self.write_error_hook,
             expect: expect.into_factory(),
             upgrade: self.upgrade,
             on_connect: self.on_connect,
@@ -162,8 +492,27 @@ where
             keep_alive: self.keep_alive,
             client_timeout: self.client_timeout,
             client_disconnect: self.client_disconnect,
+            client_shutdown: self.client_shutdown,
             secure: self.secure,
             local_addr: self.local_addr,
+            normalize_path: self.normalize_path,
+            max_requests: self.max_requests,
+            bad_request: self.bad_request,
+            alt_svc: self.alt_svc,
+            load_shed: self.load_shed,
+            max_concurrent_requests: self.max_concurrent_requests,
+            alpn_fallback: self.alpn_fallback,
+            early_data: self.early_data,
+            h2_idle_timeout: self.h2_idle_timeout,
+            graceful_shutdown: self.graceful_shutdown,
+            send_buffer: self.send_buffer,
+            recv_buffer: self.recv_buffer,
+            request_deadline: self.request_deadline,
+            request_deadline_status: self.request_deadline_status,
+            slow_request_threshold: self.slow_request_threshold,
+            slow_request_logger: self.slow_request_logger,
+            server_timing: self.server_timing,
+            write_error_hook: self.write_error_hook,
             expect: self.expect,
             upgrade: Some(upgrade.into_factory()),
             on_connect: self.on_connect,
@@ -184,6 +533,39 @@ where
         self
     }
 
+    fn config(&self) -> ServiceConfig {
+        ServiceConfig::new(
+            self.keep_alive,
+            self.client_timeout,
+            self.client_disconnect,
+            self.secure,
+            self.local_addr,
+        )
+        .set_normalize_path(self.normalize_path)
+        .set_max_requests_per_connection(self.max_requests)
+        .set_bad_request_response(self.bad_request.clone())
+        .set_alt_svc(self.alt_svc.clone())
+        .set_load_shed(self.load_shed)
+        .set_max_concurrent_requests(self.max_concurrent_requests)
+        .set_alpn_fallback(self.alpn_fallback)
+        .set_early_data(self.early_data)
+        .set_h2_idle_timeout(if self.h2_idle_timeout > 0 {
+            Some(Duration::from_millis(self.h2_idle_timeout))
+        } else {
+            None
+        })
+        .set_graceful_shutdown(self.graceful_shutdown.clone())
+        .set_client_shutdown(self.client_shutdown)
+        .set_socket_buffers(self.send_buffer, self.recv_buffer)
+        .set_request_deadline(self.request_deadline, self.request_deadline_status)
+        .set_slow_request(
+            self.slow_request_threshold,
+            self.slow_request_logger.clone(),
+        )
+        .set_server_timing(self.server_timing)
+        .set_write_error_hook(self.write_error_hook.clone())
+    }
+
     /// Finish service configuration and create *http service* for HTTP/1 protocol.
     pub fn h1<F, B>(self, service: F) -> H1Service<T, S, B, X, U>
     where
@@ -193,13 +575,7 @@ where
         S::InitError: fmt::Debug,
         S::Response: Into<Response<B>>,
     {
-        let cfg = ServiceConfig::new(
-            self.keep_alive,
-            self.client_timeout,
-            self.client_disconnect,
-            self.secure,
-            self.local_addr,
-        );
+        let cfg = self.config();
         H1Service::with_config(cfg, service.into_factory())
             .expect(self.expect)
             .upgrade(self.upgrade)
@@ -216,13 +592,7 @@ where
         S::Response: Into<Response<B>> + 'static,
         <S::Service as Service>::Future: 'static,
     {
-        let cfg = ServiceConfig::new(
-            self.keep_alive,
-            self.client_timeout,
-            self.client_disconnect,
-            self.secure,
-            self.local_addr,
-        );
+        let cfg = self.config();
         H2Service::with_config(cfg, service.into_factory()).on_connect(self.on_connect)
     }
 
@@ -236,13 +606,7 @@ where
         S::Response: Into<Response<B>> + 'static,
         <S::Service as Service>::Future: 'static,
     {
-        let cfg = ServiceConfig::new(
-            self.keep_alive,
-            self.client_timeout,
-            self.client_disconnect,
-            self.secure,
-            self.local_addr,
-        );
+        let cfg = self.config();
         HttpService::with_config(cfg, service.into_factory())
             .expect(self.expect)
             .upgrade(self.upgrade)
@@ -1,14 +1,25 @@
-use std::cell::UnsafeCell;
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell, UnsafeCell};
 use std::fmt::Write;
 use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
 use std::time::Duration;
-use std::{fmt, net};
+use std::{fmt, io, net};
 
+use actix_rt::net::TcpStream;
 use actix_rt::time::{delay_for, delay_until, Delay, Instant};
-use bytes::BytesMut;
+use bytes::{Bytes, BytesMut};
 use futures_util::{future, FutureExt};
+use http::uri::{PathAndQuery, Uri};
 use time;
 
+use crate::h2::GracefulShutdown;
+use crate::header::{HeaderName, HeaderValue};
+use crate::http::{Method, StatusCode};
+use crate::message::RequestHead;
+use crate::response::Response;
+use crate::Protocol;
+
 // "Sun, 06 Nov 1994 08:49:37 GMT".len()
 const DATE_VALUE_LENGTH: usize = 29;
 
@@ -39,6 +50,298 @@ impl From<Option<usize>> for KeepAlive {
     }
 }
 
+#[derive(Debug, PartialEq, Clone, Copy)]
+/// Trailing slash handling of request path normalization
+pub enum TrailingSlash {
+    /// Merge repeated trailing slashes, single trailing slash is kept as is
+    Merge,
+    /// Always append trailing slash
+    Append,
+    /// Trim trailing slash, root path is kept as is
+    Trim,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+/// Request path normalization policy
+///
+/// Normalization is applied to request before it enters the service.
+/// Repeated slashes are always merged into one.
+pub struct NormalizePath {
+    trailing_slash: TrailingSlash,
+    lowercase: bool,
+}
+
+impl NormalizePath {
+    /// Create normalization policy with specified trailing slash handling.
+    pub fn new(trailing_slash: TrailingSlash) -> Self {
+        NormalizePath {
+            trailing_slash,
+            lowercase: false,
+        }
+    }
+
+    /// Convert path to lowercase. By default path case is kept as is.
+    pub fn lowercase(mut self, lowercase: bool) -> Self {
+        self.lowercase = lowercase;
+        self
+    }
+
+    /// Normalize path of the request's uri in place.
+    pub(crate) fn normalize(&self, head: &mut RequestHead) {
+        let path = head.uri.path();
+        if !path.starts_with('/') || !self.need_normalize(path) {
+            return;
+        }
+
+        let mut normalized = String::with_capacity(path.len() + 1);
+        for c in path.chars() {
+            if c == '/' && normalized.ends_with('/') {
+                continue;
+            }
+            normalized.push(if self.lowercase {
+                c.to_ascii_lowercase()
+            } else {
+                c
+            });
+        }
+        match self.trailing_slash {
+            TrailingSlash::Merge => (),
+            TrailingSlash::Append => {
+                if !normalized.ends_with('/') {
+                    normalized.push('/');
+                }
+            }
+            TrailingSlash::Trim => {
+                if normalized.len() > 1 && normalized.ends_with('/') {
+                    normalized.pop();
+                }
+            }
+        }
+
+        let pq = if let Some(q) = head.uri.query() {
+            Bytes::from(format!("{}?{}", normalized, q))
+        } else {
+            Bytes::from(normalized)
+        };
+        let mut parts = head.uri.clone().into_parts();
+        if let Ok(pq) = PathAndQuery::from_maybe_shared(pq) {
+            parts.path_and_query = Some(pq);
+            if let Ok(uri) = Uri::from_parts(parts) {
+                head.uri = uri;
+            }
+        }
+    }
+
+    fn need_normalize(&self, path: &str) -> bool {
+        let trailing = match self.trailing_slash {
+            TrailingSlash::Merge => false,
+            TrailingSlash::Append => !path.ends_with('/'),
+            TrailingSlash::Trim => path.len() > 1 && path.ends_with('/'),
+        };
+        trailing
+            || path.contains("//")
+            || (self.lowercase && path.bytes().any(|b| b.is_ascii_uppercase()))
+    }
+}
+
+/// Marker of request received in TLS 1.3 early data (0-RTT)
+///
+/// Early data can be replayed by an attacker, so it is only safe for
+/// idempotent requests. Marker is inserted into request extensions by the
+/// transport, i.e. with `HttpServiceBuilder::on_connect()` data of
+/// connections accepting early data. Requests carrying it are handled
+/// according to configured `EarlyDataPolicy`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct EarlyData;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+/// Handling of requests received in TLS 1.3 early data
+pub enum EarlyDataPolicy {
+    /// Respond `425 Too Early` to every request
+    Reject,
+    /// Respond `425 Too Early` to requests other than `GET` and `HEAD`
+    SafeMethods,
+    /// Pass every request to the service, `EarlyData` marker is kept in
+    /// request extensions
+    Allow,
+}
+
+impl EarlyDataPolicy {
+    /// Check if request received in early data has to be rejected.
+    fn reject(self, method: &Method) -> bool {
+        match self {
+            EarlyDataPolicy::Reject => true,
+            EarlyDataPolicy::SafeMethods => {
+                method != Method::GET && method != Method::HEAD
+            }
+            EarlyDataPolicy::Allow => false,
+        }
+    }
+}
+
+/// Request which took longer than configured threshold to respond
+#[derive(Debug, Clone)]
+pub struct SlowRequest {
+    method: Method,
+    path: String,
+    status: StatusCode,
+    latency: Duration,
+}
+
+impl SlowRequest {
+    /// Request method
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+
+    /// Request path
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Response status
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Time from request head received until response head is sent
+    pub fn latency(&self) -> Duration {
+        self.latency
+    }
+}
+
+/// Request which response could not be written to the client
+#[derive(Debug, Clone)]
+pub struct WriteErrorContext {
+    method: Method,
+    path: String,
+    peer_addr: Option<net::SocketAddr>,
+    status: Option<StatusCode>,
+}
+
+impl WriteErrorContext {
+    /// Request method
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+
+    /// Request path
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Peer address of the connection
+    pub fn peer_addr(&self) -> Option<net::SocketAddr> {
+        self.peer_addr
+    }
+
+    /// Response status, `None` if write failed before response head was sent
+    pub fn status(&self) -> Option<StatusCode> {
+        self.status
+    }
+
+    pub(crate) fn set_status(&mut self, status: StatusCode) {
+        self.status = Some(status);
+    }
+}
+
+/// `Server-Timing` metrics of a request.
+///
+/// If server timing is enabled with `HttpServiceBuilder::server_timing()`,
+/// dispatcher stores `ServerTiming` in request extensions. Services can
+/// record additional metrics, all recorded metrics are sent to the client
+/// with `Server-Timing` response header.
+///
+/// Dispatcher records `conn` (time from connection start until request
+/// processing starts), `handshake` (http/2 handshake, first request of
+/// connection only) and `total` (time until response head is ready).
+#[derive(Debug, Clone, Default)]
+pub struct ServerTiming(Rc<RefCell<Vec<(Cow<'static, str>, Duration)>>>);
+
+impl ServerTiming {
+    /// Record metric with duration.
+    pub fn add<N: Into<Cow<'static, str>>>(&self, name: N, duration: Duration) {
+        self.0.borrow_mut().push((name.into(), duration));
+    }
+
+    /// Duration of recorded metric.
+    pub fn get(&self, name: &str) -> Option<Duration> {
+        self.0
+            .borrow()
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, dur)| *dur)
+    }
+
+    /// Encode metrics as `Server-Timing` header value, durations in milliseconds.
+    fn header_value(&self) -> Option<HeaderValue> {
+        let mut buf = String::new();
+        for (name, dur) in self.0.borrow().iter() {
+            if !buf.is_empty() {
+                buf.push_str(", ");
+            }
+            let _ = write!(buf, "{};dur={:.3}", name, dur.as_secs_f64() * 1000.0);
+        }
+        HeaderValue::from_str(&buf).ok()
+    }
+}
+
+/// Start of request, tracked if server timing is enabled
+pub(crate) struct ServerTimingTimer {
+    timing: ServerTiming,
+    start: Instant,
+}
+
+impl ServerTimingTimer {
+    /// Record `total` metric and build `Server-Timing` header.
+    pub(crate) fn finish(&self) -> Option<(HeaderName, HeaderValue)> {
+        self.timing.add("total", self.start.elapsed());
+        self.timing
+            .header_value()
+            .map(|value| (HeaderName::from_static("server-timing"), value))
+    }
+}
+
+/// Limit of concurrent requests, shared by connections of a worker
+struct RequestLimit {
+    max: usize,
+    count: Cell<usize>,
+    waiters: RefCell<Vec<Waker>>,
+}
+
+impl RequestLimit {
+    fn is_reached(&self, cx: &mut Context<'_>) -> bool {
+        if self.count.get() < self.max {
+            return false;
+        }
+        let mut waiters = self.waiters.borrow_mut();
+        if !waiters.iter().any(|w| w.will_wake(cx.waker())) {
+            waiters.push(cx.waker().clone());
+        }
+        true
+    }
+}
+
+/// Slot of concurrent request limit, released on drop
+pub(crate) struct RequestPermit(Rc<RequestLimit>);
+
+impl Drop for RequestPermit {
+    fn drop(&mut self) {
+        let limit = &self.0;
+        limit.count.set(limit.count.get() - 1);
+        for waker in limit.waiters.borrow_mut().drain(..) {
+            waker.wake();
+        }
+    }
+}
+
+/// Start of request, tracked if slow request threshold is configured
+pub(crate) struct SlowRequestTimer {
+    method: Method,
+    path: String,
+    start: Instant,
+}
+
 /// Http service configuration
 pub struct ServiceConfig(Rc<Inner>);
 
@@ -49,6 +352,25 @@ struct Inner {
     ka_enabled: bool,
     secure: bool,
     local_addr: Option<std::net::SocketAddr>,
+    normalize_path: Option<NormalizePath>,
+    max_requests: usize,
+    bad_request: Option<Rc<dyn Fn() -> Response>>,
+    alt_svc: Option<HeaderValue>,
+    load_shed: Option<u64>,
+    alpn_fallback: Protocol,
+    early_data: EarlyDataPolicy,
+    request_limit: Option<Rc<RequestLimit>>,
+    h2_idle_timeout: Option<Duration>,
+    graceful_shutdown: Option<GracefulShutdown>,
+    client_shutdown: u64,
+    send_buffer: Option<usize>,
+    recv_buffer: Option<usize>,
+    request_deadline: Option<Duration>,
+    request_deadline_status: StatusCode,
+    slow_request_threshold: Option<Duration>,
+    slow_request_logger: Option<Rc<dyn Fn(&SlowRequest)>>,
+    server_timing: bool,
+    write_error_hook: Option<Rc<dyn Fn(&WriteErrorContext, &io::Error)>>,
     timer: DateService,
 }
 
@@ -91,10 +413,319 @@ impl ServiceConfig {
             client_disconnect,
             secure,
             local_addr,
+            normalize_path: None,
+            max_requests: 0,
+            bad_request: None,
+            alt_svc: None,
+            load_shed: None,
+            alpn_fallback: Protocol::Http1,
+            early_data: EarlyDataPolicy::SafeMethods,
+            request_limit: None,
+            h2_idle_timeout: None,
+            graceful_shutdown: None,
+            client_shutdown: 0,
+            send_buffer: None,
+            recv_buffer: None,
+            request_deadline: None,
+            request_deadline_status: StatusCode::SERVICE_UNAVAILABLE,
+            slow_request_threshold: None,
+            slow_request_logger: None,
+            server_timing: false,
+            write_error_hook: None,
             timer: DateService::new(),
         }))
     }
 
+    /// Set request path normalization policy.
+    pub(crate) fn set_normalize_path(mut self, policy: Option<NormalizePath>) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("Multiple copies exist")
+            .normalize_path = policy;
+        self
+    }
+
+    /// Set maximum number of requests served per keep-alive connection.
+    pub(crate) fn set_max_requests_per_connection(mut self, max: usize) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("Multiple copies exist")
+            .max_requests = max;
+        self
+    }
+
+    /// Set factory of response for malformed requests.
+    pub(crate) fn set_bad_request_response(
+        mut self,
+        f: Option<Rc<dyn Fn() -> Response>>,
+    ) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("Multiple copies exist")
+            .bad_request = f;
+        self
+    }
+
+    /// Set `Alt-Svc` header value sent with responses.
+    pub(crate) fn set_alt_svc(mut self, value: Option<HeaderValue>) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("Multiple copies exist")
+            .alt_svc = value;
+        self
+    }
+
+    /// Set `Retry-After` seconds of responses to shed requests.
+    pub(crate) fn set_load_shed(mut self, retry_after: Option<u64>) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("Multiple copies exist")
+            .load_shed = retry_after;
+        self
+    }
+
+    /// Set protocol of connections without negotiated ALPN protocol.
+    pub(crate) fn set_alpn_fallback(mut self, proto: Protocol) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("Multiple copies exist")
+            .alpn_fallback = proto;
+        self
+    }
+
+    /// Set handling of requests received in early data.
+    pub(crate) fn set_early_data(mut self, policy: EarlyDataPolicy) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("Multiple copies exist")
+            .early_data = policy;
+        self
+    }
+
+    /// Set maximum number of concurrent requests, `0` disables limit.
+    pub(crate) fn set_max_concurrent_requests(mut self, max: usize) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("Multiple copies exist")
+            .request_limit = if max > 0 {
+            Some(Rc::new(RequestLimit {
+                max,
+                count: Cell::new(0),
+                waiters: RefCell::new(Vec::new()),
+            }))
+        } else {
+            None
+        };
+        self
+    }
+
+    /// Check if concurrent request limit is reached.
+    ///
+    /// Current task is woken up once a request slot is released.
+    pub(crate) fn request_limit_reached(&self, cx: &mut Context<'_>) -> bool {
+        match self.0.request_limit {
+            Some(ref limit) => limit.is_reached(cx),
+            None => false,
+        }
+    }
+
+    /// Take slot of concurrent request limit.
+    ///
+    /// Resolves to `None` if limit is disabled. Returns `Pending` if limit
+    /// is reached, current task is woken up once a slot is released.
+    pub(crate) fn poll_request_permit(
+        &self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<RequestPermit>> {
+        match self.0.request_limit {
+            Some(ref limit) => {
+                if limit.is_reached(cx) {
+                    Poll::Pending
+                } else {
+                    limit.count.set(limit.count.get() + 1);
+                    Poll::Ready(Some(RequestPermit(limit.clone())))
+                }
+            }
+            None => Poll::Ready(None),
+        }
+    }
+
+    /// Set idle timeout of http/2 connections without open streams.
+    pub(crate) fn set_h2_idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("Multiple copies exist")
+            .h2_idle_timeout = timeout;
+        self
+    }
+
+    /// Set handle to gracefully shut down connections.
+    pub(crate) fn set_graceful_shutdown(
+        mut self,
+        handle: Option<GracefulShutdown>,
+    ) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("Multiple copies exist")
+            .graceful_shutdown = handle;
+        self
+    }
+
+    /// Set timeout of connections after graceful shutdown begins, in milliseconds.
+    pub(crate) fn set_client_shutdown(mut self, val: u64) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("Multiple copies exist")
+            .client_shutdown = val;
+        self
+    }
+
+    /// Set `SO_SNDBUF` and `SO_RCVBUF` sizes of accepted sockets.
+    pub(crate) fn set_socket_buffers(
+        mut self,
+        send: Option<usize>,
+        recv: Option<usize>,
+    ) -> Self {
+        let inner = Rc::get_mut(&mut self.0).expect("Multiple copies exist");
+        inner.send_buffer = send;
+        inner.recv_buffer = recv;
+        self
+    }
+
+    /// Set deadline of response head and status of response sent once
+    /// the deadline is reached.
+    pub(crate) fn set_request_deadline(
+        mut self,
+        deadline: Option<Duration>,
+        status: StatusCode,
+    ) -> Self {
+        let inner = Rc::get_mut(&mut self.0).expect("Multiple copies exist");
+        inner.request_deadline = deadline;
+        inner.request_deadline_status = status;
+        self
+    }
+
+    /// Set latency threshold of slow requests and logger of such requests.
+    pub(crate) fn set_slow_request(
+        mut self,
+        threshold: Option<Duration>,
+        logger: Option<Rc<dyn Fn(&SlowRequest)>>,
+    ) -> Self {
+        let inner = Rc::get_mut(&mut self.0).expect("Multiple copies exist");
+        inner.slow_request_threshold = threshold;
+        inner.slow_request_logger = logger;
+        self
+    }
+
+    /// Enable `Server-Timing` response header.
+    pub(crate) fn set_server_timing(mut self, enabled: bool) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("Multiple copies exist")
+            .server_timing = enabled;
+        self
+    }
+
+    /// Set hook called when writing response fails.
+    pub(crate) fn set_write_error_hook(
+        mut self,
+        hook: Option<Rc<dyn Fn(&WriteErrorContext, &io::Error)>>,
+    ) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("Multiple copies exist")
+            .write_error_hook = hook;
+        self
+    }
+
+    /// Start recording of request metrics if server timing is enabled.
+    ///
+    /// `ServerTiming` is stored in request extensions.
+    pub(crate) fn server_timing_timer(
+        &self,
+        head: &RequestHead,
+        established: Instant,
+        handshake: Option<Duration>,
+    ) -> Option<ServerTimingTimer> {
+        if !self.0.server_timing {
+            return None;
+        }
+        let start = Instant::now();
+        let timing = ServerTiming::default();
+        timing.add("conn", start - established);
+        if let Some(handshake) = handshake {
+            timing.add("handshake", handshake);
+        }
+        head.extensions_mut().insert(timing.clone());
+        Some(ServerTimingTimer { timing, start })
+    }
+
+    /// Start tracking of request latency if slow request threshold is configured.
+    pub(crate) fn slow_request_timer(
+        &self,
+        head: &RequestHead,
+    ) -> Option<SlowRequestTimer> {
+        self.0.slow_request_threshold.map(|_| SlowRequestTimer {
+            method: head.method.clone(),
+            path: head.uri.path().to_owned(),
+            start: Instant::now(),
+        })
+    }
+
+    /// Capture request context if write error hook is configured.
+    pub(crate) fn write_error_context(
+        &self,
+        head: &RequestHead,
+    ) -> Option<WriteErrorContext> {
+        self.0.write_error_hook.as_ref().map(|_| WriteErrorContext {
+            method: head.method.clone(),
+            path: head.uri.path().to_owned(),
+            peer_addr: head.peer_addr,
+            status: None,
+        })
+    }
+
+    /// Call write error hook with context of the failed request.
+    pub(crate) fn write_error(&self, ctx: &WriteErrorContext, err: &io::Error) {
+        if let Some(ref hook) = self.0.write_error_hook {
+            hook(ctx, err);
+        }
+    }
+
+    /// Log request if its latency exceeds slow request threshold.
+    pub(crate) fn log_slow_request(&self, timer: &SlowRequestTimer, status: StatusCode) {
+        let threshold = match self.0.slow_request_threshold {
+            Some(threshold) => threshold,
+            None => return,
+        };
+        let latency = timer.start.elapsed();
+        if latency < threshold {
+            return;
+        }
+
+        let req = SlowRequest {
+            method: timer.method.clone(),
+            path: timer.path.clone(),
+            status,
+            latency,
+        };
+        if let Some(ref logger) = self.0.slow_request_logger {
+            logger(&req);
+        } else {
+            log::warn!(
+                "Slow request: {} {} {} {:?}",
+                req.method,
+                req.path,
+                req.status.as_u16(),
+                req.latency
+            );
+        }
+    }
+
+    /// Apply configured socket options to accepted tcp stream.
+    ///
+    /// Called before tls handshake. Failure is not fatal, connection is
+    /// served with os defaults.
+    pub(crate) fn apply_socket_options(&self, io: &TcpStream) {
+        if let Some(size) = self.0.send_buffer {
+            if let Err(e) = io.set_send_buffer_size(size) {
+                log::warn!("Can not set socket send buffer size: {}", e);
+            }
+        }
+        if let Some(size) = self.0.recv_buffer {
+            if let Err(e) = io.set_recv_buffer_size(size) {
+                log::warn!("Can not set socket receive buffer size: {}", e);
+            }
+        }
+    }
+
     #[inline]
     /// Returns true if connection is secure(https)
     pub fn secure(&self) -> bool {
@@ -107,6 +738,119 @@ impl ServiceConfig {
         self.0.local_addr
     }
 
+    #[inline]
+    /// Request path normalization policy if configured.
+    pub fn normalize_path(&self) -> Option<&NormalizePath> {
+        self.0.normalize_path.as_ref()
+    }
+
+    #[inline]
+    /// Maximum number of requests per connection, 0 means no limit.
+    pub fn max_requests_per_connection(&self) -> usize {
+        self.0.max_requests
+    }
+
+    #[inline]
+    /// Factory of response for malformed requests if configured.
+    pub fn bad_request_response(&self) -> Option<&dyn Fn() -> Response> {
+        self.0.bad_request.as_ref().map(|f| f.as_ref())
+    }
+
+    #[inline]
+    /// `Alt-Svc` header value sent with responses if configured.
+    pub fn alt_svc(&self) -> Option<&HeaderValue> {
+        self.0.alt_svc.as_ref()
+    }
+
+    #[inline]
+    /// `Retry-After` seconds sent with `503` response, if requests are shed
+    /// while service is not ready.
+    pub fn load_shed(&self) -> Option<u64> {
+        self.0.load_shed
+    }
+
+    #[inline]
+    /// Protocol of connections without negotiated ALPN protocol.
+    pub fn alpn_fallback(&self) -> Protocol {
+        self.0.alpn_fallback
+    }
+
+    /// Select protocol of connection by negotiated ALPN identifier.
+    pub(crate) fn alpn_protocol(&self, id: Option<&[u8]>) -> Protocol {
+        id.and_then(Protocol::from_alpn)
+            .unwrap_or(self.0.alpn_fallback)
+    }
+
+    #[inline]
+    /// Handling of requests received in TLS 1.3 early data.
+    pub fn early_data(&self) -> EarlyDataPolicy {
+        self.0.early_data
+    }
+
+    /// Check if request received in early data has to be rejected.
+    pub(crate) fn early_data_rejected(&self, head: &RequestHead) -> bool {
+        head.extensions().contains::<EarlyData>()
+            && self.0.early_data.reject(&head.method)
+    }
+
+    #[inline]
+    /// Maximum number of concurrent requests, `0` if not limited.
+    pub fn max_concurrent_requests(&self) -> usize {
+        self.0
+            .request_limit
+            .as_ref()
+            .map(|limit| limit.max)
+            .unwrap_or(0)
+    }
+
+    #[inline]
+    /// Idle timeout of http/2 connections without open streams if configured.
+    pub fn h2_idle_timeout(&self) -> Option<Duration> {
+        self.0.h2_idle_timeout
+    }
+
+    #[inline]
+    /// Handle to gracefully shut down connections if configured.
+    pub fn graceful_shutdown(&self) -> Option<&GracefulShutdown> {
+        self.0.graceful_shutdown.as_ref()
+    }
+
+    #[inline]
+    /// Requested `SO_SNDBUF` size of accepted sockets if configured.
+    pub fn socket_send_buffer(&self) -> Option<usize> {
+        self.0.send_buffer
+    }
+
+    #[inline]
+    /// Requested `SO_RCVBUF` size of accepted sockets if configured.
+    pub fn socket_recv_buffer(&self) -> Option<usize> {
+        self.0.recv_buffer
+    }
+
+    #[inline]
+    /// Maximum time from request start to response head if configured.
+    pub fn request_deadline(&self) -> Option<Duration> {
+        self.0.request_deadline
+    }
+
+    #[inline]
+    /// Status of response sent once request deadline is reached.
+    pub fn request_deadline_status(&self) -> StatusCode {
+        self.0.request_deadline_status
+    }
+
+    #[inline]
+    /// Latency threshold of slow request logging if configured.
+    pub fn slow_request_threshold(&self) -> Option<Duration> {
+        self.0.slow_request_threshold
+    }
+
+    #[inline]
+    /// Return state of `Server-Timing` response header
+    pub fn server_timing(&self) -> bool {
+        self.0.server_timing
+    }
+
     #[inline]
     /// Keep alive duration if configured.
     pub fn keep_alive(&self) -> Option<Duration> {
@@ -152,6 +896,16 @@ impl ServiceConfig {
         }
     }
 
+    /// Client shutdown timer, started once graceful shutdown begins
+    pub fn client_shutdown_timer(&self) -> Option<Instant> {
+        let delay = self.0.client_shutdown;
+        if delay != 0 {
+            Some(self.0.timer.now() + Duration::from_millis(delay))
+        } else {
+            None
+        }
+    }
+
     #[inline]
     /// Return keep-alive timer delay is configured.
     pub fn keep_alive_timer(&self) -> Option<Delay> {
@@ -296,4 +1050,33 @@ mod tests {
         settings.set_date(&mut buf2);
         assert_eq!(buf1, buf2);
     }
+
+    fn normalize(policy: NormalizePath, uri: &str) -> String {
+        let mut head = RequestHead::default();
+        head.uri = uri.parse().unwrap();
+        policy.normalize(&mut head);
+        head.uri.to_string()
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        let merge = NormalizePath::new(TrailingSlash::Merge);
+        assert_eq!(normalize(merge, "/a//b/"), "/a/b/");
+        assert_eq!(normalize(merge, "//a///b//?q=//"), "/a/b/?q=//");
+        assert_eq!(normalize(merge, "/a/b"), "/a/b");
+
+        let append = NormalizePath::new(TrailingSlash::Append);
+        assert_eq!(normalize(append, "/a//b/"), "/a/b/");
+        assert_eq!(normalize(append, "/a//b"), "/a/b/");
+        assert_eq!(normalize(append, "/a?q=1"), "/a/?q=1");
+
+        let trim = NormalizePath::new(TrailingSlash::Trim);
+        assert_eq!(normalize(trim, "/a//b/"), "/a/b");
+        assert_eq!(normalize(trim, "/a//b//"), "/a/b");
+        assert_eq!(normalize(trim, "//"), "/");
+        assert_eq!(normalize(trim, "/"), "/");
+
+        let lower = NormalizePath::new(TrailingSlash::Merge).lowercase(true);
+        assert_eq!(normalize(lower, "/A//B/?Q=1"), "/a/b/?Q=1");
+    }
 }
@@ -426,6 +426,10 @@ pub enum DispatchError {
     #[display(fmt = "The first request did not complete within the specified timeout")]
     SlowRequestTimeout,
 
+    /// Http/2 handshake did not complete within the specified timeout.
+    #[display(fmt = "Http/2 handshake did not complete within the specified timeout")]
+    HandshakeTimeout,
+
     /// Disconnect timeout. Makes sense for ssl streams.
     #[display(fmt = "Connection shutdown timeout")]
     DisconnectTimeout,
@@ -5,19 +5,26 @@ use std::task::{Context, Poll};
 use std::{fmt, io, net};
 
 use actix_codec::{AsyncRead, AsyncWrite, Decoder, Encoder, Framed, FramedParts};
-use actix_rt::time::{delay_until, Delay, Instant};
+use actix_rt::time::{delay_for, delay_until, Delay, Instant};
 use actix_service::Service;
 use bitflags::bitflags;
 use bytes::{Buf, BytesMut};
+use futures_channel::oneshot;
+use futures_util::future::Shared;
 use log::{error, trace};
 
 use crate::body::{Body, BodySize, MessageBody, ResponseBody};
 use crate::cloneable::CloneableService;
-use crate::config::ServiceConfig;
+use crate::config::{
+    RequestPermit, ServerTimingTimer, ServiceConfig, SlowRequestTimer, WriteErrorContext,
+};
 use crate::error::{DispatchError, Error};
 use crate::error::{ParseError, PayloadError};
+use crate::header::{ALT_SVC, RETRY_AFTER};
 use crate::helpers::DataFactory;
+use crate::http::StatusCode;
 use crate::httpmessage::HttpMessage;
+use crate::message::ConnectionType;
 use crate::request::Request;
 use crate::response::Response;
 
@@ -38,6 +45,7 @@ bitflags! {
         const READ_DISCONNECT    = 0b0001_0000;
         const WRITE_DISCONNECT   = 0b0010_0000;
         const UPGRADE            = 0b0100_0000;
+        const GRACEFUL_SHUTDOWN  = 0b1000_0000;
     }
 }
 
@@ -91,9 +99,19 @@ where
     state: State<S, B, X>,
     payload: Option<PayloadSender>,
     messages: VecDeque<DispatcherMessage>,
+    requests: usize,
+    responses: usize,
 
     ka_expire: Instant,
     ka_timer: Option<Delay>,
+    shutdown: Option<Shared<oneshot::Receiver<()>>>,
+    shutdown_timer: Option<Delay>,
+    deadline: Option<Delay>,
+    slow_request: Option<SlowRequestTimer>,
+    server_timing: Option<ServerTimingTimer>,
+    write_ctx: Option<WriteErrorContext>,
+    established: Instant,
+    permit: Option<RequestPermit>,
 
     io: T,
     read_buf: BytesMut,
@@ -104,7 +122,7 @@ where
 enum DispatcherMessage {
     Item(Request),
     Upgrade(Request),
-    Error(Response<()>),
+    Error(Response),
 }
 
 enum State<S, B, X>
@@ -228,6 +246,7 @@ where
         } else {
             (config.now(), None)
         };
+        let shutdown = config.graceful_shutdown().map(|s| s.signal());
 
         Dispatcher {
             inner: DispatcherState::Normal(InnerDispatcher {
@@ -236,6 +255,8 @@ where
                 state: State::None,
                 error: None,
                 messages: VecDeque::new(),
+                requests: 0,
+                responses: 0,
                 io,
                 codec,
                 read_buf,
@@ -247,6 +268,14 @@ where
                 peer_addr,
                 ka_expire,
                 ka_timer,
+                shutdown,
+                shutdown_timer: None,
+                deadline: None,
+                slow_request: None,
+                server_timing: None,
+                write_ctx: None,
+                established: Instant::now(),
+                permit: None,
             }),
         }
     }
@@ -302,10 +331,9 @@ where
                 .poll_write(cx, &self.write_buf[written..])
             {
                 Poll::Ready(Ok(0)) => {
-                    return Err(DispatchError::Io(io::Error::new(
-                        io::ErrorKind::WriteZero,
-                        "",
-                    )));
+                    return Err(
+                        self.write_failed(io::Error::new(io::ErrorKind::WriteZero, ""))
+                    );
                 }
                 Poll::Ready(Ok(n)) => {
                     written += n;
@@ -316,22 +344,66 @@ where
                     }
                     return Ok(true);
                 }
-                Poll::Ready(Err(err)) => return Err(DispatchError::Io(err)),
+                Poll::Ready(Err(err)) => return Err(self.write_failed(err)),
             }
         }
         if written == self.write_buf.len() {
             unsafe { self.write_buf.set_len(0) }
+            // response is written completely
+            if self.state.is_empty() {
+                self.write_ctx = None;
+            }
         } else {
             self.write_buf.advance(written);
         }
         Ok(false)
     }
 
+    /// Notify write error hook about response which can not be written.
+    fn write_failed(&mut self, err: io::Error) -> DispatchError {
+        if let Some(ctx) = self.write_ctx.take() {
+            self.codec.config().write_error(&ctx, &err);
+        }
+        DispatchError::Io(err)
+    }
+
     fn send_response(
         &mut self,
-        message: Response<()>,
+        mut message: Response<()>,
         body: ResponseBody<B>,
     ) -> Result<State<S, B, X>, DispatchError> {
+        // response head is ready, request deadline is not relevant anymore
+        self.deadline = None;
+        self.permit = None;
+        if let Some(timer) = self.slow_request.take() {
+            self.codec
+                .config()
+                .log_slow_request(&timer, message.status());
+        }
+        if let Some(timer) = self.server_timing.take() {
+            if let Some((name, value)) = timer.finish() {
+                message.headers_mut().append(name, value);
+            }
+        }
+        if let Some(ref mut ctx) = self.write_ctx {
+            ctx.set_status(message.status());
+        }
+
+        // close connection after last allowed response
+        self.responses += 1;
+        if self.max_requests_reached(self.responses)
+            || self.flags.contains(Flags::GRACEFUL_SHUTDOWN)
+        {
+            message
+                .head_mut()
+                .set_connection_type(ConnectionType::Close);
+        }
+        if let Some(alt_svc) = self.codec.config().alt_svc() {
+            if !message.headers().contains_key(ALT_SVC) {
+                message.headers_mut().insert(ALT_SVC, alt_svc.clone());
+            }
+        }
+
         self.codec
             .encode(Message::Item((message, body.size())), &mut self.write_buf)
             .map_err(|err| {
@@ -348,6 +420,11 @@ where
         }
     }
 
+    fn max_requests_reached(&self, count: usize) -> bool {
+        let max = self.codec.config().max_requests_per_connection();
+        max != 0 && count >= max
+    }
+
     fn send_continue(&mut self) {
         self.write_buf
             .extend_from_slice(b"HTTP/1.1 100 Continue\r\n\r\n");
@@ -361,10 +438,15 @@ where
             let state = match self.state {
                 State::None => match self.messages.pop_front() {
                     Some(DispatcherMessage::Item(req)) => {
+                        if !self.acquire_permit(cx) {
+                            self.messages.push_front(DispatcherMessage::Item(req));
+                            return Ok(PollResponse::DoNothing);
+                        }
                         Some(self.handle_request(req, cx)?)
                     }
                     Some(DispatcherMessage::Error(res)) => {
-                        Some(self.send_response(res, ResponseBody::Other(Body::Empty))?)
+                        let (res, body) = res.replace_body(());
+                        Some(self.send_response(res, body.into_body())?)
                     }
                     Some(DispatcherMessage::Upgrade(req)) => {
                         return Ok(PollResponse::Upgrade(req));
@@ -383,7 +465,7 @@ where
                             let (res, body) = res.replace_body(());
                             Some(self.send_response(res, body.into_body())?)
                         }
-                        Poll::Pending => None,
+                        Poll::Pending => self.poll_deadline(cx)?,
                     }
                 }
                 State::ServiceCall(ref mut fut) => {
@@ -398,7 +480,7 @@ where
                             let (res, body) = res.replace_body(());
                             Some(self.send_response(res, body.into_body())?)
                         }
-                        Poll::Pending => None,
+                        Poll::Pending => self.poll_deadline(cx)?,
                     }
                 }
                 State::SendPayload(ref mut stream) => {
@@ -456,11 +538,63 @@ where
         Ok(PollResponse::DoNothing)
     }
 
+    /// Take slot of concurrent request limit, `false` if limit is reached.
+    fn acquire_permit(&mut self, cx: &mut Context<'_>) -> bool {
+        match self.codec.config().poll_request_permit(cx) {
+            Poll::Ready(permit) => {
+                self.permit = permit;
+                true
+            }
+            Poll::Pending => false,
+        }
+    }
+
+    /// Abort pending service call if request deadline is reached.
+    fn poll_deadline(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Result<Option<State<S, B, X>>, DispatchError> {
+        let expired = match self.deadline {
+            Some(ref mut delay) => Pin::new(delay).poll(cx).is_ready(),
+            None => false,
+        };
+        if !expired {
+            return Ok(None);
+        }
+
+        trace!("Request deadline is reached, aborting request");
+        if let Some(mut payload) = self.payload.take() {
+            payload.set_error(PayloadError::Incomplete(None));
+        }
+        let mut res = Response::new(self.codec.config().request_deadline_status());
+        res.head_mut().set_connection_type(ConnectionType::Close);
+        let (res, body) = res.replace_body(());
+        self.send_response(res, body.into_body()).map(Some)
+    }
+
     fn handle_request(
         &mut self,
         req: Request,
         cx: &mut Context<'_>,
     ) -> Result<State<S, B, X>, DispatchError> {
+        if let Some(deadline) = self.codec.config().request_deadline() {
+            self.deadline = Some(delay_for(deadline));
+        }
+        self.slow_request = self.codec.config().slow_request_timer(req.head());
+        self.server_timing =
+            self.codec
+                .config()
+                .server_timing_timer(req.head(), self.established, None);
+        self.write_ctx = self.codec.config().write_error_context(req.head());
+
+        // Reject request received in early data
+        if self.codec.config().early_data_rejected(req.head()) {
+            trace!("Rejecting request received in early data");
+            let res = Response::new(StatusCode::from_u16(425).unwrap());
+            let (res, body) = res.replace_body(());
+            return self.send_response(res, body.into_body());
+        }
+
         // Handle `EXPECT: 100-Continue` header
         let req = if req.head().expect() {
             let mut task = self.expect.call(req);
@@ -481,6 +615,18 @@ where
             req
         };
 
+        // Shed request if service is not ready
+        if let Some(retry_after) = self.codec.config().load_shed() {
+            if self.service.poll_ready(cx).is_pending() {
+                trace!("Service is not ready, shedding request");
+                let res = Response::ServiceUnavailable()
+                    .header(RETRY_AFTER, retry_after)
+                    .finish();
+                let (res, body) = res.replace_body(());
+                return self.send_response(res, body.into_body());
+            }
+        }
+
         // Call service
         let mut task = self.service.call(req);
         match unsafe { Pin::new_unchecked(&mut task) }.poll(cx) {
@@ -509,6 +655,11 @@ where
 
         let mut updated = false;
         loop {
+            // do not read requests beyond allowed number
+            if self.payload.is_none() && self.max_requests_reached(self.requests) {
+                break;
+            }
+
             match self.codec.decode(&mut self.read_buf) {
                 Ok(Some(msg)) => {
                     updated = true;
@@ -516,9 +667,14 @@ where
 
                     match msg {
                         Message::Item(mut req) => {
+                            self.requests += 1;
                             let pl = self.codec.message_type();
                             req.head_mut().peer_addr = self.peer_addr;
 
+                            if let Some(policy) = self.codec.config().normalize_path() {
+                                policy.normalize(req.head_mut());
+                            }
+
                             // set on_connect data
                             if let Some(ref on_connect) = self.on_connect {
                                 on_connect.set(&mut req.extensions_mut());
@@ -537,7 +693,7 @@ where
                             }
 
                             // handle request early
-                            if self.state.is_empty() {
+                            if self.state.is_empty() && self.acquire_permit(cx) {
                                 self.state = self.handle_request(req, cx)?;
                             } else {
                                 self.messages.push_back(DispatcherMessage::Item(req));
@@ -552,7 +708,7 @@ where
                                 );
                                 self.flags.insert(Flags::READ_DISCONNECT);
                                 self.messages.push_back(DispatcherMessage::Error(
-                                    Response::InternalServerError().finish().drop_body(),
+                                    Response::InternalServerError().finish(),
                                 ));
                                 self.error = Some(DispatchError::InternalError);
                                 break;
@@ -565,7 +721,7 @@ where
                                 error!("Internal server error: unexpected eof");
                                 self.flags.insert(Flags::READ_DISCONNECT);
                                 self.messages.push_back(DispatcherMessage::Error(
-                                    Response::InternalServerError().finish().drop_body(),
+                                    Response::InternalServerError().finish(),
                                 ));
                                 self.error = Some(DispatchError::InternalError);
                                 break;
@@ -585,9 +741,11 @@ where
                     }
 
                     // Malformed requests should be responded with 400
-                    self.messages.push_back(DispatcherMessage::Error(
-                        Response::BadRequest().finish().drop_body(),
-                    ));
+                    let res = match self.codec.config().bad_request_response() {
+                        Some(f) => f(),
+                        None => Response::BadRequest().finish(),
+                    };
+                    self.messages.push_back(DispatcherMessage::Error(res));
                     self.flags.insert(Flags::READ_DISCONNECT);
                     self.error = Some(e.into());
                     break;
@@ -603,6 +761,31 @@ where
         Ok(updated)
     }
 
+    /// Stops keep-alive once graceful shutdown is initiated via
+    /// `GracefulShutdown` handle, connection is dropped if it outlives
+    /// client shutdown timeout.
+    fn poll_shutdown(&mut self, cx: &mut Context<'_>) -> Result<(), DispatchError> {
+        if let Some(ref mut signal) = self.shutdown {
+            if Pin::new(signal).poll(cx).is_ready() {
+                trace!("Http/1 connection is shutting down gracefully");
+                self.flags.insert(Flags::GRACEFUL_SHUTDOWN);
+                self.shutdown = None;
+                self.shutdown_timer = self
+                    .codec
+                    .config()
+                    .client_shutdown_timer()
+                    .map(delay_until);
+            }
+        }
+        if let Some(ref mut timer) = self.shutdown_timer {
+            if Pin::new(timer).poll(cx).is_ready() {
+                trace!("Http/1 connection shutdown timeout, dropping connection");
+                return Err(DispatchError::DisconnectTimeout);
+            }
+        }
+        Ok(())
+    }
+
     /// keep-alive timer
     fn poll_keepalive(&mut self, cx: &mut Context<'_>) -> Result<(), DispatchError> {
         if self.ka_timer.is_none() {
@@ -714,6 +897,7 @@ where
         match self.as_mut().inner {
             DispatcherState::Normal(ref mut inner) => {
                 inner.poll_keepalive(cx)?;
+                inner.poll_shutdown(cx)?;
 
                 if inner.flags.contains(Flags::SHUTDOWN) {
                     if inner.flags.contains(Flags::WRITE_DISCONNECT) {
@@ -736,7 +920,9 @@ where
                     // read socket into a buf
                     let should_disconnect =
                         if !inner.flags.contains(Flags::READ_DISCONNECT) {
-                            read_available(cx, &mut inner.io, &mut inner.read_buf)?
+                            // connection is broken, response can not be written
+                            read_available(cx, &mut inner.io, &mut inner.read_buf)
+                                .map_err(|err| inner.write_failed(err))?
                         } else {
                             None
                         };
@@ -85,7 +85,9 @@ where
         Error = DispatchError,
         InitError = (),
     > {
-        pipeline_factory(|io: TcpStream| {
+        let cfg = self.cfg.clone();
+        pipeline_factory(move |io: TcpStream| {
+            cfg.apply_socket_options(&io);
             let peer_addr = io.peer_addr().ok();
             ok((io, peer_addr))
         })
@@ -129,7 +131,12 @@ mod openssl {
             Error = SslError<HandshakeError<TcpStream>, DispatchError>,
             InitError = (),
         > {
-            pipeline_factory(
+            let cfg = self.cfg.clone();
+            pipeline_factory(move |io: TcpStream| {
+                cfg.apply_socket_options(&io);
+                ok::<_, SslError<HandshakeError<TcpStream>, DispatchError>>(io)
+            })
+            .and_then(
                 Acceptor::new(acceptor)
                     .map_err(SslError::Ssl)
                     .map_init_err(|_| panic!()),
@@ -179,7 +186,12 @@ mod rustls {
             Error = SslError<io::Error, DispatchError>,
             InitError = (),
         > {
-            pipeline_factory(
+            let cfg = self.cfg.clone();
+            pipeline_factory(move |io: TcpStream| {
+                cfg.apply_socket_options(&io);
+                ok::<_, SslError<io::Error, DispatchError>>(io)
+            })
+            .and_then(
                 Acceptor::new(config)
                     .map_err(SslError::Ssl)
                     .map_init_err(|_| panic!()),
@@ -454,7 +466,8 @@ where
             ready
         };
 
-        if ready {
+        // stop accepting connections while concurrent request limit is reached
+        if ready && !self.cfg.request_limit_reached(cx) {
             Poll::Ready(Ok(()))
         } else {
             Poll::Pending
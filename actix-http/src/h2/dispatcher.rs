@@ -1,22 +1,32 @@
+use std::cell::Cell;
+use std::collections::VecDeque;
 use std::convert::TryFrom;
 use std::future::Future;
 use std::marker::PhantomData;
 use std::net;
 use std::pin::Pin;
+use std::rc::Rc;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
 use actix_codec::{AsyncRead, AsyncWrite};
-use actix_rt::time::{Delay, Instant};
+use actix_rt::time::{delay_for, delay_until, Delay, Instant};
 use actix_service::Service;
+use actix_utils::task::LocalWaker;
 use bytes::{Bytes, BytesMut};
+use futures_channel::oneshot;
+use futures_util::future::Shared;
 use h2::server::{Connection, SendResponse};
 use h2::SendStream;
-use http::header::{HeaderValue, CONNECTION, CONTENT_LENGTH, DATE, TRANSFER_ENCODING};
+use http::header::{
+    HeaderValue, ALT_SVC, CONNECTION, CONTENT_LENGTH, DATE, RETRY_AFTER,
+    TRANSFER_ENCODING,
+};
 use log::{error, trace};
 
 use crate::body::{BodySize, MessageBody, ResponseBody};
 use crate::cloneable::CloneableService;
-use crate::config::ServiceConfig;
+use crate::config::{RequestPermit, ServerTimingTimer, ServiceConfig, SlowRequestTimer};
 use crate::error::{DispatchError, Error};
 use crate::helpers::DataFactory;
 use crate::httpmessage::HttpMessage;
@@ -40,9 +50,43 @@ where
     peer_addr: Option<net::SocketAddr>,
     ka_expire: Instant,
     ka_timer: Option<Delay>,
+    streams: Option<Rc<ActiveStreams>>,
+    idle_timer: Option<Delay>,
+    shutdown: Option<Shared<oneshot::Receiver<()>>>,
+    shutdown_timer: Option<Delay>,
+    established: Instant,
+    handshake: Option<Duration>,
+    queue: VecDeque<(Request, SendResponse<Bytes>, Option<StreamGuard>)>,
     _t: PhantomData<B>,
 }
 
+/// Number of streams being served, tracked for idle timeout.
+#[derive(Default)]
+struct ActiveStreams {
+    count: Cell<usize>,
+    waker: LocalWaker,
+}
+
+/// Marks stream as active until dropped.
+struct StreamGuard(Rc<ActiveStreams>);
+
+impl StreamGuard {
+    fn new(streams: &Rc<ActiveStreams>) -> Self {
+        streams.count.set(streams.count.get() + 1);
+        StreamGuard(streams.clone())
+    }
+}
+
+impl Drop for StreamGuard {
+    fn drop(&mut self) {
+        let count = self.0.count.get() - 1;
+        self.0.count.set(count);
+        if count == 0 {
+            self.0.waker.wake();
+        }
+    }
+}
+
 impl<T, S, B> Dispatcher<T, S, B>
 where
     T: AsyncRead + AsyncWrite + Unpin,
@@ -59,6 +103,7 @@ where
         config: ServiceConfig,
         timeout: Option<Delay>,
         peer_addr: Option<net::SocketAddr>,
+        established: Instant,
     ) -> Self {
         // let keepalive = config.keep_alive_enabled();
         // let flags = if keepalive {
@@ -76,6 +121,10 @@ where
             (config.now(), None)
         };
 
+        // track open streams for idle timeout
+        let streams = config.h2_idle_timeout().map(|_| Rc::default());
+        let shutdown = config.graceful_shutdown().map(|s| s.signal());
+
         Dispatcher {
             service,
             config,
@@ -84,9 +133,123 @@ where
             on_connect,
             ka_expire,
             ka_timer,
+            streams,
+            idle_timer: None,
+            shutdown,
+            shutdown_timer: None,
+            established,
+            handshake: Some(Instant::now() - established),
+            queue: VecDeque::new(),
             _t: PhantomData,
         }
     }
+
+    /// Starts graceful shutdown of connection once it is initiated
+    /// via `GracefulShutdown` handle. Returns true if connection outlived
+    /// graceful shutdown timeout and has to be dropped.
+    fn poll_shutdown(&mut self, cx: &mut Context<'_>) -> bool {
+        if let Some(ref mut signal) = self.shutdown {
+            if Pin::new(signal).poll(cx).is_ready() {
+                trace!("Http/2 connection is shutting down gracefully");
+                self.connection.graceful_shutdown();
+                self.shutdown = None;
+                self.shutdown_timer =
+                    self.config.client_shutdown_timer().map(delay_until);
+            }
+        }
+        if let Some(ref mut timer) = self.shutdown_timer {
+            if Pin::new(timer).poll(cx).is_ready() {
+                trace!("Http/2 connection shutdown timeout, dropping connection");
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Starts idle timer if there are no open streams. Returns true
+    /// if connection has been idle for too long and is shutting down.
+    fn poll_idle(&mut self, cx: &mut Context<'_>) -> bool {
+        let streams = match self.streams {
+            Some(ref streams) => streams,
+            None => return false,
+        };
+        if streams.count.get() > 0 {
+            streams.waker.register(cx.waker());
+            self.idle_timer = None;
+            return false;
+        }
+
+        if self.idle_timer.is_none() {
+            if let Some(timeout) = self.config.h2_idle_timeout() {
+                self.idle_timer = Some(delay_until(self.config.now() + timeout));
+            }
+        }
+        if let Some(ref mut timer) = self.idle_timer {
+            if Pin::new(timer).poll(cx).is_ready() {
+                trace!("Http/2 connection is idle, shutting down");
+                self.connection.graceful_shutdown();
+                self.streams = None;
+                self.idle_timer = None;
+                return true;
+            }
+        }
+        false
+    }
+}
+
+impl<T, S, B> Dispatcher<T, S, B>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+    S: Service<Request = Request>,
+    S::Error: Into<Error> + 'static,
+    S::Future: 'static,
+    S::Response: Into<Response<B>> + 'static,
+    B: MessageBody + 'static,
+{
+    /// Spawn service call of request.
+    fn call_service(
+        &mut self,
+        req: Request,
+        res: SendResponse<Bytes>,
+        guard: Option<StreamGuard>,
+        permit: Option<RequestPermit>,
+    ) {
+        let slow_request = self.config.slow_request_timer(req.head());
+        // handshake is reported with first request of connection
+        let server_timing = self.config.server_timing_timer(
+            req.head(),
+            self.established,
+            self.handshake.take(),
+        );
+        actix_rt::spawn(ServiceResponse::<S::Future, S::Response, S::Error, B> {
+            state: ServiceResponseState::ServiceCall(
+                self.service.call(req),
+                Some(res),
+                permit,
+            ),
+            config: self.config.clone(),
+            deadline: self.config.request_deadline().map(delay_for),
+            slow_request,
+            server_timing,
+            buffer: None,
+            _guard: guard,
+            _t: PhantomData,
+        });
+    }
+
+    /// Call service with queued requests while concurrent request limit
+    /// is not reached.
+    fn poll_queue(&mut self, cx: &mut Context<'_>) {
+        while !self.queue.is_empty() {
+            match self.config.poll_request_permit(cx) {
+                Poll::Ready(permit) => {
+                    let (req, res, guard) = self.queue.pop_front().unwrap();
+                    self.call_service(req, res, guard, permit);
+                }
+                Poll::Pending => break,
+            }
+        }
+    }
 }
 
 impl<T, S, B> Future for Dispatcher<T, S, B>
@@ -103,12 +266,16 @@ where
     #[inline]
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = self.get_mut();
+        if this.poll_shutdown(cx) {
+            return Poll::Ready(Err(DispatchError::DisconnectTimeout));
+        }
+        this.poll_queue(cx);
 
         loop {
             match Pin::new(&mut this.connection).poll_accept(cx) {
                 Poll::Ready(None) => return Poll::Ready(Ok(())),
                 Poll::Ready(Some(Err(err))) => return Poll::Ready(Err(err.into())),
-                Poll::Ready(Some(Ok((req, res)))) => {
+                Poll::Ready(Some(Ok((req, mut res)))) => {
                     // update keep-alive expire
                     if this.ka_timer.is_some() {
                         if let Some(expire) = this.config.keep_alive_expire() {
@@ -116,6 +283,21 @@ where
                         }
                     }
 
+                    // shed request if service is not ready
+                    if let Some(retry_after) = this.config.load_shed() {
+                        if this.service.poll_ready(cx).is_pending() {
+                            trace!("Service is not ready, shedding request");
+                            let mut shed = http::Response::new(());
+                            *shed.status_mut() = http::StatusCode::SERVICE_UNAVAILABLE;
+                            shed.headers_mut()
+                                .insert(RETRY_AFTER, HeaderValue::from(retry_after));
+                            if let Err(e) = res.send_response(shed, true) {
+                                trace!("Error sending h2 response: {:?}", e);
+                            }
+                            continue;
+                        }
+                    }
+
                     let (parts, body) = req.into_parts();
                     let mut req = Request::with_payload(Payload::<
                         crate::payload::PayloadStream,
@@ -130,27 +312,44 @@ where
                     head.headers = parts.headers.into();
                     head.peer_addr = this.peer_addr;
 
+                    if let Some(policy) = this.config.normalize_path() {
+                        policy.normalize(head);
+                    }
+
                     // set on_connect data
                     if let Some(ref on_connect) = this.on_connect {
                         on_connect.set(&mut req.extensions_mut());
                     }
 
-                    actix_rt::spawn(ServiceResponse::<
-                        S::Future,
-                        S::Response,
-                        S::Error,
-                        B,
-                    > {
-                        state: ServiceResponseState::ServiceCall(
-                            this.service.call(req),
-                            Some(res),
-                        ),
-                        config: this.config.clone(),
-                        buffer: None,
-                        _t: PhantomData,
-                    });
+                    // reject request received in early data
+                    if this.config.early_data_rejected(req.head()) {
+                        trace!("Rejecting request received in early data");
+                        let mut rejected = http::Response::new(());
+                        *rejected.status_mut() =
+                            http::StatusCode::from_u16(425).unwrap();
+                        if let Err(e) = res.send_response(rejected, true) {
+                            trace!("Error sending h2 response: {:?}", e);
+                        }
+                        continue;
+                    }
+
+                    // queue request while concurrent request limit is reached
+                    let guard = this.streams.as_ref().map(StreamGuard::new);
+                    if this.queue.is_empty() {
+                        if let Poll::Ready(permit) = this.config.poll_request_permit(cx)
+                        {
+                            this.call_service(req, res, guard, permit);
+                            continue;
+                        }
+                    }
+                    this.queue.push_back((req, res, guard));
+                }
+                Poll::Pending => {
+                    if this.poll_idle(cx) {
+                        continue;
+                    }
+                    return Poll::Pending;
                 }
-                Poll::Pending => return Poll::Pending,
             }
         }
     }
@@ -160,12 +359,16 @@ where
 struct ServiceResponse<F, I, E, B> {
     state: ServiceResponseState<F, B>,
     config: ServiceConfig,
+    deadline: Option<Delay>,
+    slow_request: Option<SlowRequestTimer>,
+    server_timing: Option<ServerTimingTimer>,
     buffer: Option<Bytes>,
+    _guard: Option<StreamGuard>,
     _t: PhantomData<(I, E)>,
 }
 
 enum ServiceResponseState<F, B> {
-    ServiceCall(F, Option<SendResponse<Bytes>>),
+    ServiceCall(F, Option<SendResponse<Bytes>>, Option<RequestPermit>),
     SendPayload(SendStream<Bytes>, ResponseBody<B>),
 }
 
@@ -181,6 +384,10 @@ where
         head: &ResponseHead,
         size: &mut BodySize,
     ) -> http::Response<()> {
+        if let Some(ref timer) = self.slow_request {
+            self.config.log_slow_request(timer, head.status);
+        }
+
         let mut has_date = false;
         let mut skip_len = size != &BodySize::Stream;
 
@@ -225,6 +432,20 @@ where
             res.headers_mut().append(key, value.clone());
         }
 
+        // set server-timing header
+        if let Some(ref timer) = self.server_timing {
+            if let Some((name, value)) = timer.finish() {
+                res.headers_mut().append(name, value);
+            }
+        }
+
+        // set alt-svc header
+        if let Some(alt_svc) = self.config.alt_svc() {
+            if !res.headers().contains_key(ALT_SVC) {
+                res.headers_mut().insert(ALT_SVC, alt_svc.clone());
+            }
+        }
+
         // set date header
         if !has_date {
             let mut bytes = BytesMut::with_capacity(29);
@@ -251,8 +472,17 @@ where
         let mut this = self.as_mut().project();
 
         match this.state {
-            ServiceResponseState::ServiceCall(ref mut call, ref mut send) => {
-                match unsafe { Pin::new_unchecked(call) }.poll(cx) {
+            ServiceResponseState::ServiceCall(
+                ref mut call,
+                ref mut send,
+                ref mut permit,
+            ) => {
+                let res = unsafe { Pin::new_unchecked(call) }.poll(cx);
+                if res.is_ready() {
+                    // release request slot
+                    permit.take();
+                }
+                match res {
                     Poll::Ready(Ok(res)) => {
                         let (res, body) = res.into().replace_body(());
 
@@ -278,7 +508,26 @@ where
                             self.poll(cx)
                         }
                     }
-                    Poll::Pending => Poll::Pending,
+                    Poll::Pending => {
+                        let expired = match this.deadline {
+                            Some(ref mut delay) => Pin::new(delay).poll(cx).is_ready(),
+                            None => false,
+                        };
+                        if !expired {
+                            return Poll::Pending;
+                        }
+
+                        trace!("Request deadline is reached, aborting request");
+                        let res = Response::new(this.config.request_deadline_status());
+                        let mut send = send.take().unwrap();
+                        let mut size = BodySize::Empty;
+                        let h2_res =
+                            self.as_mut().prepare_response(res.head(), &mut size);
+                        if let Err(e) = send.send_response(h2_res, true) {
+                            trace!("Error sending h2 response: {:?}", e);
+                        }
+                        Poll::Ready(())
+                    }
                     Poll::Ready(Err(e)) => {
                         let res: Response = e.into().into();
                         let (res, body) = res.replace_body(());
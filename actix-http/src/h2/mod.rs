@@ -1,9 +1,12 @@
 //! HTTP/2 implementation
 use std::pin::Pin;
+use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll};
 
 use bytes::Bytes;
+use futures_channel::oneshot;
 use futures_core::Stream;
+use futures_util::future::{FutureExt, Shared};
 use h2::RecvStream;
 
 mod dispatcher;
@@ -48,3 +51,47 @@ impl Stream for Payload {
         }
     }
 }
+
+/// Handle to gracefully shut down http/2 connections
+///
+/// Once `shutdown()` is called, every http/2 connection served with a clone
+/// of the handle sends `GOAWAY` frame with the last processed stream id,
+/// refuses new streams and closes after already open streams complete.
+/// Connections accepted afterwards are shut down right after handshake.
+///
+/// Handle is `Send`, so shutdown could be initiated from any thread, i.e.
+/// before server is stopped during rolling deploy.
+#[derive(Clone)]
+pub struct GracefulShutdown {
+    tx: Arc<Mutex<Option<oneshot::Sender<()>>>>,
+    rx: Shared<oneshot::Receiver<()>>,
+}
+
+impl GracefulShutdown {
+    /// Create new handle
+    pub fn new() -> Self {
+        let (tx, rx) = oneshot::channel();
+        GracefulShutdown {
+            tx: Arc::new(Mutex::new(Some(tx))),
+            rx: rx.shared(),
+        }
+    }
+
+    /// Initiate graceful shutdown of connections
+    pub fn shutdown(&self) {
+        if let Some(tx) = self.tx.lock().unwrap().take() {
+            let _ = tx.send(());
+        }
+    }
+
+    /// Signal which resolves once shutdown is initiated
+    pub(crate) fn signal(&self) -> Shared<oneshot::Receiver<()>> {
+        self.rx.clone()
+    }
+}
+
+impl Default for GracefulShutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
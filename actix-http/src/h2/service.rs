@@ -6,6 +6,7 @@ use std::{net, rc};
 
 use actix_codec::{AsyncRead, AsyncWrite};
 use actix_rt::net::TcpStream;
+use actix_rt::time::{Delay, Instant};
 use actix_service::{
     fn_factory, fn_service, pipeline_factory, IntoServiceFactory, Service,
     ServiceFactory,
@@ -30,6 +31,7 @@ use super::dispatcher::Dispatcher;
 pub struct H2Service<T, S, B> {
     srv: S,
     cfg: ServiceConfig,
+    h2: server::Builder,
     on_connect: Option<rc::Rc<dyn Fn(&T) -> Box<dyn DataFactory>>>,
     _t: PhantomData<(T, B)>,
 }
@@ -49,6 +51,7 @@ where
     ) -> Self {
         H2Service {
             cfg,
+            h2: server::Builder::new(),
             on_connect: None,
             srv: service.into_factory(),
             _t: PhantomData,
@@ -63,6 +66,44 @@ where
         self.on_connect = f;
         self
     }
+
+    /// Set `SETTINGS_MAX_CONCURRENT_STREAMS` advertised to client.
+    ///
+    /// By default number of concurrent streams is not limited.
+    pub fn max_concurrent_streams(mut self, max: u32) -> Self {
+        self.h2.max_concurrent_streams(max);
+        self
+    }
+
+    /// Set `SETTINGS_INITIAL_WINDOW_SIZE`, initial flow control window
+    /// of streams in octets.
+    ///
+    /// By default window size is 65,535.
+    pub fn initial_window_size(mut self, size: u32) -> Self {
+        self.h2.initial_window_size(size);
+        self
+    }
+
+    /// Set initial flow control window of connection in octets.
+    ///
+    /// By default window size is 65,535.
+    pub fn initial_connection_window_size(mut self, size: u32) -> Self {
+        self.h2.initial_connection_window_size(size);
+        self
+    }
+
+    /// Set `SETTINGS_MAX_FRAME_SIZE`, largest frame payload accepted from
+    /// client in octets.
+    ///
+    /// Value must be between 16,384 and 16,777,215, by default 16,384 is used.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is out of range.
+    pub fn max_frame_size(mut self, max: u32) -> Self {
+        self.h2.max_frame_size(max);
+        self
+    }
 }
 
 impl<S, B> H2Service<TcpStream, S, B>
@@ -83,9 +124,12 @@ where
         Error = DispatchError,
         InitError = S::InitError,
     > {
-        pipeline_factory(fn_factory(|| {
-            async {
-                Ok::<_, S::InitError>(fn_service(|io: TcpStream| {
+        let cfg = self.cfg.clone();
+        pipeline_factory(fn_factory(move || {
+            let cfg = cfg.clone();
+            async move {
+                Ok::<_, S::InitError>(fn_service(move |io: TcpStream| {
+                    cfg.apply_socket_options(&io);
                     let peer_addr = io.peer_addr().ok();
                     ok::<_, DispatchError>((io, peer_addr))
                 }))
@@ -122,7 +166,15 @@ mod openssl {
             Error = SslError<HandshakeError<TcpStream>, DispatchError>,
             InitError = S::InitError,
         > {
-            pipeline_factory(
+            let cfg = self.cfg.clone();
+            pipeline_factory(fn_factory(move || {
+                let cfg = cfg.clone();
+                ok::<_, S::InitError>(fn_service(move |io: TcpStream| {
+                    cfg.apply_socket_options(&io);
+                    ok::<_, SslError<HandshakeError<TcpStream>, DispatchError>>(io)
+                }))
+            }))
+            .and_then(
                 Acceptor::new(acceptor)
                     .map_err(SslError::Ssl)
                     .map_init_err(|_| panic!()),
@@ -167,7 +219,15 @@ mod rustls {
             let protos = vec!["h2".to_string().into()];
             config.set_protocols(&protos);
 
-            pipeline_factory(
+            let cfg = self.cfg.clone();
+            pipeline_factory(fn_factory(move || {
+                let cfg = cfg.clone();
+                ok::<_, S::InitError>(fn_service(move |io: TcpStream| {
+                    cfg.apply_socket_options(&io);
+                    ok::<_, SslError<io::Error, DispatchError>>(io)
+                }))
+            }))
+            .and_then(
                 Acceptor::new(config)
                     .map_err(SslError::Ssl)
                     .map_init_err(|_| panic!()),
@@ -204,6 +264,7 @@ where
         H2ServiceResponse {
             fut: self.srv.new_service(()),
             cfg: Some(self.cfg.clone()),
+            h2: self.h2.clone(),
             on_connect: self.on_connect.clone(),
             _t: PhantomData,
         }
@@ -216,6 +277,7 @@ pub struct H2ServiceResponse<T, S: ServiceFactory, B> {
     #[pin]
     fut: S::Future,
     cfg: Option<ServiceConfig>,
+    h2: server::Builder,
     on_connect: Option<rc::Rc<dyn Fn(&T) -> Box<dyn DataFactory>>>,
     _t: PhantomData<(T, B)>,
 }
@@ -238,6 +300,7 @@ where
             let this = self.as_mut().project();
             H2ServiceHandler::new(
                 this.cfg.take().unwrap(),
+                this.h2.clone(),
                 this.on_connect.clone(),
                 service,
             )
@@ -249,6 +312,7 @@ where
 pub struct H2ServiceHandler<T, S: Service, B> {
     srv: CloneableService<S>,
     cfg: ServiceConfig,
+    h2: server::Builder,
     on_connect: Option<rc::Rc<dyn Fn(&T) -> Box<dyn DataFactory>>>,
     _t: PhantomData<(T, B)>,
 }
@@ -263,11 +327,13 @@ where
 {
     fn new(
         cfg: ServiceConfig,
+        h2: server::Builder,
         on_connect: Option<rc::Rc<dyn Fn(&T) -> Box<dyn DataFactory>>>,
         srv: S,
     ) -> H2ServiceHandler<T, S, B> {
         H2ServiceHandler {
             cfg,
+            h2,
             on_connect,
             srv: CloneableService::new(srv),
             _t: PhantomData,
@@ -290,11 +356,22 @@ where
     type Future = H2ServiceHandlerResponse<T, S, B>;
 
     fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        self.srv.poll_ready(cx).map_err(|e| {
-            let e = e.into();
-            error!("Service readiness error: {:?}", e);
-            DispatchError::Service(e)
-        })
+        let ready = self
+            .srv
+            .poll_ready(cx)
+            .map_err(|e| {
+                let e = e.into();
+                error!("Service readiness error: {:?}", e);
+                DispatchError::Service(e)
+            })?
+            .is_ready();
+
+        // stop accepting connections while concurrent request limit is reached
+        if ready && !self.cfg.request_limit_reached(cx) {
+            Poll::Ready(Ok(()))
+        } else {
+            Poll::Pending
+        }
     }
 
     fn call(&mut self, (io, addr): Self::Request) -> Self::Future {
@@ -310,7 +387,9 @@ where
                 Some(self.cfg.clone()),
                 addr,
                 on_connect,
-                server::handshake(io),
+                self.h2.handshake(io),
+                self.cfg.client_timer(),
+                Instant::now(),
             ),
         }
     }
@@ -328,6 +407,8 @@ where
         Option<net::SocketAddr>,
         Option<Box<dyn DataFactory>>,
         Handshake<T, Bytes>,
+        Option<Delay>,
+        Instant,
     ),
 }
 
@@ -363,6 +444,8 @@ where
                 ref peer_addr,
                 ref mut on_connect,
                 ref mut handshake,
+                ref mut timer,
+                established,
             ) => match Pin::new(handshake).poll(cx) {
                 Poll::Ready(Ok(conn)) => {
                     self.state = State::Incoming(Dispatcher::new(
@@ -372,6 +455,7 @@ where
                         config.take().unwrap(),
                         None,
                         *peer_addr,
+                        established,
                     ));
                     self.poll(cx)
                 }
@@ -379,7 +463,15 @@ where
                     trace!("H2 handshake error: {}", err);
                     Poll::Ready(Err(err.into()))
                 }
-                Poll::Pending => Poll::Pending,
+                Poll::Pending => {
+                    if let Some(ref mut timer) = timer {
+                        if Pin::new(timer).poll(cx).is_ready() {
+                            trace!("H2 handshake timeout");
+                            return Poll::Ready(Err(DispatchError::HandshakeTimeout));
+                        }
+                    }
+                    Poll::Pending
+                }
             },
         }
     }
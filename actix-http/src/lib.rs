@@ -36,7 +36,10 @@ pub mod test;
 pub mod ws;
 
 pub use self::builder::HttpServiceBuilder;
-pub use self::config::{KeepAlive, ServiceConfig};
+pub use self::config::{
+    EarlyData, EarlyDataPolicy, KeepAlive, NormalizePath, ServerTiming, ServiceConfig,
+    SlowRequest, TrailingSlash, WriteErrorContext,
+};
 pub use self::error::{Error, ResponseError, Result};
 pub use self::extensions::Extensions;
 pub use self::httpmessage::HttpMessage;
@@ -72,3 +75,16 @@ pub enum Protocol {
     Http1,
     Http2,
 }
+
+impl Protocol {
+    /// Protocol of negotiated ALPN identifier.
+    ///
+    /// Returns `None` for identifiers other than `h2`, `http/1.1` and `http/1.0`.
+    pub fn from_alpn(id: &[u8]) -> Option<Protocol> {
+        match id {
+            b"h2" => Some(Protocol::Http2),
+            b"http/1.1" | b"http/1.0" => Some(Protocol::Http1),
+            _ => None,
+        }
+    }
+}
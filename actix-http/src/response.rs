@@ -181,6 +181,13 @@ impl<B> Response<B> {
         self.head.keep_alive()
     }
 
+    /// Force close connection after this response is sent, even if it is
+    /// marked as keep-alive
+    #[inline]
+    pub fn force_close(&mut self) {
+        self.head.set_connection_type(ConnectionType::Close);
+    }
+
     /// Responses extensions
     #[inline]
     pub fn extensions(&self) -> Ref<'_, Extensions> {
@@ -945,6 +952,14 @@ mod tests {
         assert!(!resp.keep_alive())
     }
 
+    #[test]
+    fn test_response_force_close() {
+        let mut resp = Response::build(StatusCode::OK).keep_alive().finish();
+        assert!(resp.keep_alive());
+        resp.force_close();
+        assert!(!resp.keep_alive());
+    }
+
     #[test]
     fn test_content_type() {
         let resp = Response::build(StatusCode::OK)
@@ -5,6 +5,7 @@ use std::{fmt, net, rc};
 
 use actix_codec::{AsyncRead, AsyncWrite, Framed};
 use actix_rt::net::TcpStream;
+use actix_rt::time::{Delay, Instant};
 use actix_service::{pipeline_factory, IntoServiceFactory, Service, ServiceFactory};
 use bytes::Bytes;
 use futures_core::{ready, Future};
@@ -183,9 +184,11 @@ where
         Error = DispatchError,
         InitError = (),
     > {
-        pipeline_factory(|io: TcpStream| {
+        let cfg = self.cfg.clone();
+        pipeline_factory(move |io: TcpStream| {
+            cfg.apply_socket_options(&io);
             let peer_addr = io.peer_addr().ok();
-            ok((io, Protocol::Http1, peer_addr))
+            ok((io, cfg.alpn_fallback(), peer_addr))
         })
         .and_then(self)
     }
@@ -229,21 +232,19 @@ mod openssl {
             Error = SslError<HandshakeError<TcpStream>, DispatchError>,
             InitError = (),
         > {
-            pipeline_factory(
+            let cfg = self.cfg.clone();
+            let socket_cfg = cfg.clone();
+            pipeline_factory(move |io: TcpStream| {
+                socket_cfg.apply_socket_options(&io);
+                ok::<_, SslError<HandshakeError<TcpStream>, DispatchError>>(io)
+            })
+            .and_then(
                 Acceptor::new(acceptor)
                     .map_err(SslError::Ssl)
                     .map_init_err(|_| panic!()),
             )
-            .and_then(|io: SslStream<TcpStream>| {
-                let proto = if let Some(protos) = io.ssl().selected_alpn_protocol() {
-                    if protos.windows(2).any(|window| window == b"h2") {
-                        Protocol::Http2
-                    } else {
-                        Protocol::Http1
-                    }
-                } else {
-                    Protocol::Http1
-                };
+            .and_then(move |io: SslStream<TcpStream>| {
+                let proto = cfg.alpn_protocol(io.ssl().selected_alpn_protocol());
                 let peer_addr = io.get_ref().peer_addr().ok();
                 ok((io, proto, peer_addr))
             })
@@ -294,21 +295,19 @@ mod rustls {
             let protos = vec!["h2".to_string().into(), "http/1.1".to_string().into()];
             config.set_protocols(&protos);
 
-            pipeline_factory(
+            let cfg = self.cfg.clone();
+            let socket_cfg = cfg.clone();
+            pipeline_factory(move |io: TcpStream| {
+                socket_cfg.apply_socket_options(&io);
+                ok::<_, SslError<io::Error, DispatchError>>(io)
+            })
+            .and_then(
                 Acceptor::new(config)
                     .map_err(SslError::Ssl)
                     .map_init_err(|_| panic!()),
             )
-            .and_then(|io: TlsStream<TcpStream>| {
-                let proto = if let Some(protos) = io.get_ref().1.get_alpn_protocol() {
-                    if protos.windows(2).any(|window| window == b"h2") {
-                        Protocol::Http2
-                    } else {
-                        Protocol::Http1
-                    }
-                } else {
-                    Protocol::Http1
-                };
+            .and_then(move |io: TlsStream<TcpStream>| {
+                let proto = cfg.alpn_protocol(io.get_ref().1.get_alpn_protocol());
                 let peer_addr = io.get_ref().0.peer_addr().ok();
                 ok((io, proto, peer_addr))
             })
@@ -535,7 +534,8 @@ where
             ready
         };
 
-        if ready {
+        // stop accepting connections while concurrent request limit is reached
+        if ready && !self.cfg.request_limit_reached(cx) {
             Poll::Ready(Ok(()))
         } else {
             Poll::Pending
@@ -553,6 +553,8 @@ where
             Protocol::Http2 => HttpServiceHandlerResponse {
                 state: State::H2Handshake(Some((
                     server::handshake(io),
+                    self.cfg.client_timer(),
+                    Instant::now(),
                     self.cfg.clone(),
                     self.srv.clone(),
                     on_connect,
@@ -592,6 +594,8 @@ where
     H2Handshake(
         Option<(
             Handshake<T, Bytes>,
+            Option<Delay>,
+            Instant,
             ServiceConfig,
             CloneableService<S>,
             Option<Box<dyn DataFactory>>,
@@ -667,14 +671,31 @@ where
                             trace!("H2 handshake error: {}", err);
                             return Poll::Ready(Err(err.into()));
                         }
-                        Poll::Pending => return Poll::Pending,
+                        Poll::Pending => {
+                            if let Some(ref mut timer) = item.1 {
+                                if Pin::new(timer).poll(cx).is_ready() {
+                                    trace!("H2 handshake timeout");
+                                    return Poll::Ready(Err(
+                                        DispatchError::HandshakeTimeout,
+                                    ));
+                                }
+                            }
+                            return Poll::Pending;
+                        }
                     }
                 } else {
                     panic!()
                 };
-                let (_, cfg, srv, on_connect, peer_addr) = data.take().unwrap();
+                let (_, _, established, cfg, srv, on_connect, peer_addr) =
+                    data.take().unwrap();
                 self.set(State::H2(Dispatcher::new(
-                    srv, conn, on_connect, cfg, None, peer_addr,
+                    srv,
+                    conn,
+                    on_connect,
+                    cfg,
+                    None,
+                    peer_addr,
+                    established,
                 )));
                 self.poll(cx)
             }
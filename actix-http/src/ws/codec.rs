@@ -1,10 +1,16 @@
 use actix_codec::{Decoder, Encoder};
 use bytes::{Bytes, BytesMut};
 
+#[cfg(feature = "compress")]
+use super::deflate::{Deflate, DeflateConfig};
 use super::frame::Parser;
 use super::proto::{CloseReason, OpCode};
 use super::ProtocolError;
 
+/// First reserved bit of frame header, marks compressed messages
+#[cfg(feature = "compress")]
+const RSV1: u8 = 0b0100_0000;
+
 /// `WebSocket` Message
 #[derive(Debug, PartialEq)]
 pub enum Message {
@@ -50,11 +56,14 @@ pub enum Item {
     Last(Bytes),
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone)]
 /// WebSockets protocol codec
 pub struct Codec {
     flags: Flags,
     max_size: usize,
+    continuation_size: usize,
+    #[cfg(feature = "compress")]
+    deflate: Option<Deflate>,
 }
 
 bitflags::bitflags! {
@@ -62,6 +71,7 @@ bitflags::bitflags! {
         const SERVER         = 0b0000_0001;
         const CONTINUATION   = 0b0000_0010;
         const W_CONTINUATION = 0b0000_0100;
+        const R_COMPRESSED   = 0b0000_1000;
     }
 }
 
@@ -70,13 +80,18 @@ impl Codec {
     pub fn new() -> Codec {
         Codec {
             max_size: 65_536,
+            continuation_size: 0,
             flags: Flags::SERVER,
+            #[cfg(feature = "compress")]
+            deflate: None,
         }
     }
 
-    /// Set max frame size
+    /// Set max frame and message size
     ///
-    /// By default max size is set to 64kb
+    /// Frames with larger declared length and fragmented messages with larger
+    /// total length are rejected with `ProtocolError::Overflow`, which maps to
+    /// `CloseCode::Size`. By default max size is set to 64kb
     pub fn max_size(mut self, size: usize) -> Self {
         self.max_size = size;
         self
@@ -89,6 +104,18 @@ impl Codec {
         self.flags.remove(Flags::SERVER);
         self
     }
+
+    #[cfg(feature = "compress")]
+    /// Enable `permessage-deflate` compression with parameters accepted
+    /// during handshake.
+    ///
+    /// Outgoing text and binary messages are compressed, incoming messages
+    /// are decompressed if marked as compressed. Decompressed messages are
+    /// limited by max size as well. Control frames are never compressed.
+    pub fn deflate(mut self, config: DeflateConfig) -> Self {
+        self.deflate = Some(Deflate::new(config));
+        self
+    }
 }
 
 impl Encoder for Codec {
@@ -97,20 +124,12 @@ impl Encoder for Codec {
 
     fn encode(&mut self, item: Message, dst: &mut BytesMut) -> Result<(), Self::Error> {
         match item {
-            Message::Text(txt) => Parser::write_message(
-                dst,
-                txt,
-                OpCode::Text,
-                true,
-                !self.flags.contains(Flags::SERVER),
-            ),
-            Message::Binary(bin) => Parser::write_message(
-                dst,
-                bin,
-                OpCode::Binary,
-                true,
-                !self.flags.contains(Flags::SERVER),
-            ),
+            Message::Text(txt) => {
+                self.write_data(dst, txt.as_bytes(), OpCode::Text, true)?
+            }
+            Message::Binary(bin) => {
+                self.write_data(dst, &bin[..], OpCode::Binary, true)?
+            }
             Message::Ping(txt) => Parser::write_message(
                 dst,
                 txt,
@@ -134,13 +153,7 @@ impl Encoder for Codec {
                         return Err(ProtocolError::ContinuationStarted);
                     } else {
                         self.flags.insert(Flags::W_CONTINUATION);
-                        Parser::write_message(
-                            dst,
-                            &data[..],
-                            OpCode::Binary,
-                            false,
-                            !self.flags.contains(Flags::SERVER),
-                        )
+                        self.write_data(dst, &data[..], OpCode::Text, false)?
                     }
                 }
                 Item::FirstBinary(data) => {
@@ -148,24 +161,12 @@ impl Encoder for Codec {
                         return Err(ProtocolError::ContinuationStarted);
                     } else {
                         self.flags.insert(Flags::W_CONTINUATION);
-                        Parser::write_message(
-                            dst,
-                            &data[..],
-                            OpCode::Text,
-                            false,
-                            !self.flags.contains(Flags::SERVER),
-                        )
+                        self.write_data(dst, &data[..], OpCode::Binary, false)?
                     }
                 }
                 Item::Continue(data) => {
                     if self.flags.contains(Flags::W_CONTINUATION) {
-                        Parser::write_message(
-                            dst,
-                            &data[..],
-                            OpCode::Continue,
-                            false,
-                            !self.flags.contains(Flags::SERVER),
-                        )
+                        self.write_data(dst, &data[..], OpCode::Continue, false)?
                     } else {
                         return Err(ProtocolError::ContinuationNotStarted);
                     }
@@ -173,13 +174,7 @@ impl Encoder for Codec {
                 Item::Last(data) => {
                     if self.flags.contains(Flags::W_CONTINUATION) {
                         self.flags.remove(Flags::W_CONTINUATION);
-                        Parser::write_message(
-                            dst,
-                            &data[..],
-                            OpCode::Continue,
-                            true,
-                            !self.flags.contains(Flags::SERVER),
-                        )
+                        self.write_data(dst, &data[..], OpCode::Continue, true)?
                     } else {
                         return Err(ProtocolError::ContinuationNotStarted);
                     }
@@ -191,13 +186,112 @@ impl Encoder for Codec {
     }
 }
 
+impl Codec {
+    /// Write frame of data message, compressed if deflate is enabled
+    fn write_data(
+        &mut self,
+        dst: &mut BytesMut,
+        data: &[u8],
+        op: OpCode,
+        fin: bool,
+    ) -> Result<(), ProtocolError> {
+        let mask = !self.flags.contains(Flags::SERVER);
+
+        #[cfg(feature = "compress")]
+        {
+            if let Some(ref mut deflate) = self.deflate {
+                let data = deflate.compress(data, fin, !mask)?;
+                let pos = dst.len();
+                Parser::write_message(dst, data, op, fin, mask);
+                // only first frame of a message is marked
+                if op != OpCode::Continue {
+                    dst[pos] |= RSV1;
+                }
+                return Ok(());
+            }
+        }
+
+        Parser::write_message(dst, data, op, fin, mask);
+        Ok(())
+    }
+
+    #[cfg(feature = "compress")]
+    /// Decompress payload of a frame of compressed message
+    fn inflate(
+        &mut self,
+        rsv1: bool,
+        finished: bool,
+        opcode: OpCode,
+        payload: Option<BytesMut>,
+    ) -> Result<Option<BytesMut>, ProtocolError> {
+        if self.deflate.is_none() {
+            return Ok(payload);
+        }
+
+        let compressed = match opcode {
+            OpCode::Text | OpCode::Binary => {
+                if !finished && !self.flags.contains(Flags::CONTINUATION) {
+                    self.flags.set(Flags::R_COMPRESSED, rsv1);
+                }
+                rsv1
+            }
+            OpCode::Continue if !rsv1 => {
+                self.flags.contains(Flags::CONTINUATION)
+                    && self.flags.contains(Flags::R_COMPRESSED)
+            }
+            _ if rsv1 => return Err(ProtocolError::InvalidCompression),
+            _ => false,
+        };
+        if !compressed {
+            return Ok(payload);
+        }
+
+        let server = self.flags.contains(Flags::SERVER);
+        let max_size = self.max_size;
+        let data = payload.as_ref().map_or(&[][..], |pl| &pl[..]);
+        match self.deflate {
+            Some(ref mut deflate) => deflate
+                .decompress(data, finished, server, max_size)
+                .map(Some),
+            None => Ok(payload),
+        }
+    }
+
+    /// Account fragment of continuation message against max size
+    fn add_continuation_size(&mut self, len: usize) -> Result<(), ProtocolError> {
+        self.continuation_size += len;
+        if self.continuation_size > self.max_size {
+            self.flags.remove(Flags::CONTINUATION);
+            Err(ProtocolError::Overflow)
+        } else {
+            Ok(())
+        }
+    }
+}
+
 impl Decoder for Codec {
     type Item = Frame;
     type Error = ProtocolError;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        #[cfg(feature = "compress")]
+        let rsv1 = !src.is_empty() && src[0] & RSV1 != 0;
+
         match Parser::parse(src, self.flags.contains(Flags::SERVER), self.max_size) {
             Ok(Some((finished, opcode, payload))) => {
+                #[cfg(feature = "compress")]
+                let payload = self.inflate(rsv1, finished, opcode, payload)?;
+
+                let len = payload.as_ref().map_or(0, |pl| pl.len());
+                if self.flags.contains(Flags::CONTINUATION) {
+                    if opcode == OpCode::Continue {
+                        self.add_continuation_size(len)?;
+                    }
+                } else if !finished {
+                    self.continuation_size = 0;
+                    self.add_continuation_size(len)?;
+                }
+
                 // continuation is not supported
                 if !finished {
                     return match opcode {
@@ -282,3 +376,207 @@ impl Decoder for Codec {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ws::CloseCode;
+
+    #[test]
+    fn test_max_size() {
+        let mut codec = Codec::new().client_mode().max_size(8);
+
+        let mut buf = BytesMut::new();
+        Parser::write_message(&mut buf, "0123456789", OpCode::Text, true, false);
+        let err = codec.decode(&mut buf).unwrap_err();
+        assert_eq!(err.close_code(), CloseCode::Size);
+
+        let mut buf = BytesMut::new();
+        Parser::write_message(&mut buf, "0123", OpCode::Text, false, false);
+        Parser::write_message(&mut buf, "4567", OpCode::Continue, false, false);
+        Parser::write_message(&mut buf, "89", OpCode::Continue, true, false);
+        assert_eq!(
+            codec.decode(&mut buf).unwrap(),
+            Some(Frame::Continuation(Item::FirstText(Bytes::from_static(
+                b"0123"
+            ))))
+        );
+        assert_eq!(
+            codec.decode(&mut buf).unwrap(),
+            Some(Frame::Continuation(Item::Continue(Bytes::from_static(
+                b"4567"
+            ))))
+        );
+        let err = codec.decode(&mut buf).unwrap_err();
+        assert_eq!(err.close_code(), CloseCode::Size);
+
+        // limit applies per message
+        let mut buf = BytesMut::new();
+        Parser::write_message(&mut buf, "0123", OpCode::Binary, false, false);
+        Parser::write_message(&mut buf, "4567", OpCode::Continue, true, false);
+        Parser::write_message(&mut buf, "01234567", OpCode::Binary, false, false);
+        assert!(codec.decode(&mut buf).unwrap().is_some());
+        assert!(codec.decode(&mut buf).unwrap().is_some());
+        assert_eq!(
+            codec.decode(&mut buf).unwrap(),
+            Some(Frame::Continuation(Item::FirstBinary(Bytes::from_static(
+                b"01234567"
+            ))))
+        );
+    }
+
+    #[test]
+    fn test_encode_continuation() {
+        let mut server = Codec::new();
+        let mut client = Codec::new().client_mode();
+
+        let mut buf = BytesMut::new();
+        let items = vec![
+            Item::FirstText(Bytes::from_static(b"text")),
+            Item::Last(Bytes::new()),
+            Item::FirstBinary(Bytes::from_static(b"binary")),
+        ];
+        for item in items {
+            server
+                .encode(Message::Continuation(item), &mut buf)
+                .unwrap();
+        }
+
+        assert_eq!(
+            client.decode(&mut buf).unwrap(),
+            Some(Frame::Continuation(Item::FirstText(Bytes::from_static(
+                b"text"
+            ))))
+        );
+        assert!(client.decode(&mut buf).unwrap().is_some());
+        assert_eq!(
+            client.decode(&mut buf).unwrap(),
+            Some(Frame::Continuation(Item::FirstBinary(Bytes::from_static(
+                b"binary"
+            ))))
+        );
+    }
+
+    #[cfg(feature = "compress")]
+    fn roundtrip(from: &mut Codec, to: &mut Codec, msg: Message) -> (u8, usize, Frame) {
+        let mut buf = BytesMut::new();
+        from.encode(msg, &mut buf).unwrap();
+        let (first, len) = (buf[0], buf.len());
+        (first, len, to.decode(&mut buf).unwrap().unwrap())
+    }
+
+    #[cfg(feature = "compress")]
+    #[test]
+    fn test_deflate() {
+        let mut server = Codec::new().deflate(DeflateConfig::new());
+        let mut client = Codec::new().client_mode().deflate(DeflateConfig::new());
+        let text = "Hello World ".repeat(100);
+
+        let (first, len, frame) =
+            roundtrip(&mut server, &mut client, Message::Text(text.clone()));
+        assert_ne!(first & RSV1, 0);
+        assert!(len < 100);
+        assert_eq!(frame, Frame::Text(Bytes::from(text.clone())));
+
+        // context takeover, repeated message is shorter
+        let (_, len2, frame) =
+            roundtrip(&mut server, &mut client, Message::Text(text.clone()));
+        assert!(len2 < len);
+        assert_eq!(frame, Frame::Text(Bytes::from(text.clone())));
+
+        let (_, _, frame) = roundtrip(
+            &mut client,
+            &mut server,
+            Message::Binary(Bytes::from_static(b"binary")),
+        );
+        assert_eq!(frame, Frame::Binary(Bytes::from_static(b"binary")));
+
+        // control frames are not compressed
+        let (first, len, frame) = roundtrip(
+            &mut server,
+            &mut client,
+            Message::Ping(Bytes::from_static(b"ping")),
+        );
+        assert_eq!(first & RSV1, 0);
+        assert_eq!(len, 6);
+        assert_eq!(frame, Frame::Ping(Bytes::from_static(b"ping")));
+
+        // fragmented message
+        let mut received = Vec::new();
+        let items = vec![
+            Item::FirstBinary(Bytes::from(text.clone())),
+            Item::Continue(Bytes::from(text.clone())),
+            Item::Last(Bytes::from(text.clone())),
+        ];
+        for item in items {
+            let (_, _, frame) =
+                roundtrip(&mut server, &mut client, Message::Continuation(item));
+            match frame {
+                Frame::Continuation(Item::FirstText(data))
+                | Frame::Continuation(Item::FirstBinary(data))
+                | Frame::Continuation(Item::Continue(data))
+                | Frame::Continuation(Item::Last(data)) => {
+                    received.extend_from_slice(&data)
+                }
+                _ => panic!(),
+            }
+        }
+        assert_eq!(received, text.repeat(3).as_bytes());
+    }
+
+    #[cfg(feature = "compress")]
+    #[test]
+    fn test_deflate_no_context_takeover() {
+        let config = DeflateConfig::new().server_no_context_takeover(true);
+        let mut server = Codec::new().deflate(config);
+        let mut client = Codec::new().client_mode().deflate(config);
+        let text = "Hello World ".repeat(100);
+
+        let (_, len, _) =
+            roundtrip(&mut server, &mut client, Message::Text(text.clone()));
+        let (_, len2, frame) =
+            roundtrip(&mut server, &mut client, Message::Text(text.clone()));
+        assert_eq!(len, len2);
+        assert_eq!(frame, Frame::Text(Bytes::from(text)));
+    }
+
+    #[cfg(feature = "compress")]
+    #[test]
+    fn test_deflate_errors() {
+        let mut server = Codec::new().deflate(DeflateConfig::new());
+
+        // decompressed size is limited
+        let mut client = Codec::new()
+            .client_mode()
+            .max_size(100)
+            .deflate(DeflateConfig::new());
+        let mut buf = BytesMut::new();
+        server
+            .encode(Message::Text("a".repeat(1000)), &mut buf)
+            .unwrap();
+        assert!(buf.len() < 100);
+        let err = client.decode(&mut buf).unwrap_err();
+        assert_eq!(err.close_code(), CloseCode::Size);
+
+        // compressed control frame
+        let mut client = Codec::new().client_mode().deflate(DeflateConfig::new());
+        let mut buf = BytesMut::new();
+        Parser::write_message(&mut buf, "ping", OpCode::Ping, true, false);
+        buf[0] |= RSV1;
+        assert!(client.decode(&mut buf).is_err());
+
+        // invalid deflate stream
+        let mut buf = BytesMut::new();
+        Parser::write_message(&mut buf, [0xff; 8], OpCode::Text, true, false);
+        buf[0] |= RSV1;
+        assert!(client.decode(&mut buf).is_err());
+
+        // uncompressed message
+        let mut buf = BytesMut::new();
+        Parser::write_message(&mut buf, "text", OpCode::Text, true, false);
+        assert_eq!(
+            client.decode(&mut buf).unwrap(),
+            Some(Frame::Text(Bytes::from_static(b"text")))
+        );
+    }
+}
@@ -0,0 +1,324 @@
+//! Per-message deflate extension, RFC 7692
+use std::{cmp, fmt, io};
+
+use bytes::BytesMut;
+use flate2::{Compress, Compression, Decompress, FlushCompress, FlushDecompress};
+use http::header;
+
+use crate::message::RequestHead;
+use crate::ws::ProtocolError;
+
+/// Trailer removed from the end of every compressed message.
+const TRAILER: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+
+/// Parameters of `permessage-deflate` extension
+///
+/// Server side configuration is passed to `handshake_deflate()`, which
+/// returns parameters accepted for the connection. These should be passed
+/// to `Codec::deflate()`.
+///
+/// With `no_context_takeover` compression context of the respective side is
+/// reset after each message, so compression of a message does not depend on
+/// previous ones. By default context is preserved for better compression.
+/// Only default 15 bit window is supported, offers which limit server window
+/// size are declined.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct DeflateConfig {
+    server_no_context_takeover: bool,
+    client_no_context_takeover: bool,
+}
+
+impl DeflateConfig {
+    /// Create default configuration
+    pub fn new() -> Self {
+        DeflateConfig::default()
+    }
+
+    /// Reset server compression context after each message
+    pub fn server_no_context_takeover(mut self, value: bool) -> Self {
+        self.server_no_context_takeover = value;
+        self
+    }
+
+    /// Request client to reset compression context after each message
+    pub fn client_no_context_takeover(mut self, value: bool) -> Self {
+        self.client_no_context_takeover = value;
+        self
+    }
+
+    /// Select first acceptable extension offer of the handshake request.
+    ///
+    /// Returns `None` if client does not offer `permessage-deflate` or none
+    /// of the offers can be accepted.
+    pub fn negotiate(&self, req: &RequestHead) -> Option<DeflateConfig> {
+        req.headers()
+            .get_all(header::SEC_WEBSOCKET_EXTENSIONS)
+            .filter_map(|hdr| hdr.to_str().ok())
+            .flat_map(|hdr| hdr.split(','))
+            .filter_map(|offer| self.accept(offer))
+            .next()
+    }
+
+    fn accept(&self, offer: &str) -> Option<DeflateConfig> {
+        let mut params = offer.split(';').map(str::trim);
+        if !params.next()?.eq_ignore_ascii_case("permessage-deflate") {
+            return None;
+        }
+
+        let mut config = *self;
+        for param in params {
+            let (name, value) = match param.find('=') {
+                Some(idx) => (
+                    param[..idx].trim(),
+                    Some(param[idx + 1..].trim().trim_matches('"')),
+                ),
+                None => (param, None),
+            };
+            match (name, value) {
+                ("server_no_context_takeover", None) => {
+                    config.server_no_context_takeover = true
+                }
+                ("client_no_context_takeover", None) => {
+                    config.client_no_context_takeover = true
+                }
+                // client window is not limited by server
+                ("client_max_window_bits", None) => (),
+                ("client_max_window_bits", Some(bits)) => match bits.parse::<u8>() {
+                    Ok(8..=15) => (),
+                    _ => return None,
+                },
+                ("server_max_window_bits", Some("15")) => (),
+                _ => return None,
+            }
+        }
+        Some(config)
+    }
+}
+
+/// Value of `Sec-WebSocket-Extensions` header
+impl fmt::Display for DeflateConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "permessage-deflate")?;
+        if self.server_no_context_takeover {
+            write!(f, "; server_no_context_takeover")?;
+        }
+        if self.client_no_context_takeover {
+            write!(f, "; client_no_context_takeover")?;
+        }
+        Ok(())
+    }
+}
+
+/// Compression state of a connection
+pub(crate) struct Deflate {
+    config: DeflateConfig,
+    compress: Compress,
+    decompress: Decompress,
+}
+
+impl Deflate {
+    pub(crate) fn new(config: DeflateConfig) -> Self {
+        Deflate {
+            config,
+            compress: Compress::new(Compression::default(), false),
+            decompress: Decompress::new(false),
+        }
+    }
+
+    /// Compress fragment of outgoing message
+    pub(crate) fn compress(
+        &mut self,
+        data: &[u8],
+        last: bool,
+        server: bool,
+    ) -> Result<Vec<u8>, ProtocolError> {
+        let mut out = Vec::with_capacity(data.len() / 2 + 64);
+        let mut pos = 0;
+        loop {
+            if out.capacity() - out.len() < 64 {
+                out.reserve(cmp::max(out.capacity(), 64));
+            }
+            let total_in = self.compress.total_in();
+            self.compress
+                .compress_vec(&data[pos..], &mut out, FlushCompress::Sync)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+            pos += (self.compress.total_in() - total_in) as usize;
+            if pos == data.len() && out.len() < out.capacity() {
+                break;
+            }
+        }
+
+        if last {
+            if out.ends_with(&TRAILER) {
+                out.truncate(out.len() - TRAILER.len());
+            }
+            let reset = if server {
+                self.config.server_no_context_takeover
+            } else {
+                self.config.client_no_context_takeover
+            };
+            if reset {
+                self.compress.reset();
+            }
+        }
+        Ok(out)
+    }
+
+    /// Decompress fragment of incoming message, output is limited by `max_size`
+    pub(crate) fn decompress(
+        &mut self,
+        data: &[u8],
+        last: bool,
+        server: bool,
+        max_size: usize,
+    ) -> Result<BytesMut, ProtocolError> {
+        let mut out = Vec::with_capacity(cmp::min(data.len() * 2, max_size) + 1);
+        self.inflate(data, &mut out, max_size)?;
+
+        if last {
+            self.inflate(&TRAILER, &mut out, max_size)?;
+            let reset = if server {
+                self.config.client_no_context_takeover
+            } else {
+                self.config.server_no_context_takeover
+            };
+            if reset {
+                self.decompress.reset(false);
+            }
+        }
+        Ok(BytesMut::from(&out[..]))
+    }
+
+    fn inflate(
+        &mut self,
+        data: &[u8],
+        out: &mut Vec<u8>,
+        max_size: usize,
+    ) -> Result<(), ProtocolError> {
+        let mut pos = 0;
+        loop {
+            if out.len() == out.capacity() {
+                if out.len() > max_size {
+                    return Err(ProtocolError::Overflow);
+                }
+                let size = cmp::max(out.len(), 1024);
+                out.reserve_exact(cmp::min(size, max_size + 1 - out.len()));
+            }
+            let total_in = self.decompress.total_in();
+            let total_out = self.decompress.total_out();
+            self.decompress
+                .decompress_vec(&data[pos..], out, FlushDecompress::Sync)
+                .map_err(|_| ProtocolError::InvalidCompression)?;
+            let consumed = (self.decompress.total_in() - total_in) as usize;
+            pos += consumed;
+
+            if out.len() < out.capacity()
+                && (pos == data.len()
+                    || consumed == 0 && self.decompress.total_out() == total_out)
+            {
+                break;
+            }
+        }
+
+        if out.len() > max_size {
+            Err(ProtocolError::Overflow)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// New compression context is created for a clone
+impl Clone for Deflate {
+    fn clone(&self) -> Self {
+        Deflate::new(self.config)
+    }
+}
+
+impl fmt::Debug for Deflate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Deflate")
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::TestRequest;
+    use crate::ws::handshake_deflate;
+
+    fn offer(ext: &str) -> crate::Request {
+        TestRequest::default()
+            .header(header::UPGRADE, "websocket")
+            .header(header::CONNECTION, "upgrade")
+            .header(header::SEC_WEBSOCKET_VERSION, "13")
+            .header(header::SEC_WEBSOCKET_KEY, "13")
+            .header(header::SEC_WEBSOCKET_EXTENSIONS, ext)
+            .finish()
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let config = DeflateConfig::new();
+
+        let req = offer("permessage-deflate; client_max_window_bits");
+        assert_eq!(config.negotiate(req.head()), Some(config));
+
+        let req = offer(
+            "permessage-deflate; server_no_context_takeover; client_no_context_takeover",
+        );
+        let accepted = config.negotiate(req.head()).unwrap();
+        assert_eq!(
+            accepted.to_string(),
+            "permessage-deflate; server_no_context_takeover; client_no_context_takeover"
+        );
+
+        // unsupported window size, falls back to next offer
+        let req = offer(
+            "permessage-deflate; server_max_window_bits=10, \
+             permessage-deflate; client_max_window_bits=\"12\"",
+        );
+        assert_eq!(config.negotiate(req.head()), Some(config));
+
+        let req = offer("permessage-deflate; server_max_window_bits=10");
+        assert_eq!(config.negotiate(req.head()), None);
+        let req = offer("permessage-deflate; unknown");
+        assert_eq!(config.negotiate(req.head()), None);
+        let req = offer("x-webkit-deflate-frame");
+        assert_eq!(config.negotiate(req.head()), None);
+
+        // server parameters
+        let config = DeflateConfig::new().client_no_context_takeover(true);
+        let req = offer("permessage-deflate");
+        assert_eq!(
+            config.negotiate(req.head()).unwrap().to_string(),
+            "permessage-deflate; client_no_context_takeover"
+        );
+    }
+
+    #[test]
+    fn test_handshake_deflate() {
+        let req = offer("permessage-deflate");
+        let (mut res, accepted) =
+            handshake_deflate(req.head(), DeflateConfig::new()).unwrap();
+        assert_eq!(accepted, Some(DeflateConfig::new()));
+        assert_eq!(
+            res.finish()
+                .headers()
+                .get(header::SEC_WEBSOCKET_EXTENSIONS)
+                .unwrap(),
+            "permessage-deflate"
+        );
+
+        let req = offer("x-webkit-deflate-frame");
+        let (mut res, accepted) =
+            handshake_deflate(req.head(), DeflateConfig::new()).unwrap();
+        assert_eq!(accepted, None);
+        assert!(!res
+            .finish()
+            .headers()
+            .contains_key(header::SEC_WEBSOCKET_EXTENSIONS));
+    }
+}
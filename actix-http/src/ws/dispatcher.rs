@@ -5,15 +5,17 @@ use std::task::{Context, Poll};
 use actix_codec::{AsyncRead, AsyncWrite, Framed};
 use actix_service::{IntoService, Service};
 use actix_utils::framed;
+use bytes::BytesMut;
+use futures_util::future::{ok, Either, Ready};
 
-use super::{Codec, Frame, Message};
+use super::{CloseCode, Codec, Frame, Item, Message};
 
 pub struct Dispatcher<S, T>
 where
     S: Service<Request = Frame, Response = Message> + 'static,
     T: AsyncRead + AsyncWrite,
 {
-    inner: framed::Dispatcher<S, T, Codec>,
+    inner: framed::Dispatcher<Reassemble<S>, T, Codec>,
 }
 
 impl<S, T> Dispatcher<S, T>
@@ -24,14 +26,38 @@ where
     S::Error: 'static,
 {
     pub fn new<F: IntoService<S>>(io: T, service: F) -> Self {
+        Dispatcher::with(Framed::new(io, Codec::new()), service)
+    }
+
+    pub fn with<F: IntoService<S>>(framed: Framed<T, Codec>, service: F) -> Self {
         Dispatcher {
-            inner: framed::Dispatcher::new(Framed::new(io, Codec::new()), service),
+            inner: framed::Dispatcher::new(
+                framed,
+                Reassemble::new(service.into_service(), None),
+            ),
         }
     }
 
-    pub fn with<F: IntoService<S>>(framed: Framed<T, Codec>, service: F) -> Self {
+    /// Create dispatcher which reassembles fragmented messages.
+    ///
+    /// Continuation frames are buffered and service receives complete
+    /// `Frame::Text` or `Frame::Binary` once last fragment arrives. Control
+    /// frames received between fragments are passed to service immediately.
+    /// If reassembled message exceeds `max_size`, close frame with
+    /// `CloseCode::Size` is sent and all following frames are ignored.
+    ///
+    /// Codec limits size of fragmented messages as well, its `max_size`
+    /// should not be less than `max_size` of reassembly.
+    pub fn with_reassembly<F: IntoService<S>>(
+        framed: Framed<T, Codec>,
+        max_size: usize,
+        service: F,
+    ) -> Self {
         Dispatcher {
-            inner: framed::Dispatcher::new(framed, service),
+            inner: framed::Dispatcher::new(
+                framed,
+                Reassemble::new(service.into_service(), Some(max_size)),
+            ),
         }
     }
 }
@@ -49,3 +75,77 @@ where
         Pin::new(&mut self.inner).poll(cx)
     }
 }
+
+/// Service which buffers fragments of a message before passing it to inner service
+struct Reassemble<S> {
+    service: S,
+    max_size: Option<usize>,
+    fragments: Option<(bool, BytesMut)>,
+    closed: bool,
+}
+
+impl<S> Reassemble<S> {
+    fn new(service: S, max_size: Option<usize>) -> Self {
+        Reassemble {
+            service,
+            max_size,
+            fragments: None,
+            closed: false,
+        }
+    }
+}
+
+impl<S> Service for Reassemble<S>
+where
+    S: Service<Request = Frame, Response = Message>,
+{
+    type Request = Frame;
+    type Response = Message;
+    type Error = S::Error;
+    type Future = Either<S::Future, Ready<Result<Message, S::Error>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, frame: Frame) -> Self::Future {
+        let max_size = match self.max_size {
+            Some(max_size) => max_size,
+            None => return Either::Left(self.service.call(frame)),
+        };
+        if self.closed {
+            return Either::Right(ok(Message::Nop));
+        }
+
+        let (text, data, last) = match frame {
+            Frame::Continuation(Item::FirstText(data)) => (Some(true), data, false),
+            Frame::Continuation(Item::FirstBinary(data)) => (Some(false), data, false),
+            Frame::Continuation(Item::Continue(data)) => (None, data, false),
+            Frame::Continuation(Item::Last(data)) => (None, data, true),
+            frame => return Either::Left(self.service.call(frame)),
+        };
+
+        // codec verifies order of fragments
+        if let Some(text) = text {
+            self.fragments = Some((text, BytesMut::new()));
+        }
+        let (text, mut buf) = match self.fragments.take() {
+            Some(fragments) => fragments,
+            None => return Either::Right(ok(Message::Nop)),
+        };
+        if buf.len() + data.len() > max_size {
+            self.closed = true;
+            return Either::Right(ok(Message::Close(Some(CloseCode::Size.into()))));
+        }
+        buf.extend_from_slice(&data);
+
+        if !last {
+            self.fragments = Some((text, buf));
+            Either::Right(ok(Message::Nop))
+        } else if text {
+            Either::Left(self.service.call(Frame::Text(buf.freeze())))
+        } else {
+            Either::Left(self.service.call(Frame::Binary(buf.freeze())))
+        }
+    }
+}
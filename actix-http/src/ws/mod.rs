@@ -13,12 +13,16 @@ use crate::message::RequestHead;
 use crate::response::{Response, ResponseBuilder};
 
 mod codec;
+#[cfg(feature = "compress")]
+mod deflate;
 mod dispatcher;
 mod frame;
 mod mask;
 mod proto;
 
 pub use self::codec::{Codec, Frame, Item, Message};
+#[cfg(feature = "compress")]
+pub use self::deflate::DeflateConfig;
 pub use self::dispatcher::Dispatcher;
 pub use self::frame::Parser;
 pub use self::proto::{hash_key, CloseCode, CloseReason, OpCode};
@@ -53,11 +57,25 @@ pub enum ProtocolError {
     /// Unknown continuation fragment
     #[display(fmt = "Unknown continuation fragment.")]
     ContinuationFragment(OpCode),
+    /// Invalid compressed frame
+    #[display(fmt = "Invalid compressed frame")]
+    InvalidCompression,
     /// Io error
     #[display(fmt = "io error: {}", _0)]
     Io(io::Error),
 }
 
+impl ProtocolError {
+    /// Close code which should be sent to the peer on this error
+    pub fn close_code(&self) -> CloseCode {
+        match self {
+            ProtocolError::Overflow => CloseCode::Size,
+            ProtocolError::Io(_) => CloseCode::Error,
+            _ => CloseCode::Protocol,
+        }
+    }
+}
+
 impl ResponseError for ProtocolError {}
 
 /// Websocket handshake errors
@@ -117,6 +135,26 @@ pub fn handshake(req: &RequestHead) -> Result<ResponseBuilder, HandshakeError> {
     Ok(handshake_response(req))
 }
 
+/// Verify `WebSocket` handshake request and create handshake response,
+/// accepting `permessage-deflate` extension if client offers it.
+///
+/// Returns parameters of accepted extension, which should be passed to
+/// `Codec::deflate()`. If extension is not accepted, response has no
+/// `Sec-WebSocket-Extensions` header and messages are not compressed.
+#[cfg(feature = "compress")]
+pub fn handshake_deflate(
+    req: &RequestHead,
+    config: DeflateConfig,
+) -> Result<(ResponseBuilder, Option<DeflateConfig>), HandshakeError> {
+    verify_handshake(req)?;
+    let mut res = handshake_response(req);
+    let accepted = config.negotiate(req);
+    if let Some(ref accepted) = accepted {
+        res.header(header::SEC_WEBSOCKET_EXTENSIONS, accepted.to_string());
+    }
+    Ok((res, accepted))
+}
+
 /// Verify `WebSocket` handshake request.
 // /// `protocols` is a sequence of known protocols. On successful handshake,
 // /// the returned response headers contain the first protocol in this list
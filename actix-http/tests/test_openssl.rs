@@ -92,6 +92,52 @@ async fn test_h2_1() -> io::Result<()> {
     Ok(())
 }
 
+#[actix_rt::test]
+async fn test_alpn_fallback() -> io::Result<()> {
+    // acceptor without ALPN support
+    let mut builder = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
+    builder
+        .set_private_key_file("../tests/key.pem", SslFiletype::PEM)
+        .unwrap();
+    builder
+        .set_certificate_chain_file("../tests/cert.pem")
+        .unwrap();
+    let acceptor = builder.build();
+
+    let srv = test_server(move || {
+        HttpService::build()
+            .finish(|req: Request| {
+                assert_eq!(req.version(), Version::HTTP_11);
+                ok::<_, Error>(Response::Ok().finish())
+            })
+            .openssl(acceptor.clone())
+            .map_err(|_| ())
+    });
+
+    let response = srv.sget("/").send().await.unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(response.version(), Version::HTTP_11);
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_h2_request_deadline() -> io::Result<()> {
+    let srv = test_server(move || {
+        HttpService::build()
+            .request_deadline(std::time::Duration::from_millis(100))
+            .h2(|_| async {
+                actix_rt::time::delay_for(std::time::Duration::from_millis(1000)).await;
+                Ok::<_, Error>(Response::Ok().finish())
+            })
+            .openssl(ssl_acceptor())
+            .map_err(|_| ())
+    });
+
+    let response = srv.sget("/").send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    Ok(())
+}
+
 #[actix_rt::test]
 async fn test_h2_body() -> io::Result<()> {
     let data = "HELLOWORLD".to_owned().repeat(64 * 1024);
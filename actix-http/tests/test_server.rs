@@ -12,7 +12,8 @@ use regex::Regex;
 
 use actix_http::httpmessage::HttpMessage;
 use actix_http::{
-    body, error, http, http::header, Error, HttpService, KeepAlive, Request, Response,
+    body, error, http, http::header, Error, HttpService, KeepAlive, NormalizePath,
+    Request, Response, SlowRequest, TrailingSlash,
 };
 
 #[actix_rt::test]
@@ -52,6 +53,50 @@ async fn test_h1_2() {
     assert!(response.status().is_success());
 }
 
+#[actix_rt::test]
+async fn test_h1_normalize_path() {
+    let cases = vec![
+        (NormalizePath::new(TrailingSlash::Merge), "/a/b/"),
+        (NormalizePath::new(TrailingSlash::Append), "/a/b/"),
+        (NormalizePath::new(TrailingSlash::Trim), "/a/b"),
+    ];
+
+    for (policy, expected) in cases {
+        let mut srv = test_server(move || {
+            HttpService::build()
+                .normalize_path(policy)
+                .h1(move |req: Request| {
+                    if req.path() == expected {
+                        ok::<_, ()>(Response::Ok().body(req.uri().to_string()))
+                    } else {
+                        ok::<_, ()>(Response::NotFound().finish())
+                    }
+                })
+                .tcp()
+        });
+
+        let response = srv.get("/a//b/?q=1").send().await.unwrap();
+        assert!(response.status().is_success());
+        let bytes = srv.load_body(response).await.unwrap();
+        assert_eq!(bytes, Bytes::from(format!("{}?q=1", expected)));
+    }
+
+    let srv = test_server(move || {
+        HttpService::build()
+            .normalize_path(NormalizePath::new(TrailingSlash::Trim).lowercase(true))
+            .h1(|req: Request| {
+                if req.path() == "/a/b" {
+                    ok::<_, ()>(Response::Ok().finish())
+                } else {
+                    ok::<_, ()>(Response::NotFound().finish())
+                }
+            })
+            .tcp()
+    });
+    let response = srv.get("/A//B/").send().await.unwrap();
+    assert!(response.status().is_success());
+}
+
 #[actix_rt::test]
 async fn test_expect_continue() {
     let srv = test_server(|| {
@@ -169,7 +214,7 @@ async fn test_chunked_payload() {
 async fn test_slow_request() {
     let srv = test_server(|| {
         HttpService::build()
-            .client_timeout(100)
+            .client_timeout(0)
             .finish(|_| future::ok::<_, ()>(Response::Ok().finish()))
             .tcp()
     });
@@ -196,6 +241,400 @@ async fn test_http1_malformed_request() {
     assert!(data.starts_with("HTTP/1.1 400 Bad Request"));
 }
 
+#[actix_rt::test]
+async fn test_http1_malformed_request_custom_response() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .bad_request_response(|| {
+                Response::BadRequest()
+                    .header("x-error", "parse")
+                    .body("{\"error\":\"malformed request\"}")
+            })
+            .h1(|_| future::ok::<_, ()>(Response::Ok().finish()))
+            .tcp()
+    });
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(b"GET /test/tests/test HTTP1.1\r\n");
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.starts_with("HTTP/1.1 400 Bad Request"));
+    assert!(data.contains("x-error: parse\r\n"));
+    assert!(data.ends_with("\r\n\r\n{\"error\":\"malformed request\"}"));
+}
+
+#[actix_rt::test]
+async fn test_http1_alt_svc() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .alt_svc(header::HeaderValue::from_static("h3=\":443\"; ma=3600"))
+            .h1(|req: Request| {
+                if req.path() == "/custom" {
+                    future::ok::<_, ()>(
+                        Response::Ok().header("alt-svc", "clear").finish(),
+                    )
+                } else {
+                    future::ok::<_, ()>(Response::Ok().finish())
+                }
+            })
+            .tcp()
+    });
+
+    let response = srv.get("/").send().await.unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(
+        response.headers().get(header::ALT_SVC).unwrap(),
+        "h3=\":443\"; ma=3600"
+    );
+
+    let response = srv.get("/custom").send().await.unwrap();
+    assert_eq!(response.headers().get(header::ALT_SVC).unwrap(), "clear");
+}
+
+#[actix_rt::test]
+async fn test_http1_load_shed() {
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use std::task::{Context, Poll};
+
+    use actix_service::{fn_factory, Service};
+
+    /// Service which is not ready after `/busy` request
+    struct BusyService(Rc<Cell<bool>>);
+
+    impl Service for BusyService {
+        type Request = Request;
+        type Response = Response;
+        type Error = Error;
+        type Future = future::Ready<Result<Response, Error>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Error>> {
+            if self.0.get() {
+                Poll::Pending
+            } else {
+                Poll::Ready(Ok(()))
+            }
+        }
+
+        fn call(&mut self, req: Request) -> Self::Future {
+            if req.path() == "/busy" {
+                self.0.set(true);
+            }
+            ok(Response::Ok().finish())
+        }
+    }
+
+    let srv = test_server(|| {
+        HttpService::build()
+            .load_shed(30)
+            .h1(fn_factory(|| {
+                ok::<_, ()>(BusyService(Rc::new(Cell::new(false))))
+            }))
+            .tcp()
+    });
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let mut data = vec![0; 1024];
+
+    let _ = stream.write_all(b"GET /busy HTTP/1.1\r\n\r\n");
+    let n = stream.read(&mut data).unwrap();
+    let response = String::from_utf8_lossy(&data[..n]).to_lowercase();
+    assert!(response.starts_with("http/1.1 200 ok\r\n"));
+    assert!(!response.contains("retry-after"));
+
+    let _ = stream.write_all(b"GET / HTTP/1.1\r\n\r\n");
+    let n = stream.read(&mut data).unwrap();
+    let response = String::from_utf8_lossy(&data[..n]).to_lowercase();
+    assert!(response.starts_with("http/1.1 503 service unavailable\r\n"));
+    assert!(response.contains("retry-after: 30\r\n"));
+}
+
+#[actix_rt::test]
+async fn test_http1_request_deadline() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .request_deadline(Duration::from_millis(100))
+            .request_deadline_status(http::StatusCode::GATEWAY_TIMEOUT)
+            .h1(|req: Request| async move {
+                if req.path() == "/slow" {
+                    delay_for(Duration::from_millis(1000)).await;
+                }
+                Ok::<_, ()>(Response::Ok().finish())
+            })
+            .tcp()
+    });
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let mut data = vec![0; 1024];
+
+    let _ = stream.write_all(b"GET / HTTP/1.1\r\n\r\n");
+    let n = stream.read(&mut data).unwrap();
+    let response = String::from_utf8_lossy(&data[..n]).to_lowercase();
+    assert!(response.starts_with("http/1.1 200 ok\r\n"));
+
+    let _ = stream.write_all(b"GET /slow HTTP/1.1\r\n\r\n");
+    let n = stream.read(&mut data).unwrap();
+    let response = String::from_utf8_lossy(&data[..n]).to_lowercase();
+    assert!(response.starts_with("http/1.1 504 gateway timeout\r\n"));
+    assert!(response.contains("connection: close\r\n"));
+}
+
+#[actix_rt::test]
+async fn test_http1_slow_request_log() {
+    use std::sync::{Arc, Mutex};
+
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let log2 = log.clone();
+    let srv = test_server(move || {
+        let log = log2.clone();
+        HttpService::build()
+            .slow_request_threshold(Duration::from_millis(100))
+            .slow_request_logger(move |req: &SlowRequest| {
+                log.lock().unwrap().push(req.clone());
+            })
+            .h1(|req: Request| async move {
+                if req.path() == "/slow" {
+                    delay_for(Duration::from_millis(200)).await;
+                }
+                Ok::<_, ()>(Response::Created().finish())
+            })
+            .tcp()
+    });
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let mut data = vec![0; 1024];
+
+    let _ = stream.write_all(b"GET /fast HTTP/1.1\r\n\r\n");
+    let n = stream.read(&mut data).unwrap();
+    assert!(data[..n].starts_with(b"HTTP/1.1 201 Created\r\n"));
+    assert!(log.lock().unwrap().is_empty());
+
+    let _ = stream.write_all(b"POST /slow?q=1 HTTP/1.1\r\n\r\n");
+    let n = stream.read(&mut data).unwrap();
+    assert!(data[..n].starts_with(b"HTTP/1.1 201 Created\r\n"));
+
+    let log = log.lock().unwrap();
+    assert_eq!(log.len(), 1);
+    assert_eq!(log[0].method(), http::Method::POST);
+    assert_eq!(log[0].path(), "/slow");
+    assert_eq!(log[0].status(), http::StatusCode::CREATED);
+    assert!(log[0].latency() >= Duration::from_millis(200));
+}
+
+#[actix_rt::test]
+async fn test_h1_write_error_hook() {
+    use actix_http::WriteErrorContext;
+    use futures::stream::repeat;
+    use std::sync::{Arc, Mutex};
+
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let log2 = log.clone();
+    let srv = test_server(move || {
+        let log = log2.clone();
+        HttpService::build()
+            .on_write_error(move |ctx: &WriteErrorContext, _: &std::io::Error| {
+                log.lock().unwrap().push(ctx.clone());
+            })
+            .h1(|_| {
+                let chunk = Bytes::from(vec![b'x'; 64 * 1024]);
+                ok::<_, ()>(Response::Ok().streaming(repeat(chunk).map(Ok::<_, Error>)))
+            })
+            .tcp()
+    });
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(b"GET /stream HTTP/1.1\r\n\r\n");
+    let mut data = vec![0; 1024];
+    let n = stream.read(&mut data).unwrap();
+    assert!(data[..n].starts_with(b"HTTP/1.1 200 OK\r\n"));
+    drop(stream);
+
+    for _ in 0..50 {
+        if !log.lock().unwrap().is_empty() {
+            break;
+        }
+        delay_for(Duration::from_millis(100)).await;
+    }
+
+    let log = log.lock().unwrap();
+    assert_eq!(log.len(), 1);
+    assert_eq!(log[0].method(), http::Method::GET);
+    assert_eq!(log[0].path(), "/stream");
+    assert_eq!(log[0].status(), Some(http::StatusCode::OK));
+    assert!(log[0].peer_addr().is_some());
+}
+
+#[actix_rt::test]
+async fn test_server_timing() {
+    use actix_http::ServerTiming;
+    use actix_rt::net::TcpStream;
+
+    fn service(req: Request) -> future::Ready<Result<Response, Error>> {
+        if let Some(timing) = req.extensions().get::<ServerTiming>() {
+            timing.add("db", Duration::from_millis(5));
+        }
+        ok(Response::Ok().finish())
+    }
+
+    let srv = test_server(|| HttpService::build().server_timing(true).h1(service).tcp());
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let mut data = vec![0; 1024];
+    let _ = stream.write_all(b"GET / HTTP/1.1\r\n\r\n");
+    let n = stream.read(&mut data).unwrap();
+    let response = String::from_utf8_lossy(&data[..n]).to_lowercase();
+    let re = Regex::new(
+        r"server-timing: conn;dur=[0-9.]+, db;dur=5\.000, total;dur=[0-9.]+\r\n",
+    )
+    .unwrap();
+    assert!(re.is_match(&response), "{}", response);
+
+    // disabled by default
+    let srv = test_server(|| HttpService::build().h1(service).tcp());
+    let response = srv.get("/").send().await.unwrap();
+    assert!(response.status().is_success());
+    assert!(!response.headers().contains_key("server-timing"));
+
+    // http/2 handshake is reported with first request of connection
+    let srv = test_server(|| HttpService::build().server_timing(true).h2(service).tcp());
+    let io = TcpStream::connect(srv.addr()).await.unwrap();
+    let (client, conn) = h2::client::handshake(io).await.unwrap();
+    actix_rt::spawn(async move {
+        let _ = conn.await;
+    });
+
+    let mut client = client.ready().await.unwrap();
+    let mut timings = Vec::new();
+    for _ in 0..2 {
+        let req = ::http::Request::get("/").body(()).unwrap();
+        let (resp, _) = client.send_request(req, true).unwrap();
+        let resp = resp.await.unwrap();
+        let value = resp.headers().get("server-timing").unwrap();
+        timings.push(value.to_str().unwrap().to_owned());
+        client = client.ready().await.unwrap();
+    }
+    let names = |timing: &str| {
+        timing
+            .split(", ")
+            .map(|metric| metric.split(';').next().unwrap().to_owned())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names(&timings[0]), vec!["conn", "handshake", "db", "total"]);
+    assert_eq!(names(&timings[1]), vec!["conn", "db", "total"]);
+}
+
+#[actix_rt::test]
+async fn test_max_concurrent_requests() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use actix_rt::net::TcpStream;
+
+    #[derive(Clone, Default)]
+    struct Concurrency {
+        active: Arc<AtomicUsize>,
+        max: Arc<AtomicUsize>,
+    }
+
+    impl Concurrency {
+        async fn call(self, _: Request) -> Result<Response, Error> {
+            let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max.fetch_max(active, Ordering::SeqCst);
+            delay_for(Duration::from_millis(100)).await;
+            self.active.fetch_sub(1, Ordering::SeqCst);
+            Ok(Response::Ok().finish())
+        }
+    }
+
+    // http/1, requests of different connections
+    let conc = Concurrency::default();
+    let conc2 = conc.clone();
+    let srv = test_server(move || {
+        let conc = conc2.clone();
+        HttpService::build()
+            .max_concurrent_requests(2)
+            .h1(move |req| conc.clone().call(req))
+            .tcp()
+    });
+    let responses = futures::future::join_all((0..4).map(|_| srv.get("/").send())).await;
+    for response in responses {
+        assert!(response.unwrap().status().is_success());
+    }
+    assert_eq!(conc.max.load(Ordering::SeqCst), 2);
+
+    // http/2, streams of one connection
+    let conc = Concurrency::default();
+    let conc2 = conc.clone();
+    let srv = test_server(move || {
+        let conc = conc2.clone();
+        HttpService::build()
+            .max_concurrent_requests(1)
+            .h2(move |req| conc.clone().call(req))
+            .tcp()
+    });
+    let io = TcpStream::connect(srv.addr()).await.unwrap();
+    let (mut client, conn) = h2::client::handshake(io).await.unwrap();
+    actix_rt::spawn(async move {
+        let _ = conn.await;
+    });
+    let mut responses = Vec::new();
+    for _ in 0..3 {
+        let req = ::http::Request::get("/").body(()).unwrap();
+        let (resp, _) = client.send_request(req, true).unwrap();
+        responses.push(resp);
+    }
+    for response in futures::future::join_all(responses).await {
+        assert!(response.unwrap().status().is_success());
+    }
+    assert_eq!(conc.max.load(Ordering::SeqCst), 1);
+
+    // limit is disabled by default
+    let conc = Concurrency::default();
+    let conc2 = conc.clone();
+    let srv = test_server(move || {
+        let conc = conc2.clone();
+        HttpService::build()
+            .h1(move |req| conc.clone().call(req))
+            .tcp()
+    });
+    let responses = futures::future::join_all((0..4).map(|_| srv.get("/").send())).await;
+    for response in responses {
+        assert!(response.unwrap().status().is_success());
+    }
+    assert_eq!(conc.max.load(Ordering::SeqCst), 4);
+}
+
+#[actix_rt::test]
+async fn test_alpn_fallback() {
+    use actix_http::Protocol;
+    use actix_rt::net::TcpStream;
+
+    assert_eq!(Protocol::from_alpn(b"h2"), Some(Protocol::Http2));
+    assert_eq!(Protocol::from_alpn(b"http/1.1"), Some(Protocol::Http1));
+    assert_eq!(Protocol::from_alpn(b"spdy/3"), None);
+
+    // tcp transport has no ALPN, http/2 with prior knowledge
+    let srv = test_server(|| {
+        HttpService::build()
+            .alpn_fallback(Protocol::Http2)
+            .finish(|req: Request| {
+                assert_eq!(req.version(), http::Version::HTTP_2);
+                ok::<_, Error>(Response::Ok().finish())
+            })
+            .tcp()
+    });
+
+    let io = TcpStream::connect(srv.addr()).await.unwrap();
+    let (client, conn) = h2::client::handshake(io).await.unwrap();
+    actix_rt::spawn(async move {
+        let _ = conn.await;
+    });
+    let mut client = client.ready().await.unwrap();
+    let req = ::http::Request::get("/").body(()).unwrap();
+    let (resp, _) = client.send_request(req, true).unwrap();
+    assert!(resp.await.unwrap().status().is_success());
+}
+
 #[actix_rt::test]
 async fn test_http1_keepalive() {
     let srv = test_server(|| {
@@ -257,6 +696,41 @@ async fn test_http1_keepalive_close() {
     assert_eq!(res, 0);
 }
 
+#[actix_rt::test]
+async fn test_http1_max_requests_per_connection() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .max_requests_per_connection(2)
+            .h1(|_| future::ok::<_, ()>(Response::Ok().finish()))
+            .tcp()
+    });
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    for i in 1..=2 {
+        let _ = stream.write_all(b"GET /test/tests/test HTTP/1.1\r\n\r\n");
+        let mut data = vec![0; 1024];
+        let n = stream.read(&mut data).unwrap();
+        let resp = String::from_utf8_lossy(&data[..n]).to_lowercase();
+        assert!(resp.starts_with("http/1.1 200 ok\r\n"));
+        assert_eq!(resp.contains("connection: close\r\n"), i == 2);
+    }
+
+    // third request is not served
+    let _ = stream.write_all(b"GET /test/tests/test HTTP/1.1\r\n\r\n");
+    let mut data = vec![0; 1024];
+    let res = stream.read(&mut data).unwrap_or(0);
+    assert_eq!(res, 0);
+
+    // pipelined requests beyond limit are dropped
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let req = b"GET /test/tests/test HTTP/1.1\r\n\r\n";
+    let _ = stream.write_all(&[&req[..], &req[..], &req[..]].concat());
+    let mut data = Vec::new();
+    let _ = stream.read_to_end(&mut data);
+    let resp = String::from_utf8_lossy(&data);
+    assert_eq!(resp.matches("HTTP/1.1 200 OK").count(), 2);
+}
+
 #[actix_rt::test]
 async fn test_http10_keepalive_default_close() {
     let srv = test_server(|| {
@@ -652,3 +1126,442 @@ async fn test_h1_on_connect() {
     let response = srv.get("/").send().await.unwrap();
     assert!(response.status().is_success());
 }
+
+#[actix_rt::test]
+async fn test_early_data() {
+    use actix_http::{EarlyData, EarlyDataPolicy, Protocol};
+    use actix_rt::net::TcpStream;
+
+    // every request is marked as received in early data
+    let srv = test_server(|| {
+        HttpService::build()
+            .on_connect(|_| EarlyData)
+            .early_data(EarlyDataPolicy::SafeMethods)
+            .h1(|_| future::ok::<_, ()>(Response::Ok().finish()))
+            .tcp()
+    });
+
+    let response = srv.get("/").send().await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::OK);
+    let response = srv.post("/").send().await.unwrap();
+    assert_eq!(response.status().as_u16(), 425);
+
+    let srv = test_server(|| {
+        HttpService::build()
+            .on_connect(|_| EarlyData)
+            .early_data(EarlyDataPolicy::Allow)
+            .h1(|req: Request| {
+                assert!(req.extensions().contains::<EarlyData>());
+                future::ok::<_, ()>(Response::Ok().finish())
+            })
+            .tcp()
+    });
+
+    let response = srv.post("/").send().await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::OK);
+
+    let srv = test_server(|| {
+        HttpService::build()
+            .on_connect(|_| EarlyData)
+            .early_data(EarlyDataPolicy::Reject)
+            .alpn_fallback(Protocol::Http2)
+            .finish(|_| ok::<_, Error>(Response::Ok().finish()))
+            .tcp()
+    });
+
+    let io = TcpStream::connect(srv.addr()).await.unwrap();
+    let (client, conn) = h2::client::handshake(io).await.unwrap();
+    actix_rt::spawn(async move {
+        let _ = conn.await;
+    });
+    let mut client = client.ready().await.unwrap();
+    let req = ::http::Request::get("/").body(()).unwrap();
+    let (resp, _) = client.send_request(req, true).unwrap();
+    assert_eq!(resp.await.unwrap().status().as_u16(), 425);
+}
+
+#[actix_rt::test]
+async fn test_socket_buffers() {
+    use actix_rt::net::TcpStream;
+
+    let srv = test_server(|| {
+        HttpService::build()
+            .socket_send_buffer(4096)
+            .socket_recv_buffer(4096)
+            .on_connect(|io: &TcpStream| {
+                (
+                    io.send_buffer_size().unwrap(),
+                    io.recv_buffer_size().unwrap(),
+                )
+            })
+            .h1(|req: Request| {
+                let (send, recv) = *req.extensions().get::<(usize, usize)>().unwrap();
+                future::ok::<_, ()>(Response::Ok().body(format!("{} {}", send, recv)))
+            })
+            .tcp()
+    });
+
+    let mut response = srv.get("/").send().await.unwrap();
+    assert!(response.status().is_success());
+    let bytes = response.body().await.unwrap();
+    let body = std::str::from_utf8(&bytes).unwrap();
+    // os may round or double requested size, but it is far below defaults
+    for size in body.split(' ') {
+        let size: usize = size.parse().unwrap();
+        assert!(
+            (4096..=2 * 4096).contains(&size),
+            "unexpected size: {}",
+            size
+        );
+    }
+}
+
+#[actix_rt::test]
+async fn test_h2_idle_timeout() {
+    use actix_rt::net::TcpStream;
+    use actix_rt::time::timeout;
+
+    let srv = test_server(|| {
+        HttpService::build()
+            .h2_idle_timeout(300)
+            .h2(|_| ok::<_, Error>(Response::Ok().body("ok")))
+            .tcp()
+    });
+
+    let io = TcpStream::connect(srv.addr()).await.unwrap();
+    let (client, conn) = h2::client::handshake(io).await.unwrap();
+    let (tx, rx) = futures::channel::oneshot::channel();
+    actix_rt::spawn(async move {
+        let _ = tx.send(conn.await);
+    });
+
+    let mut client = client.ready().await.unwrap();
+    let req = ::http::Request::get(srv.url("/")).body(()).unwrap();
+    let (resp, _) = client.send_request(req, true).unwrap();
+    let resp = resp.await.unwrap();
+    assert_eq!(resp.status(), http::StatusCode::OK);
+    let mut body = resp.into_body();
+    while let Some(chunk) = body.data().await {
+        chunk.unwrap();
+    }
+
+    // connection is kept open within the idle window
+    let mut rx = rx.fuse();
+    delay_for(Duration::from_millis(100)).await;
+    assert!(futures::poll!(&mut rx).is_pending());
+
+    // and closed once the window passes without streams
+    let res = timeout(Duration::from_secs(3), rx).await;
+    assert!(res.is_ok(), "idle connection is not closed");
+}
+
+#[actix_rt::test]
+async fn test_h2_handshake_timeout() {
+    use actix_rt::net::TcpStream;
+
+    let srv = test_server(|| {
+        HttpService::build()
+            .client_timeout(1000)
+            .h2(|_| ok::<_, Error>(Response::Ok().finish()))
+            .tcp()
+    });
+
+    // connection without preface is closed
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let mut data = Vec::new();
+    assert!(stream.read_to_end(&mut data).is_ok());
+
+    // handshake completed in time proceeds
+    let io = TcpStream::connect(srv.addr()).await.unwrap();
+    let (client, conn) = h2::client::handshake(io).await.unwrap();
+    actix_rt::spawn(async move {
+        let _ = conn.await;
+    });
+    delay_for(Duration::from_millis(1500)).await;
+
+    let mut client = client.ready().await.unwrap();
+    let req = ::http::Request::get(srv.url("/")).body(()).unwrap();
+    let (resp, _) = client.send_request(req, true).unwrap();
+    let resp = resp.await.unwrap();
+    assert_eq!(resp.status(), http::StatusCode::OK);
+}
+
+/// Read frames sent by http/2 server, returns `(type, stream id, payload)`
+fn read_h2_frames(stream: &mut net::TcpStream) -> Vec<(u8, u32, Vec<u8>)> {
+    stream
+        .set_read_timeout(Some(Duration::from_millis(500)))
+        .unwrap();
+    let mut data = Vec::new();
+    let mut buf = [0; 1024];
+    while let Ok(n) = stream.read(&mut buf) {
+        if n == 0 {
+            break;
+        }
+        data.extend_from_slice(&buf[..n]);
+    }
+
+    let mut frames = Vec::new();
+    let mut data = &data[..];
+    while data.len() >= 9 {
+        let len = u32::from_be_bytes([0, data[0], data[1], data[2]]) as usize;
+        let id = u32::from_be_bytes([data[5], data[6], data[7], data[8]]) & 0x7fff_ffff;
+        frames.push((data[3], id, data[9..9 + len].to_vec()));
+        data = &data[9 + len..];
+    }
+    frames
+}
+
+#[actix_rt::test]
+async fn test_h2_settings() {
+    const PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n\0\0\0\x04\0\0\0\0\0";
+
+    let srv = test_server(|| {
+        HttpService::build()
+            .h2(|_| ok::<_, Error>(Response::Ok().finish()))
+            .max_concurrent_streams(10)
+            .initial_window_size(1_000_000)
+            .initial_connection_window_size(2_000_000)
+            .max_frame_size(32_768)
+            .tcp()
+    });
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    stream.write_all(PREFACE).unwrap();
+    let frames = read_h2_frames(&mut stream);
+
+    // SETTINGS frame
+    let (_, _, ref payload) = frames.iter().find(|f| f.0 == 0x4).unwrap();
+    let settings: Vec<_> = payload
+        .chunks(6)
+        .map(|s| {
+            let id = u16::from_be_bytes([s[0], s[1]]);
+            (id, u32::from_be_bytes([s[2], s[3], s[4], s[5]]))
+        })
+        .collect();
+    assert!(settings.contains(&(0x3, 10)));
+    assert!(settings.contains(&(0x4, 1_000_000)));
+    assert!(settings.contains(&(0x5, 32_768)));
+
+    // connection level WINDOW_UPDATE
+    let (_, _, ref payload) = frames.iter().find(|f| f.0 == 0x8 && f.1 == 0).unwrap();
+    let increment = u32::from_be_bytes([payload[0], payload[1], payload[2], payload[3]]);
+    assert_eq!(increment, 2_000_000 - 65_535);
+}
+
+#[actix_rt::test]
+async fn test_h2_default_settings() {
+    const PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n\0\0\0\x04\0\0\0\0\0";
+
+    let srv = test_server(|| {
+        HttpService::build()
+            .h2(|_| ok::<_, Error>(Response::Ok().finish()))
+            .tcp()
+    });
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    stream.write_all(PREFACE).unwrap();
+    let frames = read_h2_frames(&mut stream);
+
+    let (_, _, ref payload) = frames.iter().find(|f| f.0 == 0x4).unwrap();
+    let ids: Vec<_> = payload
+        .chunks(6)
+        .map(|s| u16::from_be_bytes([s[0], s[1]]))
+        .collect();
+    assert!(!ids.contains(&0x3));
+    assert!(!ids.contains(&0x4));
+    assert!(!ids.contains(&0x5));
+    assert!(!frames.iter().any(|f| f.0 == 0x8 && f.1 == 0));
+}
+
+#[actix_rt::test]
+async fn test_h2_peer_addr() {
+    use actix_http::error::DispatchError;
+    use actix_rt::net::TcpStream;
+    use actix_service::pipeline_factory;
+
+    async fn request(addr: net::SocketAddr) -> (net::SocketAddr, Bytes) {
+        let io = TcpStream::connect(addr).await.unwrap();
+        let local_addr = io.local_addr().unwrap();
+        let (client, conn) = h2::client::handshake(io).await.unwrap();
+        actix_rt::spawn(async move {
+            let _ = conn.await;
+        });
+
+        let mut client = client.ready().await.unwrap();
+        let req = ::http::Request::get("/").body(()).unwrap();
+        let (resp, _) = client.send_request(req, true).unwrap();
+        let mut body = resp.await.unwrap().into_body();
+        let mut bytes = Vec::new();
+        while let Some(chunk) = body.data().await {
+            bytes.extend_from_slice(&chunk.unwrap());
+        }
+        (local_addr, Bytes::from(bytes))
+    }
+
+    fn peer_addr(req: Request) -> future::Ready<Result<Response, Error>> {
+        ok(Response::Ok().body(format!("{:?}", req.peer_addr())))
+    }
+
+    let srv = test_server(|| HttpService::build().h2(peer_addr).tcp());
+    let (local_addr, body) = request(srv.addr()).await;
+    assert_eq!(body, format!("Some({})", local_addr));
+
+    // transport without peer address
+    let srv = test_server(|| {
+        pipeline_factory(fn_service(|io: TcpStream| {
+            ok::<_, DispatchError>((io, None))
+        }))
+        .and_then(HttpService::build().h2(peer_addr))
+    });
+    let (_, body) = request(srv.addr()).await;
+    assert_eq!(body, "None");
+}
+
+#[actix_rt::test]
+async fn test_h2_graceful_shutdown() {
+    use actix_http::h2::GracefulShutdown;
+    use actix_rt::net::TcpStream;
+    use actix_rt::time::timeout;
+
+    let handle = GracefulShutdown::new();
+    let handle2 = handle.clone();
+    let srv = test_server(move || {
+        HttpService::build()
+            .graceful_shutdown(handle2.clone())
+            .h2(|req: Request| async move {
+                if req.path() == "/slow" {
+                    delay_for(Duration::from_millis(300)).await;
+                }
+                Ok::<_, Error>(Response::Ok().body("ok"))
+            })
+            .tcp()
+    });
+
+    let io = TcpStream::connect(srv.addr()).await.unwrap();
+    let (client, conn) = h2::client::handshake(io).await.unwrap();
+    let (tx, rx) = futures::channel::oneshot::channel();
+    actix_rt::spawn(async move {
+        let _ = tx.send(conn.await);
+    });
+
+    let mut client = client.ready().await.unwrap();
+    let req = ::http::Request::get(srv.url("/slow")).body(()).unwrap();
+    let (slow, _) = client.send_request(req, true).unwrap();
+    delay_for(Duration::from_millis(100)).await;
+
+    handle.shutdown();
+    delay_for(Duration::from_millis(100)).await;
+
+    // stream opened after GOAWAY is rejected
+    let refused = match client.ready().await {
+        Err(_) => true,
+        Ok(mut client) => {
+            let req = ::http::Request::get(srv.url("/")).body(()).unwrap();
+            match client.send_request(req, true) {
+                Err(_) => true,
+                Ok((resp, _)) => resp.await.is_err(),
+            }
+        }
+    };
+    assert!(refused, "new stream is accepted after shutdown");
+
+    // already open stream completes
+    let resp = slow.await.unwrap();
+    assert_eq!(resp.status(), http::StatusCode::OK);
+    let mut body = resp.into_body();
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.data().await {
+        bytes.extend_from_slice(&chunk.unwrap());
+    }
+    assert_eq!(bytes, b"ok");
+
+    // and connection is closed
+    let res = timeout(Duration::from_secs(3), rx).await;
+    assert!(res.is_ok(), "connection is not closed");
+}
+
+#[actix_rt::test]
+async fn test_h2_graceful_shutdown_timeout() {
+    use actix_http::h2::GracefulShutdown;
+    use actix_rt::net::TcpStream;
+    use actix_rt::time::timeout;
+
+    let handle = GracefulShutdown::new();
+    let handle2 = handle.clone();
+    let srv = test_server(move || {
+        HttpService::build()
+            .graceful_shutdown(handle2.clone())
+            .client_shutdown(500)
+            .h2(|_| async {
+                delay_for(Duration::from_secs(10)).await;
+                Ok::<_, Error>(Response::Ok().finish())
+            })
+            .tcp()
+    });
+
+    let io = TcpStream::connect(srv.addr()).await.unwrap();
+    let (client, conn) = h2::client::handshake(io).await.unwrap();
+    let (tx, rx) = futures::channel::oneshot::channel();
+    actix_rt::spawn(async move {
+        let _ = tx.send(conn.await);
+    });
+
+    let mut client = client.ready().await.unwrap();
+    let req = ::http::Request::get(srv.url("/")).body(()).unwrap();
+    let (slow, _) = client.send_request(req, true).unwrap();
+    delay_for(Duration::from_millis(100)).await;
+    handle.shutdown();
+
+    // connection is dropped before in-flight stream completes
+    let res = timeout(Duration::from_secs(3), rx).await;
+    assert!(res.is_ok(), "connection is not closed");
+    assert!(slow.await.is_err());
+}
+
+#[actix_rt::test]
+async fn test_h1_graceful_shutdown_timeout() {
+    use actix_http::h2::GracefulShutdown;
+
+    let handle = GracefulShutdown::new();
+    let handle2 = handle.clone();
+    let srv = test_server(move || {
+        HttpService::build()
+            .keep_alive(30)
+            .graceful_shutdown(handle2.clone())
+            .client_shutdown(300)
+            .h1(|_| future::ok::<_, ()>(Response::Ok().finish()))
+            .tcp()
+    });
+
+    let req = b"GET /test/tests/test HTTP/1.1\r\n\r\n";
+    let mut idle = net::TcpStream::connect(srv.addr()).unwrap();
+    let mut active = net::TcpStream::connect(srv.addr()).unwrap();
+    for stream in &mut [&mut idle, &mut active] {
+        stream
+            .set_read_timeout(Some(Duration::from_secs(3)))
+            .unwrap();
+        let _ = stream.write_all(req);
+        let mut data = vec![0; 1024];
+        let n = stream.read(&mut data).unwrap();
+        let resp = String::from_utf8_lossy(&data[..n]).to_lowercase();
+        assert!(resp.starts_with("http/1.1 200 ok\r\n"));
+        assert!(!resp.contains("connection: close\r\n"));
+    }
+
+    handle.shutdown();
+    delay_for(Duration::from_millis(100)).await;
+
+    // response to request received during shutdown closes connection
+    let _ = active.write_all(req);
+    let mut data = vec![0; 1024];
+    let n = active.read(&mut data).unwrap();
+    let resp = String::from_utf8_lossy(&data[..n]).to_lowercase();
+    assert!(resp.contains("connection: close\r\n"));
+
+    // idle keep-alive connection is dropped after shutdown timeout
+    let mut data = vec![0; 1024];
+    assert_eq!(idle.read(&mut data).unwrap(), 0);
+}
@@ -32,10 +32,73 @@ use crate::server::Multipart;
 impl FromRequest for Multipart {
     type Error = Error;
     type Future = Ready<Result<Multipart, Error>>;
-    type Config = ();
+    type Config = MultipartConfig;
 
     #[inline]
     fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
-        ok(Multipart::new(req.headers(), payload.take()))
+        let config = MultipartConfig::from_req(req);
+        ok(Multipart::new(req.headers(), payload.take())
+            .part_limit(config.part_limit)
+            .total_limit(config.total_limit))
+    }
+}
+
+/// Multipart extractor configuration
+///
+/// ```rust
+/// use actix_web::{web, App};
+/// use actix_multipart as mp;
+///
+/// async fn index(_payload: mp::Multipart) -> String {
+///     "Uploaded".to_owned()
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/upload")
+///             .app_data(
+///                 // limit text fields and files to 1Mb, whole upload to 10Mb
+///                 mp::MultipartConfig::default()
+///                     .part_limit(1_048_576)
+///                     .total_limit(10_485_760),
+///             )
+///             .route(web::post().to(index)),
+///     );
+/// }
+/// ```
+#[derive(Clone)]
+pub struct MultipartConfig {
+    part_limit: usize,
+    total_limit: usize,
+}
+
+impl MultipartConfig {
+    /// Set maximum size of a single field's content. By default not limited
+    pub fn part_limit(mut self, limit: usize) -> Self {
+        self.part_limit = limit;
+        self
+    }
+
+    /// Set maximum total size of all fields' content. By default not limited
+    pub fn total_limit(mut self, limit: usize) -> Self {
+        self.total_limit = limit;
+        self
+    }
+
+    /// Extract multipart config from app data, fall back to the default config.
+    fn from_req(req: &HttpRequest) -> &Self {
+        req.app_data::<Self>().unwrap_or(&DEFAULT_CONFIG)
+    }
+}
+
+/// Allow shared refs to default.
+const DEFAULT_CONFIG: MultipartConfig = MultipartConfig {
+    part_limit: usize::MAX,
+    total_limit: usize::MAX,
+};
+
+impl Default for MultipartConfig {
+    fn default() -> Self {
+        DEFAULT_CONFIG.clone()
     }
 }
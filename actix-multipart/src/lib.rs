@@ -5,4 +5,5 @@ mod extractor;
 mod server;
 
 pub use self::error::MultipartError;
+pub use self::extractor::MultipartConfig;
 pub use self::server::{Field, Multipart};
@@ -56,6 +56,16 @@ struct InnerMultipart {
     boundary: String,
     state: InnerState,
     item: InnerMultipartItem,
+    limits: Limits,
+}
+
+/// Size limits of multipart stream
+#[derive(Clone)]
+struct Limits {
+    part: usize,
+    total: usize,
+    /// number of bytes read so far, shared by all fields
+    size: Rc<Cell<usize>>,
 }
 
 impl Multipart {
@@ -73,6 +83,11 @@ impl Multipart {
                     payload: PayloadRef::new(PayloadBuffer::new(Box::new(stream))),
                     state: InnerState::FirstBoundary,
                     item: InnerMultipartItem::None,
+                    limits: Limits {
+                        part: usize::MAX,
+                        total: usize::MAX,
+                        size: Rc::new(Cell::new(0)),
+                    },
                 }))),
             },
             Err(err) => Multipart {
@@ -83,6 +98,29 @@ impl Multipart {
         }
     }
 
+    /// Set maximum size of a single field's content.
+    ///
+    /// Field which exceeds limit yields `MultipartError::Overflow` error.
+    /// By default size of a field is not limited.
+    pub fn part_limit(self, limit: usize) -> Self {
+        if let Some(ref inner) = self.inner {
+            inner.borrow_mut().limits.part = limit;
+        }
+        self
+    }
+
+    /// Set maximum total size of all fields' content.
+    ///
+    /// Content of skipped fields is counted too. Stream yields
+    /// `MultipartError::Overflow` error once limit is exceeded.
+    /// By default total size is not limited.
+    pub fn total_limit(self, limit: usize) -> Self {
+        if let Some(ref inner) = self.inner {
+            inner.borrow_mut().limits.total = limit;
+        }
+        self
+    }
+
     /// Extract boundary info from headers.
     fn boundary(headers: &HeaderMap) -> Result<String, MultipartError> {
         if let Some(content_type) = headers.get(&header::CONTENT_TYPE) {
@@ -352,6 +390,7 @@ impl InnerMultipart {
                 let field = Rc::new(RefCell::new(InnerField::new(
                     self.payload.clone(),
                     self.boundary.clone(),
+                    self.limits.clone(),
                     &headers,
                 )?));
                 self.item = InnerMultipartItem::Field(Rc::clone(&field));
@@ -413,6 +452,28 @@ impl Field {
             None
         }
     }
+
+    /// Returns true if field is a file upload, i.e. its content disposition
+    /// has `filename` parameter.
+    pub fn is_file(&self) -> bool {
+        match self.content_disposition() {
+            Some(cd) => cd.get_filename().is_some() || cd.get_filename_ext().is_some(),
+            None => false,
+        }
+    }
+
+    /// Read whole field content as utf-8 string.
+    ///
+    /// Intended for text fields, content size is constrained by
+    /// `Multipart::part_limit()`.
+    pub async fn text(mut self) -> Result<String, MultipartError> {
+        let mut body = BytesMut::new();
+        while let Some(chunk) = self.next().await {
+            body.extend_from_slice(&chunk?);
+        }
+        String::from_utf8(body.to_vec())
+            .map_err(|e| ParseError::Utf8(e.utf8_error()).into())
+    }
 }
 
 impl Stream for Field {
@@ -452,12 +513,15 @@ struct InnerField {
     boundary: String,
     eof: bool,
     length: Option<u64>,
+    limits: Limits,
+    size: usize,
 }
 
 impl InnerField {
     fn new(
         payload: PayloadRef,
         boundary: String,
+        limits: Limits,
         headers: &HeaderMap,
     ) -> Result<InnerField, PayloadError> {
         let len = if let Some(len) = headers.get(&header::CONTENT_LENGTH) {
@@ -479,6 +543,8 @@ impl InnerField {
             payload: Some(payload),
             eof: false,
             length: len,
+            limits,
+            size: 0,
         })
     }
 
@@ -530,7 +596,7 @@ impl InnerField {
         }
 
         // check boundary
-        if len > 4 && payload.buf[0] == b'\r' {
+        if len >= 4 && payload.buf[0] == b'\r' {
             let b_len = if &payload.buf[..2] == b"\r\n" && &payload.buf[2..4] == b"--" {
                 Some(4)
             } else if &payload.buf[1..3] == b"--" {
@@ -603,7 +669,19 @@ impl InnerField {
 
                 match res {
                     Poll::Pending => return Poll::Pending,
-                    Poll::Ready(Some(Ok(bytes))) => return Poll::Ready(Some(Ok(bytes))),
+                    Poll::Ready(Some(Ok(bytes))) => {
+                        self.size += bytes.len();
+                        let total = self.limits.size.get() + bytes.len();
+                        self.limits.size.set(total);
+
+                        return if self.size > self.limits.part
+                            || total > self.limits.total
+                        {
+                            Poll::Ready(Some(Err(MultipartError::Overflow)))
+                        } else {
+                            Poll::Ready(Some(Ok(bytes)))
+                        };
+                    }
                     Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
                     Poll::Ready(None) => self.eof = true,
                 }
@@ -1008,6 +1086,88 @@ mod tests {
         }
     }
 
+    fn create_upload_request() -> (Bytes, HeaderMap) {
+        let bytes = Bytes::from(
+            "--abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+             Content-Disposition: form-data; name=\"title\"\r\n\r\n\
+             My document\r\n\
+             --abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+             Content-Disposition: form-data; name=\"doc\"; filename=\"doc.bin\"\r\n\
+             Content-Type: application/octet-stream\r\n\r\n\
+             0123456789abcdef\r\n\
+             --abbc761f78ff4d7cb7573b5a23f96ef0--\r\n",
+        );
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CONTENT_TYPE,
+            header::HeaderValue::from_static(
+                "multipart/form-data; boundary=\"abbc761f78ff4d7cb7573b5a23f96ef0\"",
+            ),
+        );
+        (bytes, headers)
+    }
+
+    #[actix_rt::test]
+    async fn test_upload_text_and_file() {
+        let (bytes, headers) = create_upload_request();
+        let payload = SlowStream::new(bytes);
+
+        let mut multipart = Multipart::new(&headers, payload).part_limit(16);
+
+        let field = multipart.next().await.unwrap().unwrap();
+        assert!(!field.is_file());
+        let cd = field.content_disposition().unwrap();
+        assert_eq!(cd.get_name(), Some("title"));
+        assert_eq!(field.text().await.unwrap(), "My document");
+
+        let mut field = multipart.next().await.unwrap().unwrap();
+        assert!(field.is_file());
+        let cd = field.content_disposition().unwrap();
+        assert_eq!(cd.get_filename(), Some("doc.bin"));
+        assert_eq!(field.content_type(), &mime::APPLICATION_OCTET_STREAM);
+
+        // file content is streamed in chunks
+        let mut chunks = 0;
+        let mut content = BytesMut::new();
+        while let Some(chunk) = field.next().await {
+            chunks += 1;
+            content.extend_from_slice(&chunk.unwrap());
+        }
+        assert!(chunks > 1);
+        assert_eq!(content, "0123456789abcdef");
+        drop(field);
+
+        assert!(multipart.next().await.is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_upload_limits() {
+        let (bytes, headers) = create_upload_request();
+        let (sender, payload) = create_stream();
+        sender.send(Ok(bytes.clone())).unwrap();
+        drop(sender);
+
+        let mut multipart = Multipart::new(&headers, payload).part_limit(8);
+        let field = multipart.next().await.unwrap().unwrap();
+        match field.text().await {
+            Err(MultipartError::Overflow) => (),
+            _ => unreachable!(),
+        }
+
+        let (sender, payload) = create_stream();
+        sender.send(Ok(bytes)).unwrap();
+        drop(sender);
+
+        let mut multipart = Multipart::new(&headers, payload).total_limit(20);
+        let field = multipart.next().await.unwrap().unwrap();
+        assert_eq!(field.text().await.unwrap(), "My document");
+        let field = multipart.next().await.unwrap().unwrap();
+        match field.text().await {
+            Err(MultipartError::Overflow) => (),
+            _ => unreachable!(),
+        }
+    }
+
     // Loops, collecting all bytes until end-of-field
     async fn get_whole_field(field: &mut Field) -> BytesMut {
         let mut b = BytesMut::new();
@@ -17,15 +17,28 @@
 //! - [trace](attr.trace.html)
 //! - [patch](attr.patch.html)
 //!
+//! Handler could be registered for multiple methods with [route](attr.route.html).
+//!
+//! Handlers of a module could be registered at once with
+//! [routes](attr.routes.html).
+//!
 //! ### Attributes:
 //!
 //! - `"path"` - Raw literal string with path for which to register handle. Mandatory.
 //! - `guard="function_name"` - Registers function as guard using `actix_web::guard::fn_guard`
+//! - `guard="GuardExpr"` - Registers value of expression implementing `actix_web::guard::Guard`
+//! - `method="HTTP_METHOD"` - Registers handler for the method, [route](attr.route.html) only
+//! - `typed_path[="StructName"]` - Generates extractor struct with `String` field per dynamic
+//!   segment of the path. Struct is named after the handler by default, `get_user` handler gets
+//!   `GetUserPath` struct. Path without dynamic segments, duplicate or invalid segment names are
+//!   compile errors.
 //!
 //! ## Notes
 //!
-//! Function name can be specified as any expression that is going to be accessible to the generate
-//! code (e.g `my_guard` or `my_module::my_guard`)
+//! Function name can be specified as any path that is going to be accessible to the generate
+//! code (e.g `my_guard` or `my_module::my_guard`). Any other expression is registered as is,
+//! i.e. `guard = "actix_web::guard::Header(\"x-tenant\", \"a\")"`. Attribute `guard` could be
+//! specified multiple times, expressions are evaluated in scope of the handler's module.
 //!
 //! ## Example:
 //!
@@ -38,11 +51,18 @@
 //! async fn async_test() -> Result<HttpResponse, actix_web::Error> {
 //!     Ok(HttpResponse::Ok().finish())
 //! }
+//!
+//! #[get("/users/{id}/posts/{post}", typed_path)]
+//! async fn get_post(path: GetPostPath) -> HttpResponse {
+//!     HttpResponse::Ok().body(format!("{}/{}", path.id, path.post))
+//! }
 //! ```
 
 extern crate proc_macro;
 
 mod route;
+mod routes;
+mod typed_path;
 
 use proc_macro::TokenStream;
 use syn::parse_macro_input;
@@ -55,10 +75,13 @@ use syn::parse_macro_input;
 ///
 /// - `"path"` - Raw literal string with path for which to register handler. Mandatory.
 /// - `guard="function_name"` - Registers function as guard using `actix_web::guard::fn_guard`
+/// - `guard="GuardExpr"` - Registers value of expression implementing `actix_web::guard::Guard`
+/// - `typed_path[="StructName"]` - Generates extractor struct with `String` field per dynamic
+///   segment of the path, named `GetUserPath` for `get_user` handler by default
 #[proc_macro_attribute]
 pub fn get(args: TokenStream, input: TokenStream) -> TokenStream {
     let args = parse_macro_input!(args as syn::AttributeArgs);
-    let gen = match route::Route::new(args, input, route::GuardType::Get) {
+    let gen = match route::Route::new(args, input, Some(route::GuardType::Get)) {
         Ok(gen) => gen,
         Err(err) => return err.to_compile_error().into(),
     };
@@ -73,7 +96,7 @@ pub fn get(args: TokenStream, input: TokenStream) -> TokenStream {
 #[proc_macro_attribute]
 pub fn post(args: TokenStream, input: TokenStream) -> TokenStream {
     let args = parse_macro_input!(args as syn::AttributeArgs);
-    let gen = match route::Route::new(args, input, route::GuardType::Post) {
+    let gen = match route::Route::new(args, input, Some(route::GuardType::Post)) {
         Ok(gen) => gen,
         Err(err) => return err.to_compile_error().into(),
     };
@@ -88,7 +111,7 @@ pub fn post(args: TokenStream, input: TokenStream) -> TokenStream {
 #[proc_macro_attribute]
 pub fn put(args: TokenStream, input: TokenStream) -> TokenStream {
     let args = parse_macro_input!(args as syn::AttributeArgs);
-    let gen = match route::Route::new(args, input, route::GuardType::Put) {
+    let gen = match route::Route::new(args, input, Some(route::GuardType::Put)) {
         Ok(gen) => gen,
         Err(err) => return err.to_compile_error().into(),
     };
@@ -103,7 +126,7 @@ pub fn put(args: TokenStream, input: TokenStream) -> TokenStream {
 #[proc_macro_attribute]
 pub fn delete(args: TokenStream, input: TokenStream) -> TokenStream {
     let args = parse_macro_input!(args as syn::AttributeArgs);
-    let gen = match route::Route::new(args, input, route::GuardType::Delete) {
+    let gen = match route::Route::new(args, input, Some(route::GuardType::Delete)) {
         Ok(gen) => gen,
         Err(err) => return err.to_compile_error().into(),
     };
@@ -118,7 +141,7 @@ pub fn delete(args: TokenStream, input: TokenStream) -> TokenStream {
 #[proc_macro_attribute]
 pub fn head(args: TokenStream, input: TokenStream) -> TokenStream {
     let args = parse_macro_input!(args as syn::AttributeArgs);
-    let gen = match route::Route::new(args, input, route::GuardType::Head) {
+    let gen = match route::Route::new(args, input, Some(route::GuardType::Head)) {
         Ok(gen) => gen,
         Err(err) => return err.to_compile_error().into(),
     };
@@ -133,7 +156,7 @@ pub fn head(args: TokenStream, input: TokenStream) -> TokenStream {
 #[proc_macro_attribute]
 pub fn connect(args: TokenStream, input: TokenStream) -> TokenStream {
     let args = parse_macro_input!(args as syn::AttributeArgs);
-    let gen = match route::Route::new(args, input, route::GuardType::Connect) {
+    let gen = match route::Route::new(args, input, Some(route::GuardType::Connect)) {
         Ok(gen) => gen,
         Err(err) => return err.to_compile_error().into(),
     };
@@ -148,7 +171,7 @@ pub fn connect(args: TokenStream, input: TokenStream) -> TokenStream {
 #[proc_macro_attribute]
 pub fn options(args: TokenStream, input: TokenStream) -> TokenStream {
     let args = parse_macro_input!(args as syn::AttributeArgs);
-    let gen = match route::Route::new(args, input, route::GuardType::Options) {
+    let gen = match route::Route::new(args, input, Some(route::GuardType::Options)) {
         Ok(gen) => gen,
         Err(err) => return err.to_compile_error().into(),
     };
@@ -163,7 +186,7 @@ pub fn options(args: TokenStream, input: TokenStream) -> TokenStream {
 #[proc_macro_attribute]
 pub fn trace(args: TokenStream, input: TokenStream) -> TokenStream {
     let args = parse_macro_input!(args as syn::AttributeArgs);
-    let gen = match route::Route::new(args, input, route::GuardType::Trace) {
+    let gen = match route::Route::new(args, input, Some(route::GuardType::Trace)) {
         Ok(gen) => gen,
         Err(err) => return err.to_compile_error().into(),
     };
@@ -178,9 +201,86 @@ pub fn trace(args: TokenStream, input: TokenStream) -> TokenStream {
 #[proc_macro_attribute]
 pub fn patch(args: TokenStream, input: TokenStream) -> TokenStream {
     let args = parse_macro_input!(args as syn::AttributeArgs);
-    let gen = match route::Route::new(args, input, route::GuardType::Patch) {
+    let gen = match route::Route::new(args, input, Some(route::GuardType::Patch)) {
         Ok(gen) => gen,
         Err(err) => return err.to_compile_error().into(),
     };
     gen.generate()
 }
+
+/// Collects route handlers of inline module.
+///
+/// Creates route handler for multiple HTTP methods.
+///
+/// Syntax: `#[route("path"[, attributes])]`
+///
+/// ## Attributes:
+///
+/// - `"path"` - Raw literal string with path for which to register handler. Mandatory.
+/// - `method="HTTP_METHOD"` - Registers handler for the method, i.e. `"GET"`. Could be
+///   specified multiple times. Handler accepts all methods if none is specified.
+/// - `guard="function_name"` - Registers function as guard using `actix_web::guard::fn_guard`
+/// - `guard="GuardExpr"` - Registers value of expression implementing `actix_web::guard::Guard`
+/// - `typed_path[="StructName"]` - Generates extractor struct with `String` field per dynamic
+///   segment of the path, named `GetUserPath` for `get_user` handler by default
+///
+/// ## Example
+///
+/// ```rust
+/// use actix_web::HttpResponse;
+/// use actix_web_codegen::route;
+///
+/// #[route("/test", method = "GET", method = "HEAD")]
+/// async fn example() -> HttpResponse {
+///     HttpResponse::Ok().finish()
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn route(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as syn::AttributeArgs);
+    let gen = match route::Route::new(args, input, None) {
+        Ok(gen) => gen,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    gen.generate()
+}
+
+/// Generates `pub fn configure(config: &mut web::ServiceConfig)` function
+/// in the module which registers all handlers annotated with route macros
+/// in order of declaration. Path, method and guards of each handler are
+/// preserved. Module must not declare its own `configure` item.
+///
+/// Syntax: `#[routes]`
+///
+/// ## Example:
+///
+/// ```rust
+/// use actix_web::App;
+/// use actix_web_codegen::routes;
+///
+/// #[routes]
+/// mod api {
+///     use actix_web::{web, HttpResponse};
+///     use actix_web_codegen::{get, post};
+///
+///     #[get("/users/{id}")]
+///     async fn user(id: web::Path<u32>) -> HttpResponse {
+///         HttpResponse::Ok().body(format!("user {}", id))
+///     }
+///
+///     #[post("/users")]
+///     async fn create_user() -> HttpResponse {
+///         HttpResponse::Created().finish()
+///     }
+/// }
+///
+/// let app = App::new().configure(api::configure);
+/// ```
+#[proc_macro_attribute]
+pub fn routes(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as syn::AttributeArgs);
+    match routes::Routes::new(args, input) {
+        Ok(gen) => gen.generate(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
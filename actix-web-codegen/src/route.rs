@@ -5,6 +5,8 @@ use proc_macro2::{Span, TokenStream as TokenStream2};
 use quote::{quote, ToTokens, TokenStreamExt};
 use syn::{AttributeArgs, Ident, NestedMeta};
 
+use crate::typed_path::TypedPath;
+
 enum ResourceType {
     Async,
     Sync,
@@ -48,6 +50,21 @@ impl GuardType {
             GuardType::Patch => "Patch",
         }
     }
+
+    fn from_method(method: &str) -> Option<Self> {
+        match method {
+            "GET" => Some(GuardType::Get),
+            "POST" => Some(GuardType::Post),
+            "PUT" => Some(GuardType::Put),
+            "DELETE" => Some(GuardType::Delete),
+            "HEAD" => Some(GuardType::Head),
+            "CONNECT" => Some(GuardType::Connect),
+            "OPTIONS" => Some(GuardType::Options),
+            "TRACE" => Some(GuardType::Trace),
+            "PATCH" => Some(GuardType::Patch),
+            _ => None,
+        }
+    }
 }
 
 impl ToTokens for GuardType {
@@ -58,15 +75,48 @@ impl ToTokens for GuardType {
     }
 }
 
+/// Value of `guard` attribute
+enum Guard {
+    /// Path of function, registered with `fn_guard()`
+    Fn(syn::Path),
+    /// Expression of a type implementing `Guard` trait
+    Expr(syn::Expr),
+}
+
+impl Guard {
+    fn new(lit: &syn::LitStr) -> syn::Result<Self> {
+        match lit.parse()? {
+            syn::Expr::Path(expr) if expr.qself.is_none() => Ok(Guard::Fn(expr.path)),
+            expr => Ok(Guard::Expr(expr)),
+        }
+    }
+}
+
+impl ToTokens for Guard {
+    fn to_tokens(&self, stream: &mut TokenStream2) {
+        match self {
+            Guard::Fn(path) => {
+                stream.extend(quote! { actix_web::guard::fn_guard(#path) })
+            }
+            Guard::Expr(expr) => expr.to_tokens(stream),
+        }
+    }
+}
+
 struct Args {
     path: syn::LitStr,
-    guards: Vec<Ident>,
+    guards: Vec<Guard>,
+    methods: Vec<GuardType>,
+    /// `typed_path` attribute, with struct name if specified
+    typed_path: Option<Option<Ident>>,
 }
 
 impl Args {
     fn new(args: AttributeArgs) -> syn::Result<Self> {
         let mut path = None;
         let mut guards = Vec::new();
+        let mut methods = Vec::new();
+        let mut typed_path = None;
         for arg in args {
             match arg {
                 NestedMeta::Lit(syn::Lit::Str(lit)) => match path {
@@ -83,20 +133,59 @@ impl Args {
                 NestedMeta::Meta(syn::Meta::NameValue(nv)) => {
                     if nv.path.is_ident("guard") {
                         if let syn::Lit::Str(lit) = nv.lit {
-                            guards.push(Ident::new(&lit.value(), Span::call_site()));
+                            guards.push(Guard::new(&lit)?);
                         } else {
                             return Err(syn::Error::new_spanned(
                                 nv.lit,
                                 "Attribute guard expects literal string!",
                             ));
                         }
+                    } else if nv.path.is_ident("method") {
+                        let lit = match nv.lit {
+                            syn::Lit::Str(lit) => lit,
+                            lit => {
+                                return Err(syn::Error::new_spanned(
+                                    lit,
+                                    "Attribute method expects literal string!",
+                                ));
+                            }
+                        };
+                        let method = match GuardType::from_method(&lit.value()) {
+                            Some(method) => method,
+                            None => {
+                                return Err(syn::Error::new_spanned(
+                                    lit,
+                                    "Unknown HTTP method. Allowed: GET, POST, PUT, \
+                                     DELETE, HEAD, CONNECT, OPTIONS, TRACE, PATCH",
+                                ));
+                            }
+                        };
+                        if methods.contains(&method) {
+                            return Err(syn::Error::new_spanned(
+                                lit,
+                                "Method is specified more than once!",
+                            ));
+                        }
+                        methods.push(method);
+                    } else if nv.path.is_ident("typed_path") {
+                        if let syn::Lit::Str(lit) = nv.lit {
+                            typed_path = Some(Some(lit.parse()?));
+                        } else {
+                            return Err(syn::Error::new_spanned(
+                                nv.lit,
+                                "Attribute typed_path expects literal string!",
+                            ));
+                        }
                     } else {
                         return Err(syn::Error::new_spanned(
                             nv.path,
-                            "Unknown attribute key is specified. Allowed: guard",
+                            "Unknown attribute key is specified. Allowed: guard, method, typed_path",
                         ));
                     }
                 }
+                NestedMeta::Meta(syn::Meta::Path(p)) if p.is_ident("typed_path") => {
+                    typed_path = Some(None);
+                }
                 arg => {
                     return Err(syn::Error::new_spanned(arg, "Unknown attribute"));
                 }
@@ -105,6 +194,8 @@ impl Args {
         Ok(Args {
             path: path.unwrap(),
             guards,
+            methods,
+            typed_path,
         })
     }
 }
@@ -114,7 +205,8 @@ pub struct Route {
     args: Args,
     ast: syn::ItemFn,
     resource_type: ResourceType,
-    guard: GuardType,
+    methods: Vec<GuardType>,
+    typed_path: Option<TypedPath>,
 }
 
 fn guess_resource_type(typ: &syn::Type) -> ResourceType {
@@ -140,24 +232,50 @@ fn guess_resource_type(typ: &syn::Type) -> ResourceType {
 }
 
 impl Route {
+    /// Route with `guard` method, or with methods of `method` attributes
+    /// if `guard` is not specified, as in `#[route]`.
     pub fn new(
         args: AttributeArgs,
         input: TokenStream,
-        guard: GuardType,
+        guard: Option<GuardType>,
     ) -> syn::Result<Self> {
         if args.is_empty() {
             return Err(syn::Error::new(
                 Span::call_site(),
                 format!(
                     r#"invalid server definition, expected #[{}("<some path>")]"#,
-                    guard.as_str().to_ascii_lowercase()
+                    guard
+                        .as_ref()
+                        .map(|g| g.as_str().to_ascii_lowercase())
+                        .unwrap_or_else(|| "route".to_string())
                 ),
             ));
         }
         let ast: syn::ItemFn = syn::parse(input)?;
         let name = ast.sig.ident.clone();
 
-        let args = Args::new(args)?;
+        let mut args = Args::new(args)?;
+        let methods = match guard {
+            Some(guard) => {
+                if !args.methods.is_empty() {
+                    return Err(syn::Error::new(
+                        Span::call_site(),
+                        "Attribute method is allowed in #[route] only",
+                    ));
+                }
+                vec![guard]
+            }
+            None => std::mem::take(&mut args.methods),
+        };
+
+        let typed_path = match args.typed_path.take() {
+            Some(struct_name) => {
+                let struct_name =
+                    struct_name.unwrap_or_else(|| TypedPath::default_name(&name));
+                Some(TypedPath::new(struct_name, &name, &args.path)?)
+            }
+            None => None,
+        };
 
         let resource_type = if ast.sig.asyncness.is_some() {
             ResourceType::Async
@@ -178,29 +296,40 @@ impl Route {
             args,
             ast,
             resource_type,
-            guard,
+            methods,
+            typed_path,
         })
     }
 
     pub fn generate(&self) -> TokenStream {
         let name = &self.name;
         let resource_name = name.to_string();
-        let guard = &self.guard;
         let ast = &self.ast;
         let path = &self.args.path;
         let extra_guards = &self.args.guards;
         let resource_type = &self.resource_type;
+        let typed_path = &self.typed_path;
+        let method_guard = match self.methods.split_first() {
+            None => quote! {},
+            Some((method, [])) => quote! { .guard(actix_web::guard::#method()) },
+            Some((method, rest)) => quote! {
+                .guard(actix_web::guard::Any(actix_web::guard::#method())
+                    #(.or(actix_web::guard::#rest()))*)
+            },
+        };
         let stream = quote! {
             #[allow(non_camel_case_types)]
             pub struct #name;
 
+            #typed_path
+
             impl actix_web::dev::HttpServiceFactory for #name {
                 fn register(self, config: &mut actix_web::dev::AppService) {
                     #ast
                     let resource = actix_web::Resource::new(#path)
                         .name(#resource_name)
-                        .guard(actix_web::guard::#guard())
-                        #(.guard(actix_web::guard::fn_guard(#extra_guards)))*
+                        #method_guard
+                        #(.guard(#extra_guards))*
                         .#resource_type(#name);
 
                     actix_web::dev::HttpServiceFactory::register(resource, config)
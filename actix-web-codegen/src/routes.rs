@@ -0,0 +1,73 @@
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{AttributeArgs, Ident};
+
+/// Names of route attribute macros
+const ROUTE_MACROS: &[&str] = &[
+    "get", "post", "put", "delete", "head", "connect", "options", "trace", "patch",
+    "route",
+];
+
+/// Inline module with route handlers
+pub struct Routes {
+    ast: syn::ItemMod,
+    handlers: Vec<Ident>,
+}
+
+impl Routes {
+    pub fn new(args: AttributeArgs, input: TokenStream) -> syn::Result<Self> {
+        if let Some(arg) = args.first() {
+            return Err(syn::Error::new_spanned(arg, "Unknown attribute"));
+        }
+        let ast: syn::ItemMod = syn::parse(input)?;
+        let items = match ast.content {
+            Some((_, ref items)) => items,
+            None => {
+                return Err(syn::Error::new_spanned(
+                    ast,
+                    "Routes can be collected from inline modules only",
+                ));
+            }
+        };
+
+        let handlers = items
+            .iter()
+            .filter_map(|item| match item {
+                syn::Item::Fn(func) if func.attrs.iter().any(is_route_attr) => {
+                    Some(func.sig.ident.clone())
+                }
+                _ => None,
+            })
+            .collect();
+
+        Ok(Self { ast, handlers })
+    }
+
+    pub fn generate(mut self) -> TokenStream {
+        let handlers = &self.handlers;
+        let configure = quote! {
+            /// Register all route handlers of this module.
+            pub fn configure(config: &mut actix_web::web::ServiceConfig) {
+                config #(.service(#handlers))*;
+            }
+        };
+        if let Some((_, ref mut items)) = self.ast.content {
+            items.push(syn::Item::Verbatim(configure));
+        }
+        let ast = &self.ast;
+        let stream = quote! { #ast };
+        stream.into()
+    }
+}
+
+/// Check if attribute is one of route macros, i.e. `#[get(..)]`
+/// or `#[actix_web_codegen::get(..)]`.
+fn is_route_attr(attr: &syn::Attribute) -> bool {
+    attr.path
+        .segments
+        .last()
+        .map(|seg| ROUTE_MACROS.iter().any(|name| seg.ident == name))
+        .unwrap_or(false)
+}
@@ -0,0 +1,143 @@
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{quote, ToTokens};
+use syn::Ident;
+
+/// Struct with a field per dynamic segment of route path
+pub struct TypedPath {
+    name: Ident,
+    handler: String,
+    fields: Vec<Ident>,
+}
+
+impl TypedPath {
+    /// Parse dynamic segments of `path`, i.e. `{id}` or `{id:\d+}`.
+    ///
+    /// Errors point at `path` literal.
+    pub fn new(name: Ident, handler: &Ident, path: &syn::LitStr) -> syn::Result<Self> {
+        let fields = segment_names(&path.value())
+            .map_err(|msg| syn::Error::new_spanned(path, msg))?
+            .into_iter()
+            .map(|name| Ident::new(&name, path.span()))
+            .collect::<Vec<_>>();
+        if fields.is_empty() {
+            return Err(syn::Error::new_spanned(
+                path,
+                "typed_path requires path with dynamic segments, i.e. \"/users/{id}\"",
+            ));
+        }
+        Ok(TypedPath {
+            name,
+            handler: handler.to_string(),
+            fields,
+        })
+    }
+
+    /// Default name of struct, `get_user` handler gets `GetUserPath`.
+    pub fn default_name(handler: &Ident) -> Ident {
+        let mut name = String::new();
+        for part in handler.to_string().split('_') {
+            let mut chars = part.chars();
+            if let Some(first) = chars.next() {
+                name.extend(first.to_uppercase());
+                name.push_str(chars.as_str());
+            }
+        }
+        name.push_str("Path");
+        Ident::new(&name, Span::call_site())
+    }
+}
+
+/// Names of dynamic segments of path pattern.
+fn segment_names(path: &str) -> Result<Vec<String>, String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut chars = path.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '{' => {
+                // regex of segment could contain braces, i.e. `{id:\d{2}}`
+                let mut depth = 1;
+                let mut segment = String::new();
+                for ch in &mut chars {
+                    match ch {
+                        '{' => depth += 1,
+                        '}' => depth -= 1,
+                        _ => (),
+                    }
+                    if depth == 0 {
+                        break;
+                    }
+                    segment.push(ch);
+                }
+                if depth != 0 {
+                    return Err(format!("Unclosed dynamic segment `{{{}`", segment));
+                }
+
+                let name = segment.split(':').next().unwrap().trim();
+                if syn::parse_str::<Ident>(name).is_err() {
+                    return Err(format!(
+                        "Dynamic segment name `{}` is not a valid field name",
+                        name
+                    ));
+                }
+                if names.iter().any(|n| n == name) {
+                    return Err(format!(
+                        "Dynamic segment `{}` is specified more than once",
+                        name
+                    ));
+                }
+                names.push(name.to_string());
+            }
+            '}' => return Err("Unexpected `}` in path".to_string()),
+            _ => (),
+        }
+    }
+    Ok(names)
+}
+
+impl ToTokens for TypedPath {
+    fn to_tokens(&self, stream: &mut TokenStream2) {
+        let name = &self.name;
+        let fields = &self.fields;
+        let keys = fields.iter().map(|f| f.to_string()).collect::<Vec<_>>();
+        let doc = format!("Dynamic path segments of `{}` route", self.handler);
+
+        stream.extend(quote! {
+            #[doc = #doc]
+            #[derive(Debug, Clone)]
+            pub struct #name {
+                #(pub #fields: String,)*
+            }
+
+            impl #name {
+                fn from_match_info(
+                    req: &actix_web::HttpRequest,
+                ) -> Result<Self, actix_web::Error> {
+                    let segment = |key: &str| {
+                        req.match_info().get(key).map(|s| s.to_owned()).ok_or_else(|| {
+                            actix_web::error::ErrorNotFound(format!(
+                                "Path segment `{}` is not matched",
+                                key
+                            ))
+                        })
+                    };
+                    Ok(#name {
+                        #(#fields: segment(#keys)?,)*
+                    })
+                }
+            }
+
+            impl actix_web::FromRequest for #name {
+                type Error = actix_web::Error;
+                type Future = std::future::Ready<Result<Self, Self::Error>>;
+                type Config = ();
+
+                fn from_request(
+                    req: &actix_web::HttpRequest,
+                    _: &mut actix_web::dev::Payload,
+                ) -> Self::Future {
+                    std::future::ready(Self::from_match_info(req))
+                }
+            }
+        });
+    }
+}
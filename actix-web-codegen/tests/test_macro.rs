@@ -1,5 +1,7 @@
 use actix_web::{http, test, web::Path, App, HttpResponse, Responder};
-use actix_web_codegen::{connect, delete, get, head, options, patch, post, put, trace};
+use actix_web_codegen::{
+    connect, delete, get, head, options, patch, post, put, route, routes, trace,
+};
 use futures::{future, Future};
 
 #[get("/test")]
@@ -67,6 +69,172 @@ async fn get_param_test(_: Path<String>) -> impl Responder {
     HttpResponse::Ok()
 }
 
+#[route("/multi", method = "GET", method = "POST", method = "HEAD")]
+async fn route_test() -> impl Responder {
+    HttpResponse::Ok()
+}
+
+#[route("/any")]
+async fn any_route_test() -> impl Responder {
+    HttpResponse::Ok()
+}
+
+#[get("/users/{id}/posts/{post:\\d+}", typed_path)]
+async fn get_post(path: GetPostPath) -> impl Responder {
+    let GetPostPath { id, post } = path;
+    HttpResponse::Ok().body(format!("{}:{}", id, post))
+}
+
+#[put("/users/{id}", typed_path = "UserPath")]
+async fn put_user(path: UserPath) -> impl Responder {
+    HttpResponse::Created().body(path.id)
+}
+
+mod tenant {
+    use actix_web::guard::{Guard, Header};
+
+    pub fn tenant(name: &'static str) -> impl Guard {
+        Header("x-tenant", name)
+    }
+}
+
+fn is_internal(head: &actix_web::dev::RequestHead) -> bool {
+    head.headers().contains_key("x-internal")
+}
+
+#[get("/guarded", guard = "actix_web::guard::Header(\"x-tenant\", \"a\")")]
+async fn guarded_a() -> impl Responder {
+    HttpResponse::Ok().body("a")
+}
+
+#[get("/guarded", guard = "tenant::tenant(\"b\")", guard = "is_internal")]
+async fn guarded_b() -> impl Responder {
+    HttpResponse::Ok().body("b")
+}
+
+#[post("/guarded", guard = "actix_web::guard::Get()")]
+async fn guarded_never() -> impl Responder {
+    HttpResponse::Ok()
+}
+
+#[routes]
+mod api {
+    use actix_web::dev::RequestHead;
+    use actix_web::{web::Path, HttpResponse, Responder};
+    use actix_web_codegen::{delete, get, route};
+
+    fn is_admin(head: &RequestHead) -> bool {
+        head.headers().contains_key("x-admin")
+    }
+
+    #[get("/api/{param}")]
+    async fn get_api(param: Path<String>) -> impl Responder {
+        HttpResponse::Ok().body(param.into_inner())
+    }
+
+    #[actix_web_codegen::put("/api/{param}")]
+    async fn put_api(_: Path<String>) -> impl Responder {
+        HttpResponse::Created()
+    }
+
+    #[route("/api/{param}", method = "PATCH", method = "OPTIONS")]
+    async fn patch_api(_: Path<String>) -> impl Responder {
+        HttpResponse::Accepted()
+    }
+
+    #[delete("/api/{param}", guard = "is_admin")]
+    async fn delete_api(_: Path<String>) -> impl Responder {
+        HttpResponse::NoContent()
+    }
+
+    #[allow(dead_code)]
+    fn not_a_handler() {}
+}
+
+#[actix_rt::test]
+async fn test_routes() {
+    let srv = test::start(|| App::new().configure(api::configure));
+
+    let request = srv.request(http::Method::GET, srv.url("/api/it"));
+    let mut response = request.send().await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::OK);
+    assert_eq!(response.body().await.unwrap(), "it");
+
+    let request = srv.request(http::Method::PUT, srv.url("/api/it"));
+    let response = request.send().await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::CREATED);
+
+    let request = srv.request(http::Method::DELETE, srv.url("/api/it"));
+    let response = request.send().await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::NOT_FOUND);
+
+    let request = srv
+        .request(http::Method::DELETE, srv.url("/api/it"))
+        .header("x-admin", "1");
+    let response = request.send().await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::NO_CONTENT);
+
+    let request = srv.request(http::Method::PATCH, srv.url("/api/it"));
+    let response = request.send().await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::ACCEPTED);
+
+    let request = srv.request(http::Method::POST, srv.url("/api/it"));
+    let response = request.send().await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+async fn test_route() {
+    let srv = test::start(|| App::new().service(route_test).service(any_route_test));
+
+    for method in &[http::Method::GET, http::Method::POST, http::Method::HEAD] {
+        let request = srv.request(method.clone(), srv.url("/multi"));
+        let response = request.send().await.unwrap();
+        assert_eq!(response.status(), http::StatusCode::OK);
+    }
+
+    let request = srv.request(http::Method::PUT, srv.url("/multi"));
+    let response = request.send().await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::NOT_FOUND);
+
+    for method in &[http::Method::GET, http::Method::PATCH, http::Method::DELETE] {
+        let request = srv.request(method.clone(), srv.url("/any"));
+        let response = request.send().await.unwrap();
+        assert_eq!(response.status(), http::StatusCode::OK);
+    }
+}
+
+#[actix_rt::test]
+async fn test_guard() {
+    let srv = test::start(|| {
+        App::new()
+            .service(guarded_a)
+            .service(guarded_b)
+            .service(guarded_never)
+    });
+
+    let request = srv.get("/guarded").header("x-tenant", "a");
+    let mut response = request.send().await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::OK);
+    assert_eq!(response.body().await.unwrap(), "a");
+
+    let request = srv
+        .get("/guarded")
+        .header("x-tenant", "b")
+        .header("x-internal", "1");
+    let mut response = request.send().await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::OK);
+    assert_eq!(response.body().await.unwrap(), "b");
+
+    let request = srv.get("/guarded").header("x-tenant", "b");
+    let response = request.send().await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::NOT_FOUND);
+
+    let request = srv.post("/guarded");
+    let response = request.send().await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::NOT_FOUND);
+}
+
 #[actix_rt::test]
 async fn test_params() {
     let srv = test::start(|| {
@@ -89,6 +257,25 @@ async fn test_params() {
     assert_eq!(response.status(), http::StatusCode::NO_CONTENT);
 }
 
+#[actix_rt::test]
+async fn test_typed_path() {
+    let srv = test::start(|| App::new().service(get_post).service(put_user));
+
+    let request = srv.request(http::Method::GET, srv.url("/users/bob/posts/12"));
+    let mut response = request.send().await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::OK);
+    assert_eq!(response.body().await.unwrap(), "bob:12");
+
+    let request = srv.request(http::Method::GET, srv.url("/users/bob/posts/new"));
+    let response = request.send().await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::NOT_FOUND);
+
+    let request = srv.request(http::Method::PUT, srv.url("/users/bob"));
+    let mut response = request.send().await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::CREATED);
+    assert_eq!(response.body().await.unwrap(), "bob");
+}
+
 #[actix_rt::test]
 async fn test_body() {
     let srv = test::start(|| {
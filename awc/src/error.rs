@@ -28,6 +28,10 @@ pub enum WsClientError {
     /// Missing CONNECTION header
     #[display(fmt = "Missing CONNECTION header")]
     MissingConnectionHeader,
+    /// Server selected protocol which was not requested
+    #[display(fmt = "Invalid protocol header")]
+    #[from(ignore)]
+    InvalidProtocolHeader(HeaderValue),
     /// Missing SEC-WEBSOCKET-ACCEPT header
     #[display(fmt = "Missing SEC-WEBSOCKET-ACCEPT header")]
     MissingWebSocketAcceptHeader,
@@ -9,7 +9,7 @@ use futures_core::{ready, Future, Stream};
 
 use actix_http::cookie::Cookie;
 use actix_http::error::{CookieParseError, PayloadError};
-use actix_http::http::header::{CONTENT_LENGTH, SET_COOKIE};
+use actix_http::http::header::{CONTENT_LENGTH, SEC_WEBSOCKET_PROTOCOL, SET_COOKIE};
 use actix_http::http::{HeaderMap, StatusCode, Version};
 use actix_http::{Extensions, HttpMessage, Payload, PayloadStream, ResponseHead};
 use serde::de::DeserializeOwned;
@@ -90,6 +90,18 @@ impl<S> ClientResponse<S> {
         &self.head().headers
     }
 
+    /// Websocket protocol selected by server.
+    ///
+    /// Handshake response of `WebsocketsRequest::connect()` carries one of
+    /// protocols requested via `WebsocketsRequest::protocols()`, other
+    /// protocols fail the handshake.
+    pub fn ws_protocol(&self) -> Option<&str> {
+        self.headers()
+            .get(&SEC_WEBSOCKET_PROTOCOL)
+            .and_then(|proto| proto.to_str().ok())
+            .map(|proto| proto.trim())
+    }
+
     /// Set a body and return previous body value
     pub fn map_body<F, U>(mut self, f: F) -> ClientResponse<U>
     where
@@ -1,21 +1,28 @@
 //! Websockets client
 use std::convert::TryFrom;
 use std::fmt::Write as FmtWrite;
+use std::future::Future;
 use std::net::SocketAddr;
+use std::pin::Pin;
 use std::rc::Rc;
-use std::{fmt, str};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use std::{cmp, fmt, io, str};
 
 use actix_codec::Framed;
 use actix_http::cookie::{Cookie, CookieJar};
 use actix_http::{ws, Payload, RequestHead};
-use actix_rt::time::timeout;
+use actix_rt::time::{delay_for, timeout, Delay, Instant};
+use bytes::Bytes;
+use futures_core::Stream;
+use futures_sink::Sink;
 use percent_encoding::percent_encode;
 
 use actix_http::cookie::USERINFO;
 pub use actix_http::ws::{CloseCode, CloseReason, Codec, Frame, Message};
 
 use crate::connect::BoxedSocket;
-use crate::error::{InvalidUrl, SendRequestError, WsClientError};
+use crate::error::{InvalidUrl, SendRequestError, WsClientError, WsProtocolError};
 use crate::http::header::{
     self, HeaderName, HeaderValue, IntoHeaderValue, AUTHORIZATION,
 };
@@ -78,6 +85,25 @@ impl WebsocketsRequest {
     }
 
     /// Set supported websocket protocols
+    ///
+    /// Protocol selected by server is available via
+    /// `ClientResponse::ws_protocol()` of the handshake response. Handshake
+    /// fails with `WsClientError::InvalidProtocolHeader` if server selects
+    /// protocol which was not requested.
+    ///
+    /// ```rust,no_run
+    /// use awc::Client;
+    ///
+    /// # async fn run() {
+    /// let (res, framed) = Client::new()
+    ///     .ws("ws://localhost:8080/graphql")
+    ///     .protocols(["graphql-ws"])
+    ///     .connect()
+    ///     .await
+    ///     .unwrap();
+    /// assert_eq!(res.ws_protocol(), Some("graphql-ws"));
+    /// # }
+    /// ```
     pub fn protocols<U, V>(mut self, protos: U) -> Self
     where
         U: IntoIterator<Item = V>,
@@ -214,6 +240,74 @@ impl WebsocketsRequest {
         self.header(AUTHORIZATION, format!("Bearer {}", token))
     }
 
+    /// Complete request construction and connect to a websockets server,
+    /// reconnecting whenever connection is lost.
+    ///
+    /// Returned `Reconnect` is a stream of connection events and received
+    /// frames, and a sink of messages. If connection drops or handshake fails,
+    /// handshake is performed again after a delay defined by `backoff`, and
+    /// frames of the new connection are yielded by the same stream.
+    ///
+    /// Connection is not restored once server closes it with
+    /// `CloseCode::Normal` or client sends a close message.
+    ///
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    /// use awc::{ws, Client};
+    /// use futures::StreamExt;
+    ///
+    /// # async fn run() {
+    /// let backoff = ws::Backoff::new(Duration::from_millis(100)).max_retries(5);
+    /// let mut conn = Client::new().ws("ws://localhost:8080/ws").reconnect(backoff);
+    ///
+    /// while let Some(event) = conn.next().await {
+    ///     match event {
+    ///         Ok(ws::ReconnectEvent::Frame(frame)) => println!("{:?}", frame),
+    ///         Ok(event) => println!("{:?}", event),
+    ///         Err(err) => println!("gave up reconnecting: {}", err),
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn reconnect(mut self, backoff: Backoff) -> Reconnect {
+        let state = if let Some(e) = self.err.take() {
+            ReconnectState::Failed(Some(e.into()))
+        } else {
+            ReconnectState::Connecting(Box::pin(self.duplicate().connect()))
+        };
+        Reconnect {
+            req: self,
+            backoff,
+            state,
+            heartbeat: HeartbeatConfig::default(),
+            protocol: None,
+            attempt: 0,
+            closed: false,
+            close_sent: false,
+        }
+    }
+
+    /// Copy of the request for another connection attempt.
+    fn duplicate(&self) -> WebsocketsRequest {
+        let mut head = RequestHead::default();
+        head.method = self.head.method.clone();
+        head.version = self.head.version;
+        head.uri = self.head.uri.clone();
+        head.headers = self.head.headers.clone();
+
+        WebsocketsRequest {
+            head,
+            err: None,
+            origin: self.origin.clone(),
+            protocols: self.protocols.clone(),
+            addr: self.addr,
+            max_size: self.max_size,
+            server_mode: self.server_mode,
+            cookies: self.cookies.clone(),
+            config: self.config.clone(),
+        }
+    }
+
     /// Complete request construction and connect to a websockets server.
     pub async fn connect(
         mut self,
@@ -272,7 +366,7 @@ impl WebsocketsRequest {
             HeaderValue::from_static("13"),
         );
 
-        if let Some(protocols) = self.protocols.take() {
+        if let Some(ref protocols) = self.protocols {
             self.head.headers.insert(
                 header::SEC_WEBSOCKET_PROTOCOL,
                 HeaderValue::try_from(protocols.as_str()).unwrap(),
@@ -364,6 +458,20 @@ impl WebsocketsRequest {
             return Err(WsClientError::MissingWebSocketAcceptHeader);
         };
 
+        // Check that server selected one of requested protocols
+        if let Some(proto) = head.headers.get(&header::SEC_WEBSOCKET_PROTOCOL) {
+            let requested = match (proto.to_str(), self.protocols.as_ref()) {
+                (Ok(proto), Some(protocols)) => {
+                    protocols.split(',').any(|p| p.trim() == proto.trim())
+                }
+                _ => false,
+            };
+            if !requested {
+                log::trace!("Invalid protocol header: {:?}", proto);
+                return Err(WsClientError::InvalidProtocolHeader(proto.clone()));
+            }
+        }
+
         // response and ws framed
         Ok((
             ClientResponse::new(head, Payload::None),
@@ -393,6 +501,557 @@ impl fmt::Debug for WebsocketsRequest {
     }
 }
 
+/// Delays between reconnection attempts of `WebsocketsRequest::reconnect()`
+///
+/// Delay starts at initial value and is multiplied by `factor` on every
+/// failed attempt, up to `max_delay`. Number of attempts is reset once
+/// connection is established.
+#[derive(Clone, Debug)]
+pub struct Backoff {
+    initial: Duration,
+    max_delay: Duration,
+    factor: u32,
+    max_retries: Option<usize>,
+}
+
+impl Backoff {
+    /// Create backoff with `initial` delay before the first attempt.
+    ///
+    /// By default delay is doubled up to 30 seconds, number of attempts is
+    /// not limited.
+    pub fn new(initial: Duration) -> Self {
+        Backoff {
+            initial,
+            max_delay: Duration::from_secs(30),
+            factor: 2,
+            max_retries: None,
+        }
+    }
+
+    /// Set max delay between attempts
+    pub fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Set factor delay is multiplied by after each failed attempt
+    pub fn factor(mut self, factor: u32) -> Self {
+        self.factor = factor;
+        self
+    }
+
+    /// Set max number of consecutive attempts, error of the last attempt is
+    /// returned once it is reached.
+    pub fn max_retries(mut self, retries: usize) -> Self {
+        self.max_retries = Some(retries);
+        self
+    }
+
+    /// Delay before `attempt`, starting from 1.
+    fn delay(&self, attempt: usize) -> Duration {
+        let mut delay = self.initial;
+        for _ in 1..attempt {
+            if delay >= self.max_delay {
+                break;
+            }
+            delay = delay.checked_mul(self.factor).unwrap_or(self.max_delay);
+        }
+        cmp::min(delay, self.max_delay)
+    }
+}
+
+/// Item of `Reconnect` stream
+#[derive(Debug)]
+pub enum ReconnectEvent {
+    /// Handshake is completed, on initial connect and after reconnection
+    Connected(ClientResponse),
+    /// Connection is lost or handshake failed, next attempt is made after
+    /// `delay`. `error` is `None` if connection is closed without error.
+    Reconnecting {
+        attempt: usize,
+        delay: Duration,
+        error: Option<WsClientError>,
+    },
+    /// Frame received from server
+    Frame(Frame),
+}
+
+type ConnectFuture = Pin<
+    Box<
+        dyn Future<
+            Output = Result<(ClientResponse, Framed<BoxedSocket, Codec>), WsClientError>,
+        >,
+    >,
+>;
+
+enum ReconnectState {
+    Connecting(ConnectFuture),
+    Connected(Heartbeat<Framed<BoxedSocket, Codec>>),
+    Waiting(Delay),
+    Failed(Option<WsClientError>),
+    Done,
+}
+
+/// Websocket connection which is restored when lost
+///
+/// Created by `WebsocketsRequest::reconnect()`. Stream has to be polled to
+/// establish and restore connection. Messages can be sent only while
+/// connection is established, sink fails with `NotConnected` io error
+/// otherwise.
+pub struct Reconnect {
+    req: WebsocketsRequest,
+    backoff: Backoff,
+    heartbeat: HeartbeatConfig,
+    protocol: Option<String>,
+    state: ReconnectState,
+    attempt: usize,
+    /// Connection is not restored
+    closed: bool,
+    /// Client has sent close message
+    close_sent: bool,
+}
+
+impl Reconnect {
+    /// Send ping to server every `interval` on each connection, see
+    /// `Heartbeat`.
+    ///
+    /// Connection is restored if server does not respond in time.
+    pub fn heartbeat(mut self, interval: Duration) -> Self {
+        self.heartbeat.interval = Some(interval);
+        self
+    }
+
+    /// Set time to wait for pong before connection is considered lost.
+    ///
+    /// By default it is the same as heartbeat interval.
+    pub fn heartbeat_timeout(mut self, timeout: Duration) -> Self {
+        self.heartbeat.timeout = Some(timeout);
+        self
+    }
+
+    /// Send increasing token in heartbeat ping payload, pongs with stale
+    /// token are ignored. By default ping payload is empty.
+    pub fn heartbeat_token(mut self, value: bool) -> Self {
+        self.heartbeat.token = value;
+        self
+    }
+
+    /// Protocol selected by server for the last established connection
+    pub fn protocol(&self) -> Option<&str> {
+        self.protocol.as_deref()
+    }
+
+    /// Check if connection is currently established
+    pub fn is_connected(&self) -> bool {
+        if let ReconnectState::Connected(_) = self.state {
+            true
+        } else {
+            false
+        }
+    }
+
+    fn retry(
+        &mut self,
+        error: Option<WsClientError>,
+    ) -> Poll<Option<Result<ReconnectEvent, WsClientError>>> {
+        if self.closed {
+            self.state = ReconnectState::Done;
+            return Poll::Ready(None);
+        }
+
+        self.attempt += 1;
+        if let Some(max) = self.backoff.max_retries {
+            if self.attempt > max {
+                log::trace!("Websocket reconnect attempts exhausted");
+                self.state = ReconnectState::Done;
+                return Poll::Ready(error.map(Err));
+            }
+        }
+
+        let delay = self.backoff.delay(self.attempt);
+        log::trace!(
+            "Websocket reconnect attempt {} in {:?}",
+            self.attempt,
+            delay
+        );
+        self.state = ReconnectState::Waiting(delay_for(delay));
+        Poll::Ready(Some(Ok(ReconnectEvent::Reconnecting {
+            attempt: self.attempt,
+            delay,
+            error,
+        })))
+    }
+}
+
+impl Stream for Reconnect {
+    type Item = Result<ReconnectEvent, WsClientError>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            match this.state {
+                ReconnectState::Connecting(ref mut fut) => {
+                    return match fut.as_mut().poll(cx) {
+                        Poll::Ready(Ok((res, framed))) => {
+                            this.attempt = 0;
+                            this.protocol = res.ws_protocol().map(str::to_owned);
+                            this.state = ReconnectState::Connected(
+                                Heartbeat::with_config(framed, this.heartbeat),
+                            );
+                            Poll::Ready(Some(Ok(ReconnectEvent::Connected(res))))
+                        }
+                        Poll::Ready(Err(e)) => this.retry(Some(e)),
+                        Poll::Pending => Poll::Pending,
+                    };
+                }
+                ReconnectState::Connected(ref mut framed) => {
+                    return match Pin::new(framed).poll_next(cx) {
+                        Poll::Ready(Some(Ok(frame))) => {
+                            if let Frame::Close(ref reason) = frame {
+                                if let Some(CloseCode::Normal) =
+                                    reason.as_ref().map(|r| r.code)
+                                {
+                                    this.closed = true;
+                                }
+                                // closing handshake is completed
+                                if this.close_sent {
+                                    this.state = ReconnectState::Done;
+                                }
+                            }
+                            Poll::Ready(Some(Ok(ReconnectEvent::Frame(frame))))
+                        }
+                        Poll::Ready(Some(Err(e))) => this.retry(Some(e.into())),
+                        Poll::Ready(None) => this.retry(None),
+                        Poll::Pending => Poll::Pending,
+                    };
+                }
+                ReconnectState::Waiting(ref mut delay) => {
+                    match Pin::new(delay).poll(cx) {
+                        Poll::Ready(()) => {
+                            this.state = ReconnectState::Connecting(Box::pin(
+                                this.req.duplicate().connect(),
+                            ));
+                        }
+                        Poll::Pending => return Poll::Pending,
+                    }
+                }
+                ReconnectState::Failed(ref mut err) => {
+                    let err = err.take();
+                    this.state = ReconnectState::Done;
+                    return Poll::Ready(err.map(Err));
+                }
+                ReconnectState::Done => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+impl Sink<Message> for Reconnect {
+    type Error = WsProtocolError;
+
+    fn poll_ready(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        match self.get_mut().state {
+            ReconnectState::Connected(ref mut framed) => Pin::new(framed).poll_ready(cx),
+            _ => Poll::Ready(Err(not_connected())),
+        }
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Message) -> Result<(), Self::Error> {
+        let this = self.get_mut();
+        match this.state {
+            ReconnectState::Connected(ref mut framed) => {
+                if let Message::Close(_) = item {
+                    this.closed = true;
+                    this.close_sent = true;
+                }
+                Pin::new(framed).start_send(item)
+            }
+            _ => Err(not_connected()),
+        }
+    }
+
+    fn poll_flush(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        match self.get_mut().state {
+            ReconnectState::Connected(ref mut framed) => Pin::new(framed).poll_flush(cx),
+            _ => Poll::Ready(Ok(())),
+        }
+    }
+
+    fn poll_close(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        this.closed = true;
+        match this.state {
+            ReconnectState::Connected(ref mut framed) => Pin::new(framed).poll_close(cx),
+            _ => Poll::Ready(Ok(())),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct HeartbeatConfig {
+    interval: Option<Duration>,
+    timeout: Option<Duration>,
+    token: bool,
+}
+
+/// Websocket connection which sends periodic pings to keep it alive
+///
+/// Wraps websocket connection, i.e. one returned by
+/// `WebsocketsRequest::connect()`, and sends `Message::Ping` every
+/// `interval` while stream is polled. If `Pong` is not received within
+/// timeout, connection is shut down and stream yields `TimedOut` io error
+/// and ends.
+///
+/// All frames, including pongs, are yielded unchanged. Ping payload is empty
+/// by default. With `use_token(true)` it carries increasing 8 byte token, and
+/// only pong with the token of the last ping is accepted, so stale pongs and
+/// pongs to pings sent by application are not mistaken for heartbeat.
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use awc::{ws, Client};
+/// use futures::StreamExt;
+///
+/// # async fn run() {
+/// let (_, framed) = Client::new().ws("ws://localhost:8080/ws").connect().await.unwrap();
+/// let mut conn = ws::Heartbeat::new(framed, Duration::from_secs(5))
+///     .timeout(Duration::from_secs(10))
+///     .use_token(true);
+///
+/// while let Some(Ok(frame)) = conn.next().await {
+///     println!("{:?}", frame);
+/// }
+/// # }
+/// ```
+pub struct Heartbeat<T> {
+    inner: T,
+    config: HeartbeatConfig,
+    ping_timer: Option<Delay>,
+    pong_timer: Option<Delay>,
+    /// Ping waiting for sink to become ready
+    ping: Option<Message>,
+    flush: bool,
+    token: u64,
+    timed_out: bool,
+}
+
+impl<T> Heartbeat<T> {
+    /// Wrap websocket connection, sending ping every `interval`.
+    pub fn new(inner: T, interval: Duration) -> Self {
+        Heartbeat::with_config(
+            inner,
+            HeartbeatConfig {
+                interval: Some(interval),
+                ..HeartbeatConfig::default()
+            },
+        )
+    }
+
+    fn with_config(inner: T, config: HeartbeatConfig) -> Self {
+        Heartbeat {
+            inner,
+            config,
+            ping_timer: config.interval.map(delay_for),
+            pong_timer: None,
+            ping: None,
+            flush: false,
+            token: 0,
+            timed_out: false,
+        }
+    }
+
+    /// Set time to wait for pong before connection is considered lost.
+    ///
+    /// By default it is the same as ping interval.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.config.timeout = Some(timeout);
+        self
+    }
+
+    /// Send increasing token in ping payload, pongs with stale token are
+    /// ignored. By default ping payload is empty.
+    pub fn use_token(mut self, value: bool) -> Self {
+        self.config.token = value;
+        self
+    }
+
+    /// Get reference to the wrapped connection
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Get mutable reference to the wrapped connection
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Deconstruct to the wrapped connection
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T> Heartbeat<T>
+where
+    T: Sink<Message, Error = WsProtocolError> + Unpin,
+{
+    /// Schedule ping if interval has elapsed and previous ping is answered.
+    fn poll_interval(&mut self, cx: &mut Context<'_>) {
+        let interval = match self.config.interval {
+            Some(interval) => interval,
+            None => return,
+        };
+        let timer = match self.ping_timer {
+            Some(ref mut timer) => timer,
+            None => return,
+        };
+        if Pin::new(&mut *timer).poll(cx).is_pending() {
+            return;
+        }
+
+        timer.reset(Instant::now() + interval);
+        let _ = Pin::new(timer).poll(cx);
+
+        if self.pong_timer.is_none() {
+            self.token += 1;
+            let payload = if self.config.token {
+                Bytes::copy_from_slice(&self.token.to_be_bytes())
+            } else {
+                Bytes::new()
+            };
+            self.ping = Some(Message::Ping(payload));
+            self.pong_timer = Some(delay_for(self.config.timeout.unwrap_or(interval)));
+        }
+    }
+
+    /// Send scheduled ping.
+    fn poll_ping(&mut self, cx: &mut Context<'_>) -> Result<(), WsProtocolError> {
+        if self.ping.is_some() {
+            match Pin::new(&mut self.inner).poll_ready(cx) {
+                Poll::Ready(Ok(())) => {
+                    let ping = self.ping.take().unwrap();
+                    Pin::new(&mut self.inner).start_send(ping)?;
+                    self.flush = true;
+                }
+                Poll::Ready(Err(e)) => return Err(e),
+                Poll::Pending => return Ok(()),
+            }
+        }
+        if self.flush {
+            match Pin::new(&mut self.inner).poll_flush(cx) {
+                Poll::Ready(Ok(())) => self.flush = false,
+                Poll::Ready(Err(e)) => return Err(e),
+                Poll::Pending => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// Check received pong against the last ping.
+    fn pong(&mut self, payload: &[u8]) {
+        if self.pong_timer.is_none() {
+            return;
+        }
+        if !self.config.token || payload == &self.token.to_be_bytes()[..] {
+            self.pong_timer = None;
+        } else {
+            log::trace!("Ignoring stale heartbeat pong");
+        }
+    }
+}
+
+impl<T> Stream for Heartbeat<T>
+where
+    T: Stream<Item = Result<Frame, WsProtocolError>>
+        + Sink<Message, Error = WsProtocolError>
+        + Unpin,
+{
+    type Item = Result<Frame, WsProtocolError>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.timed_out {
+            return Poll::Ready(None);
+        }
+
+        this.poll_interval(cx);
+        if let Err(e) = this.poll_ping(cx) {
+            return Poll::Ready(Some(Err(e)));
+        }
+
+        if let Some(ref mut timer) = this.pong_timer {
+            if Pin::new(timer).poll(cx).is_ready() {
+                log::trace!("Websocket heartbeat timeout, dropping connection");
+                this.timed_out = true;
+                let _ = Pin::new(&mut this.inner).poll_close(cx);
+                return Poll::Ready(Some(Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "Websocket heartbeat timeout",
+                )
+                .into())));
+            }
+        }
+
+        let item = Pin::new(&mut this.inner).poll_next(cx);
+        if let Poll::Ready(Some(Ok(Frame::Pong(ref payload)))) = item {
+            this.pong(payload);
+        }
+        item
+    }
+}
+
+impl<T> Sink<Message> for Heartbeat<T>
+where
+    T: Sink<Message, Error = WsProtocolError> + Unpin,
+{
+    type Error = WsProtocolError;
+
+    fn poll_ready(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.get_mut().inner).poll_ready(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Message) -> Result<(), Self::Error> {
+        Pin::new(&mut self.get_mut().inner).start_send(item)
+    }
+
+    fn poll_flush(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_close(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.get_mut().inner).poll_close(cx)
+    }
+}
+
+fn not_connected() -> WsProtocolError {
+    io::Error::new(io::ErrorKind::NotConnected, "Websocket is reconnecting").into()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,8 +1,14 @@
 use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use actix_codec::Framed;
+use actix_http::http::{header, StatusCode};
 use actix_http::{body::BodySize, h1, ws, Error, HttpService, Request, Response};
 use actix_http_test::test_server;
+use awc::error::WsClientError;
+use awc::ws::{Heartbeat, ReconnectEvent};
 use bytes::Bytes;
 use futures::future::ok;
 use futures::{SinkExt, StreamExt};
@@ -68,3 +74,337 @@ async fn test_simple() {
     let item = framed.next().await.unwrap().unwrap();
     assert_eq!(item, ws::Frame::Close(Some(ws::CloseCode::Normal.into())));
 }
+
+#[actix_rt::test]
+async fn test_reassembly() {
+    let mut srv = test_server(|| {
+        HttpService::build()
+            .upgrade(|(req, mut framed): (Request, Framed<_, _>)| {
+                async move {
+                    let res = ws::handshake_response(req.head()).finish();
+                    framed
+                        .send(h1::Message::Item((res.drop_body(), BodySize::None)))
+                        .await?;
+
+                    let framed = framed.into_framed(ws::Codec::new());
+                    ws::Dispatcher::with_reassembly(framed, 16, ws_service).await
+                }
+            })
+            .finish(|_| ok::<_, Error>(Response::NotFound()))
+            .tcp()
+    });
+
+    let mut framed = srv.ws().await.unwrap();
+
+    // control frame between fragments is delivered immediately
+    let items = vec![
+        ws::Message::Continuation(ws::Item::FirstText("hello ".into())),
+        ws::Message::Ping("ping".into()),
+        ws::Message::Continuation(ws::Item::Last("world".into())),
+    ];
+    for item in items {
+        framed.send(item).await.unwrap();
+    }
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Pong(Bytes::from_static(b"ping")));
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Text(Bytes::from_static(b"hello world")));
+
+    let items = vec![
+        ws::Message::Continuation(ws::Item::FirstBinary("ab".into())),
+        ws::Message::Continuation(ws::Item::Continue("cd".into())),
+        ws::Message::Continuation(ws::Item::Last("ef".into())),
+    ];
+    for item in items {
+        framed.send(item).await.unwrap();
+    }
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Binary(Bytes::from_static(b"abcdef")));
+
+    // message exceeds reassembly limit
+    let items = vec![
+        ws::Message::Continuation(ws::Item::FirstText("0123456789".into())),
+        ws::Message::Continuation(ws::Item::Last("0123456789".into())),
+    ];
+    for item in items {
+        framed.send(item).await.unwrap();
+    }
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Close(Some(ws::CloseCode::Size.into())));
+}
+
+#[actix_rt::test]
+async fn test_reconnect() {
+    let connections = Arc::new(AtomicUsize::new(0));
+    let conns = connections.clone();
+
+    let srv = test_server(move || {
+        let conns = conns.clone();
+        HttpService::build()
+            .upgrade(move |(req, mut framed): (Request, Framed<_, _>)| {
+                let n = conns.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    let res = ws::handshake_response(req.head()).finish();
+                    framed
+                        .send(h1::Message::Item((res.drop_body(), BodySize::None)))
+                        .await?;
+
+                    // drop first connection right after handshake
+                    if n == 0 {
+                        return Ok(());
+                    }
+                    let framed = framed.into_framed(ws::Codec::new());
+                    ws::Dispatcher::with(framed, ws_service).await
+                }
+            })
+            .finish(|_| ok::<_, Error>(Response::NotFound()))
+            .tcp()
+    });
+
+    let backoff = awc::ws::Backoff::new(Duration::from_millis(10));
+    let mut conn = awc::Client::new().ws(srv.url("/")).reconnect(backoff);
+
+    match conn.next().await.unwrap().unwrap() {
+        ReconnectEvent::Connected(res) => {
+            assert_eq!(res.status(), StatusCode::SWITCHING_PROTOCOLS)
+        }
+        ev => panic!("unexpected event {:?}", ev),
+    }
+    match conn.next().await.unwrap().unwrap() {
+        ReconnectEvent::Reconnecting { attempt, delay, .. } => {
+            assert_eq!(attempt, 1);
+            assert_eq!(delay, Duration::from_millis(10));
+        }
+        ev => panic!("unexpected event {:?}", ev),
+    }
+    assert!(!conn.is_connected());
+    assert!(conn
+        .send(ws::Message::Text("text".to_string()))
+        .await
+        .is_err());
+
+    match conn.next().await.unwrap().unwrap() {
+        ReconnectEvent::Connected(_) => (),
+        ev => panic!("unexpected event {:?}", ev),
+    }
+    assert_eq!(connections.load(Ordering::SeqCst), 2);
+
+    // frames of the new connection are yielded by the same stream
+    conn.send(ws::Message::Text("text".to_string()))
+        .await
+        .unwrap();
+    match conn.next().await.unwrap().unwrap() {
+        ReconnectEvent::Frame(frame) => {
+            assert_eq!(frame, ws::Frame::Text(Bytes::from_static(b"text")))
+        }
+        ev => panic!("unexpected event {:?}", ev),
+    }
+
+    // normal close is not followed by reconnection
+    conn.send(ws::Message::Close(Some(ws::CloseCode::Normal.into())))
+        .await
+        .unwrap();
+    match conn.next().await.unwrap().unwrap() {
+        ReconnectEvent::Frame(frame) => {
+            assert_eq!(frame, ws::Frame::Close(Some(ws::CloseCode::Normal.into())))
+        }
+        ev => panic!("unexpected event {:?}", ev),
+    }
+    assert!(conn.next().await.is_none());
+    assert_eq!(connections.load(Ordering::SeqCst), 2);
+}
+
+#[actix_rt::test]
+async fn test_reconnect_max_retries() {
+    // address nothing listens on
+    let addr = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let backoff = awc::ws::Backoff::new(Duration::from_millis(10)).max_retries(2);
+    let mut conn = awc::Client::new()
+        .ws(format!("http://{}/", addr))
+        .reconnect(backoff);
+
+    for n in 1..=2 {
+        match conn.next().await.unwrap().unwrap() {
+            ReconnectEvent::Reconnecting {
+                attempt,
+                delay,
+                error,
+            } => {
+                assert_eq!(attempt, n);
+                assert_eq!(delay, Duration::from_millis(10 << (n - 1)));
+                assert!(error.is_some());
+            }
+            ev => panic!("unexpected event {:?}", ev),
+        }
+    }
+    assert!(conn.next().await.unwrap().is_err());
+    assert!(conn.next().await.is_none());
+}
+
+#[actix_rt::test]
+async fn test_heartbeat() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .upgrade(|(req, mut framed): (Request, Framed<_, _>)| {
+                async move {
+                    let res = ws::handshake_response(req.head()).finish();
+                    framed
+                        .send(h1::Message::Item((res.drop_body(), BodySize::None)))
+                        .await?;
+
+                    let framed = framed.into_framed(ws::Codec::new());
+                    ws::Dispatcher::with(framed, ws_service).await
+                }
+            })
+            .finish(|_| ok::<_, Error>(Response::NotFound()))
+            .tcp()
+    });
+
+    let (_, framed) = awc::Client::new().ws(srv.url("/")).connect().await.unwrap();
+    let mut conn = Heartbeat::new(framed, Duration::from_millis(50)).use_token(true);
+
+    // pings carry increasing token
+    for token in 1u64..=2 {
+        let item = conn.next().await.unwrap().unwrap();
+        assert_eq!(
+            item,
+            ws::Frame::Pong(Bytes::copy_from_slice(&token.to_be_bytes()))
+        );
+    }
+
+    // other frames are not changed
+    conn.send(ws::Message::Text("text".to_string()))
+        .await
+        .unwrap();
+    let item = conn.next().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Text(Bytes::from_static(b"text")));
+}
+
+#[actix_rt::test]
+async fn test_heartbeat_timeout() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .upgrade(|(req, mut framed): (Request, Framed<_, _>)| {
+                async move {
+                    let res = ws::handshake_response(req.head()).finish();
+                    framed
+                        .send(h1::Message::Item((res.drop_body(), BodySize::None)))
+                        .await?;
+
+                    // server never responds to pings
+                    let framed = framed.into_framed(ws::Codec::new());
+                    ws::Dispatcher::with(framed, |_| {
+                        ok::<_, io::Error>(ws::Message::Nop)
+                    })
+                    .await
+                }
+            })
+            .finish(|_| ok::<_, Error>(Response::NotFound()))
+            .tcp()
+    });
+
+    let (_, framed) = awc::Client::new().ws(srv.url("/")).connect().await.unwrap();
+    let mut conn = Heartbeat::new(framed, Duration::from_millis(50))
+        .timeout(Duration::from_millis(100));
+
+    let start = Instant::now();
+    match conn.next().await.unwrap() {
+        Err(ws::ProtocolError::Io(err)) => {
+            assert_eq!(err.kind(), io::ErrorKind::TimedOut)
+        }
+        item => panic!("unexpected item {:?}", item),
+    }
+    assert!(start.elapsed() >= Duration::from_millis(150));
+    assert!(conn.next().await.is_none());
+
+    // lost heartbeat restores connection
+    let backoff = awc::ws::Backoff::new(Duration::from_millis(10));
+    let mut conn = awc::Client::new()
+        .ws(srv.url("/"))
+        .reconnect(backoff)
+        .heartbeat(Duration::from_millis(50));
+
+    match conn.next().await.unwrap().unwrap() {
+        ReconnectEvent::Connected(_) => (),
+        ev => panic!("unexpected event {:?}", ev),
+    }
+    match conn.next().await.unwrap().unwrap() {
+        ReconnectEvent::Reconnecting {
+            error: Some(WsClientError::Protocol(ws::ProtocolError::Io(err))),
+            ..
+        } => assert_eq!(err.kind(), io::ErrorKind::TimedOut),
+        ev => panic!("unexpected event {:?}", ev),
+    }
+    match conn.next().await.unwrap().unwrap() {
+        ReconnectEvent::Connected(_) => (),
+        ev => panic!("unexpected event {:?}", ev),
+    }
+}
+
+#[actix_rt::test]
+async fn test_protocols() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .upgrade(|(req, mut framed): (Request, Framed<_, _>)| {
+                async move {
+                    let res = ws::handshake_response(req.head())
+                        .header(header::SEC_WEBSOCKET_PROTOCOL, "graphql-ws")
+                        .finish();
+                    framed
+                        .send(h1::Message::Item((res.drop_body(), BodySize::None)))
+                        .await?;
+
+                    let framed = framed.into_framed(ws::Codec::new());
+                    ws::Dispatcher::with(framed, ws_service).await
+                }
+            })
+            .finish(|_| ok::<_, Error>(Response::NotFound()))
+            .tcp()
+    });
+
+    let (res, _) = awc::Client::new()
+        .ws(srv.url("/"))
+        .protocols(["chat", "graphql-ws"])
+        .connect()
+        .await
+        .unwrap();
+    assert_eq!(res.ws_protocol(), Some("graphql-ws"));
+
+    let backoff = awc::ws::Backoff::new(Duration::from_millis(10));
+    let mut conn = awc::Client::new()
+        .ws(srv.url("/"))
+        .protocols(["graphql-ws"])
+        .reconnect(backoff);
+    assert_eq!(conn.protocol(), None);
+    match conn.next().await.unwrap().unwrap() {
+        ReconnectEvent::Connected(_) => (),
+        ev => panic!("unexpected event {:?}", ev),
+    }
+    assert_eq!(conn.protocol(), Some("graphql-ws"));
+
+    // server selected protocol which was not requested
+    let err = awc::Client::new()
+        .ws(srv.url("/"))
+        .protocols(["chat"])
+        .connect()
+        .await
+        .unwrap_err();
+    match err {
+        WsClientError::InvalidProtocolHeader(proto) => assert_eq!(proto, "graphql-ws"),
+        err => panic!("unexpected error {:?}", err),
+    }
+
+    let err = awc::Client::new()
+        .ws(srv.url("/"))
+        .connect()
+        .await
+        .unwrap_err();
+    match err {
+        WsClientError::InvalidProtocolHeader(_) => (),
+        err => panic!("unexpected error {:?}", err),
+    }
+}
@@ -14,7 +14,7 @@ use futures::future::{FutureExt, LocalBoxFuture};
 
 use crate::app_service::{AppEntry, AppInit, AppRoutingFactory};
 use crate::config::ServiceConfig;
-use crate::data::{Data, DataFactory};
+use crate::data::{Data, DataFactory, LazyData};
 use crate::dev::ResourceDef;
 use crate::error::Error;
 use crate::resource::Resource;
@@ -137,6 +137,40 @@ where
         self
     }
 
+    /// Set lazily constructed application data.
+    ///
+    /// This function is similar to `.data()`, but data object is
+    /// constructed on the first `Data<T>` extraction instead of during
+    /// application initialization, so expensive state is never built
+    /// by workers which do not need it. Each worker constructs its own
+    /// instance at most once and reuses it for subsequent requests.
+    ///
+    /// Data is available via `Data<T>` extractor and
+    /// `Data::try_from_extensions()` only, `HttpRequest::app_data::<Data<T>>()`
+    /// does not see lazily constructed data.
+    ///
+    /// ```rust
+    /// use actix_web::{web, App, HttpResponse};
+    ///
+    /// struct Index(Vec<String>);
+    ///
+    /// async fn search(index: web::Data<Index>) -> HttpResponse {
+    ///     HttpResponse::Ok().body(format!("{} entries", index.0.len()))
+    /// }
+    ///
+    /// let app = App::new()
+    ///     .lazy_data_factory(|| Index(vec!["expensive".to_owned()]))
+    ///     .route("/search", web::get().to(search));
+    /// ```
+    pub fn lazy_data_factory<F, D>(mut self, f: F) -> Self
+    where
+        F: Fn() -> D + 'static,
+        D: 'static,
+    {
+        self.data.push(Box::new(LazyData::new(f)));
+        self
+    }
+
     /// Set application level arbitrary data item.
     ///
     /// Application data stored with `App::app_data()` method is available
@@ -551,6 +585,59 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
     }
 
+    #[actix_rt::test]
+    async fn test_lazy_data_factory() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        let counter = Rc::new(Cell::new(0));
+        let counter2 = counter.clone();
+        let mut srv = init_service(
+            App::new()
+                .lazy_data_factory(move || {
+                    counter2.set(counter2.get() + 1);
+                    10usize
+                })
+                .service(web::resource("/none").to(HttpResponse::Ok))
+                .service(web::resource("/").to(
+                    |req: HttpRequest, data: web::Data<usize>| {
+                        assert_eq!(**data, 10);
+                        assert!(req.app_data::<web::Data<usize>>().is_none());
+                        HttpResponse::Ok()
+                    },
+                )),
+        )
+        .await;
+        assert_eq!(counter.get(), 0);
+
+        let req = TestRequest::with_uri("/none").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(counter.get(), 0);
+
+        for _ in 0..3 {
+            let req = TestRequest::default().to_request();
+            let resp = srv.call(req).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+        }
+        assert_eq!(counter.get(), 1);
+
+        // eagerly set data takes precedence
+        let mut srv = init_service(
+            App::new()
+                .data(5usize)
+                .lazy_data_factory(|| 10usize)
+                .service(web::resource("/").to(|data: web::Data<usize>| {
+                    assert_eq!(**data, 5);
+                    HttpResponse::Ok()
+                })),
+        )
+        .await;
+        let req = TestRequest::default().to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
     #[actix_rt::test]
     async fn test_extension() {
         let mut srv = init_service(App::new().app_data(10usize).service(
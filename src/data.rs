@@ -1,7 +1,11 @@
+use std::any::type_name;
+use std::cell::RefCell;
+use std::fmt;
 use std::ops::Deref;
+use std::rc::Rc;
 use std::sync::Arc;
 
-use actix_http::error::{Error, ErrorInternalServerError};
+use actix_http::error::{Error, ResponseError};
 use actix_http::Extensions;
 use futures::future::{err, ok, Ready};
 
@@ -86,6 +90,20 @@ impl<T> Data<T> {
     }
 }
 
+impl<T: 'static> Data<T> {
+    /// Get app data of type `T` stored in `extensions`.
+    ///
+    /// Returns a clone of the shared `Arc` handle, lazily constructed
+    /// data is constructed on first access.
+    pub fn try_from_extensions(extensions: &Extensions) -> Option<Data<T>> {
+        if let Some(data) = extensions.get::<Data<T>>() {
+            Some(data.clone())
+        } else {
+            extensions.get::<LazyData<T>>().map(LazyData::get_or_init)
+        }
+    }
+}
+
 impl<T> Deref for Data<T> {
     type Target = Arc<T>;
 
@@ -109,15 +127,15 @@ impl<T: 'static> FromRequest for Data<T> {
     fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
         if let Some(st) = req.app_data::<Data<T>>() {
             ok(st.clone())
+        } else if let Some(lazy) = req.app_data::<LazyData<T>>() {
+            ok(lazy.get_or_init())
         } else {
             log::debug!(
                 "Failed to construct App-level Data extractor. \
                  Request path: {:?}",
                 req.path()
             );
-            err(ErrorInternalServerError(
-                "App data is not configured, to configure use App::data()",
-            ))
+            err(DataExtractionError::new::<T>().into())
         }
     }
 }
@@ -133,6 +151,84 @@ impl<T: 'static> DataFactory for Data<T> {
     }
 }
 
+/// Error returned by `Data<T>` extractor if data of type `T` is not configured.
+///
+/// Responds with `500 Internal Server Error`. Missing type could be
+/// inspected via `Error::as_error()`, i.e. by error logging middleware.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataExtractionError {
+    type_name: &'static str,
+}
+
+impl DataExtractionError {
+    fn new<T>() -> Self {
+        DataExtractionError {
+            type_name: type_name::<T>(),
+        }
+    }
+
+    /// Type name of the missing data
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+}
+
+impl fmt::Display for DataExtractionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "App data of type `{}` is not configured, to configure use App::data()",
+            self.type_name
+        )
+    }
+}
+
+/// `InternalServerError` for `DataExtractionError`
+impl ResponseError for DataExtractionError {}
+
+/// Application data which is constructed on first extraction.
+///
+/// Registered with `App::lazy_data_factory()`, each worker constructs its own
+/// instance. Construction is synchronous and happens at most once
+/// per worker. Stored in app data as is, so it is not visible as `Data<T>`
+/// via `HttpRequest::app_data()`.
+pub(crate) struct LazyData<T> {
+    init: Rc<dyn Fn() -> T>,
+    data: RefCell<Option<Data<T>>>,
+}
+
+impl<T> LazyData<T> {
+    pub(crate) fn new<F>(f: F) -> Self
+    where
+        F: Fn() -> T + 'static,
+    {
+        LazyData {
+            init: Rc::new(f),
+            data: RefCell::new(None),
+        }
+    }
+
+    /// Get data, construct it if it is not constructed yet.
+    fn get_or_init(&self) -> Data<T> {
+        let mut data = self.data.borrow_mut();
+        data.get_or_insert_with(|| Data::new((self.init)())).clone()
+    }
+}
+
+impl<T: 'static> DataFactory for LazyData<T> {
+    fn create(&self, extensions: &mut Extensions) -> bool {
+        if !extensions.contains::<Data<T>>() && !extensions.contains::<LazyData<T>>() {
+            extensions.insert(LazyData {
+                init: self.init.clone(),
+                data: RefCell::new(None),
+            });
+            true
+        } else {
+            false
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use actix_service::Service;
@@ -167,6 +263,44 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
     }
 
+    #[actix_rt::test]
+    async fn test_data_extraction_error() {
+        let (req, mut pl) = TestRequest::default().data(10u32).to_http_parts();
+        let e = Data::<usize>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        let cause = e.as_error::<DataExtractionError>().unwrap();
+        assert_eq!(cause.type_name(), "usize");
+        assert!(e.to_string().contains("`usize`"));
+        assert!(format!("{:?}", cause).contains("usize"));
+        assert_eq!(
+            e.as_response_error().error_response().status(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+
+    #[test]
+    fn test_data_try_from_extensions() {
+        use std::cell::Cell;
+
+        let mut extensions = Extensions::new();
+        assert!(Data::<Cell<u32>>::try_from_extensions(&extensions).is_none());
+
+        // `T` does not have to be `Send + Sync`
+        let data = Data::new(Cell::new(1u32));
+        assert!(data.create(&mut extensions));
+        let found = Data::<Cell<u32>>::try_from_extensions(&extensions).unwrap();
+        assert!(Arc::ptr_eq(&found, &data));
+        assert!(Data::<u32>::try_from_extensions(&extensions).is_none());
+
+        let lazy = LazyData::new(|| 5usize);
+        assert!(lazy.create(&mut extensions));
+        let first = Data::<usize>::try_from_extensions(&extensions).unwrap();
+        let second = Data::<usize>::try_from_extensions(&extensions).unwrap();
+        assert_eq!(**first, 5);
+        assert!(Arc::ptr_eq(&first, &second));
+    }
+
     #[actix_rt::test]
     async fn test_app_data_extractor() {
         let mut srv =
@@ -218,6 +352,37 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
     }
 
+    #[actix_rt::test]
+    async fn test_optional_route_data_extractor() {
+        let data = Data::new(10usize);
+        let ptr = data.get_ref() as *const usize;
+        let mut srv = init_service(
+            App::new()
+                .service(web::resource("/with").app_data(data).route(web::get().to(
+                    move |data: Option<web::Data<usize>>| {
+                        // data is shared, not cloned
+                        assert_eq!(data.unwrap().get_ref() as *const usize, ptr);
+                        HttpResponse::Ok()
+                    },
+                )))
+                .service(web::resource("/without").route(web::get().to(
+                    |data: Option<web::Data<usize>>| {
+                        assert!(data.is_none());
+                        HttpResponse::Ok()
+                    },
+                ))),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/with").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let req = TestRequest::with_uri("/without").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
     #[actix_rt::test]
     async fn test_override_data() {
         let mut srv = init_service(App::new().data(1usize).service(
@@ -1,10 +1,11 @@
 //! Error and Result module
+pub use crate::data::DataExtractionError;
 pub use actix_http::error::*;
 use derive_more::{Display, From};
 use serde_json::error::Error as JsonError;
 use url::ParseError as UrlParseError;
 
-use crate::http::StatusCode;
+use crate::http::{header, StatusCode};
 use crate::HttpResponse;
 
 /// Errors which can occur when attempting to generate resource uri.
@@ -66,43 +67,275 @@ impl ResponseError for UrlencodedError {
 #[derive(Debug, Display, From)]
 pub enum JsonPayloadError {
     /// Payload size is bigger than allowed. (default: 32kB)
-    #[display(fmt = "Json payload size is bigger than allowed")]
-    Overflow,
+    #[display(fmt = "Json payload {} exceeds limit {}", size, limit)]
+    Overflow { size: usize, limit: usize },
     /// Content type error
     #[display(fmt = "Content type error")]
     ContentType,
+    /// Payload is not encoded with required content encoding
+    #[display(fmt = "Json payload content encoding is not allowed")]
+    ContentEncoding,
+    /// Top-level json value is not an object
+    #[display(fmt = "Json payload is not an object")]
+    NotObject,
+    /// Json string value is longer than allowed
+    #[display(fmt = "Json string value is longer than allowed")]
+    StringTooLong,
+    /// Json object has more entries than allowed
+    #[display(fmt = "Json object has more than {} entries", limit)]
+    TooManyEntries { limit: usize },
     /// Deserialize error
     #[display(fmt = "Json deserialize error: {}", _0)]
     Deserialize(JsonError),
+    /// Deserialize error caused by a field, `path` is path of the field
+    #[display(fmt = "Json deserialize error at `{}`: {}", path, error)]
+    DeserializeField { path: String, error: JsonError },
+    /// Deserialize error of a newline delimited json record
+    #[display(fmt = "Json deserialize error at line {}: {}", line, error)]
+    DeserializeLine { line: usize, error: JsonError },
     /// Payload error
     #[display(fmt = "Error that occur during reading payload: {}", _0)]
     Payload(PayloadError),
 }
 
+impl JsonPayloadError {
+    /// Convert error to a map of field paths to error messages.
+    ///
+    /// Map is suitable as a body of `422 Unprocessable Entity` response for
+    /// form-style validation, i.e. in `JsonConfig::error_handler()`. If
+    /// `JsonConfig::track_field_paths()` is enabled, data error of a field is
+    /// reported under path of the field, i.e. `items[2].name`. Missing, unknown
+    /// and duplicate fields are reported under path of the enclosing object.
+    /// Any other error is reported under `"_"` key.
+    ///
+    /// ```rust
+    /// use actix_web::{error, web, HttpResponse};
+    ///
+    /// let config = web::JsonConfig::default()
+    ///     .track_field_paths(true)
+    ///     .error_handler(|err, _| {
+    ///         let body = err.field_errors();
+    ///         error::InternalError::from_response(
+    ///             err, HttpResponse::UnprocessableEntity().json(body)).into()
+    ///     });
+    /// ```
+    pub fn field_errors(&self) -> serde_json::Map<String, serde_json::Value> {
+        let mut map = serde_json::Map::new();
+        match *self {
+            JsonPayloadError::DeserializeField {
+                ref path,
+                ref error,
+            } => {
+                map.insert(path.clone(), strip_position(error).into());
+            }
+            JsonPayloadError::Deserialize(ref error) => {
+                map.insert("_".to_owned(), strip_position(error).into());
+            }
+            _ => {
+                map.insert("_".to_owned(), self.to_string().into());
+            }
+        }
+        map
+    }
+}
+
+/// Error message without position suffix
+fn strip_position(error: &JsonError) -> String {
+    let mut msg = error.to_string();
+    let pos = format!(" at line {} column {}", error.line(), error.column());
+    if msg.ends_with(&pos) {
+        msg.truncate(msg.len() - pos.len());
+    }
+    msg
+}
+
 /// Return `BadRequest` for `JsonPayloadError`
 impl ResponseError for JsonPayloadError {
     fn error_response(&self) -> HttpResponse {
         match *self {
-            JsonPayloadError::Overflow => {
+            JsonPayloadError::Overflow { .. } => {
                 HttpResponse::new(StatusCode::PAYLOAD_TOO_LARGE)
             }
+            JsonPayloadError::ContentEncoding => {
+                HttpResponse::new(StatusCode::UNSUPPORTED_MEDIA_TYPE)
+            }
             _ => HttpResponse::new(StatusCode::BAD_REQUEST),
         }
     }
 }
 
-/// A set of errors that can occur during parsing request paths
+/// A set of errors that can occur during parsing msgpack payloads
+#[cfg(feature = "msgpack")]
 #[derive(Debug, Display, From)]
+pub enum MsgPackPayloadError {
+    /// Payload size is bigger than allowed. (default: 32kB)
+    #[display(fmt = "MsgPack payload size is bigger than allowed")]
+    Overflow,
+    /// Content type error
+    #[display(fmt = "Content type error")]
+    ContentType,
+    /// Deserialize error
+    #[display(fmt = "MsgPack deserialize error: {}", _0)]
+    Deserialize(rmp_serde::decode::Error),
+    /// Payload error
+    #[display(fmt = "Error that occur during reading payload: {}", _0)]
+    Payload(PayloadError),
+}
+
+/// Return `BadRequest` for `MsgPackPayloadError`
+#[cfg(feature = "msgpack")]
+impl ResponseError for MsgPackPayloadError {
+    fn error_response(&self) -> HttpResponse {
+        match *self {
+            MsgPackPayloadError::Overflow => {
+                HttpResponse::new(StatusCode::PAYLOAD_TOO_LARGE)
+            }
+            _ => HttpResponse::new(StatusCode::BAD_REQUEST),
+        }
+    }
+}
+
+/// A set of errors that can occur during bearer token extraction
+#[derive(Debug, Display, PartialEq)]
+pub enum BearerError {
+    /// `Authorization` header is missing or uses another scheme
+    #[display(fmt = "Bearer token is missing")]
+    Missing,
+    /// Token is not valid `token68` syntax
+    #[display(fmt = "Bearer token is malformed")]
+    Malformed,
+    /// Token was rejected by the verifier
+    #[display(fmt = "Bearer token is invalid")]
+    Invalid,
+}
+
+impl BearerError {
+    /// Build `WWW-Authenticate` challenge value for this error
+    pub(crate) fn challenge(&self, realm: Option<&str>, scope: Option<&str>) -> String {
+        let mut params = Vec::new();
+        if let Some(realm) = realm {
+            params.push(format!("realm=\"{}\"", realm));
+        }
+        if let Some(scope) = scope {
+            params.push(format!("scope=\"{}\"", scope));
+        }
+        if *self != BearerError::Missing {
+            params.push("error=\"invalid_token\"".to_owned());
+        }
+
+        if params.is_empty() {
+            "Bearer".to_owned()
+        } else {
+            format!("Bearer {}", params.join(", "))
+        }
+    }
+}
+
+/// Return `Unauthorized` with `WWW-Authenticate` challenge for `BearerError`
+impl ResponseError for BearerError {
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::Unauthorized()
+            .header(header::WWW_AUTHENTICATE, self.challenge(None, None))
+            .finish()
+    }
+}
+
+/// A set of errors that can occur during cookie extraction
+#[derive(Debug, Display, PartialEq)]
+pub enum CookieError {
+    /// `Cookie` header is malformed
+    #[display(fmt = "Cookie header is malformed")]
+    Malformed,
+    /// Cookie is not sent with the request
+    #[display(fmt = "Cookie `{}` is missing", _0)]
+    Missing(String),
+    /// Cookie signature or encryption can not be verified
+    #[display(fmt = "Cookie `{}` is invalid", _0)]
+    Invalid(String),
+    /// Cookie value can not be parsed
+    #[display(fmt = "Cookie `{}` value can not be parsed", _0)]
+    Parse(String),
+    /// Cookie name and key are not configured
+    #[display(fmt = "Cookie extractor is not configured")]
+    NotConfigured,
+}
+
+/// Return `BadRequest` for `CookieError`, `InternalServerError` if not configured
+impl ResponseError for CookieError {
+    fn status_code(&self) -> StatusCode {
+        match *self {
+            CookieError::NotConfigured => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+/// A set of errors that can occur during request deadline extraction
+#[derive(Debug, Display, PartialEq)]
+pub enum DeadlineError {
+    /// Timeout or deadline header value is malformed
+    #[display(fmt = "Request deadline header is malformed")]
+    Malformed,
+}
+
+/// Return `BadRequest` for `DeadlineError`
+impl ResponseError for DeadlineError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+}
+
+/// A set of errors that can occur during client address extraction
+#[derive(Debug, Display, PartialEq)]
+pub enum ClientIpError {
+    /// `X-Forwarded-For` header contains malformed address
+    #[display(fmt = "X-Forwarded-For header is malformed")]
+    Malformed,
+    /// Peer address of the connection is unknown
+    #[display(fmt = "Client address is unknown")]
+    Unknown,
+}
+
+/// Return `BadRequest` for malformed header, `InternalServerError` otherwise
+impl ResponseError for ClientIpError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ClientIpError::Malformed => StatusCode::BAD_REQUEST,
+            ClientIpError::Unknown => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// A set of errors that can occur during parsing request paths
+#[derive(Debug, Display)]
 pub enum PathError {
+    /// Field of extracted type is not a segment of resource pattern
+    #[display(fmt = "Path segment `{}` is not defined by resource pattern", _0)]
+    NotFound(String),
+    /// Number of path segments does not match extracted type
+    #[display(fmt = "Wrong number of path segments: {} expected {}", found, expected)]
+    SegmentCount { expected: usize, found: usize },
     /// Deserialize error
     #[display(fmt = "Path deserialize error: {}", _0)]
     Deserialize(serde::de::value::Error),
 }
 
-/// Return `BadRequest` for `PathError`
+impl From<serde::de::value::Error> for PathError {
+    fn from(err: serde::de::value::Error) -> Self {
+        PathError::Deserialize(err)
+    }
+}
+
+/// Return `InternalServerError` if resource pattern does not match extracted
+/// type, `BadRequest` otherwise
 impl ResponseError for PathError {
     fn status_code(&self) -> StatusCode {
-        StatusCode::BAD_REQUEST
+        match self {
+            PathError::NotFound(_) | PathError::SegmentCount { .. } => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+            PathError::Deserialize(_) => StatusCode::BAD_REQUEST,
+        }
     }
 }
 
@@ -121,6 +354,38 @@ impl ResponseError for QueryPayloadError {
     }
 }
 
+/// Error returned by `RequireHeaders` extractor
+///
+/// Lists names of required headers missing from the request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MissingHeadersError {
+    headers: Vec<String>,
+}
+
+impl MissingHeadersError {
+    pub(crate) fn new(headers: Vec<String>) -> Self {
+        MissingHeadersError { headers }
+    }
+
+    /// Names of missing headers
+    pub fn headers(&self) -> &[String] {
+        &self.headers
+    }
+}
+
+impl std::fmt::Display for MissingHeadersError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Missing required headers: {}", self.headers.join(", "))
+    }
+}
+
+/// Return `BadRequest` for `MissingHeadersError`
+impl ResponseError for MissingHeadersError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+}
+
 /// Error type returned when reading body as lines.
 #[derive(From, Display, Debug)]
 pub enum ReadlinesError {
@@ -166,12 +431,74 @@ mod tests {
 
     #[test]
     fn test_json_payload_error() {
-        let resp: HttpResponse = JsonPayloadError::Overflow.error_response();
+        let resp: HttpResponse =
+            JsonPayloadError::Overflow { size: 0, limit: 0 }.error_response();
         assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
         let resp: HttpResponse = JsonPayloadError::ContentType.error_response();
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
     }
 
+    #[test]
+    fn test_bearer_error() {
+        let resp: HttpResponse = BearerError::Missing.error_response();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            resp.headers().get(header::WWW_AUTHENTICATE).unwrap(),
+            "Bearer"
+        );
+        let resp: HttpResponse = BearerError::Malformed.error_response();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            resp.headers().get(header::WWW_AUTHENTICATE).unwrap(),
+            "Bearer error=\"invalid_token\""
+        );
+        assert_eq!(
+            BearerError::Invalid.challenge(Some("api"), Some("read")),
+            "Bearer realm=\"api\", scope=\"read\", error=\"invalid_token\""
+        );
+    }
+
+    #[test]
+    fn test_json_payload_field_errors() {
+        #[derive(serde_derive::Deserialize, Debug)]
+        #[allow(dead_code)]
+        struct Form {
+            name: String,
+            age: u32,
+        }
+
+        let error = serde_json::from_str::<Form>("{\"age\": 10}").unwrap_err();
+        let map = JsonPayloadError::DeserializeField {
+            path: "user".to_owned(),
+            error,
+        }
+        .field_errors();
+        assert_eq!(
+            serde_json::Value::Object(map),
+            serde_json::json!({"user": "missing field `name`"})
+        );
+
+        let e = serde_json::from_str::<Form>("{\"name\": ").unwrap_err();
+        let map = JsonPayloadError::Deserialize(e).field_errors();
+        assert_eq!(map["_"], "EOF while parsing a value");
+
+        let map = JsonPayloadError::Overflow {
+            size: 512_000,
+            limit: 262_144,
+        }
+        .field_errors();
+        assert_eq!(map["_"], "Json payload 512000 exceeds limit 262144");
+    }
+
+    #[test]
+    fn test_path_error() {
+        let err = serde_urlencoded::from_str::<i32>("bad path").unwrap_err();
+        let resp: HttpResponse = PathError::from(err).error_response();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let resp: HttpResponse = PathError::NotFound("id".to_owned()).error_response();
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
     #[test]
     fn test_query_payload_error() {
         let resp: HttpResponse = QueryPayloadError::Deserialize(
@@ -26,6 +26,7 @@
 //! ```
 #![allow(non_snake_case)]
 use std::convert::TryFrom;
+use std::ops::{Bound, RangeBounds};
 
 use actix_http::http::{self, header, uri::Uri};
 use actix_http::RequestHead;
@@ -321,6 +322,72 @@ impl Guard for HostGuard {
     }
 }
 
+/// Return predicate that matches if request's declared `Content-Length`
+/// lies within the specified range.
+///
+/// Requests without valid `Content-Length` header do not match, this can be
+/// changed with `ContentLengthGuard::missing()`.
+///
+/// ```rust
+/// use actix_web::{web, guard, App, HttpResponse};
+///
+/// fn main() {
+///     App::new().service(
+///         web::resource("/upload")
+///             .route(web::post()
+///                 .guard(guard::ContentLength(..1024))
+///                 .to(|| HttpResponse::Ok()))
+///             .route(web::post()
+///                 .guard(guard::ContentLength(1024..))
+///                 .to(|| HttpResponse::Accepted()))
+///     );
+/// }
+/// ```
+pub fn ContentLength<R: RangeBounds<u64>>(range: R) -> ContentLengthGuard {
+    let start = match range.start_bound() {
+        Bound::Included(n) => Bound::Included(*n),
+        Bound::Excluded(n) => Bound::Excluded(*n),
+        Bound::Unbounded => Bound::Unbounded,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(n) => Bound::Included(*n),
+        Bound::Excluded(n) => Bound::Excluded(*n),
+        Bound::Unbounded => Bound::Unbounded,
+    };
+    ContentLengthGuard {
+        range: (start, end),
+        missing: false,
+    }
+}
+
+#[doc(hidden)]
+pub struct ContentLengthGuard {
+    range: (Bound<u64>, Bound<u64>),
+    missing: bool,
+}
+
+impl ContentLengthGuard {
+    /// Set whether requests without `Content-Length` header match
+    pub fn missing(mut self, matches: bool) -> ContentLengthGuard {
+        self.missing = matches;
+        self
+    }
+}
+
+impl Guard for ContentLengthGuard {
+    fn check(&self, req: &RequestHead) -> bool {
+        match req.headers.get(header::CONTENT_LENGTH) {
+            Some(val) => val
+                .to_str()
+                .ok()
+                .and_then(|s| s.trim().parse::<u64>().ok())
+                .map(|len| self.range.contains(&len))
+                .unwrap_or(false),
+            None => self.missing,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use actix_http::http::{header, Method};
@@ -480,6 +547,65 @@ mod tests {
         assert!(!Trace().check(req.head()));
     }
 
+    #[test]
+    fn test_content_length() {
+        let req =
+            TestRequest::with_header(header::CONTENT_LENGTH, "100").to_http_request();
+        assert!(ContentLength(100..).check(req.head()));
+        assert!(ContentLength(..=100).check(req.head()));
+        assert!(!ContentLength(..100).check(req.head()));
+        assert!(!ContentLength(0..10).check(req.head()));
+
+        let req = TestRequest::with_header(header::CONTENT_LENGTH, "invalid")
+            .to_http_request();
+        assert!(!ContentLength(..).check(req.head()));
+        assert!(!ContentLength(..).missing(true).check(req.head()));
+
+        let req = TestRequest::default().to_http_request();
+        assert!(!ContentLength(..).check(req.head()));
+        assert!(ContentLength(..10).missing(true).check(req.head()));
+    }
+
+    #[actix_rt::test]
+    async fn test_content_length_routing() {
+        use crate::{test, web, App, HttpResponse};
+
+        let mut srv = test::init_service(
+            App::new().service(
+                web::resource("/upload")
+                    .route(
+                        web::post()
+                            .guard(ContentLength(..1024).missing(true))
+                            .to(HttpResponse::Ok),
+                    )
+                    .route(
+                        web::post()
+                            .guard(ContentLength(1024..))
+                            .to(HttpResponse::Accepted),
+                    ),
+            ),
+        )
+        .await;
+
+        let req = TestRequest::post()
+            .uri("/upload")
+            .header(header::CONTENT_LENGTH, "10")
+            .to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let req = TestRequest::post()
+            .uri("/upload")
+            .header(header::CONTENT_LENGTH, "4096")
+            .to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), http::StatusCode::ACCEPTED);
+
+        let req = TestRequest::post().uri("/upload").to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+    }
+
     #[test]
     fn test_preds() {
         let r = TestRequest::default()
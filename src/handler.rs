@@ -3,8 +3,9 @@ use std::future::Future;
 use std::marker::PhantomData;
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::time::Instant;
 
-use actix_http::{Error, Response};
+use actix_http::{Error, Payload, Response, ServerTiming};
 use actix_service::{Service, ServiceFactory};
 use futures::future::{ok, Ready};
 use futures::ready;
@@ -12,7 +13,7 @@ use pin_project::pin_project;
 
 use crate::extract::FromRequest;
 use crate::request::HttpRequest;
-use crate::responder::Responder;
+use crate::responder::{Declined, Responder};
 use crate::service::{ServiceRequest, ServiceResponse};
 
 /// Async handler converter factory
@@ -149,6 +150,7 @@ where
 /// Extract arguments from request
 pub struct Extract<T: FromRequest, S> {
     service: S,
+    keep_payload: bool,
     _t: PhantomData<T>,
 }
 
@@ -156,6 +158,17 @@ impl<T: FromRequest, S> Extract<T, S> {
     pub fn new(service: S) -> Self {
         Extract {
             service,
+            keep_payload: false,
+            _t: PhantomData,
+        }
+    }
+
+    /// Keep payload until handler completes, so payload which is not
+    /// extracted can be passed to the next route if handler declines.
+    pub fn with_payload(service: S) -> Self {
+        Extract {
+            service,
+            keep_payload: true,
             _t: PhantomData,
         }
     }
@@ -180,6 +193,7 @@ where
     fn new_service(&self, _: ()) -> Self::Future {
         ok(ExtractService {
             _t: PhantomData,
+            keep_payload: self.keep_payload,
             service: self.service.clone(),
         })
     }
@@ -187,6 +201,7 @@ where
 
 pub struct ExtractService<T: FromRequest, S> {
     service: S,
+    keep_payload: bool,
     _t: PhantomData<T>,
 }
 
@@ -209,11 +224,21 @@ where
 
     fn call(&mut self, req: ServiceRequest) -> Self::Future {
         let (req, mut payload) = req.into_parts();
+        let timing = req
+            .extensions()
+            .get::<ServerTiming>()
+            .map(|timing| (timing.clone(), Instant::now()));
         let fut = T::from_request(&req, &mut payload);
 
         ExtractResponse {
             fut,
             req,
+            payload: if self.keep_payload {
+                Some(payload)
+            } else {
+                None
+            },
+            timing,
             fut_s: None,
             service: self.service.clone(),
         }
@@ -223,7 +248,9 @@ where
 #[pin_project]
 pub struct ExtractResponse<T: FromRequest, S: Service> {
     req: HttpRequest,
+    payload: Option<Payload>,
     service: S,
+    timing: Option<(ServerTiming, Instant)>,
     #[pin]
     fut: T::Future,
     #[pin]
@@ -244,7 +271,21 @@ where
         let this = self.as_mut().project();
 
         if let Some(fut) = this.fut_s.as_pin_mut() {
-            return fut.poll(cx).map_err(|_| panic!());
+            let mut res = match ready!(fut.poll(cx)) {
+                Ok(res) => res,
+                Err(e) => match e {},
+            };
+            if let Some((ref timing, start)) = this.timing {
+                timing.add("handler", start.elapsed());
+            }
+
+            // pass remaining payload to the next route
+            if let Some(declined) =
+                res.response_mut().extensions_mut().get_mut::<Declined>()
+            {
+                declined.0 = this.payload.take();
+            }
+            return Poll::Ready(Ok(res));
         }
 
         match ready!(this.fut.poll(cx)) {
@@ -253,6 +294,11 @@ where
                 Poll::Ready(Err((e.into(), req)))
             }
             Ok(item) => {
+                if let Some((ref timing, ref mut start)) = this.timing {
+                    let now = Instant::now();
+                    timing.add("extract", now - *start);
+                    *start = now;
+                }
                 let fut = Some(this.service.call((item, this.req.clone())));
                 self.as_mut().project().fut_s.set(fut);
                 self.poll(cx)
@@ -111,7 +111,7 @@ pub use crate::app::App;
 pub use crate::extract::FromRequest;
 pub use crate::request::HttpRequest;
 pub use crate::resource::Resource;
-pub use crate::responder::{Either, Responder};
+pub use crate::responder::{Either, Handled, Negotiate, Responder};
 pub use crate::route::Route;
 pub use crate::scope::Scope;
 pub use crate::server::HttpServer;
@@ -137,7 +137,9 @@ pub mod dev {
     };
 
     pub use crate::types::form::UrlEncoded;
-    pub use crate::types::json::JsonBody;
+    pub use crate::types::json::{JsonBody, JsonField, JsonLines};
+    #[cfg(feature = "msgpack")]
+    pub use crate::types::msgpack::MsgPackBody;
     pub use crate::types::readlines::Readlines;
 
     pub use actix_http::body::{Body, BodySize, MessageBody, ResponseBody, SizedStream};
@@ -145,7 +147,7 @@ pub mod dev {
     pub use actix_http::encoding::Decoder as Decompress;
     pub use actix_http::ResponseBuilder as HttpResponseBuilder;
     pub use actix_http::{
-        Extensions, Payload, PayloadStream, RequestHead, ResponseHead,
+        Extensions, Payload, PayloadStream, RequestHead, ResponseHead, ServerTiming,
     };
     pub use actix_router::{Path, ResourceDef, ResourcePath, Url};
     pub use actix_server::Server;
@@ -3,15 +3,17 @@ use std::cmp;
 use std::future::Future;
 use std::marker::PhantomData;
 use std::pin::Pin;
+use std::rc::Rc;
 use std::str::FromStr;
 use std::task::{Context, Poll};
 
-use actix_http::body::MessageBody;
+use actix_http::body::{BodySize, MessageBody};
 use actix_http::encoding::Encoder;
-use actix_http::http::header::{ContentEncoding, ACCEPT_ENCODING};
+use actix_http::http::header::{ContentEncoding, ACCEPT_ENCODING, CONTENT_TYPE};
 use actix_http::Error;
 use actix_service::{Service, Transform};
 use futures::future::{ok, Ready};
+use mime::Mime;
 use pin_project::pin_project;
 
 use crate::dev::BodyEncoding;
@@ -36,12 +38,83 @@ use crate::service::{ServiceRequest, ServiceResponse};
 ///         );
 /// }
 /// ```
-pub struct Compress(ContentEncoding);
+///
+/// Negotiation can be tuned by restricting the set of algorithms, skipping
+/// small bodies and limiting compression to specific content types.
+///
+/// ```rust
+/// use actix_web::{middleware, App};
+/// use actix_web::http::ContentEncoding;
+///
+/// fn main() {
+///     let app = App::new().wrap(
+///         middleware::Compress::default()
+///             .encodings(&[ContentEncoding::Br, ContentEncoding::Gzip])
+///             .min_size(1024)
+///             .content_type(mime::TEXT_STAR)
+///             .content_type(mime::APPLICATION_JSON),
+///     );
+/// }
+/// ```
+pub struct Compress {
+    inner: Rc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    encoding: ContentEncoding,
+    encodings: Vec<ContentEncoding>,
+    min_size: u64,
+    content_types: Vec<Mime>,
+}
 
 impl Compress {
     /// Create new `Compress` middleware with default encoding.
     pub fn new(encoding: ContentEncoding) -> Self {
-        Compress(encoding)
+        Compress {
+            inner: Rc::new(Inner {
+                encoding,
+                encodings: Vec::new(),
+                min_size: 0,
+                content_types: Vec::new(),
+            }),
+        }
+    }
+
+    /// Set algorithms that are allowed to be negotiated.
+    ///
+    /// Only applies to `ContentEncoding::Auto`, client preferences that are
+    /// not in this list are skipped. By default all algorithms are allowed.
+    pub fn encodings(mut self, encodings: &[ContentEncoding]) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .encodings = encodings.to_vec();
+        self
+    }
+
+    /// Set minimum response body size in bytes for compression.
+    ///
+    /// Responses with known body size smaller than this value are sent
+    /// uncompressed. Streaming responses are always compressed.
+    /// By default all responses are compressed.
+    pub fn min_size(mut self, size: u64) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .min_size = size;
+        self
+    }
+
+    /// Add content type to the list of compressible types.
+    ///
+    /// Once at least one content type is added, responses with other
+    /// content types (i.e. already compressed images or archives) are sent
+    /// uncompressed. Mime with `*` subtype matches any subtype.
+    pub fn content_type(mut self, mime: Mime) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .content_types
+            .push(mime);
+        self
     }
 }
 
@@ -51,6 +124,40 @@ impl Default for Compress {
     }
 }
 
+impl Inner {
+    /// Check if response with given content type and size can be compressed
+    fn compressible<B: MessageBody>(&self, resp: &ServiceResponse<B>) -> bool {
+        if self.min_size > 0 {
+            let size = match resp.response().body().size() {
+                BodySize::Sized(size) => Some(size as u64),
+                BodySize::Sized64(size) => Some(size),
+                _ => None,
+            };
+            if let Some(size) = size {
+                if size < self.min_size {
+                    return false;
+                }
+            }
+        }
+
+        if !self.content_types.is_empty() {
+            let mime = resp
+                .headers()
+                .get(&CONTENT_TYPE)
+                .and_then(|val| val.to_str().ok())
+                .and_then(|val| val.parse::<Mime>().ok());
+
+            if let Some(mime) = mime {
+                return self.content_types.iter().any(|ct| {
+                    ct.type_() == mime.type_()
+                        && (ct.subtype() == mime::STAR || ct.subtype() == mime.subtype())
+                });
+            }
+        }
+        true
+    }
+}
+
 impl<S, B> Transform<S> for Compress
 where
     B: MessageBody,
@@ -66,14 +173,14 @@ where
     fn new_transform(&self, service: S) -> Self::Future {
         ok(CompressMiddleware {
             service,
-            encoding: self.0,
+            inner: self.inner.clone(),
         })
     }
 }
 
 pub struct CompressMiddleware<S> {
     service: S,
-    encoding: ContentEncoding,
+    inner: Rc<Inner>,
 }
 
 impl<S, B> Service for CompressMiddleware<S>
@@ -94,7 +201,7 @@ where
         // negotiate content-encoding
         let encoding = if let Some(val) = req.headers().get(&ACCEPT_ENCODING) {
             if let Ok(enc) = val.to_str() {
-                AcceptEncoding::parse(enc, self.encoding)
+                AcceptEncoding::parse(enc, self.inner.encoding, &self.inner.encodings)
             } else {
                 ContentEncoding::Identity
             }
@@ -104,6 +211,7 @@ where
 
         CompressResponse {
             encoding,
+            inner: self.inner.clone(),
             fut: self.service.call(req),
             _t: PhantomData,
         }
@@ -120,6 +228,7 @@ where
     #[pin]
     fut: S::Future,
     encoding: ContentEncoding,
+    inner: Rc<Inner>,
     _t: PhantomData<B>,
 }
 
@@ -137,8 +246,10 @@ where
             Ok(resp) => {
                 let enc = if let Some(enc) = resp.response().get_encoding() {
                     enc
-                } else {
+                } else if this.inner.compressible(&resp) {
                     *this.encoding
+                } else {
+                    ContentEncoding::Identity
                 };
 
                 Poll::Ready(Ok(
@@ -200,7 +311,11 @@ impl AcceptEncoding {
     }
 
     /// Parse a raw Accept-Encoding header value into an ordered list.
-    pub fn parse(raw: &str, encoding: ContentEncoding) -> ContentEncoding {
+    pub fn parse(
+        raw: &str,
+        encoding: ContentEncoding,
+        allowed: &[ContentEncoding],
+    ) -> ContentEncoding {
         let mut encodings: Vec<_> = raw
             .replace(' ', "")
             .split(',')
@@ -211,7 +326,9 @@ impl AcceptEncoding {
         for enc in encodings {
             if let Some(enc) = enc {
                 if encoding == ContentEncoding::Auto {
-                    return enc.encoding;
+                    if allowed.is_empty() || allowed.contains(&enc.encoding) {
+                        return enc.encoding;
+                    }
                 } else if encoding == enc.encoding {
                     return encoding;
                 }
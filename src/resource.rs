@@ -5,20 +5,21 @@ use std::pin::Pin;
 use std::rc::Rc;
 use std::task::{Context, Poll};
 
-use actix_http::{Error, Extensions, Response};
+use actix_http::{Error, Extensions, Payload, Response};
 use actix_router::IntoPattern;
 use actix_service::boxed::{self, BoxService, BoxServiceFactory};
 use actix_service::{
     apply, apply_fn_factory, IntoServiceFactory, Service, ServiceFactory, Transform,
 };
 use futures::future::{ok, Either, LocalBoxFuture, Ready};
+use futures::ready;
 
 use crate::data::Data;
 use crate::dev::{insert_slash, AppService, HttpServiceFactory, ResourceDef};
 use crate::extract::FromRequest;
 use crate::guard::Guard;
 use crate::handler::Factory;
-use crate::responder::Responder;
+use crate::responder::{Declined, Responder};
 use crate::route::{CreateRouteService, Route, RouteService};
 use crate::service::{ServiceRequest, ServiceResponse};
 
@@ -503,7 +504,7 @@ impl Future for CreateResourceService {
                 })
                 .collect();
             Poll::Ready(Ok(ResourceService {
-                routes,
+                routes: Rc::new(RefCell::new(routes)),
                 data: self.data.clone(),
                 default: self.default.take(),
             }))
@@ -514,7 +515,7 @@ impl Future for CreateResourceService {
 }
 
 pub struct ResourceService {
-    routes: Vec<RouteService>,
+    routes: Rc<RefCell<Vec<RouteService>>>,
     data: Option<Rc<Extensions>>,
     default: Option<HttpService>,
 }
@@ -525,24 +526,24 @@ impl Service for ResourceService {
     type Error = Error;
     type Future = Either<
         Ready<Result<ServiceResponse, Error>>,
-        LocalBoxFuture<'static, Result<ServiceResponse, Error>>,
+        Either<
+            ResourceRouteResponse,
+            LocalBoxFuture<'static, Result<ServiceResponse, Error>>,
+        >,
     >;
 
     fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         Poll::Ready(Ok(()))
     }
 
-    fn call(&mut self, mut req: ServiceRequest) -> Self::Future {
-        for route in self.routes.iter_mut() {
-            if route.check(&mut req) {
-                if let Some(ref data) = self.data {
-                    req.set_data_container(data.clone());
-                }
-                return Either::Right(route.call(req));
-            }
-        }
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let req = match call_route(&self.routes, self.data.as_ref(), 0, req) {
+            Ok(fut) => return Either::Right(Either::Left(fut)),
+            Err(req) => req,
+        };
+
         if let Some(ref mut default) = self.default {
-            Either::Right(default.call(req))
+            Either::Right(Either::Right(default.call(req)))
         } else {
             let req = req.into_parts().0;
             Either::Left(ok(ServiceResponse::new(
@@ -553,6 +554,70 @@ impl Service for ResourceService {
     }
 }
 
+/// Call first route, starting from `start` index, which guards match request.
+fn call_route(
+    routes: &Rc<RefCell<Vec<RouteService>>>,
+    data: Option<&Rc<Extensions>>,
+    start: usize,
+    mut req: ServiceRequest,
+) -> Result<ResourceRouteResponse, ServiceRequest> {
+    for (idx, route) in routes.borrow_mut().iter_mut().enumerate().skip(start) {
+        if route.check(&mut req) {
+            if let Some(data) = data {
+                req.set_data_container(data.clone());
+            }
+            return Ok(ResourceRouteResponse {
+                fut: route.call(req),
+                idx,
+                routes: routes.clone(),
+            });
+        }
+    }
+    Err(req)
+}
+
+#[doc(hidden)]
+pub struct ResourceRouteResponse {
+    fut: LocalBoxFuture<'static, Result<ServiceResponse, Error>>,
+    idx: usize,
+    routes: Rc<RefCell<Vec<RouteService>>>,
+}
+
+impl Future for ResourceRouteResponse {
+    type Output = Result<ServiceResponse, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        loop {
+            let mut res = ready!(self.fut.as_mut().poll(cx))?;
+
+            // handler declined request, try next matching route
+            let declined = res.response_mut().extensions_mut().remove::<Declined>();
+            let payload = match declined {
+                Some(Declined(payload)) => payload.unwrap_or(Payload::None),
+                None => return Poll::Ready(Ok(res)),
+            };
+            let (req, res) = res.into_parts();
+            let req = match ServiceRequest::from_parts(req, payload) {
+                Ok(req) => req,
+                Err((req, _)) => return Poll::Ready(Ok(ServiceResponse::new(req, res))),
+            };
+
+            match call_route(&self.routes, None, self.idx + 1, req) {
+                Ok(next) => {
+                    self.fut = next.fut;
+                    self.idx = next.idx;
+                }
+                Err(req) => {
+                    return Poll::Ready(Ok(ServiceResponse::new(
+                        req.into_parts().0,
+                        res,
+                    )))
+                }
+            }
+        }
+    }
+}
+
 #[doc(hidden)]
 pub struct ResourceEndpoint {
     factory: Rc<RefCell<Option<ResourceFactory>>>,
@@ -589,8 +654,8 @@ mod tests {
     use crate::http::{header, HeaderValue, Method, StatusCode};
     use crate::middleware::DefaultHeaders;
     use crate::service::ServiceRequest;
-    use crate::test::{call_service, init_service, TestRequest};
-    use crate::{guard, web, App, Error, HttpResponse};
+    use crate::test::{call_service, init_service, read_body, TestRequest};
+    use crate::{guard, web, App, Error, Handled, HttpRequest, HttpResponse};
 
     #[actix_rt::test]
     async fn test_middleware() {
@@ -763,6 +828,81 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::NO_CONTENT);
     }
 
+    #[actix_rt::test]
+    async fn test_declined_route() {
+        let mut srv = init_service(
+            App::new().service(
+                web::resource("/test")
+                    .route(web::get().to(|| async { Handled::<HttpResponse>::Declined }))
+                    .route(web::get().to(|req: HttpRequest| async move {
+                        if req.headers().contains_key(header::ACCEPT) {
+                            Handled::Response(HttpResponse::Ok().body("accept"))
+                        } else {
+                            Handled::Declined
+                        }
+                    })),
+            ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/test")
+            .header(header::ACCEPT, "text/plain")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(read_body(resp).await, "accept");
+
+        // all matching routes declined
+        let req = TestRequest::with_uri("/test").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        // payload is passed to the next route
+        let mut srv = init_service(
+            App::new().service(
+                web::resource("/test")
+                    .route(
+                        web::post()
+                            .to_handled(|| async { Handled::<HttpResponse>::Declined }),
+                    )
+                    .route(
+                        web::post()
+                            .to(|body: String| HttpResponse::Created().body(body)),
+                    ),
+            ),
+        )
+        .await;
+        let req = TestRequest::with_uri("/test")
+            .method(Method::POST)
+            .set_payload("payload")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        assert_eq!(read_body(resp).await, "payload");
+
+        // payload of handler registered with `to()` is not kept
+        let mut srv = init_service(
+            App::new().service(
+                web::resource("/test")
+                    .route(
+                        web::post().to(|| async { Handled::<HttpResponse>::Declined }),
+                    )
+                    .route(
+                        web::post()
+                            .to(|body: String| HttpResponse::Created().body(body)),
+                    ),
+            ),
+        )
+        .await;
+        let req = TestRequest::with_uri("/test")
+            .method(Method::POST)
+            .set_payload("payload")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        assert_eq!(read_body(resp).await, "");
+    }
+
     #[actix_rt::test]
     async fn test_data() {
         let mut srv = init_service(
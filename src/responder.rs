@@ -1,3 +1,4 @@
+use std::cmp::Ordering;
 use std::convert::TryFrom;
 use std::future::Future;
 use std::marker::PhantomData;
@@ -5,12 +6,15 @@ use std::pin::Pin;
 use std::task::{Context, Poll};
 
 use actix_http::error::InternalError;
+use actix_http::http::header::{self, IntoHeaderValue};
 use actix_http::http::{
-    header::IntoHeaderValue, Error as HttpError, HeaderMap, HeaderName, StatusCode,
+    Error as HttpError, HeaderMap, HeaderName, HeaderValue, StatusCode,
 };
-use actix_http::{Error, Response, ResponseBuilder};
+use actix_http::{Error, HttpMessage, Payload, Response, ResponseBuilder};
 use bytes::{Bytes, BytesMut};
-use futures::future::{err, ok, Either as EitherFuture, Ready};
+use futures::future::{
+    err, ok, Either as EitherFuture, FutureExt, LocalBoxFuture, Ready,
+};
 use futures::ready;
 use pin_project::{pin_project, project};
 
@@ -103,6 +107,66 @@ where
     }
 }
 
+/// Handler result that allows to decline handling of a request.
+///
+/// When a handler returns `Handled::Declined`, the resource tries the next
+/// route whose guards match the request. Fallthrough is limited to routes of
+/// the same resource, other resources and scopes matching the request path
+/// are not tried. If no route is left, *404 Not Found* response is returned.
+///
+/// Request payload is passed to the next route only if the declining handler
+/// is registered with `Route::to_handled()` and did not extract the payload.
+///
+/// ```rust
+/// use actix_web::{web, App, HttpRequest, HttpResponse, Handled};
+///
+/// async fn html(req: HttpRequest) -> Handled<HttpResponse> {
+///     match req.headers().get("accept") {
+///         Some(accept) if accept == "text/html" => {
+///             Handled::Response(HttpResponse::Ok().body("<p>Hello</p>"))
+///         }
+///         _ => Handled::Declined,
+///     }
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/")
+///             .route(web::get().to(html))
+///             .route(web::get().to(|| HttpResponse::Ok().body("Hello"))),
+///     );
+/// }
+/// ```
+#[derive(Debug, PartialEq)]
+pub enum Handled<T> {
+    /// Request is handled, respond with the value
+    Response(T),
+    /// Request is not handled, try the next route
+    Declined,
+}
+
+/// Response extension that marks a declined request.
+pub(crate) struct Declined(pub(crate) Option<Payload>);
+
+impl<T> Responder for Handled<T>
+where
+    T: Responder,
+{
+    type Error = T::Error;
+    type Future = EitherFuture<T::Future, Ready<Result<Response, T::Error>>>;
+
+    fn respond_to(self, req: &HttpRequest) -> Self::Future {
+        match self {
+            Handled::Response(t) => EitherFuture::Left(t.respond_to(req)),
+            Handled::Declined => {
+                let mut res = Response::build(StatusCode::NOT_FOUND).finish();
+                res.extensions_mut().insert(Declined(None));
+                EitherFuture::Right(ok(res))
+            }
+        }
+    }
+}
+
 impl<T, E> Responder for Result<T, E>
 where
     T: Responder,
@@ -410,6 +474,122 @@ where
     }
 }
 
+type NegotiateVariant =
+    Box<dyn FnOnce(&HttpRequest) -> LocalBoxFuture<'static, Result<Response, Error>>>;
+
+/// Responder which selects response by the request's `Accept` header.
+///
+/// Each variant is registered with a media type, the variant with the best
+/// match is used and its response gets `Content-Type` header set to the
+/// variant's media type. Variants are tried in order of quality of the
+/// accepted media ranges, registration order breaks ties. If request has no
+/// valid `Accept` header, the first variant is used. If no variant is
+/// acceptable, *406 Not Acceptable* response is returned.
+///
+/// ```rust
+/// use actix_web::{web, App, Negotiate};
+/// use serde_json::json;
+///
+/// async fn index() -> Negotiate {
+///     Negotiate::new()
+///         .variant(mime::APPLICATION_JSON, web::Json(json!({"name": "test"})))
+///         .variant(mime::TEXT_HTML_UTF_8, "<p>test</p>")
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(web::resource("/").to(index));
+/// }
+/// ```
+pub struct Negotiate {
+    variants: Vec<(mime::Mime, NegotiateVariant)>,
+}
+
+impl Negotiate {
+    /// Create responder without variants
+    pub fn new() -> Self {
+        Negotiate {
+            variants: Vec::new(),
+        }
+    }
+
+    /// Add response variant for the media type
+    pub fn variant<R>(mut self, mime: mime::Mime, responder: R) -> Self
+    where
+        R: Responder + 'static,
+        R::Future: 'static,
+    {
+        self.variants.push((
+            mime,
+            Box::new(move |req| {
+                responder
+                    .respond_to(req)
+                    .map(|res| res.map_err(|e| e.into()))
+                    .boxed_local()
+            }),
+        ));
+        self
+    }
+
+    /// Index of the variant which is the best match for the `Accept` header
+    fn select(&self, req: &HttpRequest) -> Option<usize> {
+        let mut accept = match req.get_header::<header::Accept>() {
+            Some(accept) => accept.0,
+            None if self.variants.is_empty() => return None,
+            None => return Some(0),
+        };
+        accept.sort_by(|a, b| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+
+        accept
+            .iter()
+            .filter(|range| range.quality > header::q(0))
+            .find_map(|range| {
+                self.variants.iter().position(|(mime, _)| {
+                    range.item == mime::STAR_STAR
+                        || (range.item.type_() == mime.type_()
+                            && (range.item.subtype() == mime::STAR
+                                || range.item.subtype() == mime.subtype()))
+                })
+            })
+    }
+}
+
+impl Default for Negotiate {
+    fn default() -> Self {
+        Negotiate::new()
+    }
+}
+
+impl Responder for Negotiate {
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Response, Error>>;
+
+    fn respond_to(mut self, req: &HttpRequest) -> Self::Future {
+        let idx = match self.select(req) {
+            Some(idx) => idx,
+            None => {
+                let res = Response::build(StatusCode::NOT_ACCEPTABLE)
+                    .header(header::VARY, "accept")
+                    .finish();
+                return ok(res).boxed_local();
+            }
+        };
+
+        let (mime, variant) = self.variants.swap_remove(idx);
+        variant(req)
+            .map(move |res| {
+                res.map(|mut res| {
+                    if let Ok(value) = HeaderValue::from_str(mime.as_ref()) {
+                        res.headers_mut().insert(header::CONTENT_TYPE, value);
+                    }
+                    res.headers_mut()
+                        .append(header::VARY, HeaderValue::from_static("accept"));
+                    res
+                })
+            })
+            .boxed_local()
+    }
+}
+
 impl<T> Responder for InternalError<T>
 where
     T: std::fmt::Debug + std::fmt::Display + 'static,
@@ -653,4 +833,54 @@ pub(crate) mod tests {
             HeaderValue::from_static("json")
         );
     }
+
+    #[actix_rt::test]
+    async fn test_negotiate_responder() {
+        let mut srv =
+            init_service(App::new().service(web::resource("/").to(|| async {
+                Negotiate::new()
+                    .variant(
+                        mime::APPLICATION_JSON,
+                        web::Json(serde_json::json!({"name": "test"})),
+                    )
+                    .variant(mime::TEXT_HTML_UTF_8, "<p>test</p>")
+            })))
+            .await;
+
+        let req = TestRequest::default()
+            .header("accept", "application/json")
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(CONTENT_TYPE).unwrap(),
+            HeaderValue::from_static("application/json")
+        );
+        assert_eq!(resp.headers().get("vary").unwrap(), "accept");
+        assert_eq!(resp.response().body().bin_ref(), b"{\"name\":\"test\"}");
+
+        let req = TestRequest::default()
+            .header("accept", "application/json;q=0.9, text/*")
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(
+            resp.headers().get(CONTENT_TYPE).unwrap(),
+            HeaderValue::from_static("text/html; charset=utf-8")
+        );
+        assert_eq!(resp.response().body().bin_ref(), b"<p>test</p>");
+
+        // no accept header, first variant is used
+        let req = TestRequest::default().to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(
+            resp.headers().get(CONTENT_TYPE).unwrap(),
+            HeaderValue::from_static("application/json")
+        );
+
+        let req = TestRequest::default()
+            .header("accept", "image/png, text/html;q=0")
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_ACCEPTABLE);
+    }
 }
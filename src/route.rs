@@ -5,12 +5,12 @@ use std::task::{Context, Poll};
 
 use actix_http::{http::Method, Error};
 use actix_service::{Service, ServiceFactory};
-use futures::future::{ready, FutureExt, LocalBoxFuture};
+use futures::future::{ok, ready, FutureExt, LocalBoxFuture};
 
 use crate::extract::FromRequest;
 use crate::guard::{self, Guard};
 use crate::handler::{Extract, Factory, Handler};
-use crate::responder::Responder;
+use crate::responder::{Handled, Responder};
 use crate::service::{ServiceRequest, ServiceResponse};
 use crate::HttpResponse;
 
@@ -223,6 +223,24 @@ impl Route {
     ///     );
     /// }
     /// ```
+    ///
+    /// Handler does not have to be an `async fn`. A plain function that
+    /// returns a boxed future of a responder is registered the same way.
+    ///
+    /// ```rust
+    /// use actix_web::{web, App, Error, HttpResponse};
+    /// use futures::future::{FutureExt, LocalBoxFuture};
+    ///
+    /// fn index(path: web::Path<String>) -> LocalBoxFuture<'static, Result<HttpResponse, Error>> {
+    ///     async move { Ok(HttpResponse::Ok().body(path.into_inner())) }.boxed_local()
+    /// }
+    ///
+    /// fn main() {
+    ///     let app = App::new().service(
+    ///         web::resource("/{name}").route(web::get().to(index))
+    ///     );
+    /// }
+    /// ```
     pub fn to<F, T, R, U>(mut self, handler: F) -> Self
     where
         F: Factory<T, R, U>,
@@ -234,6 +252,75 @@ impl Route {
             Box::new(RouteNewService::new(Extract::new(Handler::new(handler))));
         self
     }
+
+    /// Set handler function which may decline the request.
+    ///
+    /// Same as `to()`, but payload which is not extracted by the handler is
+    /// kept until the handler completes. If handler returns
+    /// `Handled::Declined`, the payload is passed to the next route of the
+    /// resource. Handlers registered with `to()` may decline as well, the
+    /// next route receives empty payload then.
+    ///
+    /// ```rust
+    /// use actix_web::{web, App, HttpRequest, HttpResponse, Handled};
+    ///
+    /// async fn preview(req: HttpRequest) -> Handled<HttpResponse> {
+    ///     match req.query_string() {
+    ///         "preview" => Handled::Response(HttpResponse::Ok().finish()),
+    ///         _ => Handled::Declined,
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let app = App::new().service(
+    ///         web::resource("/upload")
+    ///             .route(web::post().to_handled(preview))
+    ///             .route(web::post().to(|body: String| HttpResponse::Ok().body(body))),
+    ///     );
+    /// }
+    /// ```
+    pub fn to_handled<F, T, R, U>(mut self, handler: F) -> Self
+    where
+        F: Factory<T, R, Handled<U>>,
+        T: FromRequest + 'static,
+        R: Future<Output = Handled<U>> + 'static,
+        U: Responder + 'static,
+    {
+        self.service = Box::new(RouteNewService::new(Extract::with_payload(
+            Handler::new(handler),
+        )));
+        self
+    }
+
+    /// Set raw service function, which receives `ServiceRequest` as is.
+    ///
+    /// Extractors are not used, function is responsible for building
+    /// response from the request. Returned error is propagated to the
+    /// outer services, use `ServiceRequest::error_response()` to respond
+    /// with an error from within the route.
+    ///
+    /// ```rust
+    /// use actix_web::{dev, web, App, Error, HttpResponse};
+    ///
+    /// async fn index(req: dev::ServiceRequest) -> Result<dev::ServiceResponse, Error> {
+    ///     let name = req.match_info().get("name").unwrap_or("world").to_owned();
+    ///     Ok(req.into_response(HttpResponse::Ok().body(format!("Hello {}!", name))))
+    /// }
+    ///
+    /// fn main() {
+    ///     let app = App::new().service(
+    ///         web::resource("/{name}").route(web::get().to_service(index))
+    ///     );
+    /// }
+    /// ```
+    pub fn to_service<F, R>(mut self, service: F) -> Self
+    where
+        F: Fn(ServiceRequest) -> R + Clone + 'static,
+        R: Future<Output = Result<ServiceResponse, Error>> + 'static,
+    {
+        self.service = Box::new(RouteFnNewService { service });
+        self
+    }
 }
 
 struct RouteNewService<T>
@@ -338,17 +425,67 @@ where
     }
 }
 
+struct RouteFnNewService<F> {
+    service: F,
+}
+
+impl<F, R> ServiceFactory for RouteFnNewService<F>
+where
+    F: Fn(ServiceRequest) -> R + Clone + 'static,
+    R: Future<Output = Result<ServiceResponse, Error>> + 'static,
+{
+    type Config = ();
+    type Request = ServiceRequest;
+    type Response = ServiceResponse;
+    type Error = Error;
+    type InitError = ();
+    type Service = BoxedRouteService<ServiceRequest, Self::Response>;
+    type Future = LocalBoxFuture<'static, Result<Self::Service, Self::InitError>>;
+
+    fn new_service(&self, _: ()) -> Self::Future {
+        let service: BoxedRouteService<_, _> = Box::new(RouteFnService {
+            service: self.service.clone(),
+        });
+        ok(service).boxed_local()
+    }
+}
+
+struct RouteFnService<F> {
+    service: F,
+}
+
+impl<F, R> Service for RouteFnService<F>
+where
+    F: Fn(ServiceRequest) -> R + 'static,
+    R: Future<Output = Result<ServiceResponse, Error>> + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        (self.service)(req).boxed_local()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::time::Duration;
 
     use actix_rt::time::delay_for;
     use bytes::Bytes;
+    use futures::future::{FutureExt, LocalBoxFuture};
     use serde_derive::Serialize;
 
+    use crate::dev::{ServiceRequest, ServiceResponse};
     use crate::http::{Method, StatusCode};
     use crate::test::{call_service, init_service, read_body, TestRequest};
-    use crate::{error, web, App, HttpResponse};
+    use crate::{error, web, App, Error, HttpMessage, HttpResponse};
 
     #[derive(Serialize, PartialEq, Debug)]
     struct MyObject {
@@ -428,4 +565,103 @@ mod tests {
         let body = read_body(resp).await;
         assert_eq!(body, Bytes::from_static(b"{\"name\":\"test\"}"));
     }
+
+    #[actix_rt::test]
+    async fn test_route_boxed_future() {
+        fn index(
+            path: web::Path<String>,
+        ) -> LocalBoxFuture<'static, Result<HttpResponse, Error>> {
+            async move {
+                delay_for(Duration::from_millis(25)).await;
+                Ok(HttpResponse::Ok().body(path.into_inner()))
+            }
+            .boxed_local()
+        }
+
+        fn json() -> LocalBoxFuture<'static, web::Json<MyObject>> {
+            futures::future::ready(web::Json(MyObject {
+                name: "test".to_string(),
+            }))
+            .boxed_local()
+        }
+
+        let mut srv = init_service(
+            App::new()
+                .service(web::resource("/json").route(web::get().to(json)))
+                .service(web::resource("/{name}").route(web::get().to(index))),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/hello").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = read_body(resp).await;
+        assert_eq!(body, Bytes::from_static(b"hello"));
+
+        let req = TestRequest::with_uri("/json").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = read_body(resp).await;
+        assert_eq!(body, Bytes::from_static(b"{\"name\":\"test\"}"));
+    }
+
+    #[actix_rt::test]
+    async fn test_route_server_timing() {
+        use crate::dev::ServerTiming;
+
+        let mut srv = init_service(App::new().service(web::resource("/{name}").route(
+            web::get().to(|_: web::Path<String>| {
+                async {
+                    delay_for(Duration::from_millis(25)).await;
+                    "ok"
+                }
+            }),
+        )))
+        .await;
+
+        let timing = ServerTiming::default();
+        let req = TestRequest::with_uri("/test").to_request();
+        req.extensions_mut().insert(timing.clone());
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(timing.get("extract").is_some());
+        assert!(timing.get("handler").unwrap() >= Duration::from_millis(25));
+    }
+
+    #[actix_rt::test]
+    async fn test_route_to_service() {
+        async fn service(req: ServiceRequest) -> Result<ServiceResponse, Error> {
+            if req.content_type() != "text/plain" {
+                let err = error::ErrorBadRequest("unsupported content type");
+                return Ok(req.error_response(err));
+            }
+            req.extensions_mut().insert(10usize);
+            let name = req.match_info().get("name").unwrap().to_owned();
+            let count = *req.extensions().get::<usize>().unwrap();
+            Ok(
+                req.into_response(
+                    HttpResponse::Ok().body(format!("{}:{}", name, count)),
+                ),
+            )
+        }
+
+        let mut srv =
+            init_service(App::new().service(
+                web::resource("/{name}").route(web::post().to_service(service)),
+            ))
+            .await;
+
+        let req = TestRequest::post()
+            .uri("/test")
+            .header("content-type", "text/plain")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = read_body(resp).await;
+        assert_eq!(body, Bytes::from_static(b"test:10"));
+
+        let req = TestRequest::post().uri("/test").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
 }
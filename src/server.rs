@@ -2,6 +2,7 @@ use std::marker::PhantomData;
 use std::sync::{Arc, Mutex};
 use std::{fmt, io, net};
 
+use actix_http::h2::GracefulShutdown;
 use actix_http::{body::MessageBody, Error, HttpService, KeepAlive, Request, Response};
 use actix_server::{Server, ServerBuilder};
 use actix_service::{map_config, IntoServiceFactory, Service, ServiceFactory};
@@ -32,6 +33,8 @@ struct Config {
     keep_alive: KeepAlive,
     client_timeout: u64,
     client_shutdown: u64,
+    graceful_shutdown: GracefulShutdown,
+    graceful_shutdown_timeout: u64,
 }
 
 /// An HTTP Server.
@@ -89,6 +92,8 @@ where
                 keep_alive: KeepAlive::Timeout(5),
                 client_timeout: 5000,
                 client_shutdown: 5000,
+                graceful_shutdown: GracefulShutdown::new(),
+                graceful_shutdown_timeout: 0,
             })),
             backlog: 1024,
             sockets: Vec::new(),
@@ -169,7 +174,7 @@ where
     /// Set server connection shutdown timeout in milliseconds.
     ///
     /// Defines a timeout for shutdown connection. If a shutdown procedure does not complete
-    /// within this time, the request is dropped.
+    /// within this time, the request is dropped. This timeout affects secure connections.
     ///
     /// To disable timeout set value to 0.
     ///
@@ -179,6 +184,32 @@ where
         self
     }
 
+    /// Set handle to gracefully shut down connections.
+    ///
+    /// Once `GracefulShutdown::shutdown()` is called, http/2 connections send
+    /// `GOAWAY` frame and close after in-flight streams complete, http/1
+    /// connections stop keep-alive.
+    ///
+    /// By default connections are not shut down gracefully.
+    pub fn graceful_shutdown(self, handle: GracefulShutdown) -> Self {
+        self.config.lock().unwrap().graceful_shutdown = handle;
+        self
+    }
+
+    /// Set graceful shutdown timeout of connections in milliseconds.
+    ///
+    /// Connection which is still open when the timeout expires after graceful
+    /// shutdown is initiated via `graceful_shutdown()` handle is dropped.
+    /// Unlike `client_shutdown()`, it does not apply to tls shutdown.
+    ///
+    /// To disable timeout set value to 0.
+    ///
+    /// By default graceful shutdown timeout is disabled.
+    pub fn graceful_shutdown_timeout(self, val: u64) -> Self {
+        self.config.lock().unwrap().graceful_shutdown_timeout = val;
+        self
+    }
+
     /// Set server host name.
     ///
     /// Host name is used by application router as a hostname for url generation.
@@ -257,6 +288,8 @@ where
                 HttpService::build()
                     .keep_alive(c.keep_alive)
                     .client_timeout(c.client_timeout)
+                    .client_shutdown(c.graceful_shutdown_timeout)
+                    .graceful_shutdown(c.graceful_shutdown.clone())
                     .local_addr(addr)
                     .finish(map_config(factory(), move |_| cfg.clone()))
                     .tcp()
@@ -304,6 +337,8 @@ where
                 HttpService::build()
                     .keep_alive(c.keep_alive)
                     .client_timeout(c.client_timeout)
+                    .client_shutdown(c.graceful_shutdown_timeout)
+                    .graceful_shutdown(c.graceful_shutdown.clone())
                     .client_disconnect(c.client_shutdown)
                     .finish(map_config(factory(), move |_| cfg.clone()))
                     .openssl(acceptor.clone())
@@ -351,6 +386,8 @@ where
                 HttpService::build()
                     .keep_alive(c.keep_alive)
                     .client_timeout(c.client_timeout)
+                    .client_shutdown(c.graceful_shutdown_timeout)
+                    .graceful_shutdown(c.graceful_shutdown.clone())
                     .client_disconnect(c.client_shutdown)
                     .finish(map_config(factory(), move |_| cfg.clone()))
                     .rustls(config.clone())
@@ -475,6 +512,8 @@ where
                 HttpService::build()
                     .keep_alive(c.keep_alive)
                     .client_timeout(c.client_timeout)
+                    .client_shutdown(c.graceful_shutdown_timeout)
+                    .graceful_shutdown(c.graceful_shutdown.clone())
                     .finish(map_config(factory(), move |_| config.clone())),
             )
         })?;
@@ -517,6 +556,8 @@ where
                         HttpService::build()
                             .keep_alive(c.keep_alive)
                             .client_timeout(c.client_timeout)
+                            .client_shutdown(c.graceful_shutdown_timeout)
+                            .graceful_shutdown(c.graceful_shutdown.clone())
                             .finish(map_config(factory(), move |_| config.clone())),
                     )
             },
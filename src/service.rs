@@ -331,6 +331,12 @@ impl<B> ServiceResponse<B> {
         &self.request
     }
 
+    /// Deconstruct to the original request and the response
+    #[inline]
+    pub fn into_parts(self) -> (HttpRequest, Response<B>) {
+        (self.request, self.response)
+    }
+
     /// Get reference to response
     #[inline]
     pub fn response(&self) -> &Response<B> {
@@ -343,6 +349,13 @@ impl<B> ServiceResponse<B> {
         &mut self.response
     }
 
+    /// Force close connection after this response is sent, even if it is
+    /// marked as keep-alive
+    #[inline]
+    pub fn force_close(&mut self) {
+        self.response.force_close()
+    }
+
     /// Get the response status code
     #[inline]
     pub fn status(&self) -> StatusCode {
@@ -0,0 +1,352 @@
+//! Bearer token extractor
+
+use std::fmt;
+use std::sync::Arc;
+
+use actix_http::error::{Error, InternalError};
+use actix_http::http::header::{AUTHORIZATION, WWW_AUTHENTICATE};
+use futures::future::{err, ok, Ready};
+
+use crate::dev::Payload;
+use crate::error::BearerError;
+use crate::extract::FromRequest;
+use crate::request::HttpRequest;
+use crate::HttpResponse;
+
+/// Extract bearer token from the request's `Authorization` header.
+///
+/// Header must use the `Bearer` scheme (case-insensitive) followed by a
+/// token in `token68` syntax, as described in RFC 6750. Extraction fails
+/// with `401 Unauthorized` and a `WWW-Authenticate` challenge otherwise.
+///
+/// With the `bearer-verify` feature enabled, a verifier can be registered
+/// with [**BearerConfig**](struct.BearerConfig.html) to validate the token
+/// and resolve it to claims.
+///
+/// ## Example
+///
+/// ```rust
+/// use actix_web::{web, App};
+///
+/// async fn index(auth: web::Bearer) -> String {
+///     format!("Token: {}", auth.token())
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///        web::resource("/index.html").route(web::get().to(index)));
+/// }
+/// ```
+#[derive(Clone, PartialEq)]
+pub struct Bearer {
+    token: String,
+    #[cfg(feature = "bearer-verify")]
+    claims: Option<serde_json::Value>,
+}
+
+impl Bearer {
+    /// Parse bearer token from `Authorization` header value
+    pub fn parse(value: &str) -> Result<Self, BearerError> {
+        let value = value.trim();
+        let idx = value.find(' ').unwrap_or(value.len());
+        if !value[..idx].eq_ignore_ascii_case("bearer") {
+            return Err(BearerError::Missing);
+        }
+
+        let token = value[idx..].trim_start();
+        if !is_token68(token) {
+            return Err(BearerError::Malformed);
+        }
+
+        Ok(Bearer {
+            token: token.to_owned(),
+            #[cfg(feature = "bearer-verify")]
+            claims: None,
+        })
+    }
+
+    /// Get token value
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// Deconstruct to a token value
+    pub fn into_token(self) -> String {
+        self.token
+    }
+
+    /// Get claims produced by the configured verifier
+    #[cfg(feature = "bearer-verify")]
+    pub fn claims(&self) -> Option<&serde_json::Value> {
+        self.claims.as_ref()
+    }
+}
+
+impl fmt::Debug for Bearer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Bearer").field("token", &"******").finish()
+    }
+}
+
+/// Check `token68` syntax from RFC 7235
+fn is_token68(token: &str) -> bool {
+    let body = token.trim_end_matches('=');
+    !body.is_empty()
+        && body
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"-._~+/".contains(&b))
+}
+
+impl FromRequest for Bearer {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+    type Config = BearerConfig;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let config = BearerConfig::from_req(req);
+
+        let res = match req.headers().get(AUTHORIZATION) {
+            Some(value) => value
+                .to_str()
+                .map_err(|_| BearerError::Malformed)
+                .and_then(Bearer::parse),
+            None => Err(BearerError::Missing),
+        };
+
+        #[cfg(feature = "bearer-verify")]
+        let res = res.and_then(|mut bearer| {
+            if let Some(ref verifier) = config.verifier {
+                bearer.claims = Some((verifier)(&bearer.token)?);
+            }
+            Ok(bearer)
+        });
+
+        match res {
+            Ok(bearer) => ok(bearer),
+            Err(e) => {
+                log::debug!(
+                    "Failed during Bearer extractor: {}. Request path: {:?}",
+                    e,
+                    req.path()
+                );
+
+                if let Some(ref error_handler) = config.ehandler {
+                    err((error_handler)(e, req))
+                } else {
+                    let challenge =
+                        e.challenge(config.realm.as_deref(), config.scope.as_deref());
+                    let resp = HttpResponse::Unauthorized()
+                        .header(WWW_AUTHENTICATE, challenge)
+                        .finish();
+                    err(InternalError::from_response(e, resp).into())
+                }
+            }
+        }
+    }
+}
+
+/// Bearer extractor configuration
+///
+/// ## Example
+///
+/// ```rust
+/// use actix_web::{web, App};
+///
+/// async fn index(auth: web::Bearer) -> String {
+///     format!("Token: {}", auth.token())
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/index.html")
+///             // change bearer extractor configuration
+///             .app_data(web::BearerConfig::default().realm("api").scope("read"))
+///             .route(web::get().to(index))
+///     );
+/// }
+/// ```
+#[derive(Clone)]
+pub struct BearerConfig {
+    realm: Option<String>,
+    scope: Option<String>,
+    ehandler: Option<Arc<dyn Fn(BearerError, &HttpRequest) -> Error + Send + Sync>>,
+    #[cfg(feature = "bearer-verify")]
+    verifier: Option<
+        Arc<dyn Fn(&str) -> Result<serde_json::Value, BearerError> + Send + Sync>,
+    >,
+}
+
+impl BearerConfig {
+    /// Set realm reported in `WWW-Authenticate` challenge
+    pub fn realm(mut self, realm: &str) -> Self {
+        self.realm = Some(realm.to_owned());
+        self
+    }
+
+    /// Set scope reported in `WWW-Authenticate` challenge
+    pub fn scope(mut self, scope: &str) -> Self {
+        self.scope = Some(scope.to_owned());
+        self
+    }
+
+    /// Set custom error handler
+    pub fn error_handler<F>(mut self, f: F) -> Self
+    where
+        F: Fn(BearerError, &HttpRequest) -> Error + Send + Sync + 'static,
+    {
+        self.ehandler = Some(Arc::new(f));
+        self
+    }
+
+    /// Set token verifier
+    ///
+    /// Verifier checks token signature and resolves it to claims, these
+    /// are available via `Bearer::claims()`. Returned error is reported
+    /// to the client, usually it is `BearerError::Invalid`.
+    #[cfg(feature = "bearer-verify")]
+    pub fn verifier<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&str) -> Result<serde_json::Value, BearerError> + Send + Sync + 'static,
+    {
+        self.verifier = Some(Arc::new(f));
+        self
+    }
+
+    /// Extract bearer config from app data, fall back to the default config.
+    fn from_req(req: &HttpRequest) -> &Self {
+        req.app_data::<Self>().unwrap_or(&DEFAULT_CONFIG)
+    }
+}
+
+/// Allow shared refs to default.
+const DEFAULT_CONFIG: BearerConfig = BearerConfig {
+    realm: None,
+    scope: None,
+    ehandler: None,
+    #[cfg(feature = "bearer-verify")]
+    verifier: None,
+};
+
+impl Default for BearerConfig {
+    fn default() -> Self {
+        DEFAULT_CONFIG.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_http::http::{header, StatusCode};
+
+    use super::*;
+    use crate::test::TestRequest;
+
+    fn status_and_challenge(e: Error) -> (StatusCode, String) {
+        let resp = e.as_response_error().error_response();
+        let challenge = resp
+            .headers()
+            .get(header::WWW_AUTHENTICATE)
+            .map(|v| v.to_str().unwrap().to_owned())
+            .unwrap_or_default();
+        (resp.status(), challenge)
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            Bearer::parse("Bearer abc.DEF-_~+/==").unwrap().token(),
+            "abc.DEF-_~+/=="
+        );
+        assert_eq!(Bearer::parse("bearer   abc").unwrap().token(), "abc");
+        assert_eq!(Bearer::parse("Basic abc"), Err(BearerError::Missing));
+        assert_eq!(Bearer::parse("Bearerabc"), Err(BearerError::Missing));
+        assert_eq!(Bearer::parse("Bearer"), Err(BearerError::Malformed));
+        assert_eq!(Bearer::parse("Bearer ==="), Err(BearerError::Malformed));
+        assert_eq!(Bearer::parse("Bearer a=b"), Err(BearerError::Malformed));
+        assert_eq!(Bearer::parse("Bearer a b"), Err(BearerError::Malformed));
+    }
+
+    #[actix_rt::test]
+    async fn test_missing_header() {
+        let (req, mut pl) = TestRequest::default().to_http_parts();
+        let e = Bearer::from_request(&req, &mut pl).await.unwrap_err();
+        assert_eq!(
+            status_and_challenge(e),
+            (StatusCode::UNAUTHORIZED, "Bearer".to_owned())
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_malformed_token() {
+        let (req, mut pl) = TestRequest::default()
+            .header(header::AUTHORIZATION, "Bearer not a token")
+            .app_data(BearerConfig::default().realm("api"))
+            .to_http_parts();
+        let e = Bearer::from_request(&req, &mut pl).await.unwrap_err();
+        assert_eq!(
+            status_and_challenge(e),
+            (
+                StatusCode::UNAUTHORIZED,
+                "Bearer realm=\"api\", error=\"invalid_token\"".to_owned()
+            )
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_valid_token() {
+        let (req, mut pl) = TestRequest::default()
+            .header(header::AUTHORIZATION, "Bearer mF_9.B5f-4.1JqM")
+            .to_http_parts();
+        let bearer = Bearer::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(bearer.token(), "mF_9.B5f-4.1JqM");
+        assert_eq!(format!("{:?}", bearer), "Bearer { token: \"******\" }");
+        assert_eq!(bearer.into_token(), "mF_9.B5f-4.1JqM");
+    }
+
+    #[actix_rt::test]
+    async fn test_custom_error_handler() {
+        let (req, mut pl) = TestRequest::default()
+            .app_data(BearerConfig::default().error_handler(|e, _| {
+                let resp = HttpResponse::Forbidden().finish();
+                InternalError::from_response(e, resp).into()
+            }))
+            .to_http_parts();
+        let e = Bearer::from_request(&req, &mut pl).await.unwrap_err();
+        assert_eq!(
+            e.as_response_error().error_response().status(),
+            StatusCode::FORBIDDEN
+        );
+    }
+
+    #[cfg(feature = "bearer-verify")]
+    #[actix_rt::test]
+    async fn test_verifier() {
+        let config = BearerConfig::default().verifier(|token| {
+            if token == "valid" {
+                Ok(serde_json::json!({"sub": "user1"}))
+            } else {
+                Err(BearerError::Invalid)
+            }
+        });
+
+        let (req, mut pl) = TestRequest::default()
+            .header(header::AUTHORIZATION, "Bearer valid")
+            .app_data(config.clone())
+            .to_http_parts();
+        let bearer = Bearer::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(bearer.claims().unwrap()["sub"], "user1");
+
+        let (req, mut pl) = TestRequest::default()
+            .header(header::AUTHORIZATION, "Bearer forged")
+            .app_data(config)
+            .to_http_parts();
+        let e = Bearer::from_request(&req, &mut pl).await.unwrap_err();
+        assert_eq!(
+            status_and_challenge(e),
+            (
+                StatusCode::UNAUTHORIZED,
+                "Bearer error=\"invalid_token\"".to_owned()
+            )
+        );
+    }
+}
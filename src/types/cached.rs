@@ -0,0 +1,213 @@
+//! Memoizing extractor
+
+use std::{fmt, ops};
+
+use futures::future::{ok, FutureExt, LocalBoxFuture};
+
+use crate::dev::Payload;
+use crate::extract::FromRequest;
+use crate::request::HttpRequest;
+
+/// Extractor which runs inner extractor once per request.
+///
+/// Successfully extracted value is stored in request extensions, subsequent
+/// `Cached<T>` extractions of the same request, i.e. in middleware and then
+/// in handler, get a clone of the stored value instead of running `T`
+/// extractor again. Failed extraction is not stored.
+///
+/// Only `Cached<T>` extractions share the value, plain `T` extractor
+/// always runs. Middleware has to complete extraction before it calls
+/// the next service, as handler extraction starts once it is called.
+///
+/// ## Example
+///
+/// ```rust
+/// use actix_web::{dev, web, App, Error, FromRequest, HttpRequest};
+/// use futures::future::{ok, Ready};
+///
+/// #[derive(Clone)]
+/// struct User {
+///     name: String,
+/// }
+///
+/// impl FromRequest for User {
+///     type Error = Error;
+///     type Future = Ready<Result<Self, Self::Error>>;
+///     type Config = ();
+///
+///     fn from_request(req: &HttpRequest, _: &mut dev::Payload) -> Self::Future {
+///         // expensive lookup
+///         ok(User { name: "user".to_owned() })
+///     }
+/// }
+///
+/// async fn index(user: web::Cached<User>) -> String {
+///     format!("Welcome {}!", user.name)
+/// }
+///
+/// fn main() {
+///     let app = App::new().route("/", web::get().to(index));
+/// }
+/// ```
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+pub struct Cached<T>(pub T);
+
+impl<T> Cached<T> {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> ops::Deref for Cached<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> ops::DerefMut for Cached<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Cached<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Cached<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Extracted value stored in request extensions
+struct CachedValue<T>(T);
+
+impl<T> FromRequest for Cached<T>
+where
+    T: FromRequest + Clone + 'static,
+{
+    type Error = T::Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+    type Config = T::Config;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        if let Some(value) = req.extensions().get::<CachedValue<T>>() {
+            return ok(Cached(value.0.clone())).boxed_local();
+        }
+
+        let req = req.clone();
+        T::from_request(&req, payload)
+            .map(move |res| {
+                let value = res?;
+                req.extensions_mut().insert(CachedValue(value.clone()));
+                Ok(Cached(value))
+            })
+            .boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use actix_service::Service;
+    use futures::future::{err, Ready};
+
+    use super::*;
+    use crate::dev::ServiceRequest;
+    use crate::test::{call_service, init_service, read_body, TestRequest};
+    use crate::{error, web, App, Error};
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct User(String);
+
+    impl FromRequest for User {
+        type Error = Error;
+        type Future = Ready<Result<Self, Self::Error>>;
+        type Config = ();
+
+        fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+            req.app_data::<Arc<AtomicUsize>>()
+                .unwrap()
+                .fetch_add(1, Ordering::SeqCst);
+            match req.headers().get("x-user") {
+                Some(name) => ok(User(name.to_str().unwrap().to_owned())),
+                None => err(error::ErrorUnauthorized("no user")),
+            }
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_cached_extracted_once() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut srv = init_service(
+            App::new()
+                .app_data(calls.clone())
+                .wrap_fn(|req, srv| {
+                    // complete extraction before handler is called
+                    let (req, mut pl) = req.into_parts();
+                    let user = Cached::<User>::from_request(&req, &mut pl)
+                        .now_or_never()
+                        .unwrap();
+                    let req = ServiceRequest::from_parts(req, pl).ok().unwrap();
+                    let fut = srv.call(req);
+                    async move {
+                        assert_eq!(user?.into_inner(), User("bob".to_owned()));
+                        fut.await
+                    }
+                })
+                .route(
+                    "/",
+                    web::get()
+                        .to(|user: Cached<User>| async move { user.into_inner().0 }),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::get().header("x-user", "bob").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert!(resp.status().is_success());
+        assert_eq!(read_body(resp).await, "bob");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // value is stored per request
+        let req = TestRequest::get().header("x-user", "bob").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert!(resp.status().is_success());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[actix_rt::test]
+    async fn test_cached_error_is_not_stored() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let req = TestRequest::default()
+            .app_data(calls.clone())
+            .to_http_request();
+
+        assert!(Cached::<User>::extract(&req).await.is_err());
+        assert!(Cached::<User>::extract(&req).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        let req = TestRequest::default()
+            .app_data(calls.clone())
+            .header("x-user", "alice")
+            .to_http_request();
+        let user = Cached::<User>::extract(&req).await.unwrap();
+        assert_eq!(*user, User("alice".to_owned()));
+        let user = Cached::<User>::extract(&req).await.unwrap();
+        assert_eq!(*user, User("alice".to_owned()));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        // plain extractor does not use stored value
+        assert!(User::extract(&req).await.is_ok());
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+}
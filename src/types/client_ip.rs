@@ -0,0 +1,359 @@
+//! Client address extractor
+
+use std::borrow::Cow;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::{fmt, ops};
+
+use actix_http::error::Error;
+use actix_http::http::header::HeaderName;
+use futures::future::{err, ok, Ready};
+
+use crate::dev::Payload;
+use crate::error::ClientIpError;
+use crate::extract::FromRequest;
+use crate::request::HttpRequest;
+
+const X_FORWARDED_FOR: HeaderName = HeaderName::from_static("x-forwarded-for");
+
+/// Extract address of the client which sent the request.
+///
+/// `X-Forwarded-For` header is honored only if the request comes from a
+/// trusted proxy. Header is walked from the right, each hop is accepted
+/// as long as the previous one is trusted, so addresses injected by the
+/// client itself are never used. Without trusted proxies, peer address
+/// of the connection is returned.
+///
+/// Malformed addresses in the used part of the header are rejected with
+/// `400 Bad Request`, unknown peer address with `500 Internal Server Error`.
+///
+/// [**ClientIpConfig**](struct.ClientIpConfig.html) allows to configure
+/// trusted proxies.
+///
+/// ## Example
+///
+/// ```rust
+/// use actix_web::{web, App};
+///
+/// async fn index(ip: web::ClientIp) -> String {
+///     format!("Hello {}", ip)
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///        web::resource("/index.html")
+///            .app_data(web::ClientIpConfig::default().trust_proxy("10.0.0.0/8"))
+///            .route(web::get().to(index)));
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub IpAddr);
+
+impl ClientIp {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> IpAddr {
+        self.0
+    }
+
+    fn from_req(
+        req: &HttpRequest,
+        config: &ClientIpConfig,
+    ) -> Result<Self, ClientIpError> {
+        let mut addr = req
+            .peer_addr()
+            .map(|addr| canonical(addr.ip()))
+            .ok_or(ClientIpError::Unknown)?;
+
+        let values = req.headers().get_all(&X_FORWARDED_FOR).collect::<Vec<_>>();
+
+        'hops: for value in values.into_iter().rev() {
+            if !config.is_trusted(addr) {
+                break;
+            }
+            let value = value.to_str().map_err(|_| ClientIpError::Malformed)?;
+            for hop in value.split(',').rev() {
+                if !config.is_trusted(addr) {
+                    break 'hops;
+                }
+                addr = parse_hop(hop)?;
+            }
+        }
+        Ok(ClientIp(addr))
+    }
+}
+
+impl ops::Deref for ClientIp {
+    type Target = IpAddr;
+
+    fn deref(&self) -> &IpAddr {
+        &self.0
+    }
+}
+
+impl fmt::Display for ClientIp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromRequest for ClientIp {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+    type Config = ClientIpConfig;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let config = ClientIpConfig::from_req(req);
+
+        match ClientIp::from_req(req, config) {
+            Ok(ip) => ok(ip),
+            Err(e) => {
+                log::debug!(
+                    "Failed during ClientIp extractor. Request path: {:?}",
+                    req.path()
+                );
+
+                if let Some(ref error_handler) = config.ehandler {
+                    err((error_handler)(e, req))
+                } else {
+                    err(e.into())
+                }
+            }
+        }
+    }
+}
+
+/// Parse single `X-Forwarded-For` entry, port is allowed and ignored
+fn parse_hop(hop: &str) -> Result<IpAddr, ClientIpError> {
+    let hop = hop.trim();
+    if let Ok(ip) = hop.parse::<IpAddr>() {
+        return Ok(canonical(ip));
+    }
+    hop.parse::<std::net::SocketAddr>()
+        .map(|addr| canonical(addr.ip()))
+        .map_err(|_| ClientIpError::Malformed)
+}
+
+/// Convert ipv4-mapped ipv6 address to ipv4 one
+fn canonical(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+        ip => ip,
+    }
+}
+
+/// Client address extractor configuration
+///
+/// ## Example
+///
+/// ```rust
+/// use actix_web::{web, App};
+///
+/// async fn index(ip: web::ClientIp) -> String {
+///     format!("Hello {}", ip)
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/index.html")
+///             // trust local load balancers
+///             .app_data(
+///                 web::ClientIpConfig::default()
+///                     .trust_proxy("127.0.0.1")
+///                     .trust_proxy("192.168.0.0/16"),
+///             )
+///             .route(web::get().to(index))
+///     );
+/// }
+/// ```
+#[derive(Clone)]
+pub struct ClientIpConfig {
+    trusted: Cow<'static, [(IpAddr, u8)]>,
+    ehandler: Option<Arc<dyn Fn(ClientIpError, &HttpRequest) -> Error + Send + Sync>>,
+}
+
+impl ClientIpConfig {
+    /// Trust proxies within the given network, in CIDR notation
+    /// (`10.0.0.0/8`, `fd00::/8`) or as a single address.
+    ///
+    /// By default no proxies are trusted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if network is malformed.
+    pub fn trust_proxy(mut self, cidr: &str) -> Self {
+        let (ip, prefix) = match cidr.find('/') {
+            Some(idx) => (&cidr[..idx], Some(&cidr[idx + 1..])),
+            None => (cidr, None),
+        };
+        let ip = ip
+            .parse::<IpAddr>()
+            .unwrap_or_else(|_| panic!("Invalid network: {}", cidr));
+        let max = if ip.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .parse::<u8>()
+                .ok()
+                .filter(|prefix| *prefix <= max)
+                .unwrap_or_else(|| panic!("Invalid network: {}", cidr)),
+            None => max,
+        };
+        self.trusted.to_mut().push((ip, prefix));
+        self
+    }
+
+    /// Set custom error handler
+    pub fn error_handler<F>(mut self, f: F) -> Self
+    where
+        F: Fn(ClientIpError, &HttpRequest) -> Error + Send + Sync + 'static,
+    {
+        self.ehandler = Some(Arc::new(f));
+        self
+    }
+
+    fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.trusted.iter().any(|(net, prefix)| match (net, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - u32::from(*prefix)).unwrap_or(0);
+                u32::from(*net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - u32::from(*prefix)).unwrap_or(0);
+                u128::from(*net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        })
+    }
+
+    /// Extract client ip config from app data, fall back to the default config.
+    fn from_req(req: &HttpRequest) -> &Self {
+        req.app_data::<Self>().unwrap_or(&DEFAULT_CONFIG)
+    }
+}
+
+/// Allow shared refs to default.
+const DEFAULT_CONFIG: ClientIpConfig = ClientIpConfig {
+    trusted: Cow::Borrowed(&[]),
+    ehandler: None,
+};
+
+impl Default for ClientIpConfig {
+    fn default() -> Self {
+        DEFAULT_CONFIG.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_http::http::StatusCode;
+
+    use super::*;
+    use crate::test::TestRequest;
+
+    fn config() -> ClientIpConfig {
+        ClientIpConfig::default()
+            .trust_proxy("10.0.0.0/8")
+            .trust_proxy("fd00::/8")
+            .trust_proxy("127.0.0.1")
+    }
+
+    async fn extract(req: TestRequest) -> Result<ClientIp, Error> {
+        let (req, mut pl) = req.to_http_parts();
+        ClientIp::from_request(&req, &mut pl).await
+    }
+
+    #[test]
+    fn test_trusted_networks() {
+        let config = config();
+        assert!(config.is_trusted("10.1.2.3".parse().unwrap()));
+        assert!(config.is_trusted("127.0.0.1".parse().unwrap()));
+        assert!(!config.is_trusted("127.0.0.2".parse().unwrap()));
+        assert!(!config.is_trusted("11.0.0.1".parse().unwrap()));
+        assert!(config.is_trusted("fd12::1".parse().unwrap()));
+        assert!(!config.is_trusted("fe80::1".parse().unwrap()));
+
+        let all = ClientIpConfig::default().trust_proxy("0.0.0.0/0");
+        assert!(all.is_trusted("8.8.8.8".parse().unwrap()));
+        assert!(!all.is_trusted("::1".parse().unwrap()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid network")]
+    fn test_invalid_network() {
+        let _ = ClientIpConfig::default().trust_proxy("10.0.0.0/33");
+    }
+
+    #[actix_rt::test]
+    async fn test_peer_addr() {
+        let req = TestRequest::default()
+            .peer_addr("203.0.113.7:4000".parse().unwrap())
+            .header("x-forwarded-for", "198.51.100.1");
+        let ip = extract(req).await.unwrap();
+        assert_eq!(ip, ClientIp("203.0.113.7".parse().unwrap()));
+
+        let e = extract(TestRequest::default()).await.unwrap_err();
+        assert_eq!(
+            e.as_response_error().error_response().status(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_trusted_chain() {
+        let req = TestRequest::default()
+            .peer_addr("10.0.0.1:4000".parse().unwrap())
+            .header("x-forwarded-for", "198.51.100.1, 10.0.0.2")
+            .app_data(config());
+        let ip = extract(req).await.unwrap();
+        assert_eq!(ip.into_inner(), "198.51.100.1".parse::<IpAddr>().unwrap());
+
+        // entries from separate headers are joined in order
+        let req = TestRequest::default()
+            .peer_addr("[::ffff:127.0.0.1]:4000".parse().unwrap())
+            .header("x-forwarded-for", "[2001:db8::1]:5000")
+            .header("x-forwarded-for", "fd00::2")
+            .app_data(config());
+        let ip = extract(req).await.unwrap();
+        assert_eq!(ip.into_inner(), "2001:db8::1".parse::<IpAddr>().unwrap());
+
+        // all hops are trusted
+        let req = TestRequest::default()
+            .peer_addr("10.0.0.1:4000".parse().unwrap())
+            .header("x-forwarded-for", "10.0.0.3,10.0.0.2")
+            .app_data(config());
+        let ip = extract(req).await.unwrap();
+        assert_eq!(ip.into_inner(), "10.0.0.3".parse::<IpAddr>().unwrap());
+    }
+
+    #[actix_rt::test]
+    async fn test_untrusted_chain() {
+        // spoofed entries left of the first untrusted hop are ignored
+        let req = TestRequest::default()
+            .peer_addr("10.0.0.1:4000".parse().unwrap())
+            .header(
+                "x-forwarded-for",
+                "garbage, 10.0.0.5, 198.51.100.1, 10.0.0.2",
+            )
+            .app_data(config());
+        let ip = extract(req).await.unwrap();
+        assert_eq!(ip.into_inner(), "198.51.100.1".parse::<IpAddr>().unwrap());
+
+        // header from untrusted peer is ignored
+        let req = TestRequest::default()
+            .peer_addr("198.51.100.9:4000".parse().unwrap())
+            .header("x-forwarded-for", "10.0.0.2")
+            .app_data(config());
+        let ip = extract(req).await.unwrap();
+        assert_eq!(ip.into_inner(), "198.51.100.9".parse::<IpAddr>().unwrap());
+
+        let req = TestRequest::default()
+            .peer_addr("10.0.0.1:4000".parse().unwrap())
+            .header("x-forwarded-for", "198.51.100.1, unknown")
+            .app_data(config());
+        let e = extract(req).await.unwrap_err();
+        assert_eq!(
+            e.as_response_error().error_response().status(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+}
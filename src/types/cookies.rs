@@ -0,0 +1,428 @@
+//! Cookie extractors
+
+use std::slice;
+
+use actix_http::cookie::Cookie;
+use actix_http::error::Error;
+use futures::future::{err, ok, Ready};
+
+use crate::dev::Payload;
+use crate::error::CookieError;
+use crate::extract::FromRequest;
+use crate::request::HttpRequest;
+use crate::HttpMessage;
+
+#[cfg(feature = "secure-cookies")]
+pub use self::secure::{CookieConfig, PrivateCookie, SignedCookie};
+
+/// Extract cookies sent with the request.
+///
+/// Malformed `Cookie` header is rejected with `400 Bad Request`.
+///
+/// ## Example
+///
+/// ```rust
+/// use actix_web::{web, App};
+///
+/// async fn index(cookies: web::Cookies) -> String {
+///     match cookies.get("theme") {
+///         Some(cookie) => format!("Theme: {}", cookie.value()),
+///         None => "Default theme".to_owned(),
+///     }
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///        web::resource("/index.html").route(web::get().to(index)));
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cookies(Vec<Cookie<'static>>);
+
+impl Cookies {
+    /// Get cookie by name
+    pub fn get(&self, name: &str) -> Option<&Cookie<'static>> {
+        self.0.iter().find(|cookie| cookie.name() == name)
+    }
+
+    /// Iterate over all cookies
+    pub fn iter(&self) -> slice::Iter<'_, Cookie<'static>> {
+        self.0.iter()
+    }
+
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> Vec<Cookie<'static>> {
+        self.0
+    }
+}
+
+impl FromRequest for Cookies {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+    type Config = ();
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        match req.cookies() {
+            Ok(cookies) => ok(Cookies(cookies.clone())),
+            Err(_) => {
+                log::debug!(
+                    "Failed during Cookies extractor. Request path: {:?}",
+                    req.path()
+                );
+                err(CookieError::Malformed.into())
+            }
+        }
+    }
+}
+
+#[cfg(feature = "secure-cookies")]
+mod secure {
+    use std::borrow::Cow;
+    use std::str::FromStr;
+    use std::sync::Arc;
+    use std::{fmt, ops};
+
+    use actix_http::cookie::{CookieJar, Key};
+    use actix_http::error::Error;
+    use futures::future::{err, ok, Ready};
+
+    use crate::dev::Payload;
+    use crate::error::CookieError;
+    use crate::extract::FromRequest;
+    use crate::request::HttpRequest;
+    use crate::HttpMessage;
+
+    /// Extract typed value of signed cookie.
+    ///
+    /// Cookie name and signing key are configured with
+    /// [**CookieConfig**](struct.CookieConfig.html). Value must implement
+    /// `FromStr`. Missing, tampered or unparsable cookie is rejected with
+    /// `400 Bad Request`, missing configuration with
+    /// `500 Internal Server Error`.
+    ///
+    /// Signed cookie value is readable by the client, use
+    /// [**PrivateCookie**](struct.PrivateCookie.html) to hide it.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use actix_web::cookie::Key;
+    /// use actix_web::{web, App};
+    ///
+    /// async fn index(user_id: web::SignedCookie<u64>) -> String {
+    ///     format!("User #{}", user_id)
+    /// }
+    ///
+    /// fn main() {
+    ///     let app = App::new().service(
+    ///         web::resource("/index.html")
+    ///             .app_data(web::CookieConfig::new("user_id", Key::from_master(&[0; 32])))
+    ///             .route(web::get().to(index)));
+    /// }
+    /// ```
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct SignedCookie<T>(pub T);
+
+    /// Extract typed value of encrypted cookie.
+    ///
+    /// Cookie value is authenticated and encrypted, otherwise it works the
+    /// same way as [**SignedCookie**](struct.SignedCookie.html).
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct PrivateCookie<T>(pub T);
+
+    macro_rules! cookie_extractor {
+        ($name:ident, $jar:ident) => {
+            impl<T> $name<T> {
+                /// Deconstruct to an inner value
+                pub fn into_inner(self) -> T {
+                    self.0
+                }
+            }
+
+            impl<T> ops::Deref for $name<T> {
+                type Target = T;
+
+                fn deref(&self) -> &T {
+                    &self.0
+                }
+            }
+
+            impl<T> ops::DerefMut for $name<T> {
+                fn deref_mut(&mut self) -> &mut T {
+                    &mut self.0
+                }
+            }
+
+            impl<T: fmt::Display> fmt::Display for $name<T> {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    self.0.fmt(f)
+                }
+            }
+
+            impl<T> FromRequest for $name<T>
+            where
+                T: FromStr,
+            {
+                type Error = Error;
+                type Future = Ready<Result<Self, Error>>;
+                type Config = CookieConfig;
+
+                #[inline]
+                fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+                    let config = CookieConfig::from_req(req);
+
+                    match config.value(req, |jar, key, name| jar.$jar(key).get(name)) {
+                        Ok(value) => ok($name(value)),
+                        Err(e) => {
+                            log::debug!(
+                                "Failed during {} extractor. Request path: {:?}",
+                                stringify!($name),
+                                req.path()
+                            );
+
+                            if let Some(ref error_handler) = config.ehandler {
+                                err((error_handler)(e, req))
+                            } else {
+                                err(e.into())
+                            }
+                        }
+                    }
+                }
+            }
+        };
+    }
+
+    cookie_extractor!(SignedCookie, signed);
+    cookie_extractor!(PrivateCookie, private);
+
+    /// Signed and private cookie extractors configuration
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use actix_web::cookie::Key;
+    /// use actix_web::{error, web, App, HttpResponse};
+    ///
+    /// async fn index(session: web::PrivateCookie<String>) -> String {
+    ///     format!("Session {}", session)
+    /// }
+    ///
+    /// fn main() {
+    ///     let key = Key::generate();
+    ///     let app = App::new().service(
+    ///         web::resource("/index.html")
+    ///             .app_data(
+    ///                 web::CookieConfig::new("session", key).error_handler(|err, _| {
+    ///                     error::InternalError::from_response(
+    ///                         err,
+    ///                         HttpResponse::Unauthorized().finish(),
+    ///                     )
+    ///                     .into()
+    ///                 }),
+    ///             )
+    ///             .route(web::get().to(index)),
+    ///     );
+    /// }
+    /// ```
+    #[derive(Clone)]
+    pub struct CookieConfig {
+        name: Cow<'static, str>,
+        key: Option<Key>,
+        ehandler: Option<Arc<dyn Fn(CookieError, &HttpRequest) -> Error + Send + Sync>>,
+    }
+
+    impl CookieConfig {
+        /// Create config for cookie `name` signed or encrypted with `key`
+        pub fn new(name: &str, key: Key) -> Self {
+            CookieConfig {
+                name: Cow::Owned(name.to_owned()),
+                key: Some(key),
+                ehandler: None,
+            }
+        }
+
+        /// Set custom error handler
+        pub fn error_handler<F>(mut self, f: F) -> Self
+        where
+            F: Fn(CookieError, &HttpRequest) -> Error + Send + Sync + 'static,
+        {
+            self.ehandler = Some(Arc::new(f));
+            self
+        }
+
+        /// Verify configured cookie with `get` and parse its value.
+        fn value<T, F>(&self, req: &HttpRequest, get: F) -> Result<T, CookieError>
+        where
+            T: FromStr,
+            F: FnOnce(
+                &mut CookieJar,
+                &Key,
+                &str,
+            ) -> Option<actix_http::cookie::Cookie<'static>>,
+        {
+            let key = self.key.as_ref().ok_or(CookieError::NotConfigured)?;
+            let name = self.name.as_ref();
+            let cookie = req
+                .cookies()
+                .map_err(|_| CookieError::Malformed)?
+                .iter()
+                .find(|cookie| cookie.name() == name)
+                .cloned()
+                .ok_or_else(|| CookieError::Missing(name.to_owned()))?;
+
+            let mut jar = CookieJar::new();
+            jar.add_original(cookie);
+            let cookie = get(&mut jar, key, name)
+                .ok_or_else(|| CookieError::Invalid(name.to_owned()))?;
+            cookie
+                .value()
+                .parse()
+                .map_err(|_| CookieError::Parse(name.to_owned()))
+        }
+
+        /// Extract cookie config from app data, fall back to the default config.
+        fn from_req(req: &HttpRequest) -> &Self {
+            req.app_data::<Self>().unwrap_or(&DEFAULT_CONFIG)
+        }
+    }
+
+    /// Allow shared refs to default, cookies can not be verified without key.
+    const DEFAULT_CONFIG: CookieConfig = CookieConfig {
+        name: Cow::Borrowed(""),
+        key: None,
+        ehandler: None,
+    };
+
+    /// Default config has no key, extraction fails until it is configured.
+    impl Default for CookieConfig {
+        fn default() -> Self {
+            DEFAULT_CONFIG.clone()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use actix_http::cookie::Cookie;
+        use actix_http::http::StatusCode;
+
+        use super::*;
+        use crate::test::TestRequest;
+
+        fn key() -> Key {
+            Key::from_master(&[7; 32])
+        }
+
+        /// Cookie header value with cookie signed or encrypted by `add`
+        fn cookie<F>(add: F) -> String
+        where
+            F: FnOnce(&mut CookieJar, &Key),
+        {
+            let mut jar = CookieJar::new();
+            add(&mut jar, &key());
+            jar.delta().next().unwrap().encoded().to_string()
+        }
+
+        fn status(e: Error) -> StatusCode {
+            e.as_response_error().error_response().status()
+        }
+
+        #[actix_rt::test]
+        async fn test_signed_cookie() {
+            let value = cookie(|jar, key| jar.signed(key).add(Cookie::new("id", "42")));
+            let (req, mut pl) = TestRequest::with_header("cookie", value.clone())
+                .app_data(CookieConfig::new("id", key()))
+                .to_http_parts();
+            let id = SignedCookie::<u64>::from_request(&req, &mut pl)
+                .await
+                .unwrap();
+            assert_eq!(id.into_inner(), 42);
+
+            // value is readable, but can not be altered
+            let tampered = value.replace("42", "43");
+            assert_ne!(tampered, value);
+            let (req, mut pl) = TestRequest::with_header("cookie", tampered)
+                .app_data(CookieConfig::new("id", key()))
+                .to_http_parts();
+            let e = SignedCookie::<u64>::from_request(&req, &mut pl)
+                .await
+                .unwrap_err();
+            assert_eq!(status(e), StatusCode::BAD_REQUEST);
+
+            // signed with another key
+            let (req, mut pl) = TestRequest::with_header("cookie", value.clone())
+                .app_data(CookieConfig::new("id", Key::from_master(&[8; 32])))
+                .to_http_parts();
+            assert!(SignedCookie::<u64>::from_request(&req, &mut pl)
+                .await
+                .is_err());
+
+            // not configured
+            let (req, mut pl) =
+                TestRequest::with_header("cookie", value).to_http_parts();
+            let e = SignedCookie::<u64>::from_request(&req, &mut pl)
+                .await
+                .unwrap_err();
+            assert_eq!(status(e), StatusCode::INTERNAL_SERVER_ERROR);
+        }
+
+        #[actix_rt::test]
+        async fn test_private_cookie() {
+            let value = cookie(|jar, key| {
+                jar.private(key).add(Cookie::new("session", "secret"))
+            });
+            assert!(!value.contains("secret"));
+
+            let (req, mut pl) = TestRequest::with_header("cookie", value.clone())
+                .app_data(CookieConfig::new("session", key()))
+                .to_http_parts();
+            let session = PrivateCookie::<String>::from_request(&req, &mut pl)
+                .await
+                .unwrap();
+            assert_eq!(*session, "secret");
+
+            // private cookie is not accepted as signed one
+            let (req, mut pl) = TestRequest::with_header("cookie", value)
+                .app_data(CookieConfig::new("session", key()))
+                .to_http_parts();
+            assert!(SignedCookie::<String>::from_request(&req, &mut pl)
+                .await
+                .is_err());
+
+            let (req, mut pl) = TestRequest::with_header("cookie", "other=1")
+                .app_data(CookieConfig::new("session", key()))
+                .to_http_parts();
+            let e = PrivateCookie::<String>::from_request(&req, &mut pl)
+                .await
+                .unwrap_err();
+            assert_eq!(status(e), StatusCode::BAD_REQUEST);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_http::http::StatusCode;
+
+    use super::*;
+    use crate::test::TestRequest;
+
+    #[actix_rt::test]
+    async fn test_cookies() {
+        let (req, mut pl) = TestRequest::with_header("cookie", "theme=dark; lang=en")
+            .header("cookie", "id=1")
+            .to_http_parts();
+        let cookies = Cookies::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(cookies.get("theme").unwrap().value(), "dark");
+        assert_eq!(cookies.get("id").unwrap().value(), "1");
+        assert!(cookies.get("missing").is_none());
+        assert_eq!(cookies.iter().count(), 3);
+
+        let (req, mut pl) = TestRequest::with_header("cookie", "=value").to_http_parts();
+        let e = Cookies::from_request(&req, &mut pl).await.unwrap_err();
+        assert_eq!(
+            e.as_response_error().error_response().status(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+}
@@ -0,0 +1,332 @@
+//! Request deadline extractor
+
+use std::borrow::Cow;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use actix_http::error::Error;
+use futures::future::{err, ok, Ready};
+
+use crate::dev::Payload;
+use crate::error::DeadlineError;
+use crate::extract::FromRequest;
+use crate::request::HttpRequest;
+
+/// Extract deadline of the request propagated by the caller.
+///
+/// Deadline is read from the timeout header (`grpc-timeout` by default),
+/// which holds relative timeout like `100m`, or from the deadline header
+/// (`x-request-deadline` by default), which holds absolute deadline as
+/// milliseconds since unix epoch. Timeout header takes precedence.
+///
+/// If neither header is present, deadline is not limited. Malformed
+/// header values are rejected with `400 Bad Request`.
+///
+/// [**DeadlineConfig**](struct.DeadlineConfig.html) allows to configure
+/// header names.
+///
+/// ## Example
+///
+/// ```rust
+/// use actix_web::{web, App, HttpResponse};
+///
+/// async fn index(deadline: web::Deadline) -> HttpResponse {
+///     if deadline.is_expired() {
+///         return HttpResponse::GatewayTimeout().finish();
+///     }
+///     HttpResponse::Ok().finish()
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///        web::resource("/index.html").route(web::get().to(index)));
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deadline(Option<Instant>);
+
+impl Deadline {
+    /// Deadline which is not limited
+    pub fn unlimited() -> Self {
+        Deadline(None)
+    }
+
+    /// Deadline after the given timeout from now
+    pub fn from_timeout(timeout: Duration) -> Self {
+        Deadline(Some(Instant::now() + timeout))
+    }
+
+    /// Instant when deadline expires, if limited
+    pub fn instant(&self) -> Option<Instant> {
+        self.0
+    }
+
+    /// Time left until deadline expires, if limited
+    pub fn remaining(&self) -> Option<Duration> {
+        self.0
+            .map(|at| at.saturating_duration_since(Instant::now()))
+    }
+
+    /// Returns true if deadline is limited and already expired
+    pub fn is_expired(&self) -> bool {
+        match self.0 {
+            Some(at) => at <= Instant::now(),
+            None => false,
+        }
+    }
+
+    /// Parse timeout in `grpc-timeout` format, i.e. up to 8 digits followed
+    /// by unit: `H`, `M`, `S`, `m`, `u` or `n`.
+    pub fn parse_timeout(value: &str) -> Result<Duration, DeadlineError> {
+        let value = value.trim();
+        let len = value.len();
+        if !(2..=9).contains(&len) || !value.is_char_boundary(len - 1) {
+            return Err(DeadlineError::Malformed);
+        }
+        let (digits, unit) = value.split_at(len - 1);
+        if !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(DeadlineError::Malformed);
+        }
+        let n = digits
+            .parse::<u64>()
+            .map_err(|_| DeadlineError::Malformed)?;
+
+        match unit {
+            "H" => Ok(Duration::from_secs(n * 3600)),
+            "M" => Ok(Duration::from_secs(n * 60)),
+            "S" => Ok(Duration::from_secs(n)),
+            "m" => Ok(Duration::from_millis(n)),
+            "u" => Ok(Duration::from_micros(n)),
+            "n" => Ok(Duration::from_nanos(n)),
+            _ => Err(DeadlineError::Malformed),
+        }
+    }
+
+    /// Parse absolute deadline as milliseconds since unix epoch
+    pub fn parse_deadline(value: &str) -> Result<Self, DeadlineError> {
+        let millis = value
+            .trim()
+            .parse::<u64>()
+            .map_err(|_| DeadlineError::Malformed)?;
+        let at = UNIX_EPOCH + Duration::from_millis(millis);
+        let now = Instant::now();
+
+        Ok(match at.duration_since(SystemTime::now()) {
+            Ok(left) => Deadline(Some(now + left)),
+            // deadline is in the past
+            Err(_) => Deadline(Some(now)),
+        })
+    }
+
+    fn from_req(
+        req: &HttpRequest,
+        config: &DeadlineConfig,
+    ) -> Result<Self, DeadlineError> {
+        if let Some(value) = req.headers().get(config.timeout_header.as_ref()) {
+            let value = value.to_str().map_err(|_| DeadlineError::Malformed)?;
+            return Deadline::parse_timeout(value).map(Deadline::from_timeout);
+        }
+        if let Some(value) = req.headers().get(config.deadline_header.as_ref()) {
+            let value = value.to_str().map_err(|_| DeadlineError::Malformed)?;
+            return Deadline::parse_deadline(value);
+        }
+        Ok(Deadline::unlimited())
+    }
+}
+
+impl FromRequest for Deadline {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+    type Config = DeadlineConfig;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let config = DeadlineConfig::from_req(req);
+
+        match Deadline::from_req(req, config) {
+            Ok(deadline) => ok(deadline),
+            Err(e) => {
+                log::debug!(
+                    "Failed during Deadline extractor. Request path: {:?}",
+                    req.path()
+                );
+
+                if let Some(ref error_handler) = config.ehandler {
+                    err((error_handler)(e, req))
+                } else {
+                    err(e.into())
+                }
+            }
+        }
+    }
+}
+
+/// Deadline extractor configuration
+///
+/// ## Example
+///
+/// ```rust
+/// use actix_web::{web, App};
+///
+/// async fn index(deadline: web::Deadline) -> String {
+///     format!("Remaining: {:?}", deadline.remaining())
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/index.html")
+///             // change deadline extractor configuration
+///             .app_data(web::DeadlineConfig::default().timeout_header("x-timeout"))
+///             .route(web::get().to(index))
+///     );
+/// }
+/// ```
+#[derive(Clone)]
+pub struct DeadlineConfig {
+    timeout_header: Cow<'static, str>,
+    deadline_header: Cow<'static, str>,
+    ehandler: Option<Arc<dyn Fn(DeadlineError, &HttpRequest) -> Error + Send + Sync>>,
+}
+
+impl DeadlineConfig {
+    /// Set name of header with relative timeout. By default `grpc-timeout`
+    pub fn timeout_header(mut self, name: &str) -> Self {
+        self.timeout_header = Cow::Owned(name.to_owned());
+        self
+    }
+
+    /// Set name of header with absolute deadline. By default `x-request-deadline`
+    pub fn deadline_header(mut self, name: &str) -> Self {
+        self.deadline_header = Cow::Owned(name.to_owned());
+        self
+    }
+
+    /// Set custom error handler
+    pub fn error_handler<F>(mut self, f: F) -> Self
+    where
+        F: Fn(DeadlineError, &HttpRequest) -> Error + Send + Sync + 'static,
+    {
+        self.ehandler = Some(Arc::new(f));
+        self
+    }
+
+    /// Extract deadline config from app data, fall back to the default config.
+    fn from_req(req: &HttpRequest) -> &Self {
+        req.app_data::<Self>().unwrap_or(&DEFAULT_CONFIG)
+    }
+}
+
+/// Allow shared refs to default.
+const DEFAULT_CONFIG: DeadlineConfig = DeadlineConfig {
+    timeout_header: Cow::Borrowed("grpc-timeout"),
+    deadline_header: Cow::Borrowed("x-request-deadline"),
+    ehandler: None,
+};
+
+impl Default for DeadlineConfig {
+    fn default() -> Self {
+        DEFAULT_CONFIG.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_http::http::StatusCode;
+
+    use super::*;
+    use crate::test::TestRequest;
+
+    #[test]
+    fn test_parse_timeout() {
+        assert_eq!(Deadline::parse_timeout("1H"), Ok(Duration::from_secs(3600)));
+        assert_eq!(Deadline::parse_timeout("2M"), Ok(Duration::from_secs(120)));
+        assert_eq!(Deadline::parse_timeout("3S"), Ok(Duration::from_secs(3)));
+        assert_eq!(
+            Deadline::parse_timeout("100m"),
+            Ok(Duration::from_millis(100))
+        );
+        assert_eq!(Deadline::parse_timeout("5u"), Ok(Duration::from_micros(5)));
+        assert_eq!(
+            Deadline::parse_timeout("99999999n"),
+            Ok(Duration::from_nanos(99_999_999))
+        );
+
+        assert_eq!(Deadline::parse_timeout("m"), Err(DeadlineError::Malformed));
+        assert_eq!(Deadline::parse_timeout("10"), Err(DeadlineError::Malformed));
+        assert_eq!(
+            Deadline::parse_timeout("10s"),
+            Err(DeadlineError::Malformed)
+        );
+        assert_eq!(
+            Deadline::parse_timeout("-1S"),
+            Err(DeadlineError::Malformed)
+        );
+        assert_eq!(
+            Deadline::parse_timeout("123456789S"),
+            Err(DeadlineError::Malformed)
+        );
+        assert_eq!(Deadline::parse_timeout("1é"), Err(DeadlineError::Malformed));
+    }
+
+    #[actix_rt::test]
+    async fn test_timeout_header() {
+        let (req, mut pl) =
+            TestRequest::with_header("grpc-timeout", "10S").to_http_parts();
+        let deadline = Deadline::from_request(&req, &mut pl).await.unwrap();
+        let remaining = deadline.remaining().unwrap();
+        assert!(remaining <= Duration::from_secs(10));
+        assert!(remaining > Duration::from_secs(9));
+        assert!(!deadline.is_expired());
+
+        let (req, mut pl) =
+            TestRequest::with_header("grpc-timeout", "0m").to_http_parts();
+        let deadline = Deadline::from_request(&req, &mut pl).await.unwrap();
+        assert!(deadline.is_expired());
+
+        let (req, mut pl) =
+            TestRequest::with_header("grpc-timeout", "soon").to_http_parts();
+        let e = Deadline::from_request(&req, &mut pl).await.unwrap_err();
+        assert_eq!(
+            e.as_response_error().error_response().status(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_deadline_header() {
+        let at = SystemTime::now() + Duration::from_secs(60);
+        let millis = at.duration_since(UNIX_EPOCH).unwrap().as_millis();
+        let (req, mut pl) =
+            TestRequest::with_header("x-request-deadline", millis.to_string())
+                .to_http_parts();
+        let deadline = Deadline::from_request(&req, &mut pl).await.unwrap();
+        let remaining = deadline.remaining().unwrap();
+        assert!(remaining <= Duration::from_secs(60));
+        assert!(remaining > Duration::from_secs(58));
+
+        let (req, mut pl) =
+            TestRequest::with_header("x-request-deadline", "1000").to_http_parts();
+        let deadline = Deadline::from_request(&req, &mut pl).await.unwrap();
+        assert!(deadline.is_expired());
+
+        let (req, mut pl) = TestRequest::default().to_http_parts();
+        let deadline = Deadline::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(deadline, Deadline::unlimited());
+        assert_eq!(deadline.remaining(), None);
+        assert!(!deadline.is_expired());
+    }
+
+    #[actix_rt::test]
+    async fn test_custom_header_names() {
+        let (req, mut pl) = TestRequest::with_header("x-timeout", "5S")
+            .header("grpc-timeout", "invalid")
+            .app_data(
+                DeadlineConfig::default()
+                    .timeout_header("x-timeout")
+                    .deadline_header("x-deadline"),
+            )
+            .to_http_parts();
+        let deadline = Deadline::from_request(&req, &mut pl).await.unwrap();
+        assert!(deadline.remaining().unwrap() <= Duration::from_secs(5));
+    }
+}
@@ -501,4 +501,25 @@ mod tests {
         use crate::responder::tests::BodyTest;
         assert_eq!(resp.body().bin_ref(), b"hello=world&counter=123");
     }
+
+    #[actix_rt::test]
+    async fn test_responder_serialize_error() {
+        #[derive(Serialize)]
+        struct Nested {
+            info: Info,
+        }
+
+        let req = TestRequest::default().to_http_request();
+        let form = Form(Nested {
+            info: Info {
+                hello: "world".to_string(),
+                counter: 123,
+            },
+        });
+        let err = form.respond_to(&req).await.unwrap_err();
+        assert_eq!(
+            err.as_response_error().error_response().status(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
 }
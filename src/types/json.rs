@@ -1,24 +1,35 @@
 //! Json extractor/responder
 
 use std::future::Future;
+use std::marker::PhantomData;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::task::{Context, Poll};
-use std::{fmt, ops};
+use std::{fmt, io, ops};
 
-use bytes::BytesMut;
-use futures::future::{err, ok, FutureExt, LocalBoxFuture, Ready};
-use futures::StreamExt;
-use serde::de::DeserializeOwned;
-use serde::Serialize;
-use serde_json;
+use actix_threadpool::CpuFuture;
+use bytes::{Bytes, BytesMut};
+use futures::channel::mpsc;
+use futures::executor::block_on;
+use futures::future::{err, ok, poll_fn, FutureExt, LocalBoxFuture, Ready};
+use futures::{ready, SinkExt, Stream, StreamExt};
+use pin_project::pin_project;
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, IgnoredAny, MapAccess, Visitor,
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{self, Map, Value};
 
-use actix_http::http::{header::CONTENT_LENGTH, StatusCode};
+use actix_http::http::header::{
+    ContentEncoding, HeaderValue, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE,
+};
+use actix_http::http::StatusCode;
 use actix_http::{HttpMessage, Payload, Response};
 
 #[cfg(feature = "compress")]
 use crate::dev::Decompress;
-use crate::error::{Error, JsonPayloadError};
+use crate::error::{Error, JsonPayloadError, PayloadError};
 use crate::extract::FromRequest;
 use crate::request::HttpRequest;
 use crate::responder::Responder;
@@ -80,6 +91,25 @@ use crate::responder::Responder;
 /// }
 /// # fn main() {}
 /// ```
+///
+/// Response has `200 OK` status, use `Responder::with_status()` to respond
+/// with a different one. If value can not be serialized, response is
+/// `500 Internal Server Error` regardless of requested status.
+///
+/// ```rust
+/// use actix_web::{http::StatusCode, web, Responder};
+/// use serde_derive::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct MyObj {
+///     id: u64,
+/// }
+///
+/// async fn create() -> impl Responder {
+///     web::Json(MyObj { id: 1 }).with_status(StatusCode::CREATED)
+/// }
+/// # fn main() {}
+/// ```
 pub struct Json<T>(pub T);
 
 impl<T> Json<T> {
@@ -87,6 +117,11 @@ impl<T> Json<T> {
     pub fn into_inner(self) -> T {
         self.0
     }
+
+    /// Respond with indented json
+    pub fn pretty(self) -> JsonPretty<T> {
+        JsonPretty(self.0)
+    }
 }
 
 impl<T> ops::Deref for Json<T> {
@@ -125,15 +160,436 @@ impl<T: Serialize> Responder for Json<T> {
     type Error = Error;
     type Future = Ready<Result<Response, Error>>;
 
-    fn respond_to(self, _: &HttpRequest) -> Self::Future {
-        let body = match serde_json::to_string(&self.0) {
-            Ok(body) => body,
-            Err(e) => return err(e.into()),
+    fn respond_to(self, req: &HttpRequest) -> Self::Future {
+        let config = JsonConfig::from_req(req);
+        json_response(
+            &self.0,
+            config.pretty_requested(req),
+            config.response_content_type,
+        )
+    }
+}
+
+/// Indented json responder
+///
+/// Same as `Json` responder, but value is serialized with
+/// `serde_json::to_string_pretty()`, which is easier to read on debug
+/// endpoints. Use [`JsonConfig::pretty_query()`](struct.JsonConfig.html#method.pretty_query)
+/// to let client request indented output of `Json` responses.
+///
+/// ## Example
+///
+/// ```rust
+/// use actix_web::{web, Responder};
+/// use serde_derive::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Status {
+///     workers: usize,
+/// }
+///
+/// async fn debug() -> impl Responder {
+///     web::Json(Status { workers: 4 }).pretty()
+/// }
+/// # fn main() {}
+/// ```
+pub struct JsonPretty<T>(pub T);
+
+impl<T> JsonPretty<T> {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: Serialize> Responder for JsonPretty<T> {
+    type Error = Error;
+    type Future = Ready<Result<Response, Error>>;
+
+    fn respond_to(self, req: &HttpRequest) -> Self::Future {
+        let config = JsonConfig::from_req(req);
+        json_response(&self.0, true, config.response_content_type)
+    }
+}
+
+fn json_response<T: Serialize>(
+    value: &T,
+    pretty: bool,
+    content_type: &'static str,
+) -> Ready<Result<Response, Error>> {
+    let body = if pretty {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
+    };
+    let body = match body {
+        Ok(body) => body,
+        Err(e) => return err(e.into()),
+    };
+
+    ok(Response::build(StatusCode::OK)
+        .content_type(content_type)
+        .body(body))
+}
+
+/// Streaming json array responder
+///
+/// Serializes items of the stream one by one and sends them as a json array
+/// using chunked transfer encoding, so the whole collection is never buffered
+/// in memory. Empty stream produces `[]`. Response status is sent before
+/// the first item, so if stream yields an error or item can not be
+/// serialized, error is logged and connection is dropped without completing
+/// the body, client does not receive truncated array as a valid response.
+///
+/// ## Example
+///
+/// ```rust
+/// use actix_web::{web, App, Error};
+/// use futures::stream;
+///
+/// async fn index() -> web::JsonStream<impl futures::Stream<Item = Result<u64, Error>>> {
+///     web::JsonStream(stream::iter((0..1000).map(Ok)))
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///        web::resource("/index.html").route(web::get().to(index)));
+/// }
+/// ```
+pub struct JsonStream<S>(pub S);
+
+impl<S> JsonStream<S> {
+    /// Deconstruct to an inner stream
+    pub fn into_inner(self) -> S {
+        self.0
+    }
+}
+
+impl<S, T, E> Responder for JsonStream<S>
+where
+    S: Stream<Item = Result<T, E>> + 'static,
+    T: Serialize,
+    E: Into<Error>,
+{
+    type Error = Error;
+    type Future = Ready<Result<Response, Error>>;
+
+    fn respond_to(self, req: &HttpRequest) -> Self::Future {
+        ok(Response::build(StatusCode::OK)
+            .content_type(JsonConfig::from_req(req).response_content_type)
+            .streaming(JsonStreamBody {
+                stream: self.0,
+                started: false,
+                first: true,
+                done: false,
+            }))
+    }
+}
+
+#[pin_project]
+struct JsonStreamBody<S> {
+    #[pin]
+    stream: S,
+    started: bool,
+    first: bool,
+    done: bool,
+}
+
+impl<S, T, E> Stream for JsonStreamBody<S>
+where
+    S: Stream<Item = Result<T, E>>,
+    T: Serialize,
+    E: Into<Error>,
+{
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+
+        if *this.done {
+            return Poll::Ready(None);
+        }
+        if !*this.started {
+            *this.started = true;
+            return Poll::Ready(Some(Ok(Bytes::from_static(b"["))));
+        }
+
+        let item = match ready!(this.stream.poll_next(cx)) {
+            Some(Ok(item)) => serde_json::to_vec(&item).map_err(|e| e.into()),
+            Some(Err(e)) => Err(e.into()),
+            None => {
+                *this.done = true;
+                return Poll::Ready(Some(Ok(Bytes::from_static(b"]"))));
+            }
         };
 
+        match item {
+            Ok(item) => {
+                let mut buf = Vec::with_capacity(item.len() + 1);
+                if !*this.first {
+                    buf.push(b',');
+                }
+                *this.first = false;
+                buf.extend_from_slice(&item);
+                Poll::Ready(Some(Ok(Bytes::from(buf))))
+            }
+            Err(e) => {
+                log::error!("Error during streaming json array: {}", e);
+                *this.done = true;
+                Poll::Ready(Some(Err(e)))
+            }
+        }
+    }
+}
+
+/// Size of body chunks produced by `JsonWriter`.
+const WRITER_CHUNK_SIZE: usize = 8192;
+
+/// Number of serialized chunks `JsonWriter` keeps ahead of the socket.
+const WRITER_BUFFER: usize = 4;
+
+/// Max number of `JsonWriter` responses which wait for the socket on the
+/// thread pool.
+const WRITER_MAX_STREAMING: usize = 8;
+
+/// Number of `JsonWriter` responses which currently stream chunks.
+static STREAMING_WRITERS: AtomicUsize = AtomicUsize::new(0);
+
+/// Json responder which serializes value directly into response body
+///
+/// Unlike `Json<T>`, the value is not serialized into an intermediate
+/// `String`. Serialization runs on the thread pool and is written in fixed
+/// size chunks, which are sent using chunked transfer encoding as soon as
+/// the socket accepts them, so only a few chunks are held in memory at once.
+/// If value can not be serialized after part of the body was sent, error is
+/// logged and connection is dropped.
+///
+/// Serialization waits for the socket on the thread pool thread, so a slow
+/// client occupies the thread until its body is sent. At most 8 responses
+/// are streamed at once, other responses are serialized without waiting and
+/// sent as a single chunk.
+///
+/// ## Example
+///
+/// ```rust
+/// use actix_web::{web, App};
+///
+/// async fn index() -> web::JsonWriter<Vec<u64>> {
+///     web::JsonWriter((0..100_000).collect())
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///        web::resource("/index.html").route(web::get().to(index)));
+/// }
+/// ```
+pub struct JsonWriter<T>(pub T);
+
+impl<T> JsonWriter<T> {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Responder for JsonWriter<T>
+where
+    T: Serialize + Send + 'static,
+{
+    type Error = Error;
+    type Future = Ready<Result<Response, Error>>;
+
+    fn respond_to(self, req: &HttpRequest) -> Self::Future {
+        let (tx, rx) = mpsc::channel(WRITER_BUFFER);
+        let value = self.0;
+        let permit = StreamingPermit::acquire();
+        let task = actix_threadpool::run(move || {
+            let mut writer = ChunkWriter {
+                buf: BytesMut::with_capacity(WRITER_CHUNK_SIZE),
+                tx,
+                permit,
+            };
+            serde_json::to_writer(&mut writer, &value)?;
+            Ok(writer.buf.freeze())
+        });
+
         ok(Response::build(StatusCode::OK)
-            .content_type("application/json")
-            .body(body))
+            .content_type(JsonConfig::from_req(req).response_content_type)
+            .streaming(JsonWriterBody {
+                rx,
+                task,
+                done: false,
+            }))
+    }
+}
+
+/// Slot of `JsonWriter` response which is allowed to stream chunks.
+struct StreamingPermit;
+
+impl StreamingPermit {
+    fn acquire() -> Option<Self> {
+        if STREAMING_WRITERS.fetch_add(1, Ordering::SeqCst) < WRITER_MAX_STREAMING {
+            Some(StreamingPermit)
+        } else {
+            STREAMING_WRITERS.fetch_sub(1, Ordering::SeqCst);
+            None
+        }
+    }
+}
+
+impl Drop for StreamingPermit {
+    fn drop(&mut self) {
+        STREAMING_WRITERS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// `io::Write` implementation which sends filled chunks to the response body
+///
+/// Without permit, chunks are not sent and the whole body is buffered.
+/// Remaining buffer is sent after serialization completes.
+struct ChunkWriter {
+    buf: BytesMut,
+    tx: mpsc::Sender<Bytes>,
+    permit: Option<StreamingPermit>,
+}
+
+impl ChunkWriter {
+    /// Blocks until chunk is accepted, so serialization can not run ahead
+    /// of the socket.
+    fn send_chunk(&mut self) -> io::Result<()> {
+        let chunk = self.buf.split().freeze();
+        block_on(self.tx.send(chunk))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "Body is dropped"))
+    }
+}
+
+impl io::Write for ChunkWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(data);
+        if self.permit.is_some() && self.buf.len() >= WRITER_CHUNK_SIZE {
+            self.send_chunk()?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+struct JsonWriterBody {
+    rx: mpsc::Receiver<Bytes>,
+    task: CpuFuture<Bytes, serde_json::Error>,
+    done: bool,
+}
+
+impl Stream for JsonWriterBody {
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if this.done {
+            return Poll::Ready(None);
+        }
+        if let Some(chunk) = ready!(this.rx.poll_next_unpin(cx)) {
+            return Poll::Ready(Some(Ok(chunk)));
+        }
+
+        let res = ready!(Pin::new(&mut this.task).poll(cx));
+        this.done = true;
+        match res {
+            Ok(rest) if rest.is_empty() => Poll::Ready(None),
+            Ok(rest) => Poll::Ready(Some(Ok(rest))),
+            Err(e) => {
+                log::error!("Error during json serialization: {:?}", e);
+                Poll::Ready(Some(Err(e.into())))
+            }
+        }
+    }
+}
+
+/// Non-empty collection wrapper
+///
+/// Deserialization of `NonEmpty<T>` fails if resulting collection has no items,
+/// so `Json<NonEmpty<Vec<T>>>` extractor responds with `400 Bad Request` to
+/// an empty json array. Any collection which can be iterated by reference is
+/// supported.
+///
+/// ## Example
+///
+/// ```rust
+/// use actix_web::{web, App};
+///
+/// /// `ids` contains at least one item
+/// async fn index(ids: web::Json<web::NonEmpty<Vec<u64>>>) -> String {
+///     format!("First id: {}", ids[0])
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///        web::resource("/index.html").route(
+///            web::post().to(index))
+///     );
+/// }
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct NonEmpty<T>(T);
+
+impl<T> NonEmpty<T> {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> ops::Deref for NonEmpty<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> fmt::Debug for NonEmpty<T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for NonEmpty<T>
+where
+    T: Deserialize<'de>,
+    for<'a> &'a T: IntoIterator,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = T::deserialize(deserializer)?;
+        if (&value).into_iter().next().is_none() {
+            Err(de::Error::invalid_length(0, &"a non-empty collection"))
+        } else {
+            Ok(NonEmpty(value))
+        }
+    }
+}
+
+impl<T: Serialize> Serialize for NonEmpty<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize(serializer)
     }
 }
 
@@ -146,6 +602,9 @@ impl<T: Serialize> Responder for Json<T> {
 /// [**JsonConfig**](struct.JsonConfig.html) allows to configure extraction
 /// process.
 ///
+/// With `simd-json` feature enabled payload is parsed by simd accelerated
+/// `simd-json` parser instead of `serde_json`.
+///
 /// ## Example
 ///
 /// ```rust
@@ -179,73 +638,476 @@ where
 
     #[inline]
     fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
-        let req2 = req.clone();
-        let (limit, err, ctype) = req
-            .app_data::<Self::Config>()
-            .map(|c| (c.limit, c.ehandler.clone(), c.content_type.clone()))
-            .unwrap_or((32768, None, None));
-
-        JsonBody::new(req, payload, ctype)
-            .limit(limit)
-            .map(move |res| match res {
-                Err(e) => {
-                    log::debug!(
-                        "Failed to deserialize Json from payload. \
-                         Request path: {}",
-                        req2.path()
-                    );
-                    if let Some(err) = err {
-                        Err((*err)(e, &req2))
-                    } else {
-                        Err(e.into())
-                    }
+        extract(req, payload, |data, _| Ok(Json(data)))
+    }
+}
+
+/// Extract json payload configured by `JsonConfig`, convert deserialized
+/// value with `f`.
+///
+/// Errors are passed to configured error handler, `JsonTruncated` is
+/// inserted to request extensions if payload was truncated.
+fn extract<U, T, F>(
+    req: &HttpRequest,
+    payload: &mut Payload,
+    f: F,
+) -> LocalBoxFuture<'static, Result<T, Error>>
+where
+    U: DeserializeOwned + 'static,
+    T: 'static,
+    F: FnOnce(U, Bytes) -> Result<T, JsonPayloadError> + 'static,
+{
+    let req = req.clone();
+    let config = JsonConfig::from_req(&req);
+    let ehandler = config.ehandler.clone();
+    let mut body = JsonBody::<U>::from_config(&req, payload, config);
+
+    async move {
+        let res = poll_fn(|cx| body.poll_with_raw(cx))
+            .await
+            .and_then(|(data, raw)| f(data, raw));
+
+        match res {
+            Ok(data) => {
+                if body.is_truncated() {
+                    req.extensions_mut().insert(JsonTruncated);
                 }
-                Ok(data) => Ok(Json(data)),
-            })
-            .boxed_local()
+                Ok(data)
+            }
+            Err(e) => {
+                log::debug!(
+                    "Failed to deserialize Json from payload. \
+                     Request path: {}",
+                    req.path()
+                );
+                if let Some(ehandler) = ehandler {
+                    Err((*ehandler)(e, &req))
+                } else {
+                    Err(e.into())
+                }
+            }
+        }
     }
+    .boxed_local()
 }
 
-/// Json extractor configuration
+/// Json extractor which keeps raw payload bytes
+///
+/// Resolves to deserialized value along with exact payload bytes which were
+/// deserialized, after decompression. This is useful to verify payload
+/// signature, i.e. HMAC of a webhook request. Extractor is configured with
+/// [**JsonConfig**](struct.JsonConfig.html).
+///
+/// ## Example
 ///
 /// ```rust
-/// use actix_web::{error, web, App, FromRequest, HttpResponse};
+/// use actix_web::{web, App};
 /// use serde_derive::Deserialize;
 ///
 /// #[derive(Deserialize)]
-/// struct Info {
-///     username: String,
+/// struct Event {
+///     kind: String,
 /// }
 ///
-/// /// deserialize `Info` from request's body, max payload size is 4kb
-/// async fn index(info: web::Json<Info>) -> String {
-///     format!("Welcome {}!", info.username)
+/// async fn index(event: web::JsonWithRaw<Event>) -> String {
+///     let (event, raw) = event.into_inner();
+///     format!("Event {} of {} bytes", event.kind, raw.len())
 /// }
 ///
 /// fn main() {
 ///     let app = App::new().service(
-///         web::resource("/index.html")
-///             .app_data(
-///                 // change json extractor configuration
-///                 web::Json::<Info>::configure(|cfg| {
-///                     cfg.limit(4096)
-///                        .content_type(|mime| {  // <- accept text/plain content type
-///                            mime.type_() == mime::TEXT && mime.subtype() == mime::PLAIN
-///                        })
-///                        .error_handler(|err, req| {  // <- create custom error response
-///                           error::InternalError::from_response(
-///                               err, HttpResponse::Conflict().finish()).into()
-///                        })
-///             }))
-///             .route(web::post().to(index))
-///     );
+///        web::resource("/webhook").route(web::post().to(index)));
 /// }
 /// ```
-#[derive(Clone)]
-pub struct JsonConfig {
-    limit: usize,
-    ehandler: Option<Arc<dyn Fn(JsonPayloadError, &HttpRequest) -> Error + Send + Sync>>,
+pub struct JsonWithRaw<T>(pub T, pub Bytes);
+
+impl<T> JsonWithRaw<T> {
+    /// Deconstruct to a value and raw payload bytes
+    pub fn into_inner(self) -> (T, Bytes) {
+        (self.0, self.1)
+    }
+
+    /// Get raw payload bytes
+    pub fn raw(&self) -> &Bytes {
+        &self.1
+    }
+}
+
+impl<T> ops::Deref for JsonWithRaw<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> fmt::Debug for JsonWithRaw<T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("JsonWithRaw")
+            .field(&self.0)
+            .field(&self.1)
+            .finish()
+    }
+}
+
+impl<T> FromRequest for JsonWithRaw<T>
+where
+    T: DeserializeOwned + 'static,
+{
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Error>>;
+    type Config = JsonConfig;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        extract(req, payload, |data, raw| Ok(JsonWithRaw(data, raw)))
+    }
+}
+
+/// Json extractor which keeps unknown fields
+///
+/// Resolves to value deserialized from json object along with top-level
+/// fields which are not known to `T`, so handler can log or forward them.
+/// Field is unknown if `T` ignores it, fields collected with
+/// `#[serde(flatten)]` are not considered unknown. Extractor is configured
+/// with [**JsonConfig**](struct.JsonConfig.html).
+///
+/// ## Example
+///
+/// ```rust
+/// use actix_web::{web, App};
+/// use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Event {
+///     kind: String,
+/// }
+///
+/// async fn index(event: web::JsonWithExtra<Event>) -> String {
+///     let (event, extra) = event.into_inner();
+///     format!("Event {} with {} unknown fields", event.kind, extra.len())
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///        web::resource("/events").route(web::post().to(index)));
+/// }
+/// ```
+pub struct JsonWithExtra<T>(pub T, pub Map<String, Value>);
+
+impl<T> JsonWithExtra<T> {
+    /// Deconstruct to a value and unknown fields
+    pub fn into_inner(self) -> (T, Map<String, Value>) {
+        (self.0, self.1)
+    }
+
+    /// Get unknown fields
+    pub fn extra(&self) -> &Map<String, Value> {
+        &self.1
+    }
+}
+
+impl<T> ops::Deref for JsonWithExtra<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> fmt::Debug for JsonWithExtra<T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("JsonWithExtra")
+            .field(&self.0)
+            .field(&self.1)
+            .finish()
+    }
+}
+
+impl<T> FromRequest for JsonWithExtra<T>
+where
+    T: DeserializeOwned + 'static,
+{
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Error>>;
+    type Config = JsonConfig;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        extract(req, payload, |map, _| {
+            let (data, extra) =
+                split_extra(map).map_err(JsonPayloadError::Deserialize)?;
+            Ok(JsonWithExtra(data, extra))
+        })
+    }
+}
+
+/// Deserialize `T` from json object, collect fields ignored by `T`.
+fn split_extra<T>(
+    map: Map<String, Value>,
+) -> Result<(T, Map<String, Value>), serde_json::Error>
+where
+    T: DeserializeOwned,
+{
+    /// Object deserializer, values are passed to `ExtraValue`
+    struct ExtraMap {
+        iter: serde_json::map::IntoIter,
+        entry: Option<(String, Value)>,
+        extra: Map<String, Value>,
+    }
+
+    impl<'de> Deserializer<'de> for &mut ExtraMap {
+        type Error = serde_json::Error;
+
+        fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            visitor.visit_map(self)
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+            bytes byte_buf option unit unit_struct newtype_struct seq tuple
+            tuple_struct map struct enum identifier ignored_any
+        }
+    }
+
+    impl<'de> MapAccess<'de> for &mut ExtraMap {
+        type Error = serde_json::Error;
+
+        fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+        where
+            K: DeserializeSeed<'de>,
+        {
+            match self.iter.next() {
+                Some((key, value)) => {
+                    let res = seed.deserialize(Value::String(key.clone()))?;
+                    self.entry = Some((key, value));
+                    Ok(Some(res))
+                }
+                None => Ok(None),
+            }
+        }
+
+        fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+        where
+            V: DeserializeSeed<'de>,
+        {
+            match self.entry.take() {
+                Some((key, value)) => seed.deserialize(ExtraValue {
+                    key,
+                    value,
+                    extra: &mut self.extra,
+                }),
+                None => Err(de::Error::custom("value is missing")),
+            }
+        }
+
+        fn size_hint(&self) -> Option<usize> {
+            Some(self.iter.len())
+        }
+    }
+
+    /// Field value deserializer, ignored value is moved to unknown fields
+    struct ExtraValue<'a> {
+        key: String,
+        value: Value,
+        extra: &'a mut Map<String, Value>,
+    }
+
+    macro_rules! forward_to_value {
+        ($($method:ident)*) => {
+            $(
+                fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+                where
+                    V: Visitor<'de>,
+                {
+                    self.value.$method(visitor)
+                }
+            )*
+        };
+    }
+
+    impl<'de, 'a> Deserializer<'de> for ExtraValue<'a> {
+        type Error = serde_json::Error;
+
+        forward_to_value! {
+            deserialize_any deserialize_bool deserialize_i8 deserialize_i16
+            deserialize_i32 deserialize_i64 deserialize_u8 deserialize_u16
+            deserialize_u32 deserialize_u64 deserialize_f32 deserialize_f64
+            deserialize_char deserialize_str deserialize_string deserialize_bytes
+            deserialize_byte_buf deserialize_option deserialize_unit deserialize_seq
+            deserialize_map deserialize_identifier
+        }
+
+        fn deserialize_unit_struct<V>(
+            self,
+            name: &'static str,
+            visitor: V,
+        ) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            self.value.deserialize_unit_struct(name, visitor)
+        }
+
+        fn deserialize_newtype_struct<V>(
+            self,
+            name: &'static str,
+            visitor: V,
+        ) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            self.value.deserialize_newtype_struct(name, visitor)
+        }
+
+        fn deserialize_tuple<V>(
+            self,
+            len: usize,
+            visitor: V,
+        ) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            self.value.deserialize_tuple(len, visitor)
+        }
+
+        fn deserialize_tuple_struct<V>(
+            self,
+            name: &'static str,
+            len: usize,
+            visitor: V,
+        ) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            self.value.deserialize_tuple_struct(name, len, visitor)
+        }
+
+        fn deserialize_struct<V>(
+            self,
+            name: &'static str,
+            fields: &'static [&'static str],
+            visitor: V,
+        ) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            self.value.deserialize_struct(name, fields, visitor)
+        }
+
+        fn deserialize_enum<V>(
+            self,
+            name: &'static str,
+            variants: &'static [&'static str],
+            visitor: V,
+        ) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            self.value.deserialize_enum(name, variants, visitor)
+        }
+
+        fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            self.extra.insert(self.key, self.value);
+            visitor.visit_unit()
+        }
+    }
+
+    let mut de = ExtraMap {
+        iter: map.into_iter(),
+        entry: None,
+        extra: Map::new(),
+    };
+    let value = T::deserialize(&mut de)?;
+    Ok((value, de.extra))
+}
+
+/// Marker of json payload truncated at the limit
+///
+/// Inserted into request extensions by `Json`, `JsonWithRaw` and
+/// `JsonWithExtra` extractors if payload exceeds the limit and truncation
+/// is enabled with
+/// [`JsonConfig::truncate_oversized()`](struct.JsonConfig.html#method.truncate_oversized).
+///
+/// ## Example
+///
+/// ```rust
+/// use actix_web::{web, HttpMessage, HttpRequest};
+///
+/// async fn ingest(req: HttpRequest, event: web::Json<serde_json::Value>) -> String {
+///     if req.extensions().contains::<web::JsonTruncated>() {
+///         format!("Sampled truncated event {}", event.0)
+///     } else {
+///         format!("Sampled event {}", event.0)
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JsonTruncated;
+
+/// Json extractor configuration
+///
+/// ```rust
+/// use actix_web::{error, web, App, FromRequest, HttpResponse};
+/// use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Info {
+///     username: String,
+/// }
+///
+/// /// deserialize `Info` from request's body, max payload size is 4kb
+/// async fn index(info: web::Json<Info>) -> String {
+///     format!("Welcome {}!", info.username)
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/index.html")
+///             .app_data(
+///                 // change json extractor configuration
+///                 web::Json::<Info>::configure(|cfg| {
+///                     cfg.limit(4096)
+///                        .content_type(|mime| {  // <- accept text/plain content type
+///                            mime.type_() == mime::TEXT && mime.subtype() == mime::PLAIN
+///                        })
+///                        .error_handler(|err, req| {  // <- create custom error response
+///                           error::InternalError::from_response(
+///                               err, HttpResponse::Conflict().finish()).into()
+///                        })
+///             }))
+///             .route(web::post().to(index))
+///     );
+/// }
+/// ```
+#[derive(Clone)]
+pub struct JsonConfig {
+    limit: usize,
+    ehandler: Option<Arc<dyn Fn(JsonPayloadError, &HttpRequest) -> Error + Send + Sync>>,
     content_type: Option<Arc<dyn Fn(mime::Mime) -> bool + Send + Sync>>,
+    require_object: bool,
+    max_string_length: Option<usize>,
+    max_object_entries: Option<usize>,
+    drain: bool,
+    drain_limit: usize,
+    assume_json: bool,
+    truncate: bool,
+    track_paths: bool,
+    encoding: Option<ContentEncoding>,
+    pretty_query: Option<&'static str>,
+    response_content_type: &'static str,
 }
 
 impl JsonConfig {
@@ -272,15 +1134,194 @@ impl JsonConfig {
         self.content_type = Some(Arc::new(predicate));
         self
     }
+
+    /// Reject payloads which top-level value is not a json object.
+    ///
+    /// Arrays and scalars are rejected with `JsonPayloadError::NotObject`
+    /// before deserialization. By default any json value is accepted.
+    pub fn require_object(mut self, require: bool) -> Self {
+        self.require_object = require;
+        self
+    }
+
+    /// Set maximum length of any string value or object key in payload.
+    ///
+    /// Length is measured in chars, escape sequence and escaped surrogate pair
+    /// count as a single char. Longer strings are rejected with
+    /// `JsonPayloadError::StringTooLong` before deserialization. By default
+    /// string length is not limited.
+    pub fn max_string_length(mut self, max: usize) -> Self {
+        self.max_string_length = Some(max);
+        self
+    }
+
+    /// Set maximum number of entries of any object in payload.
+    ///
+    /// Bounds size of maps, i.e. `Json<HashMap<String, T>>`, which keys are
+    /// chosen by the client. Entries are counted while payload is scanned
+    /// before deserialization, objects with more entries are rejected with
+    /// `JsonPayloadError::TooManyEntries` before any map is built. Hasher of
+    /// the map is chosen by the extracted type, default `HashMap` hasher is
+    /// randomly seeded, which protects from hash collision attacks. By
+    /// default number of entries is not limited.
+    pub fn max_object_entries(mut self, max: usize) -> Self {
+        self.max_object_entries = Some(max);
+        self
+    }
+
+    /// Read remaining payload if extraction fails before payload is consumed.
+    ///
+    /// Draining lets keep-alive connection serve next requests, otherwise
+    /// unread payload is dropped. At most `drain_limit()` bytes are read,
+    /// connection with larger payload is closed. Payload with content type
+    /// which is not json is drained as well. By default payload is not
+    /// drained.
+    pub fn on_error_drain(mut self, drain: bool) -> Self {
+        self.drain = drain;
+        self
+    }
+
+    /// Set max number of bytes read by `on_error_drain()`.
+    ///
+    /// By default up to 256Kb are drained.
+    pub fn drain_limit(mut self, limit: usize) -> Self {
+        self.drain_limit = limit;
+        self
+    }
+
+    /// Parse payload as json if request has no `Content-Type` header.
+    ///
+    /// Requests with content type which is not json are still rejected.
+    /// By default requests without content type are rejected.
+    pub fn assume_json_when_absent(mut self, assume: bool) -> Self {
+        self.assume_json = assume;
+        self
+    }
+
+    /// Parse first `limit` bytes of oversized payload instead of rejecting it.
+    ///
+    /// Reading stops at the limit and the prefix is deserialized, so it still
+    /// has to be valid json, i.e. a record padded with whitespace. Rest of
+    /// payload is not read unless `on_error_drain()` is enabled. Extractors
+    /// flag truncated payload by inserting
+    /// [`JsonTruncated`](struct.JsonTruncated.html) into request extensions.
+    /// By default oversized payloads are rejected with
+    /// `JsonPayloadError::Overflow`.
+    pub fn truncate_oversized(mut self, truncate: bool) -> Self {
+        self.truncate = truncate;
+        self
+    }
+
+    /// Report path of the field which caused data error.
+    ///
+    /// Data errors of a field are reported as
+    /// `JsonPayloadError::DeserializeField` with path of the field, i.e.
+    /// `items[2].name`, see `JsonPayloadError::field_errors()`. Path
+    /// tracking allocates for each visited object key. By default errors are
+    /// reported as `JsonPayloadError::Deserialize` without path.
+    ///
+    /// Has no effect if `simd-json` feature is enabled.
+    pub fn track_field_paths(mut self, track: bool) -> Self {
+        self.track_paths = track;
+        self
+    }
+
+    /// Require payload to be sent with given `Content-Encoding`.
+    ///
+    /// Header is checked before payload is decompressed, requests without
+    /// the header or with other encoding are rejected with
+    /// `JsonPayloadError::ContentEncoding` (`415 Unsupported Media Type`).
+    /// `ContentEncoding::Identity` rejects any encoded payload.
+    ///
+    /// ```rust
+    /// use actix_web::{http::ContentEncoding, web, App};
+    ///
+    /// let app = App::new().service(
+    ///     web::resource("/events")
+    ///         .app_data(web::JsonConfig::default().require_encoding(ContentEncoding::Gzip))
+    /// );
+    /// ```
+    pub fn require_encoding(mut self, encoding: ContentEncoding) -> Self {
+        self.encoding = Some(encoding);
+        self
+    }
+
+    /// Indent `Json` responses if request has query parameter `name`.
+    ///
+    /// Any value of the parameter except `0` and `false` enables indented
+    /// output, i.e. `?pretty` or `?pretty=1`. Config applies to handlers of
+    /// the resource it is registered for. By default `Json` responses are
+    /// not indented.
+    ///
+    /// ```rust
+    /// use actix_web::{web, App};
+    ///
+    /// let app = App::new().service(
+    ///     web::resource("/status")
+    ///         .app_data(web::JsonConfig::default().pretty_query("pretty"))
+    ///         .to(|| async { web::Json(vec!["worker-1", "worker-2"]) })
+    /// );
+    /// ```
+    pub fn pretty_query(mut self, name: &'static str) -> Self {
+        self.pretty_query = Some(name);
+        self
+    }
+
+    /// Set `Content-Type` of `Json`, `JsonPretty`, `JsonStream` and
+    /// `JsonWriter` responses.
+    ///
+    /// Does not affect content type check of request payload. By default
+    /// responses have `application/json; charset=utf-8` content type.
+    ///
+    /// Panics if `value` is not a valid header value.
+    pub fn response_content_type(mut self, value: &'static str) -> Self {
+        HeaderValue::from_static(value);
+        self.response_content_type = value;
+        self
+    }
+
+    /// Check if request query asks for indented response.
+    fn pretty_requested(&self, req: &HttpRequest) -> bool {
+        let name = match self.pretty_query {
+            Some(name) => name,
+            None => return false,
+        };
+        serde_urlencoded::from_str::<Vec<(String, String)>>(req.query_string())
+            .map(|params| {
+                params
+                    .iter()
+                    .any(|(key, value)| key == name && value != "0" && value != "false")
+            })
+            .unwrap_or(false)
+    }
+
+    /// Extract json config from app data, fall back to the default config.
+    fn from_req(req: &HttpRequest) -> &Self {
+        req.app_data::<Self>().unwrap_or(&DEFAULT_CONFIG)
+    }
 }
 
+/// Allow shared refs to default.
+const DEFAULT_CONFIG: JsonConfig = JsonConfig {
+    limit: 32768, // 2^15 bytes, (~32kB)
+    ehandler: None,
+    content_type: None,
+    require_object: false,
+    max_string_length: None,
+    max_object_entries: None,
+    drain: false,
+    drain_limit: 262_144,
+    assume_json: false,
+    truncate: false,
+    track_paths: false,
+    encoding: None,
+    pretty_query: None,
+    response_content_type: "application/json; charset=utf-8",
+};
+
 impl Default for JsonConfig {
     fn default() -> Self {
-        JsonConfig {
-            limit: 32768,
-            ehandler: None,
-            content_type: None,
-        }
+        DEFAULT_CONFIG.clone()
     }
 }
 
@@ -294,13 +1335,24 @@ impl Default for JsonConfig {
 /// * content length is greater than 256k
 pub struct JsonBody<U> {
     limit: usize,
+    require_object: bool,
+    max_string_length: Option<usize>,
+    max_object_entries: Option<usize>,
+    drain: Option<usize>,
+    truncate: bool,
+    truncated: bool,
+    track_paths: bool,
+    ndjson: bool,
+    ctype_absent: bool,
+    encoding: Option<HeaderValue>,
     length: Option<usize>,
     #[cfg(feature = "compress")]
     stream: Option<Decompress<Payload>>,
     #[cfg(not(feature = "compress"))]
     stream: Option<Payload>,
     err: Option<JsonPayloadError>,
-    fut: Option<LocalBoxFuture<'static, Result<U, JsonPayloadError>>>,
+    fut: Option<LocalBoxFuture<'static, Result<(Bytes, bool), JsonPayloadError>>>,
+    _t: PhantomData<U>,
 }
 
 impl<U> JsonBody<U>
@@ -308,13 +1360,56 @@ where
     U: DeserializeOwned + 'static,
 {
     /// Create `JsonBody` for request.
+    ///
+    /// Payload is left in place if request content type is not json, so
+    /// other extractors can still read it.
     pub fn new(
         req: &HttpRequest,
         payload: &mut Payload,
         ctype: Option<Arc<dyn Fn(mime::Mime) -> bool + Send + Sync>>,
+    ) -> Self {
+        let mut body = JsonBody::create(req, ctype);
+        // content type of request without it can be assumed later
+        if body.err.is_none() || body.ctype_absent || body.ndjson {
+            body.take_payload(req, payload);
+        }
+        body
+    }
+
+    /// Create `JsonBody` for request with checks configured by `config`.
+    ///
+    /// Payload is taken only if it is going to be parsed or drained.
+    pub fn from_config(
+        req: &HttpRequest,
+        payload: &mut Payload,
+        config: &JsonConfig,
+    ) -> Self {
+        let mut body = JsonBody::create(req, config.content_type.clone())
+            .limit(config.limit)
+            .require_object(config.require_object)
+            .max_string_length(config.max_string_length)
+            .max_object_entries(config.max_object_entries)
+            .on_error_drain(config.drain)
+            .drain_limit(config.drain_limit)
+            .assume_json_when_absent(config.assume_json)
+            .truncate_oversized(config.truncate)
+            .track_field_paths(config.track_paths)
+            .require_encoding(config.encoding);
+        if body.err.is_none() || body.drain.is_some() {
+            body.take_payload(req, payload);
+        }
+        body
+    }
+
+    /// Check request headers, payload is not taken yet.
+    fn create(
+        req: &HttpRequest,
+        ctype: Option<Arc<dyn Fn(mime::Mime) -> bool + Send + Sync>>,
     ) -> Self {
         // check content-type
+        let mut ndjson = false;
         let json = if let Ok(Some(mime)) = req.mime_type() {
+            ndjson = mime.subtype() == "x-ndjson";
             mime.subtype() == mime::JSON
                 || mime.suffix() == Some(mime::JSON)
                 || ctype.as_ref().map_or(false, |predicate| predicate(mime))
@@ -322,62 +1417,252 @@ where
             false
         };
 
-        if !json {
-            return JsonBody {
-                limit: 262_144,
-                length: None,
-                stream: None,
-                fut: None,
-                err: Some(JsonPayloadError::ContentType),
-            };
-        }
-
         let len = req
             .headers()
             .get(&CONTENT_LENGTH)
             .and_then(|l| l.to_str().ok())
             .and_then(|s| s.parse::<usize>().ok());
 
-        #[cfg(feature = "compress")]
-        let payload = Decompress::from_headers(payload.take(), req.headers());
-        #[cfg(not(feature = "compress"))]
-        let payload = payload.take();
-
         JsonBody {
             limit: 262_144,
+            require_object: false,
+            max_string_length: None,
+            max_object_entries: None,
+            drain: None,
+            truncate: false,
+            truncated: false,
+            track_paths: false,
+            ndjson,
+            ctype_absent: !req.headers().contains_key(CONTENT_TYPE),
+            encoding: req.headers().get(&CONTENT_ENCODING).cloned(),
             length: len,
-            stream: Some(payload),
+            stream: None,
             fut: None,
-            err: None,
+            err: if json {
+                None
+            } else {
+                Some(JsonPayloadError::ContentType)
+            },
+            _t: PhantomData,
         }
     }
 
+    fn take_payload(&mut self, req: &HttpRequest, payload: &mut Payload) {
+        #[cfg(feature = "compress")]
+        let payload = Decompress::from_headers(payload.take(), req.headers());
+        #[cfg(not(feature = "compress"))]
+        let payload = {
+            let _ = req;
+            payload.take()
+        };
+        self.stream = Some(payload);
+    }
+
     /// Change max size of payload. By default max size is 256Kb
     pub fn limit(mut self, limit: usize) -> Self {
         self.limit = limit;
         self
     }
-}
 
-impl<U> Future for JsonBody<U>
-where
-    U: DeserializeOwned + 'static,
-{
-    type Output = Result<U, JsonPayloadError>;
+    /// Reject payloads which top-level value is not a json object.
+    pub fn require_object(mut self, require: bool) -> Self {
+        self.require_object = require;
+        self
+    }
 
-    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        if let Some(ref mut fut) = self.fut {
-            return Pin::new(fut).poll(cx);
-        }
+    /// Set maximum length of any string value or object key in payload.
+    pub fn max_string_length(mut self, max: Option<usize>) -> Self {
+        self.max_string_length = max;
+        self
+    }
+
+    /// Set maximum number of entries of any object in payload.
+    pub fn max_object_entries(mut self, max: Option<usize>) -> Self {
+        self.max_object_entries = max;
+        self
+    }
+
+    /// Read remaining payload if extraction fails before payload is consumed.
+    ///
+    /// Payload with content type which is not json is not taken by
+    /// `new()`, so it is not drained, see `from_config()`.
+    pub fn on_error_drain(mut self, drain: bool) -> Self {
+        self.drain = if drain {
+            Some(self.drain.unwrap_or(262_144))
+        } else {
+            None
+        };
+        self
+    }
+
+    /// Set max number of bytes read by `on_error_drain()`, connection with
+    /// larger payload is closed. By default limit is 256Kb.
+    pub fn drain_limit(mut self, limit: usize) -> Self {
+        if self.drain.is_some() {
+            self.drain = Some(limit);
+        }
+        self
+    }
+
+    /// Parse payload as json if request has no `Content-Type` header.
+    pub fn assume_json_when_absent(mut self, assume: bool) -> Self {
+        if assume && self.ctype_absent {
+            if let Some(JsonPayloadError::ContentType) = self.err {
+                self.err = None;
+            }
+        }
+        self
+    }
+
+    /// Parse first `limit` bytes of oversized payload instead of rejecting it.
+    pub fn truncate_oversized(mut self, truncate: bool) -> Self {
+        self.truncate = truncate;
+        self
+    }
+
+    /// Report path of the field which caused data error.
+    pub fn track_field_paths(mut self, track: bool) -> Self {
+        self.track_paths = track;
+        self
+    }
+
+    /// Reject payload which is not sent with given `Content-Encoding`.
+    pub fn require_encoding(mut self, encoding: Option<ContentEncoding>) -> Self {
+        if let Some(encoding) = encoding {
+            let matches = match self.encoding {
+                Some(ref value) => match value.to_str() {
+                    Ok(value) => value.trim().eq_ignore_ascii_case(encoding.as_str()),
+                    Err(_) => false,
+                },
+                None => encoding == ContentEncoding::Identity,
+            };
+            if !matches {
+                self.err = Some(JsonPayloadError::ContentEncoding);
+            }
+        }
+        self
+    }
+
+    /// Returns true if payload exceeded the limit and was truncated.
+    ///
+    /// Flag is set once body resolves to deserialized value.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Deserialize each line of payload independently.
+    ///
+    /// Returns stream of records of newline delimited json payload, empty
+    /// lines are skipped. Limit applies to each line instead of the whole
+    /// payload. In addition to json content types, `application/x-ndjson`
+    /// content type is accepted.
+    pub fn newline_delimited(self) -> JsonLines<U> {
+        let err = match self.err {
+            Some(JsonPayloadError::ContentType) if self.ndjson => None,
+            err => err,
+        };
+
+        JsonLines {
+            limit: self.limit,
+            line: 0,
+            buf: BytesMut::new(),
+            stream: self.stream,
+            err,
+            _t: PhantomData,
+        }
+    }
+
+    /// Resolve to `err`, draining remaining payload first if configured.
+    fn fail(&mut self, err: JsonPayloadError) -> Option<JsonPayloadError> {
+        match (self.stream.take(), self.drain) {
+            (Some(mut stream), Some(max)) => {
+                self.fut = Some(
+                    async move {
+                        drain_payload(&mut stream, max).await;
+                        Err(err)
+                    }
+                    .boxed_local(),
+                );
+                None
+            }
+            _ => Some(err),
+        }
+    }
+}
+
+impl JsonBody<IgnoredAny> {
+    /// Deserialize single top-level field of json object payload.
+    ///
+    /// Resolves to `None` if payload object has no such field. Payload is
+    /// parsed once, other fields are skipped without being deserialized, so
+    /// payload does not have to match any type. Limit and other checks apply
+    /// to the whole payload, duplicate field is rejected.
+    ///
+    /// ```rust
+    /// use actix_web::{dev, HttpRequest};
+    ///
+    /// fn tenant(
+    ///     req: &HttpRequest,
+    ///     payload: &mut dev::Payload,
+    /// ) -> dev::JsonField<String> {
+    ///     dev::JsonBody::new(req, payload, None).extract_field("tenant_id")
+    /// }
+    /// ```
+    pub fn extract_field<T>(self, name: &str) -> JsonField<T>
+    where
+        T: DeserializeOwned,
+    {
+        JsonField {
+            body: self,
+            name: name.to_owned(),
+            _t: PhantomData,
+        }
+    }
+}
+
+impl<U> JsonBody<U>
+where
+    U: DeserializeOwned + 'static,
+{
+    /// Poll for deserialized value along with raw payload bytes.
+    fn poll_with_raw(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(U, Bytes), JsonPayloadError>> {
+        let raw = ready!(self.poll_body(cx))?;
+        Poll::Ready(from_slice::<U>(&raw, self.track_paths).map(|value| (value, raw)))
+    }
+
+    /// Poll for buffered payload which passed configured checks.
+    fn poll_body(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Bytes, JsonPayloadError>> {
+        if let Some(ref mut fut) = self.fut {
+            let (raw, truncated) = ready!(Pin::new(fut).poll(cx))?;
+            self.truncated = truncated;
+            return Poll::Ready(Ok(raw));
+        }
 
         if let Some(err) = self.err.take() {
-            return Poll::Ready(Err(err));
+            return match self.fail(err) {
+                Some(err) => Poll::Ready(Err(err)),
+                None => self.poll_body(cx),
+            };
         }
 
         let limit = self.limit;
+        let require_object = self.require_object;
+        let max_string_length = self.max_string_length;
+        let max_object_entries = self.max_object_entries;
+        let drain = self.drain;
+        let truncate = self.truncate;
         if let Some(len) = self.length.take() {
-            if len > limit {
-                return Poll::Ready(Err(JsonPayloadError::Overflow));
+            if len > limit && !truncate {
+                return match self.fail(JsonPayloadError::Overflow { size: len, limit }) {
+                    Some(err) => Poll::Ready(Err(err)),
+                    None => self.poll_body(cx),
+                };
             }
         }
         let mut stream = self.stream.take().unwrap();
@@ -385,22 +1670,417 @@ where
         self.fut = Some(
             async move {
                 let mut body = BytesMut::with_capacity(8192);
+                let mut truncated = false;
 
                 while let Some(item) = stream.next().await {
                     let chunk = item?;
                     if (body.len() + chunk.len()) > limit {
-                        return Err(JsonPayloadError::Overflow);
+                        if let Some(max) = drain {
+                            drain_payload(&mut stream, max).await;
+                        }
+                        if !truncate {
+                            return Err(JsonPayloadError::Overflow {
+                                size: body.len() + chunk.len(),
+                                limit,
+                            });
+                        }
+                        let rest = limit - body.len();
+                        body.extend_from_slice(&chunk[..rest]);
+                        truncated = true;
+                        break;
                     } else {
                         body.extend_from_slice(&chunk);
                     }
                 }
-                Ok(serde_json::from_slice::<U>(&body)?)
+
+                if require_object {
+                    let first = body.iter().find(|b| !b.is_ascii_whitespace());
+                    if first != Some(&b'{') {
+                        return Err(JsonPayloadError::NotObject);
+                    }
+                }
+                if let Some(max) = max_string_length {
+                    if has_long_string(&body, max) {
+                        return Err(JsonPayloadError::StringTooLong);
+                    }
+                }
+                if let Some(limit) = max_object_entries {
+                    if has_large_object(&body, limit) {
+                        return Err(JsonPayloadError::TooManyEntries { limit });
+                    }
+                }
+                Ok((body.freeze(), truncated))
             }
             .boxed_local(),
         );
 
-        self.poll(cx)
+        self.poll_body(cx)
+    }
+}
+
+/// Read and discard remaining payload, give up after `max` bytes.
+async fn drain_payload<S>(stream: &mut S, max: usize)
+where
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin,
+{
+    let mut read = 0;
+    while let Some(Ok(chunk)) = stream.next().await {
+        read += chunk.len();
+        if read > max {
+            log::debug!("Payload is too large to drain, limit is {}", max);
+            break;
+        }
+    }
+}
+
+impl<U> Unpin for JsonBody<U> {}
+
+impl<U> Future for JsonBody<U>
+where
+    U: DeserializeOwned + 'static,
+{
+    type Output = Result<U, JsonPayloadError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.poll_with_raw(cx)
+            .map(|res| res.map(|(value, _)| value))
+    }
+}
+
+/// Deserialize buffered json payload.
+///
+/// If `track` is set, path of the field which caused data error is kept in
+/// the error, see `JsonPayloadError::field_errors()`.
+#[cfg(not(feature = "simd-json"))]
+fn from_slice<U: DeserializeOwned>(
+    body: &[u8],
+    track: bool,
+) -> Result<U, JsonPayloadError> {
+    let mut de = serde_json::Deserializer::from_slice(body);
+    let value = if track {
+        serde_path_to_error::deserialize(&mut de).map_err(|error| {
+            deserialize_error(error.path().to_string(), error.into_inner())
+        })?
+    } else {
+        U::deserialize(&mut de).map_err(JsonPayloadError::Deserialize)?
+    };
+    de.end().map_err(JsonPayloadError::Deserialize)?;
+    Ok(value)
+}
+
+/// Deserialize buffered json payload with simd accelerated parser.
+///
+/// Parser works in place, so payload is copied to keep raw bytes intact.
+/// Errors of simd parser can not be told apart from data errors, so all of
+/// them are reported as `JsonPayloadError::Deserialize` and `track` is
+/// ignored.
+#[cfg(feature = "simd-json")]
+fn from_slice<U: DeserializeOwned>(
+    body: &[u8],
+    _track: bool,
+) -> Result<U, JsonPayloadError> {
+    let mut buf = body.to_vec();
+    simdjson::Deserializer::from_slice(&mut buf)
+        .and_then(|mut de| U::deserialize(&mut de))
+        .map_err(|error| JsonPayloadError::Deserialize(de::Error::custom(error)))
+}
+
+/// Attach path of the failed field to data errors
+#[cfg(not(feature = "simd-json"))]
+fn deserialize_error(path: String, error: serde_json::Error) -> JsonPayloadError {
+    if error.is_data() && path != "." {
+        JsonPayloadError::DeserializeField { path, error }
+    } else {
+        JsonPayloadError::Deserialize(error)
+    }
+}
+
+/// Stream of newline delimited json records
+///
+/// Created by `JsonBody::newline_delimited()`. Malformed record is reported
+/// with `JsonPayloadError::DeserializeLine` error which contains line
+/// number, stream continues with the next line. Stream ends after payload
+/// or overflow error.
+pub struct JsonLines<U> {
+    limit: usize,
+    line: usize,
+    buf: BytesMut,
+    #[cfg(feature = "compress")]
+    stream: Option<Decompress<Payload>>,
+    #[cfg(not(feature = "compress"))]
+    stream: Option<Payload>,
+    err: Option<JsonPayloadError>,
+    _t: PhantomData<U>,
+}
+
+impl<U> Unpin for JsonLines<U> {}
+
+impl<U> JsonLines<U>
+where
+    U: DeserializeOwned,
+{
+    /// Deserialize next complete line from the buffer
+    fn next_line(&mut self, eof: bool) -> Option<Result<U, JsonPayloadError>> {
+        loop {
+            let line = match self.buf.iter().position(|b| *b == b'\n') {
+                Some(pos) => self.buf.split_to(pos + 1),
+                None if eof && !self.buf.is_empty() => self.buf.split(),
+                None => return None,
+            };
+            self.line += 1;
+
+            let line = trim_ascii_whitespace(&line);
+            if line.is_empty() {
+                continue;
+            }
+            if line.len() > self.limit {
+                self.stream.take();
+                return Some(Err(JsonPayloadError::Overflow {
+                    size: line.len(),
+                    limit: self.limit,
+                }));
+            }
+            return Some(serde_json::from_slice(line).map_err(|error| {
+                JsonPayloadError::DeserializeLine {
+                    line: self.line,
+                    error,
+                }
+            }));
+        }
+    }
+}
+
+impl<U> Stream for JsonLines<U>
+where
+    U: DeserializeOwned,
+{
+    type Item = Result<U, JsonPayloadError>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if let Some(err) = this.err.take() {
+            this.stream.take();
+            return Poll::Ready(Some(Err(err)));
+        }
+
+        loop {
+            if let Some(item) = this.next_line(this.stream.is_none()) {
+                return Poll::Ready(Some(item));
+            }
+
+            let stream = match this.stream {
+                Some(ref mut stream) => stream,
+                None => return Poll::Ready(None),
+            };
+            if this.buf.len() > this.limit {
+                let size = this.buf.len();
+                this.stream.take();
+                this.buf.clear();
+                return Poll::Ready(Some(Err(JsonPayloadError::Overflow {
+                    size,
+                    limit: this.limit,
+                })));
+            }
+
+            match ready!(Pin::new(stream).poll_next(cx)) {
+                Some(Ok(chunk)) => this.buf.extend_from_slice(&chunk),
+                Some(Err(e)) => {
+                    this.stream.take();
+                    this.buf.clear();
+                    return Poll::Ready(Some(Err(e.into())));
+                }
+                None => {
+                    this.stream.take();
+                }
+            }
+        }
+    }
+}
+
+/// Future of single json object field
+///
+/// Created by `JsonBody::extract_field()`.
+pub struct JsonField<T> {
+    body: JsonBody<IgnoredAny>,
+    name: String,
+    _t: PhantomData<T>,
+}
+
+impl<T> Unpin for JsonField<T> {}
+
+impl<T> Future for JsonField<T>
+where
+    T: DeserializeOwned,
+{
+    type Output = Result<Option<T>, JsonPayloadError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let body = ready!(this.body.poll_body(cx))?;
+        Poll::Ready(extract_field(&body, &this.name).map_err(JsonPayloadError::from))
+    }
+}
+
+/// Deserialize top-level field `name` of json object, skip other values.
+fn extract_field<T>(body: &[u8], name: &str) -> Result<Option<T>, serde_json::Error>
+where
+    T: DeserializeOwned,
+{
+    /// Compare object key with field name without allocation
+    struct KeySeed<'a>(&'a str);
+
+    impl<'de, 'a> DeserializeSeed<'de> for KeySeed<'a> {
+        type Value = bool;
+
+        fn deserialize<D>(self, deserializer: D) -> Result<bool, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            deserializer.deserialize_str(self)
+        }
+    }
+
+    impl<'de, 'a> Visitor<'de> for KeySeed<'a> {
+        type Value = bool;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a field name")
+        }
+
+        fn visit_str<E: de::Error>(self, key: &str) -> Result<bool, E> {
+            Ok(key == self.0)
+        }
+    }
+
+    struct FieldVisitor<'a, T>(&'a str, PhantomData<T>);
+
+    impl<'de, 'a, T> Visitor<'de> for FieldVisitor<'a, T>
+    where
+        T: DeserializeOwned,
+    {
+        type Value = Option<T>;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a json object")
+        }
+
+        fn visit_map<A>(self, mut map: A) -> Result<Option<T>, A::Error>
+        where
+            A: MapAccess<'de>,
+        {
+            let mut value = None;
+            while let Some(found) = map.next_key_seed(KeySeed(self.0))? {
+                if !found {
+                    map.next_value::<IgnoredAny>()?;
+                } else if value.is_some() {
+                    return Err(de::Error::custom(format_args!(
+                        "duplicate field `{}`",
+                        self.0
+                    )));
+                } else {
+                    value = Some(map.next_value()?);
+                }
+            }
+            Ok(value)
+        }
+    }
+
+    let mut de = serde_json::Deserializer::from_slice(body);
+    let value = de.deserialize_map(FieldVisitor(name, PhantomData))?;
+    de.end()?;
+    Ok(value)
+}
+
+fn trim_ascii_whitespace(line: &[u8]) -> &[u8] {
+    let start = line
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(line.len());
+    let end = line
+        .iter()
+        .rposition(|b| !b.is_ascii_whitespace())
+        .map_or(start, |pos| pos + 1);
+    &line[start..end]
+}
+
+/// Check if json text contains string longer than `max` chars.
+fn has_long_string(body: &[u8], max: usize) -> bool {
+    let mut iter = body.iter();
+    while let Some(b) = iter.next() {
+        if *b != b'"' {
+            continue;
+        }
+        let mut len = 0;
+        while let Some(b) = iter.next() {
+            match *b {
+                b'"' => break,
+                b'\\' => {
+                    // `\uXXXX` escape is followed by 4 hex digits
+                    if iter.next() == Some(&b'u') {
+                        let mut code = 0;
+                        for h in iter.by_ref().take(4) {
+                            code = code * 16 + (*h as char).to_digit(16).unwrap_or(0);
+                        }
+                        // low surrogate is a part of the preceding char
+                        if (0xDC00..=0xDFFF).contains(&code) {
+                            continue;
+                        }
+                    }
+                }
+                // utf-8 continuation byte
+                0x80..=0xBF => continue,
+                _ => (),
+            }
+            len += 1;
+            if len > max {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// Check if any object in json `body` has more than `max` entries.
+///
+/// Entries are counted by name separators, payload is not validated.
+fn has_large_object(body: &[u8], max: usize) -> bool {
+    // entries count of each open object, `None` for arrays
+    let mut stack: Vec<Option<usize>> = Vec::new();
+    let mut iter = body.iter();
+    while let Some(b) = iter.next() {
+        match *b {
+            b'"' => {
+                while let Some(b) = iter.next() {
+                    match *b {
+                        b'"' => break,
+                        b'\\' => {
+                            iter.next();
+                        }
+                        _ => (),
+                    }
+                }
+            }
+            b'{' => stack.push(Some(0)),
+            b'[' => stack.push(None),
+            b'}' | b']' => {
+                stack.pop();
+            }
+            b':' => {
+                if let Some(Some(count)) = stack.last_mut() {
+                    *count += 1;
+                    if *count > max {
+                        return true;
+                    }
+                }
+            }
+            _ => (),
+        }
     }
+    false
 }
 
 #[cfg(test)]
@@ -409,7 +2089,7 @@ mod tests {
     use serde_derive::{Deserialize, Serialize};
 
     use super::*;
-    use crate::error::InternalError;
+    use crate::error::{self, InternalError};
     use crate::http::header;
     use crate::test::{load_stream, TestRequest};
     use crate::HttpResponse;
@@ -421,14 +2101,26 @@ mod tests {
 
     fn json_eq(err: JsonPayloadError, other: JsonPayloadError) -> bool {
         match err {
-            JsonPayloadError::Overflow => match other {
-                JsonPayloadError::Overflow => true,
+            JsonPayloadError::Overflow { .. } => match other {
+                JsonPayloadError::Overflow { .. } => true,
                 _ => false,
             },
             JsonPayloadError::ContentType => match other {
                 JsonPayloadError::ContentType => true,
                 _ => false,
             },
+            JsonPayloadError::NotObject => match other {
+                JsonPayloadError::NotObject => true,
+                _ => false,
+            },
+            JsonPayloadError::StringTooLong => match other {
+                JsonPayloadError::StringTooLong => true,
+                _ => false,
+            },
+            JsonPayloadError::TooManyEntries { .. } => match other {
+                JsonPayloadError::TooManyEntries { .. } => true,
+                _ => false,
+            },
             _ => false,
         }
     }
@@ -437,161 +2129,1115 @@ mod tests {
     async fn test_responder() {
         let req = TestRequest::default().to_http_request();
 
-        let j = Json(MyObject {
-            name: "test".to_string(),
-        });
-        let resp = j.respond_to(&req).await.unwrap();
-        assert_eq!(resp.status(), StatusCode::OK);
+        let j = Json(MyObject {
+            name: "test".to_string(),
+        });
+        let resp = j.respond_to(&req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            header::HeaderValue::from_static("application/json; charset=utf-8")
+        );
+
+        use crate::responder::tests::BodyTest;
+        assert_eq!(resp.body().bin_ref(), b"{\"name\":\"test\"}");
+    }
+
+    #[actix_rt::test]
+    async fn test_responder_with_status() {
+        let req = TestRequest::default().to_http_request();
+
+        let j = Json(MyObject {
+            name: "test".to_string(),
+        });
+        let resp = j
+            .with_status(StatusCode::CREATED)
+            .respond_to(&req)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            header::HeaderValue::from_static("application/json; charset=utf-8")
+        );
+
+        use crate::responder::tests::BodyTest;
+        assert_eq!(resp.body().bin_ref(), b"{\"name\":\"test\"}");
+
+        // map keys must be strings
+        let mut map = std::collections::HashMap::new();
+        map.insert(vec![1u8], 1);
+        let err = Json(map)
+            .with_status(StatusCode::CREATED)
+            .respond_to(&req)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.as_response_error().error_response().status(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_responder_pretty() {
+        use crate::responder::tests::BodyTest;
+        let pretty = b"{\n  \"name\": \"test\"\n}";
+        let obj = || MyObject {
+            name: "test".to_string(),
+        };
+
+        let req = TestRequest::default().to_http_request();
+        let resp = Json(obj()).pretty().respond_to(&req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            header::HeaderValue::from_static("application/json; charset=utf-8")
+        );
+        assert_eq!(resp.body().bin_ref(), pretty);
+
+        // query parameter is ignored without config
+        let req = TestRequest::with_uri("/?pretty=1").to_http_request();
+        let resp = Json(obj()).respond_to(&req).await.unwrap();
+        assert_eq!(resp.body().bin_ref(), b"{\"name\":\"test\"}");
+
+        for (uri, indented) in &[
+            ("/?pretty=1", true),
+            ("/?a=b&pretty", true),
+            ("/?pretty=0", false),
+            ("/?pretty=false", false),
+            ("/?other=1", false),
+        ] {
+            let req = TestRequest::with_uri(uri)
+                .app_data(JsonConfig::default().pretty_query("pretty"))
+                .to_http_request();
+            let resp = Json(obj()).respond_to(&req).await.unwrap();
+            if *indented {
+                assert_eq!(resp.body().bin_ref(), pretty, "{}", uri);
+            } else {
+                assert_eq!(resp.body().bin_ref(), b"{\"name\":\"test\"}", "{}", uri);
+            }
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_responder_content_type() {
+        let req = TestRequest::default()
+            .app_data(
+                JsonConfig::default().response_content_type("application/vnd.api+json"),
+            )
+            .to_http_request();
+        let obj = || MyObject {
+            name: "test".to_string(),
+        };
+
+        let resp = Json(obj()).respond_to(&req).await.unwrap();
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            header::HeaderValue::from_static("application/vnd.api+json")
+        );
+        let resp = Json(obj()).pretty().respond_to(&req).await.unwrap();
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            header::HeaderValue::from_static("application/vnd.api+json")
+        );
+
+        // default response content type is accepted by extractor
+        let req = TestRequest::default().to_http_request();
+        let resp = Json(obj()).respond_to(&req).await.unwrap();
+        let ctype = resp.headers().get(header::CONTENT_TYPE).unwrap().clone();
+        let (req, mut pl) = TestRequest::default()
+            .header(header::CONTENT_TYPE, ctype)
+            .set_payload(Bytes::from_static(b"{\"name\": \"test\"}"))
+            .to_http_parts();
+        let s = Json::<MyObject>::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(s.into_inner(), obj());
+    }
+
+    async fn read_stream_body(mut resp: Response) -> Bytes {
+        let mut body = resp.take_body();
+        let mut bytes = BytesMut::new();
+        while let Some(chunk) = body.next().await {
+            bytes.extend_from_slice(&chunk.unwrap());
+        }
+        bytes.freeze()
+    }
+
+    #[actix_rt::test]
+    async fn test_json_writer_not_streaming() {
+        // all streaming slots are taken
+        let permits: Vec<_> = std::iter::from_fn(StreamingPermit::acquire).collect();
+
+        let req = TestRequest::default().to_http_request();
+        let data: Vec<u32> = (0..10_000).collect();
+        let mut resp = JsonWriter(data.clone()).respond_to(&req).await.unwrap();
+        let mut body = resp.take_body();
+        assert_eq!(
+            body.next().await.unwrap().unwrap(),
+            Bytes::from(serde_json::to_vec(&data).unwrap())
+        );
+        assert!(body.next().await.is_none());
+        drop(permits);
+    }
+
+    #[actix_rt::test]
+    async fn test_stream_responder() {
+        let req = TestRequest::default().to_http_request();
+
+        let items = vec![
+            MyObject {
+                name: "first".to_string(),
+            },
+            MyObject {
+                name: "second".to_string(),
+            },
+        ];
+        let stream = futures::stream::iter(items.into_iter().map(Ok::<_, Error>));
+        let resp = JsonStream(stream).respond_to(&req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            header::HeaderValue::from_static("application/json; charset=utf-8")
+        );
+        assert_eq!(
+            read_stream_body(resp).await,
+            Bytes::from_static(b"[{\"name\":\"first\"},{\"name\":\"second\"}]")
+        );
+
+        let stream = futures::stream::empty::<Result<MyObject, Error>>();
+        let resp = JsonStream(stream).respond_to(&req).await.unwrap();
+        assert_eq!(read_stream_body(resp).await, Bytes::from_static(b"[]"));
+
+        let stream = futures::stream::iter(vec![
+            Ok(1),
+            Err(error::ErrorInternalServerError("db error")),
+            Ok(3),
+        ]);
+        let mut resp = JsonStream(stream).respond_to(&req).await.unwrap();
+        let mut body = resp.take_body();
+        assert_eq!(
+            body.next().await.unwrap().unwrap(),
+            Bytes::from_static(b"[")
+        );
+        assert_eq!(
+            body.next().await.unwrap().unwrap(),
+            Bytes::from_static(b"1")
+        );
+        assert!(body.next().await.unwrap().is_err());
+        assert!(body.next().await.is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_custom_error_responder() {
+        let (req, mut pl) = TestRequest::default()
+            .header(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
+            )
+            .header(
+                header::CONTENT_LENGTH,
+                header::HeaderValue::from_static("16"),
+            )
+            .set_payload(Bytes::from_static(b"{\"name\": \"test\"}"))
+            .app_data(JsonConfig::default().limit(10).error_handler(|err, _| {
+                let msg = MyObject {
+                    name: "invalid request".to_string(),
+                };
+                let resp = HttpResponse::BadRequest()
+                    .body(serde_json::to_string(&msg).unwrap());
+                InternalError::from_response(err, resp).into()
+            }))
+            .to_http_parts();
+
+        let s = Json::<MyObject>::from_request(&req, &mut pl).await;
+        let mut resp = Response::from_error(s.err().unwrap().into());
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let body = load_stream(resp.take_body()).await.unwrap();
+        let msg: MyObject = serde_json::from_slice(&body).unwrap();
+        assert_eq!(msg.name, "invalid request");
+    }
+
+    #[actix_rt::test]
+    async fn test_extract() {
+        let (req, mut pl) = TestRequest::default()
+            .header(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
+            )
+            .header(
+                header::CONTENT_LENGTH,
+                header::HeaderValue::from_static("16"),
+            )
+            .set_payload(Bytes::from_static(b"{\"name\": \"test\"}"))
+            .to_http_parts();
+
+        let s = Json::<MyObject>::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(s.name, "test");
+        assert_eq!(
+            s.into_inner(),
+            MyObject {
+                name: "test".to_string()
+            }
+        );
+
+        let (req, mut pl) = TestRequest::default()
+            .header(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
+            )
+            .header(
+                header::CONTENT_LENGTH,
+                header::HeaderValue::from_static("16"),
+            )
+            .set_payload(Bytes::from_static(b"{\"name\": \"test\"}"))
+            .app_data(JsonConfig::default().limit(10))
+            .to_http_parts();
+
+        let s = Json::<MyObject>::from_request(&req, &mut pl).await;
+        assert!(
+            format!("{}", s.err().unwrap()).contains("Json payload 16 exceeds limit 10")
+        );
+
+        let (req, mut pl) = TestRequest::default()
+            .header(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
+            )
+            .header(
+                header::CONTENT_LENGTH,
+                header::HeaderValue::from_static("16"),
+            )
+            .set_payload(Bytes::from_static(b"{\"name\": \"test\"}"))
+            .app_data(
+                JsonConfig::default()
+                    .limit(10)
+                    .error_handler(|_, _| JsonPayloadError::ContentType.into()),
+            )
+            .to_http_parts();
+        let s = Json::<MyObject>::from_request(&req, &mut pl).await;
+        assert!(format!("{}", s.err().unwrap()).contains("Content type error"));
+    }
+
+    #[actix_rt::test]
+    async fn test_json_body() {
+        let (req, mut pl) = TestRequest::default().to_http_parts();
+        let json = JsonBody::<MyObject>::new(&req, &mut pl, None).await;
+        assert!(json_eq(json.err().unwrap(), JsonPayloadError::ContentType));
+
+        let (req, mut pl) = TestRequest::default()
+            .header(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/text"),
+            )
+            .to_http_parts();
+        let json = JsonBody::<MyObject>::new(&req, &mut pl, None).await;
+        assert!(json_eq(json.err().unwrap(), JsonPayloadError::ContentType));
+
+        let (req, mut pl) = TestRequest::default()
+            .header(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
+            )
+            .header(
+                header::CONTENT_LENGTH,
+                header::HeaderValue::from_static("10000"),
+            )
+            .to_http_parts();
+
+        let json = JsonBody::<MyObject>::new(&req, &mut pl, None)
+            .limit(100)
+            .await;
+        assert!(json_eq(
+            json.err().unwrap(),
+            JsonPayloadError::Overflow { size: 0, limit: 0 }
+        ));
+
+        let (req, mut pl) = TestRequest::default()
+            .header(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
+            )
+            .header(
+                header::CONTENT_LENGTH,
+                header::HeaderValue::from_static("16"),
+            )
+            .set_payload(Bytes::from_static(b"{\"name\": \"test\"}"))
+            .to_http_parts();
+
+        let json = JsonBody::<MyObject>::new(&req, &mut pl, None).await;
+        assert_eq!(
+            json.ok().unwrap(),
+            MyObject {
+                name: "test".to_owned()
+            }
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_json_overflow_size() {
+        let (req, mut pl) = TestRequest::default()
+            .header(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
+            )
+            .header(
+                header::CONTENT_LENGTH,
+                header::HeaderValue::from_static("512000"),
+            )
+            .to_http_parts();
+        let err = JsonBody::<MyObject>::new(&req, &mut pl, None)
+            .limit(262_144)
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Json payload 512000 exceeds limit 262144");
+
+        let (req, mut pl) = TestRequest::default()
+            .header(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
+            )
+            .set_payload(Bytes::from_static(b"{\"name\": \"test\"}"))
+            .to_http_parts();
+        let err = JsonBody::<MyObject>::new(&req, &mut pl, None)
+            .limit(10)
+            .await
+            .unwrap_err();
+        match err {
+            JsonPayloadError::Overflow { size, limit } => {
+                assert_eq!(size, 16);
+                assert_eq!(limit, 10);
+            }
+            _ => panic!("unexpected error: {}", err),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_json_body_extract_field() {
+        // rest of payload does not match any type and is skipped
+        let mut body = String::from("{\"items\": [");
+        for i in 0..1000 {
+            body.push_str(&format!("{{\"id\": {}, \"tags\": [null, 1.5, \"x\"]}},", i));
+        }
+        body.push_str("{}], \"tenant_id\": \"acme\", \"name\": 5}");
+        let len = body.len();
+
+        let req = || {
+            TestRequest::default().header(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
+            )
+        };
+
+        let (req1, mut pl) = req().set_payload(body.clone()).to_http_parts();
+        let tenant = JsonBody::new(&req1, &mut pl, None)
+            .limit(len)
+            .extract_field::<String>("tenant_id")
+            .await
+            .unwrap();
+        assert_eq!(tenant, Some("acme".to_owned()));
+
+        // the same payload can not be deserialized as a whole
+        let (req1, mut pl) = req().set_payload(body.clone()).to_http_parts();
+        let res = JsonBody::<MyObject>::new(&req1, &mut pl, None)
+            .limit(len)
+            .await;
+        assert!(res.is_err());
+
+        let (req1, mut pl) = req().set_payload(body.clone()).to_http_parts();
+        let res = JsonBody::new(&req1, &mut pl, None)
+            .limit(len - 1)
+            .extract_field::<String>("tenant_id")
+            .await;
+        assert!(json_eq(
+            res.err().unwrap(),
+            JsonPayloadError::Overflow { size: 0, limit: 0 }
+        ));
+
+        let (req1, mut pl) = req()
+            .set_payload(Bytes::from_static(b"{\"name\": \"test\"}"))
+            .to_http_parts();
+        let tenant = JsonBody::new(&req1, &mut pl, None)
+            .extract_field::<String>("tenant_id")
+            .await
+            .unwrap();
+        assert_eq!(tenant, None);
+
+        let (req1, mut pl) = req()
+            .set_payload(Bytes::from_static(b"{\"id\": 1, \"id\": 2}"))
+            .to_http_parts();
+        let res = JsonBody::new(&req1, &mut pl, None)
+            .extract_field::<u32>("id")
+            .await;
+        assert!(res.is_err());
+
+        let (req1, mut pl) = req()
+            .set_payload(Bytes::from_static(b"[{\"id\": 1}]"))
+            .to_http_parts();
+        let res = JsonBody::new(&req1, &mut pl, None)
+            .extract_field::<u32>("id")
+            .await;
+        assert!(res.is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_json_body_newline_delimited() {
+        let (req, mut pl) = TestRequest::default()
+            .header(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/x-ndjson"),
+            )
+            .set_payload(Bytes::from_static(
+                b"{\"name\": \"first\"}\r\n\n{\"name\": 1}\n{\"name\": \"third\"}",
+            ))
+            .to_http_parts();
+
+        let mut lines = JsonBody::<MyObject>::new(&req, &mut pl, None)
+            .limit(20)
+            .newline_delimited();
+        assert_eq!(
+            lines.next().await.unwrap().unwrap(),
+            MyObject {
+                name: "first".to_string()
+            }
+        );
+        match lines.next().await.unwrap() {
+            Err(JsonPayloadError::DeserializeLine { line, .. }) => assert_eq!(line, 3),
+            _ => panic!("error expected"),
+        }
+        assert_eq!(
+            lines.next().await.unwrap().unwrap(),
+            MyObject {
+                name: "third".to_string()
+            }
+        );
+        assert!(lines.next().await.is_none());
+
+        let (req, mut pl) = TestRequest::default()
+            .header(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
+            )
+            .set_payload(Bytes::from_static(
+                b"{\"name\": \"first\"}\n{\"name\": \"very long name\"}\n",
+            ))
+            .to_http_parts();
+
+        let mut lines = JsonBody::<MyObject>::new(&req, &mut pl, None)
+            .limit(20)
+            .newline_delimited();
+        assert!(lines.next().await.unwrap().is_ok());
+        assert!(json_eq(
+            lines.next().await.unwrap().err().unwrap(),
+            JsonPayloadError::Overflow { size: 0, limit: 0 }
+        ));
+        assert!(lines.next().await.is_none());
+
+        let (req, mut pl) = TestRequest::default().to_http_parts();
+        let mut lines =
+            JsonBody::<MyObject>::new(&req, &mut pl, None).newline_delimited();
+        assert!(json_eq(
+            lines.next().await.unwrap().err().unwrap(),
+            JsonPayloadError::ContentType
+        ));
+        assert!(lines.next().await.is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_json_with_raw() {
+        let (req, mut pl) = TestRequest::default()
+            .header(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
+            )
+            .header(
+                header::CONTENT_LENGTH,
+                header::HeaderValue::from_static("19"),
+            )
+            .set_payload(Bytes::from_static(b"{ \"name\" : \"test\" }"))
+            .to_http_parts();
+
+        let s = JsonWithRaw::<MyObject>::from_request(&req, &mut pl)
+            .await
+            .unwrap();
+        assert_eq!(s.name, "test");
+        assert_eq!(s.raw(), &Bytes::from_static(b"{ \"name\" : \"test\" }"));
+        let (obj, raw) = s.into_inner();
+        assert_eq!(
+            obj,
+            MyObject {
+                name: "test".to_string()
+            }
+        );
+        assert_eq!(raw.len(), 19);
+
+        let (req, mut pl) = TestRequest::default()
+            .header(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
+            )
+            .set_payload(Bytes::from_static(b"{\"name\": \"test\"}"))
+            .app_data(JsonConfig::default().limit(10))
+            .to_http_parts();
+        let s = JsonWithRaw::<MyObject>::from_request(&req, &mut pl).await;
+        let resp = Response::from_error(s.err().unwrap());
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[actix_rt::test]
+    async fn test_json_with_extra() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        #[serde(rename_all = "lowercase")]
+        enum Kind {
+            Create,
+        }
+
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Event {
+            kind: Kind,
+            tags: Option<Vec<String>>,
+            object: MyObject,
+        }
+
+        let (req, mut pl) = TestRequest::default()
+            .header(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
+            )
+            .set_payload(Bytes::from_static(
+                b"{\"kind\": \"create\", \"version\": 2, \
+                  \"object\": {\"name\": \"test\", \"id\": 1}, \
+                  \"meta\": {\"tags\": [\"a\"]}}",
+            ))
+            .to_http_parts();
+
+        let s = JsonWithExtra::<Event>::from_request(&req, &mut pl)
+            .await
+            .unwrap();
+        assert_eq!(s.kind, Kind::Create);
+        assert_eq!(s.extra().len(), 2);
+        let (event, extra) = s.into_inner();
+        assert_eq!(
+            event,
+            Event {
+                kind: Kind::Create,
+                tags: None,
+                object: MyObject {
+                    name: "test".to_string()
+                },
+            }
+        );
+        assert_eq!(extra["version"], serde_json::json!(2));
+        assert_eq!(extra["meta"], serde_json::json!({"tags": ["a"]}));
+
+        let (req, mut pl) = TestRequest::default()
+            .header(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
+            )
+            .set_payload(Bytes::from_static(b"{\"version\": 2}"))
+            .to_http_parts();
+        let s = JsonWithExtra::<Event>::from_request(&req, &mut pl).await;
+        let resp = Response::from_error(s.err().unwrap());
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn test_json_assume_json_when_absent() {
+        let config = JsonConfig::default().assume_json_when_absent(true);
+
+        let (req, mut pl) = TestRequest::default()
+            .set_payload(Bytes::from_static(b"{\"name\": \"test\"}"))
+            .app_data(config.clone())
+            .to_http_parts();
+        let s = Json::<MyObject>::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(s.name, "test");
+
+        let (req, mut pl) = TestRequest::default()
+            .header(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
+            )
+            .set_payload(Bytes::from_static(b"{\"name\": \"test\"}"))
+            .app_data(config)
+            .to_http_parts();
+        let s = Json::<MyObject>::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(s.name, "test");
+
+        let (req, mut pl) = TestRequest::default()
+            .header(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("text/plain"),
+            )
+            .set_payload(Bytes::from_static(b"{\"name\": \"test\"}"))
+            .to_http_parts();
+        let json = JsonBody::<MyObject>::new(&req, &mut pl, None)
+            .assume_json_when_absent(true)
+            .await;
+        assert!(json_eq(json.err().unwrap(), JsonPayloadError::ContentType));
+
+        // disabled by default
+        let (req, mut pl) = TestRequest::default()
+            .set_payload(Bytes::from_static(b"{\"name\": \"test\"}"))
+            .to_http_parts();
+        let s = Json::<MyObject>::from_request(&req, &mut pl).await;
+        assert!(s.is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_json_max_object_entries() {
+        use std::collections::HashMap;
+
+        let payload = b"{\"a\": 1, \"b\": 2, \"c\": 3, \"d\": 4}";
+        let (req, mut pl) = TestRequest::default()
+            .header(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
+            )
+            .set_payload(Bytes::from_static(payload))
+            .app_data(JsonConfig::default().max_object_entries(3))
+            .to_http_parts();
+        let s = Json::<HashMap<String, u32>>::from_request(&req, &mut pl).await;
+        let err = s.err().unwrap();
         assert_eq!(
-            resp.headers().get(header::CONTENT_TYPE).unwrap(),
-            header::HeaderValue::from_static("application/json")
+            err.as_response_error().error_response().status(),
+            StatusCode::BAD_REQUEST
         );
+        assert_eq!(err.to_string(), "Json object has more than 3 entries");
 
-        use crate::responder::tests::BodyTest;
-        assert_eq!(resp.body().bin_ref(), b"{\"name\":\"test\"}");
+        let (req, mut pl) = TestRequest::default()
+            .header(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
+            )
+            .set_payload(Bytes::from_static(payload))
+            .app_data(JsonConfig::default().max_object_entries(4))
+            .to_http_parts();
+        let s = Json::<HashMap<String, u32>>::from_request(&req, &mut pl)
+            .await
+            .unwrap();
+        assert_eq!(s.len(), 4);
+
+        assert!(!has_large_object(b"[{\"a\": 1}, {\"b\": \"x:y\"}]", 1));
+        assert!(has_large_object(b"{\"a\": {\"b\": 1, \"c\": 2}}", 1));
+        assert!(!has_large_object(b"{\"a\\\":\": [1, 2, 3]}", 1));
     }
 
     #[actix_rt::test]
-    async fn test_custom_error_responder() {
+    async fn test_json_max_string_length() {
         let (req, mut pl) = TestRequest::default()
             .header(
                 header::CONTENT_TYPE,
                 header::HeaderValue::from_static("application/json"),
             )
+            .set_payload(Bytes::from_static(b"{\"name\": \"very long name\"}"))
+            .to_http_parts();
+
+        let json = JsonBody::<MyObject>::new(&req, &mut pl, None)
+            .max_string_length(Some(8))
+            .await;
+        assert!(json_eq(
+            json.err().unwrap(),
+            JsonPayloadError::StringTooLong
+        ));
+
+        let (req, mut pl) = TestRequest::default()
             .header(
-                header::CONTENT_LENGTH,
-                header::HeaderValue::from_static("16"),
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
             )
-            .set_payload(Bytes::from_static(b"{\"name\": \"test\"}"))
-            .app_data(JsonConfig::default().limit(10).error_handler(|err, _| {
-                let msg = MyObject {
-                    name: "invalid request".to_string(),
-                };
-                let resp = HttpResponse::BadRequest()
-                    .body(serde_json::to_string(&msg).unwrap());
-                InternalError::from_response(err, resp).into()
-            }))
+            .set_payload(Bytes::from_static(b"{\"name\": \"very long name\"}"))
+            .app_data(JsonConfig::default().max_string_length(8))
             .to_http_parts();
-
         let s = Json::<MyObject>::from_request(&req, &mut pl).await;
-        let mut resp = Response::from_error(s.err().unwrap().into());
+        let resp = Response::from_error(s.err().unwrap());
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
 
-        let body = load_stream(resp.take_body()).await.unwrap();
-        let msg: MyObject = serde_json::from_slice(&body).unwrap();
-        assert_eq!(msg.name, "invalid request");
+        let (req, mut pl) = TestRequest::default()
+            .header(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
+            )
+            .set_payload(Bytes::from_static(
+                "{\"name\": \"t\\u00e9st\\\"é\"}".as_bytes(),
+            ))
+            .app_data(JsonConfig::default().max_string_length(8))
+            .to_http_parts();
+        let s = Json::<MyObject>::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(s.name, "tést\"é");
+
+        assert!(!has_long_string(b"[\"abc\", 12345678]", 3));
+        assert!(has_long_string(b"{\"abcd\": 1}", 3));
+
+        // surrogate pair is a single char
+        assert!(!has_long_string(b"[\"\\uD83D\\uDE00\"]", 1));
+        assert!(has_long_string(b"[\"a\\uD83D\\uDE00\"]", 1));
+        assert!(!has_long_string("[\"a😀\"]".as_bytes(), 2));
     }
 
     #[actix_rt::test]
-    async fn test_extract() {
+    async fn test_json_require_object() {
         let (req, mut pl) = TestRequest::default()
             .header(
                 header::CONTENT_TYPE,
                 header::HeaderValue::from_static("application/json"),
             )
+            .set_payload(Bytes::from_static(b" [{\"name\": \"test\"}]"))
+            .app_data(JsonConfig::default().require_object(true))
+            .to_http_parts();
+
+        let s = Json::<serde_json::Value>::from_request(&req, &mut pl).await;
+        let resp = Response::from_error(s.err().unwrap());
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let (req, mut pl) = TestRequest::default()
             .header(
-                header::CONTENT_LENGTH,
-                header::HeaderValue::from_static("16"),
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
             )
-            .set_payload(Bytes::from_static(b"{\"name\": \"test\"}"))
+            .set_payload(Bytes::from_static(b"\"test\""))
             .to_http_parts();
+        let json = JsonBody::<serde_json::Value>::new(&req, &mut pl, None)
+            .require_object(true)
+            .await;
+        assert!(json_eq(json.err().unwrap(), JsonPayloadError::NotObject));
 
+        let (req, mut pl) = TestRequest::default()
+            .header(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
+            )
+            .set_payload(Bytes::from_static(b"\n {\"name\": \"test\"}"))
+            .app_data(JsonConfig::default().require_object(true))
+            .to_http_parts();
         let s = Json::<MyObject>::from_request(&req, &mut pl).await.unwrap();
         assert_eq!(s.name, "test");
-        assert_eq!(
-            s.into_inner(),
-            MyObject {
-                name: "test".to_string()
-            }
-        );
+    }
+
+    #[actix_rt::test]
+    async fn test_json_string_number() {
+        #[derive(Deserialize, Debug)]
+        struct Counter {
+            count: u32,
+        }
 
         let (req, mut pl) = TestRequest::default()
             .header(
                 header::CONTENT_TYPE,
                 header::HeaderValue::from_static("application/json"),
             )
+            .set_payload(Bytes::from_static(b"{\"count\":\"5\"}"))
+            .to_http_parts();
+        let json = JsonBody::<Counter>::new(&req, &mut pl, None).await;
+        match json.err().unwrap() {
+            JsonPayloadError::Deserialize(error) => assert!(error.is_data()),
+            err => panic!("unexpected error: {}", err),
+        }
+
+        let (req, mut pl) = TestRequest::default()
             .header(
-                header::CONTENT_LENGTH,
-                header::HeaderValue::from_static("16"),
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
             )
-            .set_payload(Bytes::from_static(b"{\"name\": \"test\"}"))
-            .app_data(JsonConfig::default().limit(10))
+            .set_payload(Bytes::from_static(b"{\"count\":5}"))
             .to_http_parts();
+        let json = JsonBody::<Counter>::new(&req, &mut pl, None).await;
+        assert_eq!(json.ok().unwrap().count, 5);
+    }
 
-        let s = Json::<MyObject>::from_request(&req, &mut pl).await;
-        assert!(format!("{}", s.err().unwrap())
-            .contains("Json payload size is bigger than allowed"));
+    #[actix_rt::test]
+    async fn test_json_non_empty() {
+        let (req, mut pl) = TestRequest::default()
+            .header(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
+            )
+            .set_payload(Bytes::from_static(b"[]"))
+            .to_http_parts();
+
+        let s = Json::<NonEmpty<Vec<u32>>>::from_request(&req, &mut pl).await;
+        let err = s.err().unwrap();
+        assert!(err.to_string().contains("expected a non-empty collection"));
+        let resp = Response::from_error(err);
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
 
         let (req, mut pl) = TestRequest::default()
             .header(
                 header::CONTENT_TYPE,
                 header::HeaderValue::from_static("application/json"),
             )
+            .set_payload(Bytes::from_static(b"[1, 2]"))
+            .to_http_parts();
+
+        let s = Json::<NonEmpty<Vec<u32>>>::from_request(&req, &mut pl)
+            .await
+            .unwrap();
+        assert_eq!(s[0], 1);
+        assert_eq!(s.into_inner().into_inner(), vec![1, 2]);
+
+        let value: NonEmpty<std::collections::HashMap<String, u32>> =
+            serde_json::from_str("{\"a\": 1}").unwrap();
+        assert_eq!(serde_json::to_string(&value).unwrap(), "{\"a\":1}");
+        assert!(serde_json::from_str::<NonEmpty<Vec<u32>>>("[]").is_err());
+    }
+
+    #[cfg(not(feature = "simd-json"))]
+    #[actix_rt::test]
+    async fn test_json_track_field_paths() {
+        #[derive(Deserialize, Debug)]
+        struct Counter {
+            count: u32,
+        }
+
+        let (req, mut pl) = TestRequest::default()
             .header(
-                header::CONTENT_LENGTH,
-                header::HeaderValue::from_static("16"),
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
             )
-            .set_payload(Bytes::from_static(b"{\"name\": \"test\"}"))
-            .app_data(
-                JsonConfig::default()
-                    .limit(10)
-                    .error_handler(|_, _| JsonPayloadError::ContentType.into()),
+            .set_payload(Bytes::from_static(b"{\"count\":\"5\"}"))
+            .to_http_parts();
+        let json = JsonBody::<Counter>::new(&req, &mut pl, None)
+            .track_field_paths(true)
+            .await;
+        match json.err().unwrap() {
+            JsonPayloadError::DeserializeField { path, error } => {
+                assert_eq!(path, "count");
+                assert!(error.is_data());
+            }
+            err => panic!("unexpected error: {}", err),
+        }
+
+        let (req, mut pl) = TestRequest::default()
+            .header(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
             )
+            .set_payload(Bytes::from_static(b"[{\"count\":5},{\"count\":-1}]"))
+            .app_data(JsonConfig::default().track_field_paths(true))
             .to_http_parts();
-        let s = Json::<MyObject>::from_request(&req, &mut pl).await;
-        assert!(format!("{}", s.err().unwrap()).contains("Content type error"));
+        let res = Json::<Vec<Counter>>::from_request(&req, &mut pl).await;
+        let resp = HttpResponse::from_error(res.err().unwrap());
+        let err = resp.error().unwrap().as_error::<JsonPayloadError>();
+        assert_eq!(
+            err.unwrap().field_errors().keys().next().unwrap(),
+            "[1].count"
+        );
     }
 
     #[actix_rt::test]
-    async fn test_json_body() {
-        let (req, mut pl) = TestRequest::default().to_http_parts();
-        let json = JsonBody::<MyObject>::new(&req, &mut pl, None).await;
-        assert!(json_eq(json.err().unwrap(), JsonPayloadError::ContentType));
+    async fn test_json_body_error_drain() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        for &drain in &[false, true] {
+            let read = Rc::new(Cell::new(0));
+            let read2 = read.clone();
+            let stream = futures::stream::iter(vec![
+                Ok::<_, crate::error::PayloadError>(Bytes::from_static(b"[1,")),
+                Ok(Bytes::from_static(b"2,")),
+                Ok(Bytes::from_static(b"3]")),
+            ])
+            .inspect(move |_| read2.set(read2.get() + 1));
+
+            let (req, _) = TestRequest::default()
+                .header(
+                    header::CONTENT_TYPE,
+                    header::HeaderValue::from_static("application/json"),
+                )
+                .header(
+                    header::CONTENT_LENGTH,
+                    header::HeaderValue::from_static("7"),
+                )
+                .to_http_parts();
+            let stream: crate::dev::PayloadStream = Box::pin(stream);
+            let mut pl = Payload::Stream(stream);
+
+            let json = JsonBody::<Vec<u32>>::new(&req, &mut pl, None)
+                .limit(4)
+                .on_error_drain(drain)
+                .await;
+            assert!(json_eq(
+                json.err().unwrap(),
+                JsonPayloadError::Overflow { size: 0, limit: 0 }
+            ));
+            assert_eq!(read.get(), if drain { 3 } else { 0 });
+        }
 
+        // draining stops once limit is exceeded
+        let read = Rc::new(Cell::new(0));
+        let read2 = read.clone();
+        let stream = futures::stream::iter(vec![
+            Ok::<_, crate::error::PayloadError>(Bytes::from_static(b"[1,")),
+            Ok(Bytes::from_static(b"2,")),
+            Ok(Bytes::from_static(b"3]")),
+        ])
+        .inspect(move |_| read2.set(read2.get() + 1));
+        let (req, _) = TestRequest::default()
+            .header(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
+            )
+            .to_http_parts();
+        let stream: crate::dev::PayloadStream = Box::pin(stream);
+        let mut pl = Payload::Stream(stream);
+        let json = JsonBody::<Vec<u32>>::new(&req, &mut pl, None)
+            .limit(1)
+            .on_error_drain(true)
+            .drain_limit(3)
+            .await;
+        assert!(json.is_err());
+        assert_eq!(read.get(), 3);
+    }
+
+    #[actix_rt::test]
+    async fn test_json_content_type_leaves_payload() {
         let (req, mut pl) = TestRequest::default()
             .header(
                 header::CONTENT_TYPE,
-                header::HeaderValue::from_static("application/text"),
+                header::HeaderValue::from_static("text/plain"),
+            )
+            .set_payload(Bytes::from_static(b"hello"))
+            .to_http_parts();
+
+        let json = Option::<Json<MyObject>>::from_request(&req, &mut pl)
+            .await
+            .unwrap();
+        assert!(json.is_none());
+        let s = String::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(s, "hello");
+
+        let (req, mut pl) = TestRequest::default()
+            .header(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("text/plain"),
             )
+            .set_payload(Bytes::from_static(b"hello"))
             .to_http_parts();
         let json = JsonBody::<MyObject>::new(&req, &mut pl, None).await;
         assert!(json_eq(json.err().unwrap(), JsonPayloadError::ContentType));
+        let s = String::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(s, "hello");
+    }
 
+    #[actix_rt::test]
+    async fn test_json_truncate_oversized() {
+        // record padded with whitespace, valid prefix
+        let payload = format!("{}{}", "{\"name\": \"test\"}", " ".repeat(32));
         let (req, mut pl) = TestRequest::default()
             .header(
                 header::CONTENT_TYPE,
                 header::HeaderValue::from_static("application/json"),
             )
+            .header(header::CONTENT_LENGTH, payload.len())
+            .set_payload(payload.clone())
+            .to_http_parts();
+        let mut body = JsonBody::<MyObject>::new(&req, &mut pl, None)
+            .limit(20)
+            .truncate_oversized(true);
+        let json = (&mut body).await.unwrap();
+        assert_eq!(json.name, "test");
+        assert!(body.is_truncated());
+
+        let (req, mut pl) = TestRequest::default()
             .header(
-                header::CONTENT_LENGTH,
-                header::HeaderValue::from_static("10000"),
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
+            )
+            .set_payload(payload)
+            .app_data(JsonConfig::default().limit(20).truncate_oversized(true))
+            .to_http_parts();
+        let s = Json::<MyObject>::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(s.name, "test");
+        assert!(req.extensions().contains::<JsonTruncated>());
+
+        // payload within limit is not flagged
+        let (req, mut pl) = TestRequest::default()
+            .header(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
             )
+            .set_payload(Bytes::from_static(b"{\"name\": \"test\"}"))
+            .app_data(JsonConfig::default().limit(20).truncate_oversized(true))
             .to_http_parts();
+        let s = JsonWithRaw::<MyObject>::from_request(&req, &mut pl)
+            .await
+            .unwrap();
+        assert_eq!(s.name, "test");
+        assert!(!req.extensions().contains::<JsonTruncated>());
 
+        // truncated prefix still has to be valid json
+        let (req, mut pl) = TestRequest::default()
+            .header(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
+            )
+            .set_payload(Bytes::from_static(b"{\"name\": \"test test test\"}"))
+            .to_http_parts();
         let json = JsonBody::<MyObject>::new(&req, &mut pl, None)
-            .limit(100)
+            .limit(20)
+            .truncate_oversized(true)
             .await;
-        assert!(json_eq(json.err().unwrap(), JsonPayloadError::Overflow));
+        match json.err().unwrap() {
+            JsonPayloadError::Deserialize(_) => (),
+            err => panic!("Unexpected error: {}", err),
+        }
+    }
+
+    #[cfg(feature = "compress")]
+    #[actix_rt::test]
+    async fn test_json_require_encoding() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let mut enc = GzEncoder::new(Vec::new(), Compression::default());
+        enc.write_all(b"{\"name\": \"test\"}").unwrap();
+        let gzipped = Bytes::from(enc.finish().unwrap());
+        let config = JsonConfig::default().require_encoding(ContentEncoding::Gzip);
 
         let (req, mut pl) = TestRequest::default()
             .header(
                 header::CONTENT_TYPE,
                 header::HeaderValue::from_static("application/json"),
             )
+            .header(header::CONTENT_ENCODING, "gzip")
+            .set_payload(gzipped)
+            .app_data(config.clone())
+            .to_http_parts();
+        let s = Json::<MyObject>::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(s.name, "test");
+
+        // uncompressed payload is rejected
+        let (req, mut pl) = TestRequest::default()
             .header(
-                header::CONTENT_LENGTH,
-                header::HeaderValue::from_static("16"),
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
             )
             .set_payload(Bytes::from_static(b"{\"name\": \"test\"}"))
+            .app_data(config)
             .to_http_parts();
-
-        let json = JsonBody::<MyObject>::new(&req, &mut pl, None).await;
+        let e = Json::<MyObject>::from_request(&req, &mut pl)
+            .await
+            .err()
+            .unwrap();
         assert_eq!(
-            json.ok().unwrap(),
-            MyObject {
-                name: "test".to_owned()
-            }
+            e.as_response_error().error_response().status(),
+            StatusCode::UNSUPPORTED_MEDIA_TYPE
         );
+
+        // other encoding is rejected
+        let (req, mut pl) = TestRequest::default()
+            .header(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
+            )
+            .header(header::CONTENT_ENCODING, "deflate")
+            .to_http_parts();
+        let json = JsonBody::<MyObject>::new(&req, &mut pl, None)
+            .require_encoding(Some(ContentEncoding::Gzip))
+            .await;
+        match json.err().unwrap() {
+            JsonPayloadError::ContentEncoding => (),
+            err => panic!("Unexpected error: {}", err),
+        }
     }
 
     #[actix_rt::test]
@@ -651,4 +3297,129 @@ mod tests {
         let s = Json::<MyObject>::from_request(&req, &mut pl).await;
         assert!(s.is_err())
     }
+    #[actix_rt::test]
+    async fn test_with_json_and_vendor_content_types() {
+        let request = |ctype: &'static str, config: JsonConfig| {
+            TestRequest::with_header(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static(ctype),
+            )
+            .set_payload(Bytes::from_static(b"{\"name\": \"test\"}"))
+            .app_data(config)
+            .to_http_parts()
+        };
+
+        // `+json` suffix is accepted by default
+        let (req, mut pl) = request("application/vnd.api+json", JsonConfig::default());
+        let s = Json::<MyObject>::from_request(&req, &mut pl).await;
+        assert!(s.is_ok());
+
+        let (req, mut pl) = request("application/json-seq", JsonConfig::default());
+        let s = Json::<MyObject>::from_request(&req, &mut pl).await;
+        assert!(s.is_err());
+
+        let config = JsonConfig::default().content_type(|mime: mime::Mime| {
+            mime.type_() == mime::APPLICATION && mime.subtype() == "json-seq"
+        });
+        let (req, mut pl) = request("application/json-seq", config.clone());
+        let s = Json::<MyObject>::from_request(&req, &mut pl).await;
+        assert!(s.is_ok());
+
+        let (req, mut pl) = request("application/json", config);
+        let s = Json::<MyObject>::from_request(&req, &mut pl).await;
+        assert!(s.is_ok());
+    }
+
+    #[cfg(feature = "simd-json")]
+    #[test]
+    fn test_simd_json_matches_serde_json() {
+        use std::collections::HashMap;
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Record {
+            id: u64,
+            name: String,
+            tags: Vec<String>,
+            score: f64,
+            meta: HashMap<String, Option<bool>>,
+        }
+
+        let payloads: &[&[u8]] = &[
+            br#"{"id":1,"name":"a\"b\u00e9","tags":[],"score":1.5,"meta":{}}"#,
+            br#" {"id": 2, "name": "", "tags": ["x", "y"], "score": -0.25,
+                 "meta": {"a": true, "b": null}} "#,
+            br#"{"id":3,"name":"c","tags":["z"],"score":1e3,"meta":{},"extra":[1]}"#,
+        ];
+        for payload in payloads {
+            let simd: Record = from_slice(payload, false).unwrap();
+            let serde: Record = serde_json::from_slice(payload).unwrap();
+            assert_eq!(simd, serde);
+
+            let simd: serde_json::Value = from_slice(payload, false).unwrap();
+            let serde: serde_json::Value = serde_json::from_slice(payload).unwrap();
+            assert_eq!(simd, serde);
+        }
+
+        let invalid: &[&[u8]] = &[
+            b"",
+            b"{",
+            br#"{"id":1}"#,
+            br#"{"id":"1","name":"a","tags":[],"score":1,"meta":{}}"#,
+            br#"{"id":1,"name":"a","tags":[],"score":1,"meta":{}} x"#,
+        ];
+        for payload in invalid {
+            match from_slice::<Record>(payload, true) {
+                Err(JsonPayloadError::Deserialize(_)) => (),
+                res => panic!("unexpected result: {:?}", res),
+            }
+            assert!(serde_json::from_slice::<Record>(payload).is_err());
+        }
+    }
+
+    #[cfg(feature = "simd-json")]
+    #[test]
+    fn test_simd_json_large_payload() {
+        let item = r#"{"name":"test","values":[1,2,3,4,5,6,7,8],"nested":{"a":"b"}}"#;
+        let payload = format!("[{}]", vec![item; 1000].join(","));
+
+        let simd: serde_json::Value = from_slice(payload.as_bytes(), false).unwrap();
+        let serde: serde_json::Value =
+            serde_json::from_slice(payload.as_bytes()).unwrap();
+        assert_eq!(simd, serde);
+        assert_eq!(simd.as_array().unwrap().len(), 1000);
+    }
+
+    /// Compares parse time of both backends, run with
+    /// `cargo test --features simd-json bench_simd_json -- --ignored --nocapture`
+    #[cfg(feature = "simd-json")]
+    #[test]
+    #[ignore]
+    fn bench_simd_json_parse_time() {
+        use std::time::Instant;
+
+        let item = r#"{"name":"test","values":[1,2,3,4,5,6,7,8],"nested":{"a":"b"}}"#;
+        let payload = format!("[{}]", vec![item; 10_000].join(","));
+        let rounds = 100;
+
+        let start = Instant::now();
+        for _ in 0..rounds {
+            let _: serde_json::Value = from_slice(payload.as_bytes(), false).unwrap();
+        }
+        let simd = start.elapsed();
+
+        let start = Instant::now();
+        for _ in 0..rounds {
+            let _: serde_json::Value =
+                serde_json::from_slice(payload.as_bytes()).unwrap();
+        }
+        let serde = start.elapsed();
+
+        println!(
+            "{} bytes x {}: simd-json {:?}, serde_json {:?}",
+            payload.len(),
+            rounds,
+            simd,
+            serde
+        );
+    }
 }
@@ -1,15 +1,36 @@
 //! Helper types
 
+mod bearer;
+mod cached;
+mod client_ip;
+mod cookies;
+mod deadline;
 pub(crate) mod form;
 pub(crate) mod json;
+#[cfg(feature = "msgpack")]
+pub(crate) mod msgpack;
 mod path;
 pub(crate) mod payload;
 mod query;
 pub(crate) mod readlines;
+mod require_headers;
 
+pub use self::bearer::{Bearer, BearerConfig};
+pub use self::cached::Cached;
+pub use self::client_ip::{ClientIp, ClientIpConfig};
+pub use self::cookies::Cookies;
+#[cfg(feature = "secure-cookies")]
+pub use self::cookies::{CookieConfig, PrivateCookie, SignedCookie};
+pub use self::deadline::{Deadline, DeadlineConfig};
 pub use self::form::{Form, FormConfig};
-pub use self::json::{Json, JsonConfig};
+pub use self::json::{
+    Json, JsonConfig, JsonPretty, JsonStream, JsonTruncated, JsonWithExtra, JsonWithRaw,
+    JsonWriter, NonEmpty,
+};
+#[cfg(feature = "msgpack")]
+pub use self::msgpack::{MsgPack, MsgPackConfig};
 pub use self::path::{Path, PathConfig};
 pub use self::payload::{Payload, PayloadConfig};
-pub use self::query::{Query, QueryConfig};
+pub use self::query::{Query, QueryConfig, QueryWithRaw};
 pub use self::readlines::Readlines;
+pub use self::require_headers::{RequireHeaders, RequireHeadersConfig};
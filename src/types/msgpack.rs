@@ -0,0 +1,525 @@
+//! MessagePack extractor/responder
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::{fmt, ops};
+
+use bytes::BytesMut;
+use futures::future::{err, ok, FutureExt, LocalBoxFuture, Ready};
+use futures::StreamExt;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use actix_http::http::{header::CONTENT_LENGTH, StatusCode};
+use actix_http::{HttpMessage, Payload, Response};
+
+#[cfg(feature = "compress")]
+use crate::dev::Decompress;
+use crate::error::{Error, ErrorInternalServerError, MsgPackPayloadError};
+use crate::extract::FromRequest;
+use crate::request::HttpRequest;
+use crate::responder::Responder;
+
+/// MessagePack helper
+///
+/// MsgPack mirrors [**Json**](struct.Json.html), but uses binary
+/// MessagePack format. It can be used for response generation and for
+/// extracting typed information from request's payload. Structs are
+/// serialized as maps, with field names.
+///
+/// [**MsgPackConfig**](struct.MsgPackConfig.html) allows to configure
+/// extraction process.
+///
+/// ## Example
+///
+/// ```rust
+/// use actix_web::{web, App};
+/// use serde_derive::{Deserialize, Serialize};
+///
+/// #[derive(Deserialize, Serialize)]
+/// struct Info {
+///     username: String,
+/// }
+///
+/// /// deserialize `Info` from request's body and send it back
+/// async fn index(info: web::MsgPack<Info>) -> web::MsgPack<Info> {
+///     info
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///        web::resource("/index.html").route(
+///            web::post().to(index))
+///     );
+/// }
+/// ```
+pub struct MsgPack<T>(pub T);
+
+impl<T> MsgPack<T> {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> ops::Deref for MsgPack<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> ops::DerefMut for MsgPack<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T> fmt::Debug for MsgPack<T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "MsgPack: {:?}", self.0)
+    }
+}
+
+impl<T> fmt::Display for MsgPack<T>
+where
+    T: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl<T: Serialize> Responder for MsgPack<T> {
+    type Error = Error;
+    type Future = Ready<Result<Response, Error>>;
+
+    fn respond_to(self, _: &HttpRequest) -> Self::Future {
+        let body = match rmp_serde::to_vec_named(&self.0) {
+            Ok(body) => body,
+            Err(e) => return err(ErrorInternalServerError(e)),
+        };
+
+        ok(Response::build(StatusCode::OK)
+            .content_type("application/msgpack")
+            .body(body))
+    }
+}
+
+/// MessagePack extractor. Allow to extract typed information from request's
+/// payload.
+///
+/// Request's content type must be `application/msgpack`,
+/// `application/x-msgpack` or have `+msgpack` suffix.
+impl<T> FromRequest for MsgPack<T>
+where
+    T: DeserializeOwned + 'static,
+{
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Error>>;
+    type Config = MsgPackConfig;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let req2 = req.clone();
+        let config = MsgPackConfig::from_req(req);
+
+        let limit = config.limit;
+        let ctype = config.content_type.clone();
+        let err = config.ehandler.clone();
+
+        MsgPackBody::new(req, payload, ctype)
+            .limit(limit)
+            .map(move |res| match res {
+                Err(e) => {
+                    log::debug!(
+                        "Failed to deserialize MsgPack from payload. \
+                         Request path: {}",
+                        req2.path()
+                    );
+                    if let Some(err) = err {
+                        Err((*err)(e, &req2))
+                    } else {
+                        Err(e.into())
+                    }
+                }
+                Ok(data) => Ok(MsgPack(data)),
+            })
+            .boxed_local()
+    }
+}
+
+/// MessagePack extractor configuration
+///
+/// ```rust
+/// use actix_web::{error, web, App, FromRequest, HttpResponse};
+/// use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Info {
+///     username: String,
+/// }
+///
+/// /// deserialize `Info` from request's body, max payload size is 4kb
+/// async fn index(info: web::MsgPack<Info>) -> String {
+///     format!("Welcome {}!", info.username)
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/index.html")
+///             .app_data(
+///                 // change msgpack extractor configuration
+///                 web::MsgPack::<Info>::configure(|cfg| {
+///                     cfg.limit(4096)
+///                        .error_handler(|err, req| {  // <- create custom error response
+///                           error::InternalError::from_response(
+///                               err, HttpResponse::Conflict().finish()).into()
+///                        })
+///             }))
+///             .route(web::post().to(index))
+///     );
+/// }
+/// ```
+#[derive(Clone)]
+pub struct MsgPackConfig {
+    limit: usize,
+    ehandler:
+        Option<Arc<dyn Fn(MsgPackPayloadError, &HttpRequest) -> Error + Send + Sync>>,
+    content_type: Option<Arc<dyn Fn(mime::Mime) -> bool + Send + Sync>>,
+}
+
+impl MsgPackConfig {
+    /// Change max size of payload. By default max size is 32Kb
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Set custom error handler
+    pub fn error_handler<F>(mut self, f: F) -> Self
+    where
+        F: Fn(MsgPackPayloadError, &HttpRequest) -> Error + Send + Sync + 'static,
+    {
+        self.ehandler = Some(Arc::new(f));
+        self
+    }
+
+    /// Set predicate for allowed content types
+    pub fn content_type<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(mime::Mime) -> bool + Send + Sync + 'static,
+    {
+        self.content_type = Some(Arc::new(predicate));
+        self
+    }
+
+    /// Extract msgpack config from app data, fall back to the default config.
+    fn from_req(req: &HttpRequest) -> &Self {
+        req.app_data::<Self>().unwrap_or(&DEFAULT_CONFIG)
+    }
+}
+
+/// Allow shared refs to default.
+const DEFAULT_CONFIG: MsgPackConfig = MsgPackConfig {
+    limit: 32768, // 2^15 bytes, (~32kB)
+    ehandler: None,
+    content_type: None,
+};
+
+impl Default for MsgPackConfig {
+    fn default() -> Self {
+        DEFAULT_CONFIG.clone()
+    }
+}
+
+/// Request's payload MessagePack parser, it resolves to a deserialized `T`
+/// value.
+///
+/// Returns error:
+///
+/// * content type is not `application/msgpack`
+///   (unless specified in [`MsgPackConfig`](struct.MsgPackConfig.html))
+/// * content length is greater than 256k
+pub struct MsgPackBody<U> {
+    limit: usize,
+    length: Option<usize>,
+    #[cfg(feature = "compress")]
+    stream: Option<Decompress<Payload>>,
+    #[cfg(not(feature = "compress"))]
+    stream: Option<Payload>,
+    err: Option<MsgPackPayloadError>,
+    fut: Option<LocalBoxFuture<'static, Result<U, MsgPackPayloadError>>>,
+}
+
+impl<U> MsgPackBody<U>
+where
+    U: DeserializeOwned + 'static,
+{
+    /// Create `MsgPackBody` for request.
+    pub fn new(
+        req: &HttpRequest,
+        payload: &mut Payload,
+        ctype: Option<Arc<dyn Fn(mime::Mime) -> bool + Send + Sync>>,
+    ) -> Self {
+        // check content-type
+        let msgpack = if let Ok(Some(mime)) = req.mime_type() {
+            mime.type_() == mime::APPLICATION
+                && (mime.subtype() == "msgpack"
+                    || mime.subtype() == "x-msgpack"
+                    || mime.suffix().map_or(false, |s| s == "msgpack"))
+                || ctype.as_ref().map_or(false, |predicate| predicate(mime))
+        } else {
+            false
+        };
+
+        if !msgpack {
+            return MsgPackBody {
+                limit: 262_144,
+                length: None,
+                stream: None,
+                fut: None,
+                err: Some(MsgPackPayloadError::ContentType),
+            };
+        }
+
+        let len = req
+            .headers()
+            .get(&CONTENT_LENGTH)
+            .and_then(|l| l.to_str().ok())
+            .and_then(|s| s.parse::<usize>().ok());
+
+        #[cfg(feature = "compress")]
+        let payload = Decompress::from_headers(payload.take(), req.headers());
+        #[cfg(not(feature = "compress"))]
+        let payload = payload.take();
+
+        MsgPackBody {
+            limit: 262_144,
+            length: len,
+            stream: Some(payload),
+            fut: None,
+            err: None,
+        }
+    }
+
+    /// Change max size of payload. By default max size is 256Kb
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+}
+
+impl<U> Future for MsgPackBody<U>
+where
+    U: DeserializeOwned + 'static,
+{
+    type Output = Result<U, MsgPackPayloadError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(ref mut fut) = self.fut {
+            return Pin::new(fut).poll(cx);
+        }
+
+        if let Some(err) = self.err.take() {
+            return Poll::Ready(Err(err));
+        }
+
+        let limit = self.limit;
+        if let Some(len) = self.length.take() {
+            if len > limit {
+                return Poll::Ready(Err(MsgPackPayloadError::Overflow));
+            }
+        }
+        let mut stream = self.stream.take().unwrap();
+
+        self.fut = Some(
+            async move {
+                let mut body = BytesMut::with_capacity(8192);
+
+                while let Some(item) = stream.next().await {
+                    let chunk = item?;
+                    if (body.len() + chunk.len()) > limit {
+                        return Err(MsgPackPayloadError::Overflow);
+                    } else {
+                        body.extend_from_slice(&chunk);
+                    }
+                }
+                Ok(rmp_serde::from_slice::<U>(&body)?)
+            }
+            .boxed_local(),
+        );
+
+        self.poll(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use serde_derive::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::error::InternalError;
+    use crate::http::header;
+    use crate::test::{load_stream, TestRequest};
+    use crate::HttpResponse;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct MyObject {
+        name: String,
+    }
+
+    fn msgpack_eq(err: MsgPackPayloadError, other: MsgPackPayloadError) -> bool {
+        match err {
+            MsgPackPayloadError::Overflow => match other {
+                MsgPackPayloadError::Overflow => true,
+                _ => false,
+            },
+            MsgPackPayloadError::ContentType => match other {
+                MsgPackPayloadError::ContentType => true,
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    fn encoded() -> Bytes {
+        Bytes::from(
+            rmp_serde::to_vec_named(&MyObject {
+                name: "test".to_owned(),
+            })
+            .unwrap(),
+        )
+    }
+
+    #[actix_rt::test]
+    async fn test_responder() {
+        let req = TestRequest::default().to_http_request();
+
+        let j = MsgPack(MyObject {
+            name: "test".to_string(),
+        });
+        let mut resp = j.respond_to(&req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            header::HeaderValue::from_static("application/msgpack")
+        );
+
+        let body = load_stream(resp.take_body()).await.unwrap();
+        let obj: MyObject = rmp_serde::from_slice(&body).unwrap();
+        assert_eq!(obj.name, "test");
+    }
+
+    #[actix_rt::test]
+    async fn test_extract() {
+        let (req, mut pl) = TestRequest::default()
+            .header(header::CONTENT_TYPE, "application/msgpack")
+            .header(header::CONTENT_LENGTH, encoded().len())
+            .set_payload(encoded())
+            .to_http_parts();
+
+        let s = MsgPack::<MyObject>::from_request(&req, &mut pl)
+            .await
+            .unwrap();
+        assert_eq!(s.name, "test");
+        assert_eq!(
+            s.into_inner(),
+            MyObject {
+                name: "test".to_string()
+            }
+        );
+
+        let (req, mut pl) = TestRequest::default()
+            .header(header::CONTENT_TYPE, "application/x-msgpack")
+            .set_payload(encoded())
+            .app_data(MsgPackConfig::default().limit(4).error_handler(|e, _| {
+                let resp = HttpResponse::BadRequest().finish();
+                InternalError::from_response(e, resp).into()
+            }))
+            .to_http_parts();
+
+        let s = MsgPack::<MyObject>::from_request(&req, &mut pl).await;
+        assert!(format!("{}", s.err().unwrap()).contains("payload size is bigger"));
+    }
+
+    #[actix_rt::test]
+    async fn test_roundtrip() {
+        let req = TestRequest::default().to_http_request();
+        let obj = MyObject {
+            name: "roundtrip".to_string(),
+        };
+        let mut resp = MsgPack(obj).respond_to(&req).await.unwrap();
+        let ctype = resp.headers().get(header::CONTENT_TYPE).unwrap().clone();
+        let body = load_stream(resp.take_body()).await.unwrap();
+
+        let (req, mut pl) = TestRequest::default()
+            .header(header::CONTENT_TYPE, ctype)
+            .set_payload(body)
+            .to_http_parts();
+        let s = MsgPack::<MyObject>::from_request(&req, &mut pl)
+            .await
+            .unwrap();
+        assert_eq!(s.name, "roundtrip");
+    }
+
+    #[actix_rt::test]
+    async fn test_msgpack_body() {
+        let (req, mut pl) = TestRequest::default().to_http_parts();
+        let res = MsgPackBody::<MyObject>::new(&req, &mut pl, None).await;
+        assert!(msgpack_eq(
+            res.err().unwrap(),
+            MsgPackPayloadError::ContentType
+        ));
+
+        let (req, mut pl) = TestRequest::default()
+            .header(header::CONTENT_TYPE, "application/json")
+            .to_http_parts();
+        let res = MsgPackBody::<MyObject>::new(&req, &mut pl, None).await;
+        assert!(msgpack_eq(
+            res.err().unwrap(),
+            MsgPackPayloadError::ContentType
+        ));
+
+        let (req, mut pl) = TestRequest::default()
+            .header(header::CONTENT_TYPE, "application/msgpack")
+            .header(header::CONTENT_LENGTH, "10000")
+            .to_http_parts();
+        let res = MsgPackBody::<MyObject>::new(&req, &mut pl, None)
+            .limit(100)
+            .await;
+        assert!(msgpack_eq(
+            res.err().unwrap(),
+            MsgPackPayloadError::Overflow
+        ));
+
+        let (req, mut pl) = TestRequest::default()
+            .header(header::CONTENT_TYPE, "application/vnd.api+msgpack")
+            .set_payload(encoded())
+            .to_http_parts();
+        let res = MsgPackBody::<MyObject>::new(&req, &mut pl, None).await;
+        assert_eq!(
+            res.ok().unwrap(),
+            MyObject {
+                name: "test".to_owned()
+            }
+        );
+
+        let (req, mut pl) = TestRequest::default()
+            .header(header::CONTENT_TYPE, "application/msgpack")
+            .set_payload(Bytes::from_static(b"\xc1"))
+            .to_http_parts();
+        let res = MsgPackBody::<MyObject>::new(&req, &mut pl, None).await;
+        match res.err().unwrap() {
+            MsgPackPayloadError::Deserialize(_) => (),
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+}
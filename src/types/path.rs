@@ -2,8 +2,8 @@
 use std::sync::Arc;
 use std::{fmt, ops};
 
-use actix_http::error::{Error, ErrorNotFound};
-use actix_router::PathDeserializer;
+use actix_http::error::Error;
+use actix_router::{Path as RouterPath, PathDeserializer, Url};
 use futures::future::{ready, Ready};
 use serde::de;
 
@@ -172,25 +172,197 @@ where
             .unwrap_or(None);
 
         ready(
-            de::Deserialize::deserialize(PathDeserializer::new(req.match_info()))
+            deserialize_path(req.match_info())
                 .map(|inner| Path { inner })
                 .map_err(move |e| {
                     log::debug!(
-                        "Failed during Path extractor deserialization. \
+                        "Failed during Path extractor deserialization: {}. \
                          Request path: {:?}",
+                        e,
                         req.path()
                     );
                     if let Some(error_handler) = error_handler {
-                        let e = PathError::Deserialize(e);
                         (error_handler)(e, req)
                     } else {
-                        ErrorNotFound(e)
+                        e.into()
                     }
                 }),
         )
     }
 }
 
+/// Deserialize `T` from matched path segments.
+///
+/// Resource pattern which does not match `T` is reported separately from
+/// segment values which can not be parsed.
+fn deserialize_path<T>(path: &RouterPath<Url>) -> Result<T, PathError>
+where
+    T: de::DeserializeOwned,
+{
+    let mut error = None;
+    let res = T::deserialize(SegmentCheck {
+        path,
+        error: &mut error,
+    });
+    match (res, error) {
+        (Ok(value), _) => Ok(value),
+        (Err(_), Some(error)) => Err(error),
+        (Err(e), None) => Err(PathError::Deserialize(e)),
+    }
+}
+
+/// Wrapper of `PathDeserializer` which checks segments requested by `T`
+struct SegmentCheck<'a, 'de> {
+    path: &'de RouterPath<Url>,
+    error: &'a mut Option<PathError>,
+}
+
+impl<'a, 'de> SegmentCheck<'a, 'de> {
+    /// Check number of segments, `T` may ignore extra segments unless `exact`
+    fn check_count(
+        &mut self,
+        expected: usize,
+        exact: bool,
+    ) -> Result<(), de::value::Error> {
+        let found = self.path.len();
+        if found < expected || exact && found != expected {
+            *self.error = Some(PathError::SegmentCount { expected, found });
+            Err(de::Error::custom("wrong number of path segments"))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+macro_rules! single_segment {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V>(mut self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: de::Visitor<'de>,
+            {
+                self.check_count(1, true)?;
+                PathDeserializer::new(self.path).$method(visitor)
+            }
+        )*
+    };
+}
+
+macro_rules! forward_segments {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: de::Visitor<'de>,
+            {
+                PathDeserializer::new(self.path).$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'a, 'de> de::Deserializer<'de> for SegmentCheck<'a, 'de> {
+    type Error = de::value::Error;
+
+    single_segment! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32
+        deserialize_i64 deserialize_u8 deserialize_u16 deserialize_u32
+        deserialize_u64 deserialize_f32 deserialize_f64 deserialize_char
+        deserialize_str deserialize_string
+    }
+
+    forward_segments! {
+        deserialize_any deserialize_bytes deserialize_byte_buf deserialize_option
+        deserialize_unit deserialize_seq deserialize_map deserialize_identifier
+        deserialize_ignored_any
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        PathDeserializer::new(self.path).deserialize_unit_struct(name, visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_tuple<V>(
+        mut self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.check_count(len, false)?;
+        PathDeserializer::new(self.path).deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        mut self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.check_count(len, false)?;
+        PathDeserializer::new(self.path).deserialize_tuple_struct(name, len, visitor)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        let res =
+            PathDeserializer::new(self.path).deserialize_struct(name, fields, visitor);
+
+        // required field is not a segment of resource pattern
+        if let Err(ref e) = res {
+            let path = self.path;
+            let missing = fields.iter().find(|field| {
+                path.get(field).is_none() && *e == de::Error::missing_field(field)
+            });
+            if let Some(field) = missing {
+                *self.error = Some(PathError::NotFound((*field).to_owned()));
+            }
+        }
+        res
+    }
+
+    fn deserialize_enum<V>(
+        mut self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.check_count(1, false)?;
+        PathDeserializer::new(self.path).deserialize_enum(name, variants, visitor)
+    }
+}
+
 /// Path extractor configuration
 ///
 /// ```rust
@@ -232,6 +404,11 @@ pub struct PathConfig {
 
 impl PathConfig {
     /// Set custom error handler
+    ///
+    /// Without custom handler, segment values which can not be deserialized
+    /// result in `400 Bad Request` response. Resource pattern which does not
+    /// match extracted type, i.e. `PathError::NotFound` and
+    /// `PathError::SegmentCount`, results in `500 Internal Server Error`.
     pub fn error_handler<F>(mut self, f: F) -> Self
     where
         F: Fn(PathError, &HttpRequest) -> Error + Send + Sync + 'static,
@@ -250,12 +427,13 @@ impl Default for PathConfig {
 #[cfg(test)]
 mod tests {
     use actix_router::ResourceDef;
+    use bytes::Bytes;
     use derive_more::Display;
     use serde_derive::Deserialize;
 
     use super::*;
-    use crate::test::TestRequest;
-    use crate::{error, http, HttpResponse};
+    use crate::test::{call_service, init_service, read_body, TestRequest};
+    use crate::{error, http, web, App, HttpResponse};
 
     #[derive(Deserialize, Debug, Display)]
     #[display(fmt = "MyStruct({}, {})", key, value)]
@@ -357,6 +535,86 @@ mod tests {
         assert_eq!(res[1], "32".to_owned());
     }
 
+    #[actix_rt::test]
+    async fn test_struct_extract_from_route() {
+        #[derive(Deserialize)]
+        struct PostParams {
+            uid: u64,
+            pid: String,
+        }
+
+        async fn post(params: Path<PostParams>) -> String {
+            format!("{}:{}", params.uid, params.pid)
+        }
+
+        let mut srv = init_service(
+            App::new().route("/users/{uid}/posts/{pid}", web::get().to(post)),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/users/42/posts/hello").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"42:hello"));
+
+        let req = TestRequest::with_uri("/users/me/posts/hello").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn test_path_error() {
+        let resource = ResourceDef::new("/{key}/");
+        let mut req = TestRequest::with_uri("/name/").to_srv_request();
+        resource.match_path(req.match_info_mut());
+        let (req, mut pl) = req.into_parts();
+
+        match deserialize_path::<MyStruct>(req.match_info()) {
+            Err(PathError::NotFound(field)) => assert_eq!(field, "value"),
+            res => panic!("unexpected result {:?}", res),
+        }
+
+        // optional field does not have to be a segment
+        #[derive(Deserialize, Debug)]
+        struct Optional {
+            key: String,
+            value: Option<String>,
+        }
+        let res = deserialize_path::<Optional>(req.match_info()).unwrap();
+        assert_eq!((res.key.as_str(), res.value), ("name", None));
+        match deserialize_path::<(String, String)>(req.match_info()) {
+            Err(PathError::SegmentCount { expected, found }) => {
+                assert_eq!((expected, found), (2, 1))
+            }
+            res => panic!("unexpected result {:?}", res),
+        }
+        match deserialize_path::<u32>(req.match_info()) {
+            Err(PathError::Deserialize(_)) => (),
+            res => panic!("unexpected result {:?}", res),
+        }
+
+        // misconfigured route is a server error
+        let err = Path::<MyStruct>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        let res: HttpResponse = err.into();
+        assert_eq!(res.status(), http::StatusCode::INTERNAL_SERVER_ERROR);
+        let err = Path::<u32>::from_request(&req, &mut pl).await.unwrap_err();
+        let res: HttpResponse = err.into();
+        assert_eq!(res.status(), http::StatusCode::BAD_REQUEST);
+
+        let resource = ResourceDef::new("/{key}/{value}/");
+        let mut req = TestRequest::with_uri("/name/user1/").to_srv_request();
+        resource.match_path(req.match_info_mut());
+        match deserialize_path::<String>(req.match_info()) {
+            Err(PathError::SegmentCount { expected, found }) => {
+                assert_eq!((expected, found), (1, 2))
+            }
+            res => panic!("unexpected result {:?}", res),
+        }
+        assert!(deserialize_path::<(String,)>(req.match_info()).is_ok());
+    }
+
     #[actix_rt::test]
     async fn test_custom_err_handler() {
         let (req, mut pl) = TestRequest::with_uri("/name/user1/")
@@ -140,33 +140,113 @@ where
 
     #[inline]
     fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
-        let error_handler = req
-            .app_data::<Self::Config>()
-            .map(|c| c.ehandler.clone())
-            .unwrap_or(None);
-
-        serde_urlencoded::from_str::<T>(req.query_string())
-            .map(|val| ok(Query(val)))
-            .unwrap_or_else(move |e| {
-                let e = QueryPayloadError::Deserialize(e);
-
-                log::debug!(
-                    "Failed during Query extractor deserialization. \
-                     Request path: {:?}",
-                    req.path()
-                );
-
-                let e = if let Some(error_handler) = error_handler {
-                    (error_handler)(e, req)
-                } else {
-                    e.into()
-                };
-
-                err(e)
-            })
+        match deserialize_query(req) {
+            Ok(val) => ok(Query(val)),
+            Err(e) => err(e),
+        }
     }
 }
 
+/// Query extractor which keeps raw query string
+///
+/// Resolves to deserialized value along with the query string exactly as it
+/// was received, before percent-decoding. This is useful to verify signed
+/// query strings, i.e. HMAC, where the exact encoding matters. Extractor is
+/// configured with [**QueryConfig**](struct.QueryConfig.html).
+///
+/// ## Example
+///
+/// ```rust
+/// use actix_web::{web, App};
+/// use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Download {
+///     file: String,
+///     signature: String,
+/// }
+///
+/// async fn index(query: web::QueryWithRaw<Download>) -> String {
+///     let (download, raw) = query.into_inner();
+///     format!("Download {} signed over {:?}", download.file, raw)
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///        web::resource("/download").route(web::get().to(index)));
+/// }
+/// ```
+pub struct QueryWithRaw<T>(pub T, pub String);
+
+impl<T> QueryWithRaw<T> {
+    /// Deconstruct to a value and raw query string
+    pub fn into_inner(self) -> (T, String) {
+        (self.0, self.1)
+    }
+
+    /// Get raw query string
+    pub fn raw(&self) -> &str {
+        &self.1
+    }
+}
+
+impl<T> ops::Deref for QueryWithRaw<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for QueryWithRaw<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("QueryWithRaw")
+            .field(&self.0)
+            .field(&self.1)
+            .finish()
+    }
+}
+
+impl<T> FromRequest for QueryWithRaw<T>
+where
+    T: de::DeserializeOwned,
+{
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+    type Config = QueryConfig;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        match deserialize_query(req) {
+            Ok(val) => ok(QueryWithRaw(val, req.query_string().to_owned())),
+            Err(e) => err(e),
+        }
+    }
+}
+
+fn deserialize_query<T: de::DeserializeOwned>(req: &HttpRequest) -> Result<T, Error> {
+    let error_handler = req
+        .app_data::<QueryConfig>()
+        .map(|c| c.ehandler.clone())
+        .unwrap_or(None);
+
+    serde_urlencoded::from_str::<T>(req.query_string()).map_err(move |e| {
+        let e = QueryPayloadError::Deserialize(e);
+
+        log::debug!(
+            "Failed during Query extractor deserialization. \
+             Request path: {:?}",
+            req.path()
+        );
+
+        if let Some(error_handler) = error_handler {
+            (error_handler)(e, req)
+        } else {
+            e.into()
+        }
+    })
+}
+
 /// Query extractor configuration
 ///
 /// ## Example
@@ -272,6 +352,29 @@ mod tests {
         assert_eq!(s.id, "test1");
     }
 
+    #[actix_rt::test]
+    async fn test_query_with_raw() {
+        let req =
+            TestRequest::with_uri("/name/user1/?id=a%20b+c&sig=x").to_srv_request();
+        let (req, mut pl) = req.into_parts();
+
+        let s = QueryWithRaw::<Id>::from_request(&req, &mut pl)
+            .await
+            .unwrap();
+        assert_eq!(s.id, "a b c");
+        assert_eq!(s.raw(), "id=a%20b+c&sig=x");
+
+        let (id, raw) = s.into_inner();
+        assert_eq!(id.id, "a b c");
+        assert_eq!(raw, "id=a%20b+c&sig=x");
+
+        let req = TestRequest::with_uri("/name/user1/?sig=x").to_srv_request();
+        let (req, mut pl) = req.into_parts();
+        assert!(QueryWithRaw::<Id>::from_request(&req, &mut pl)
+            .await
+            .is_err());
+    }
+
     #[actix_rt::test]
     async fn test_custom_error_responder() {
         let req = TestRequest::with_uri("/name/user1/")
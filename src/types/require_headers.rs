@@ -0,0 +1,211 @@
+//! Required headers extractor
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use actix_http::error::Error;
+use actix_http::http::header::HeaderName;
+use futures::future::{err, ok, Ready};
+
+use crate::dev::Payload;
+use crate::error::MissingHeadersError;
+use crate::extract::FromRequest;
+use crate::request::HttpRequest;
+
+/// Check that request has all headers required by
+/// [**RequireHeadersConfig**](struct.RequireHeadersConfig.html).
+///
+/// Extraction fails with `400 Bad Request` listing missing headers, so the
+/// handler is not executed. If no config is registered, no headers are
+/// required.
+///
+/// ## Example
+///
+/// ```rust
+/// use actix_web::{web, App, HttpRequest};
+///
+/// async fn index(_: web::RequireHeaders, req: HttpRequest) -> String {
+///     format!("Version: {:?}", req.headers().get("x-api-version"))
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/index.html")
+///             .app_data(web::RequireHeadersConfig::new(&["X-Api-Version"]))
+///             .route(web::get().to(index))
+///     );
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RequireHeaders;
+
+impl FromRequest for RequireHeaders {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+    type Config = RequireHeadersConfig;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let config = RequireHeadersConfig::from_req(req);
+        let missing: Vec<_> = config
+            .headers
+            .iter()
+            .filter(|name| !req.headers().contains_key(*name))
+            .map(|name| name.as_str().to_owned())
+            .collect();
+        if missing.is_empty() {
+            return ok(RequireHeaders);
+        }
+
+        let e = MissingHeadersError::new(missing);
+        log::debug!(
+            "Failed during RequireHeaders extractor: {}. Request path: {:?}",
+            e,
+            req.path()
+        );
+        if let Some(ref error_handler) = config.ehandler {
+            err((error_handler)(e, req))
+        } else {
+            err(e.into())
+        }
+    }
+}
+
+/// Headers required by `RequireHeaders` extractor
+///
+/// ## Example
+///
+/// ```rust
+/// use actix_web::{error, web, App, HttpResponse};
+///
+/// async fn index(_: web::RequireHeaders) -> &'static str {
+///     "Welcome!"
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/index.html")
+///             .app_data(
+///                 web::RequireHeadersConfig::new(&["X-Api-Version", "X-Request-Id"])
+///                     .error_handler(|err, _| {
+///                         error::InternalError::from_response(
+///                             err,
+///                             HttpResponse::PreconditionFailed().finish(),
+///                         )
+///                         .into()
+///                     }),
+///             )
+///             .route(web::get().to(index))
+///     );
+/// }
+/// ```
+#[derive(Clone)]
+pub struct RequireHeadersConfig {
+    headers: Cow<'static, [HeaderName]>,
+    ehandler:
+        Option<Arc<dyn Fn(MissingHeadersError, &HttpRequest) -> Error + Send + Sync>>,
+}
+
+impl RequireHeadersConfig {
+    /// Create config requiring given headers
+    ///
+    /// Panics if any of `headers` is not a valid header name.
+    pub fn new(headers: &[&str]) -> Self {
+        RequireHeadersConfig {
+            headers: Cow::Owned(
+                headers
+                    .iter()
+                    .map(|name| {
+                        HeaderName::from_bytes(name.as_bytes())
+                            .unwrap_or_else(|_| panic!("Invalid header name: {}", name))
+                    })
+                    .collect(),
+            ),
+            ehandler: None,
+        }
+    }
+
+    /// Set custom error handler
+    pub fn error_handler<F>(mut self, f: F) -> Self
+    where
+        F: Fn(MissingHeadersError, &HttpRequest) -> Error + Send + Sync + 'static,
+    {
+        self.ehandler = Some(Arc::new(f));
+        self
+    }
+
+    /// Extract config from app data, fall back to the default config.
+    fn from_req(req: &HttpRequest) -> &Self {
+        req.app_data::<Self>().unwrap_or(&DEFAULT_CONFIG)
+    }
+}
+
+/// Allow shared refs to default, no headers are required.
+const DEFAULT_CONFIG: RequireHeadersConfig = RequireHeadersConfig {
+    headers: Cow::Borrowed(&[]),
+    ehandler: None,
+};
+
+impl Default for RequireHeadersConfig {
+    fn default() -> Self {
+        DEFAULT_CONFIG.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_http::http::StatusCode;
+    use bytes::Bytes;
+
+    use super::*;
+    use crate::test::{self, TestRequest};
+    use crate::{web, App, HttpResponse};
+
+    #[actix_rt::test]
+    async fn test_require_headers() {
+        let mut srv = test::init_service(
+            App::new().service(
+                web::resource("/")
+                    .app_data(RequireHeadersConfig::new(&[
+                        "X-Api-Version",
+                        "X-Request-Id",
+                    ]))
+                    .to(|_: RequireHeaders| HttpResponse::Ok()),
+            ),
+        )
+        .await;
+
+        let req = TestRequest::default()
+            .header("x-request-id", "1")
+            .to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body = test::read_body(resp).await;
+        assert_eq!(
+            body,
+            Bytes::from_static(b"Missing required headers: x-api-version")
+        );
+
+        let req = TestRequest::default().to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        let body = test::read_body(resp).await;
+        assert_eq!(
+            body,
+            Bytes::from_static(b"Missing required headers: x-api-version, x-request-id")
+        );
+
+        let req = TestRequest::default()
+            .header("x-request-id", "1")
+            .header("x-api-version", "2")
+            .to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[actix_rt::test]
+    async fn test_require_headers_no_config() {
+        let req = TestRequest::default().to_http_request();
+        let res = RequireHeaders::from_request(&req, &mut Payload::None).await;
+        assert_eq!(res.unwrap(), RequireHeaders);
+    }
+}
@@ -144,3 +144,54 @@ async fn test_start_ssl() {
     thread::sleep(Duration::from_millis(100));
     let _ = sys.stop();
 }
+
+#[cfg(unix)]
+#[actix_rt::test]
+async fn test_graceful_shutdown_timeout() {
+    use actix_http::h2::GracefulShutdown;
+    use std::io::{Read, Write};
+
+    let addr = unused_addr();
+    let handle = GracefulShutdown::new();
+    let handle2 = handle.clone();
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let sys = actix_rt::System::new("test");
+
+        let srv = HttpServer::new(|| {
+            App::new().service(web::resource("/").to(|| HttpResponse::Ok()))
+        })
+        .workers(1)
+        .keep_alive(30)
+        .graceful_shutdown(handle2)
+        .graceful_shutdown_timeout(300)
+        .disable_signals()
+        .bind(format!("{}", addr))
+        .unwrap()
+        .run();
+
+        let _ = tx.send((srv, actix_rt::System::current()));
+        let _ = sys.run();
+    });
+    let (srv, sys) = rx.recv().unwrap();
+
+    let mut stream = net::TcpStream::connect(addr).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(3)))
+        .unwrap();
+    let _ = stream.write_all(b"GET / HTTP/1.1\r\n\r\n");
+    let mut data = vec![0; 1024];
+    let n = stream.read(&mut data).unwrap();
+    assert!(data[..n].starts_with(b"HTTP/1.1 200 OK\r\n"));
+
+    // idle keep-alive connection is dropped after shutdown timeout
+    handle.shutdown();
+    assert_eq!(stream.read(&mut data).unwrap(), 0);
+
+    // stop
+    let _ = srv.stop(false);
+
+    thread::sleep(Duration::from_millis(100));
+    let _ = sys.stop();
+}
@@ -0,0 +1,86 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use futures::future::poll_fn;
+
+use actix_web::dev::{BodySize, MessageBody};
+use actix_web::{test, web, Error, Responder};
+
+/// Allocator which tracks peak of allocated memory
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let size = ALLOCATED.fetch_add(layout.size(), Ordering::SeqCst);
+            PEAK.fetch_max(size + layout.size(), Ordering::SeqCst);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// Sends response body nowhere, returns peak memory increase and body size.
+async fn peak_of<R: Responder<Error = Error>>(
+    responder: R,
+    expected: &[u8],
+) -> (usize, usize) {
+    let req = test::TestRequest::default().to_http_request();
+
+    let base = ALLOCATED.load(Ordering::SeqCst);
+    PEAK.store(base, Ordering::SeqCst);
+
+    let mut resp = responder.respond_to(&req).await.unwrap();
+    let mut body = resp.take_body();
+    let mut size = 0;
+    while let Some(chunk) = poll_fn(|cx| body.poll_next(cx)).await {
+        let chunk = chunk.unwrap();
+        assert_eq!(&chunk[..], &expected[size..size + chunk.len()]);
+        size += chunk.len();
+    }
+    drop(body);
+    drop(resp);
+
+    (PEAK.load(Ordering::SeqCst) - base, size)
+}
+
+#[actix_rt::test]
+async fn test_json_writer_peak_memory() {
+    // start thread pool
+    let (_, size) = peak_of(web::JsonWriter(vec![1u8]), b"[1]").await;
+    assert_eq!(size, 3);
+
+    let data: Vec<String> = (0..200_000).map(|i| format!("item-{:08}", i)).collect();
+    let expected = serde_json::to_vec(&data).unwrap();
+    let req = test::TestRequest::default().to_http_request();
+    let resp = web::JsonWriter(vec![1u8]).respond_to(&req).await.unwrap();
+    assert_eq!(resp.body().size(), BodySize::Stream);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "application/json; charset=utf-8"
+    );
+
+    let (json_peak, size) = peak_of(web::Json(data.clone()), &expected).await;
+    assert_eq!(size, expected.len());
+    assert!(json_peak >= expected.len());
+
+    let (writer_peak, size) = peak_of(web::JsonWriter(data), &expected).await;
+    assert_eq!(size, expected.len());
+    assert!(
+        writer_peak < expected.len() / 10,
+        "peak {} of body {}",
+        writer_peak,
+        expected.len()
+    );
+}
@@ -16,7 +16,7 @@ use rand::{distributions::Alphanumeric, Rng};
 
 use actix_web::dev::BodyEncoding;
 use actix_web::middleware::Compress;
-use actix_web::{dev, test, web, App, Error, HttpResponse};
+use actix_web::{dev, test, web, App, Error, FromRequest, HttpResponse};
 
 const STR: &str = "Hello World Hello World Hello World Hello World Hello World \
                    Hello World Hello World Hello World Hello World Hello World \
@@ -76,6 +76,39 @@ impl futures::Stream for TestBody {
     }
 }
 
+#[actix_rt::test]
+async fn test_force_close_response() {
+    async fn close() -> HttpResponse {
+        let mut res = HttpResponse::Ok().finish();
+        res.force_close();
+        res
+    }
+
+    let srv = test::start(|| {
+        App::new()
+            .service(web::resource("/keep").to(HttpResponse::Ok))
+            .service(web::resource("/close").to(close))
+    });
+
+    let mut stream = std::net::TcpStream::connect(srv.addr()).unwrap();
+    let mut data = vec![0; 1024];
+
+    let _ = stream.write_all(b"GET /keep HTTP/1.1\r\n\r\n");
+    let n = stream.read(&mut data).unwrap();
+    assert!(data[..n].starts_with(b"HTTP/1.1 200 OK\r\n"));
+
+    // connection is still alive
+    let _ = stream.write_all(b"GET /close HTTP/1.1\r\n\r\n");
+    let n = stream.read(&mut data).unwrap();
+    let response = String::from_utf8_lossy(&data[..n]).to_lowercase();
+    assert!(response.starts_with("http/1.1 200 ok\r\n"));
+    assert!(response.contains("connection: close\r\n"));
+
+    // connection is closed by server
+    let _ = stream.write_all(b"GET /keep HTTP/1.1\r\n\r\n");
+    assert_eq!(stream.read(&mut data).unwrap_or(0), 0);
+}
+
 #[actix_rt::test]
 async fn test_body() {
     let srv = test::start(|| {
@@ -241,6 +274,82 @@ async fn test_body_gzip_large() {
     assert_eq!(Bytes::from(dec), Bytes::from(data));
 }
 
+#[actix_rt::test]
+async fn test_body_compress_negotiation() {
+    let data = STR.repeat(10);
+    let srv_data = data.clone();
+
+    let srv = test::start_with(test::config().h1(), move || {
+        let data = srv_data.clone();
+        App::new()
+            .wrap(
+                Compress::default()
+                    .encodings(&[ContentEncoding::Gzip, ContentEncoding::Deflate])
+                    .min_size(1024)
+                    .content_type(mime::TEXT_STAR),
+            )
+            .service(web::resource("/").route(web::to(move || {
+                HttpResponse::Ok()
+                    .content_type("text/plain")
+                    .body(data.clone())
+            })))
+            .service(web::resource("/small").route(web::to(|| {
+                HttpResponse::Ok().content_type("text/plain").body("small")
+            })))
+            .service(web::resource("/image").route(web::to(|| {
+                HttpResponse::Ok()
+                    .content_type("image/png")
+                    .body(STR.repeat(10))
+            })))
+    });
+
+    // br is not allowed, gzip is negotiated
+    let mut response = srv
+        .get("/")
+        .no_decompress()
+        .header(ACCEPT_ENCODING, "br, gzip")
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(response.headers().get(CONTENT_ENCODING).unwrap(), "gzip");
+
+    let bytes = response.body().await.unwrap();
+    let mut e = GzDecoder::new(&bytes[..]);
+    let mut dec = Vec::new();
+    e.read_to_end(&mut dec).unwrap();
+    assert_eq!(Bytes::from(dec), Bytes::from(data.clone()));
+
+    // client does not accept compression
+    let mut response = srv.get("/").no_decompress().send().await.unwrap();
+    assert!(response.status().is_success());
+    assert!(response.headers().get(CONTENT_ENCODING).is_none());
+    let bytes = response.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from(data));
+
+    // below min size
+    let mut response = srv
+        .get("/small")
+        .no_decompress()
+        .header(ACCEPT_ENCODING, "gzip")
+        .send()
+        .await
+        .unwrap();
+    assert!(response.headers().get(CONTENT_ENCODING).is_none());
+    let bytes = response.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(b"small"));
+
+    // content type is not in allow list
+    let response = srv
+        .get("/image")
+        .no_decompress()
+        .header(ACCEPT_ENCODING, "gzip")
+        .send()
+        .await
+        .unwrap();
+    assert!(response.headers().get(CONTENT_ENCODING).is_none());
+}
+
 #[actix_rt::test]
 async fn test_body_gzip_large_random() {
     let data = rand::thread_rng()
@@ -845,6 +954,49 @@ async fn test_reading_deflate_encoding_large_random_rustls() {
 //     }
 // }
 
+#[actix_rt::test]
+async fn test_json_error_drain_keep_alive() {
+    use std::net;
+
+    let srv = test::start_with(test::config().h1(), || {
+        App::new().service(
+            web::resource("/")
+                .app_data(web::Json::<Vec<u32>>::configure(|cfg| {
+                    cfg.limit(1024).on_error_drain(true)
+                }))
+                .route(web::post().to(|data: web::Json<Vec<u32>>| {
+                    HttpResponse::Ok().body(format!("{}", data.len()))
+                })),
+        )
+    });
+
+    let body = "1".repeat(256 * 1024);
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    stream
+        .set_read_timeout(Some(std::time::Duration::from_secs(5)))
+        .unwrap();
+    let req = format!(
+        "POST / HTTP/1.1\r\ncontent-type: application/json\r\n\
+         content-length: {}\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    stream.write_all(req.as_bytes()).unwrap();
+    stream
+        .write_all(
+            b"POST / HTTP/1.1\r\ncontent-type: application/json\r\n\
+              content-length: 5\r\nconnection: close\r\n\r\n[1,2]",
+        )
+        .unwrap();
+
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.starts_with("HTTP/1.1 413 Payload Too Large"));
+    let second = &data[data.rfind("HTTP/1.1").unwrap()..];
+    assert!(second.starts_with("HTTP/1.1 200 OK"));
+    assert!(second.ends_with("\r\n\r\n2"));
+}
+
 #[actix_rt::test]
 async fn test_slow_request() {
     use std::net;